@@ -0,0 +1,30 @@
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// Environment variable setting the time budget in seconds, like
+/// `--time-budget`.
+pub const BUDGET_VAR: &str = "INVAR_TIME_BUDGET";
+
+/// Why items were skipped once the budget ran out, for summaries.
+pub const SKIPPED_REASON: &str = "skipped, the time budget ran out";
+
+static DEADLINE: OnceLock<Instant> = OnceLock::new();
+
+/// Limit how long bulk operations may keep querying remote APIs, counting from
+/// now.
+///
+/// Once the budget runs out, operations going through many components skip
+/// the remaining ones and report them instead of waiting on a slow network.
+/// Only the first call has an effect.
+pub fn set(budget: Duration) {
+    let _ = DEADLINE.set(Instant::now() + budget);
+}
+
+/// Whether the budget [set](set) for this process ran out. There is no limit
+/// if none was set.
+#[must_use]
+pub fn is_exhausted() -> bool {
+    DEADLINE
+        .get()
+        .is_some_and(|deadline| Instant::now() >= *deadline)
+}
@@ -0,0 +1,125 @@
+use super::CACHE_DIR;
+use crate::{local_storage, network};
+use reqwest::blocking::Client;
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Environment variable that turns off the HTTP cache, like `--no-cache`.
+pub const NO_CACHE_VAR: &str = "INVAR_NO_CACHE";
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Use (or bypass) the cache of API responses for the rest of the process.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether API responses are cached, see [`set_enabled`].
+#[must_use]
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// A response kept along with the validators to revalidate it with.
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    body: String,
+}
+
+/// Directory (relative to the pack root) cached API responses are kept in,
+/// named by the hash of their URL.
+#[must_use]
+pub fn dir() -> PathBuf {
+    Path::new(CACHE_DIR).join("http")
+}
+
+fn entry_path(url: &str) -> PathBuf {
+    dir().join(format!("{}.json", blake3::hash(url.as_bytes()).to_hex()))
+}
+
+/// `GET` the body of `url`, revalidating a cached response with its `ETag` or
+/// `Last-Modified` instead of downloading it again if it's unchanged.
+///
+/// Responses without either validator aren't cached. Failing to read or write
+/// the cache is never an error, the request is just made as usual. If the API
+/// can't be reached (or [offline mode](network::set_offline) is on), the
+/// cached response is used as is.
+///
+/// # Errors
+///
+/// This function will return an error if the request fails or the server
+/// responds with an error status, unless there's a cached response to fall
+/// back to.
+pub fn get(client: &Client, url: &str) -> Result<String, network::Error> {
+    if !is_enabled() {
+        return Ok(network::send(client.get(url))?.error_for_status()?.text()?);
+    }
+    let path = entry_path(url);
+    let cached = fs::read(&path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<Entry>(&bytes).ok())
+        .filter(|entry| entry.url == url);
+
+    let mut request = client.get(url);
+    if let Some(entry) = &cached {
+        if let Some(etag) = &entry.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &entry.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+    let response = match network::send(request) {
+        Err(error) if error.is_unreachable() => {
+            let entry = cached.ok_or(error)?;
+            tracing::debug!(url, "Offline, using the cached response");
+            return Ok(entry.body);
+        }
+        response => response?,
+    };
+    if let (StatusCode::NOT_MODIFIED, Some(entry)) = (response.status(), cached) {
+        tracing::debug!(url, "Not modified, using the cached response");
+        return Ok(entry.body);
+    }
+
+    let response = response.error_for_status()?;
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from)
+    };
+    let (etag, last_modified) = (header(ETAG), header(LAST_MODIFIED));
+    let body = response.text()?;
+    if etag.is_some() || last_modified.is_some() {
+        let entry = Entry {
+            url: url.to_string(),
+            etag,
+            last_modified,
+            body,
+        };
+        if let Err(error) = store(&path, &entry) {
+            tracing::debug!(url, %error, "Failed to cache the response");
+        }
+        return Ok(entry.body);
+    }
+    Ok(body)
+}
+
+fn store(path: &Path, entry: &Entry) -> local_storage::Result<()> {
+    super::prepare_dir()?;
+    let io_error = |source| local_storage::Error::Io {
+        source,
+        faulty_path: Some(path.to_path_buf()),
+    };
+    fs::create_dir_all(dir()).map_err(io_error)?;
+    local_storage::write_atomic(path, &serde_json::to_vec(entry)?, false).map_err(io_error)
+}
@@ -0,0 +1,197 @@
+use crate::index::file::{Algorithm, Hashes};
+use crate::plan::{Action, Plan};
+use crate::progress::{self, Event, Task};
+use crate::{local_storage, network};
+use reqwest::blocking::Client;
+use reqwest::header::RANGE;
+use reqwest::StatusCode;
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::{io, thread};
+use url::Url;
+use walkdir::WalkDir;
+
+/// Directory (relative to the pack root) where downloaded files are kept, named
+/// by their SHA512 hash.
+pub const CACHE_DIR: &str = ".invar/cache";
+
+/// Cache of API responses, revalidated with their `ETag`s.
+pub mod http;
+
+/// How many downloads [`fetch_all`] runs at once by default.
+pub const DEFAULT_CONCURRENCY: usize = 8;
+
+/// A remote file to be fetched into the cache.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Request {
+    pub url: Url,
+    /// Hex-encoded SHA512 hash of the file, which is also its name in the
+    /// cache.
+    pub sha512: String,
+}
+
+impl Request {
+    /// Where the file ends up once it's fetched.
+    #[must_use]
+    pub fn path(&self) -> PathBuf {
+        Path::new(CACHE_DIR).join(&self.sha512)
+    }
+
+    /// Where the file is downloaded to before it's verified. If a download is
+    /// interrupted, it picks up from here next time.
+    fn partial_path(&self) -> PathBuf {
+        Path::new(CACHE_DIR).join(format!("{}.part", self.sha512))
+    }
+}
+
+/// Fetch a file into the cache, unless it's already there.
+///
+/// Interrupted downloads are resumed if the server supports range requests,
+/// and restarted otherwise. If the server says there's nothing left to fetch
+/// (`416 Range Not Satisfiable`), the partial file is verified as it is. The
+/// file only makes it into the cache if its hash matches the [`Request`],
+/// and is deleted otherwise.
+///
+/// # Errors
+///
+/// This function will return an error if the download fails, the downloaded
+/// file's hash doesn't match, or on I/O errors.
+pub fn fetch(client: &Client, request: &Request) -> Result<PathBuf, Error> {
+    let path = request.path();
+    if path.is_file() {
+        return Ok(path);
+    }
+    prepare_dir()?;
+
+    let partial = request.partial_path();
+    let io_error = |source| local_storage::Error::Io {
+        source,
+        faulty_path: Some(partial.clone()),
+    };
+    let offset = fs::metadata(&partial).map_or(0, |metadata| metadata.len());
+    let download_error = |source| Error::Download {
+        url: request.url.clone(),
+        source,
+    };
+    progress::emit(&Event::FetchStarted { url: &request.url });
+    let mut builder = client.get(request.url.clone());
+    if offset > 0 {
+        tracing::debug!(url = %request.url, offset, "Resuming download");
+        builder = builder.header(RANGE, format!("bytes={offset}-"));
+    }
+    let response = network::send(builder).map_err(download_error)?;
+    if offset > 0 && response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        tracing::debug!(url = %request.url, offset, "Download already complete");
+    } else {
+        let mut response = response
+            .error_for_status()
+            .map_err(|error| download_error(error.into()))?;
+        let resumed = response.status() == StatusCode::PARTIAL_CONTENT;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(resumed)
+            .truncate(!resumed)
+            .open(&partial)
+            .map_err(io_error)?;
+        io::copy(&mut response, &mut file).map_err(io_error)?;
+    }
+
+    let bytes = fs::read(&partial).map_err(io_error)?;
+    if Hashes::compute(&bytes).hex(Algorithm::Sha512).as_ref() != Some(&request.sha512) {
+        fs::remove_file(&partial).map_err(io_error)?;
+        return Err(Error::Mismatch {
+            url: request.url.clone(),
+        });
+    }
+    fs::rename(&partial, &path).map_err(io_error)?;
+    Ok(path)
+}
+
+/// [Fetch](fetch) many files into the cache, running up to `concurrency`
+/// downloads at once.
+///
+/// Results are in the same order as the `requests`.
+#[must_use]
+pub fn fetch_all(requests: &[Request], concurrency: usize) -> Vec<Result<PathBuf, Error>> {
+    let client = Client::new();
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new((0..requests.len()).map(|_| None).collect::<Vec<_>>());
+    let task = Task::start("Downloading", requests.len());
+    let worker = || loop {
+        let index = next.fetch_add(1, Ordering::Relaxed);
+        let Some(request) = requests.get(index) else {
+            break;
+        };
+        let result = fetch(&client, request);
+        if let Err(error) = &result {
+            tracing::warn!(url = %request.url, %error, "Failed to fetch");
+        }
+        results
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)[index] = Some(result);
+        task.advance(None);
+    };
+    thread::scope(|scope| {
+        for _ in 0..concurrency.clamp(1, requests.len().max(1)) {
+            scope.spawn(worker);
+        }
+    });
+    results
+        .into_inner()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// Compute the [`Plan`] for emptying the cache, of both downloaded files and
+/// [API responses](http).
+///
+/// # Errors
+///
+/// This function will return an error if the cache can't be scanned.
+pub fn plan_clear() -> local_storage::Result<Plan> {
+    let mut plan = Plan::new();
+    if !Path::new(CACHE_DIR).is_dir() {
+        return Ok(plan);
+    }
+    for entry in WalkDir::new(CACHE_DIR).sort_by_file_name() {
+        let entry = entry?;
+        if entry.file_type().is_file() && entry.file_name() != ".gitignore" {
+            plan.push(Action::RemoveFile {
+                path: entry.into_path(),
+            });
+        }
+    }
+    Ok(plan)
+}
+
+fn prepare_dir() -> Result<(), local_storage::Error> {
+    local_storage::ensure_writable()?;
+    let io_error = |source| local_storage::Error::Io {
+        source,
+        faulty_path: Some(PathBuf::from(CACHE_DIR)),
+    };
+    fs::create_dir_all(CACHE_DIR).map_err(io_error)?;
+    let gitignore = Path::new(CACHE_DIR).join(".gitignore");
+    if !gitignore.exists() {
+        fs::write(gitignore, "*\n").map_err(io_error)?;
+    }
+    Ok(())
+}
+
+/// Errors that may arise while fetching files into the cache.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    LocalStorage(#[from] local_storage::Error),
+
+    #[error("Failed to download {url}")]
+    Download { url: Url, source: network::Error },
+
+    #[error("The file downloaded from {url} doesn't match its expected hash")]
+    Mismatch { url: Url },
+}
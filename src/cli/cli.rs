@@ -1,8 +1,17 @@
 use clap::builder::styling::AnsiColor::{BrightBlue, White, Yellow};
-use clap::builder::Styles;
+use clap::builder::{BoolishValueParser, Styles};
 use clap::Parser;
-use invar::Loader;
+use invar::component::{Category, FileNaming, Filter, ListFormat, Source, Tag};
+use invar::history::Operation;
+use invar::index::OverrideSide;
+use invar::server::bundle::SecretsEncryption;
+use invar::{
+    budget, cache, local_storage, network, terminal, BackupFormat, Destination, ExportFormat,
+    Loader, VersionPart,
+};
 use semver::Version;
+use std::path::PathBuf;
+use url::Url;
 
 /// Styling for [`clap`]'s CLI interface.
 const STYLES: Styles = Styles::styled()
@@ -13,12 +22,89 @@ const STYLES: Styles = Styles::styled()
 
 #[derive(Parser, Debug)]
 #[command(version, author, about, styles(STYLES))]
+#[expect(
+    clippy::struct_excessive_bools,
+    reason = "They're all independent flags"
+)]
 pub struct Options {
     #[command(subcommand)]
     pub subcommand: Subcommand,
 
-    #[arg(short('f'), long("format"), default_value_t = OutputFormat::default())]
+    /// How to print reports and dry-run plans.
+    #[arg(
+        short('f'),
+        long("output"),
+        alias("format"),
+        global = true,
+        default_value_t = OutputFormat::default()
+    )]
     pub output_format: OutputFormat,
+
+    /// Only show what would be done, without changing anything.
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Never prompt: take the newest versions, skip tags and optional
+    /// components, and fail where input is required.
+    #[arg(
+        long,
+        global = true,
+        env = terminal::NONINTERACTIVE_VAR,
+        value_parser = BoolishValueParser::new()
+    )]
+    pub non_interactive: bool,
+
+    /// Never write to the pack, its cache or its history, to inspect packs in
+    /// CI or on shared mounts. Turned on by itself where the pack file can't
+    /// be written to.
+    #[arg(
+        long,
+        global = true,
+        env = local_storage::READ_ONLY_VAR,
+        value_parser = BoolishValueParser::new()
+    )]
+    pub read_only: bool,
+
+    /// Don't use or update the cache of API responses, always fetching
+    /// project and version lists anew.
+    #[arg(
+        long,
+        global = true,
+        env = cache::http::NO_CACHE_VAR,
+        value_parser = BoolishValueParser::new()
+    )]
+    pub no_cache: bool,
+
+    /// Don't touch the network, using cached data where there is some and
+    /// failing right away where there isn't. Turned on by itself once the
+    /// network turns out to be unreachable.
+    #[arg(
+        long,
+        global = true,
+        env = network::OFFLINE_VAR,
+        value_parser = BoolishValueParser::new()
+    )]
+    pub offline: bool,
+
+    /// Flush every written file to disk before it replaces the previous
+    /// version, so that even power loss can't leave it half written.
+    #[arg(
+        long,
+        global = true,
+        env = local_storage::FSYNC_VAR,
+        value_parser = BoolishValueParser::new()
+    )]
+    pub fsync: bool,
+
+    /// Don't commit the changes made, even if the pack's `vcs_mode` is
+    /// `track_components`.
+    #[arg(long, global = true)]
+    pub no_commit: bool,
+
+    /// Stop querying remote APIs after this many seconds, skipping the
+    /// components left in bulk operations like `component update --all`.
+    #[arg(long, global = true, env = budget::BUDGET_VAR, value_name = "SECONDS")]
+    pub time_budget: Option<u64>,
 }
 
 #[derive(clap::Subcommand, Debug)]
@@ -40,6 +126,24 @@ pub enum Subcommand {
         #[command(subcommand)]
         action: ServerAction,
     },
+
+    /// Manage the self-hosted mirror of the pack's files.
+    Mirror {
+        #[command(subcommand)]
+        action: MirrorAction,
+    },
+
+    /// Manage the cached Minecraft and loader version lists.
+    Meta {
+        #[command(subcommand)]
+        action: MetaAction,
+    },
+
+    /// Manage the cache of downloaded files and API responses.
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
 }
 
 #[derive(clap::Subcommand, Debug)]
@@ -72,14 +176,234 @@ pub enum PackAction {
     /// Read the local storage and show Invar sees.
     Show,
 
-    /// Export the modpack in `.mrpack` format.
-    Export,
+    /// Estimate how much the pack takes to download and install.
+    Size {
+        /// Only count what's needed on this side.
+        #[arg(short, long, default_value_t = OverrideSide::default())]
+        side: OverrideSide,
+    },
+
+    /// Export the modpack in `.mrpack` (or CurseForge's) format.
+    Export {
+        /// Don't check that the stored hashes match the actual files.
+        #[arg(long)]
+        skip_verify: bool,
+
+        /// Export even if the pack has problems `pack doctor --offline` would
+        /// report.
+        #[arg(long)]
+        force: bool,
+
+        /// The format to export the pack in.
+        #[arg(long("as"), default_value_t = ExportFormat::default())]
+        format: ExportFormat,
+
+        /// Only export what's needed on this side, like a server pack without
+        /// client-only mods and shaders.
+        #[arg(short, long, default_value_t = OverrideSide::default())]
+        side: OverrideSide,
+
+        /// Upload the exported artifact to an `s3://<bucket>/<prefix>` or
+        /// `sftp://[<user>@]<host>[:<port>]/<dir>` URL, and print where it can
+        /// be downloaded from.
+        #[arg(long)]
+        upload: Option<Destination>,
+
+        /// The base URL the upload destination is served from, if it's not
+        /// the bucket's own public URL.
+        #[arg(long, requires = "upload")]
+        public_url: Option<Url>,
+
+        /// Export the pack with this profile applied.
+        #[arg(short, long)]
+        profile: Option<String>,
+
+        /// Export with the options of this preset from the pack's settings.
+        #[arg(long, conflicts_with_all = ["format", "side", "upload", "profile"])]
+        preset: Option<String>,
+
+        /// Keep running, and export again whenever the pack file, component
+        /// metadata, local components or overrides change.
+        #[arg(short, long)]
+        watch: bool,
+
+        /// Restart the server after every export while watching, to try the
+        /// changes out right away.
+        #[arg(long, requires = "watch")]
+        restart_server: bool,
+    },
+
+    /// Set up a new pack in the current directory from an existing `.mrpack`.
+    Import {
+        /// The `.mrpack` to import.
+        path: PathBuf,
+
+        /// Don't ask for confirmation if there's already a pack in the current
+        /// directory.
+        #[arg(short, long)]
+        overwrite: bool,
+    },
+
+    /// Move the pack to another Minecraft version, updating or removing
+    /// components as needed.
+    #[command(arg_required_else_help = true)]
+    Retarget {
+        /// The Minecraft version to move to.
+        minecraft_version: Version,
+
+        /// The loader version to use with the new Minecraft version. Asked for
+        /// if not provided. Ignored if no loader is used.
+        #[arg(long)]
+        loader_version: Option<Version>,
+
+        /// Don't ask for confirmation before removing unavailable components.
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Import default client options (including keybinds) from an existing
+    /// instance.
+    #[command(arg_required_else_help = true)]
+    ImportDefaults {
+        /// The instance directory to import options from.
+        instance: PathBuf,
+    },
+
+    /// Fetch the Git repositories included in the pack.
+    Fetch,
+
+    /// Increment the pack's version, then commit and tag the release.
+    Bump {
+        /// Which part of the version to increment.
+        part: VersionPart,
+
+        /// Only write the new version, without committing or tagging it.
+        #[arg(long)]
+        no_git: bool,
+
+        /// Export the pack with the new version afterwards.
+        #[arg(short, long)]
+        export: bool,
+    },
+
+    /// Rename the pack, along with its exports and the server's containers.
+    #[command(arg_required_else_help = true)]
+    Rename {
+        /// The pack's new name.
+        name: String,
+    },
+
+    /// Show the log of operations run on the pack.
+    History {
+        /// Only show operations of this kind.
+        #[arg(short, long)]
+        operation: Option<Operation>,
+
+        /// Only show this many of the latest operations.
+        #[arg(short('n'), long)]
+        last: Option<usize>,
+    },
+
+    /// Check the pack for problems, failing if there are any.
+    Doctor {
+        /// Accept the changes made to local components, recording their
+        /// current hashes before checking.
+        #[arg(long)]
+        update_hashes: bool,
+    },
+
+    /// List the configs that belong to none of the pack's mods, and pick
+    /// which of them to delete.
+    PruneConfigs,
+
+    /// Install the pack as a client instance into a directory: components,
+    /// local components and overrides included.
+    Install {
+        /// The directory to install the pack into.
+        #[arg(default_value = "instance")]
+        target: PathBuf,
+
+        /// Ask which optional components to install.
+        #[arg(short, long)]
+        ask_optional: bool,
+
+        /// Install optional components not chosen before when running with
+        /// `--non-interactive`, instead of skipping them.
+        #[arg(short, long)]
+        with_optional: bool,
+    },
 }
 
 #[derive(clap::Subcommand, Debug)]
 pub enum ComponentAction {
     /// Show the existing components in the pack.
-    List,
+    List {
+        /// Only show components of this category.
+        #[arg(short, long)]
+        category: Option<Category>,
+
+        /// Only show components with this tag, main or not.
+        #[arg(short, long)]
+        tag: Option<Tag>,
+
+        /// Only show components needed on this side, or `both` for those
+        /// needed everywhere.
+        #[arg(short, long, visible_alias = "side")]
+        env: Option<OverrideSide>,
+
+        /// Only show components from this source.
+        #[arg(short, long)]
+        source: Option<Source>,
+
+        /// Sort the components: by slug, biggest file first, or most recently
+        /// changed first.
+        #[arg(long)]
+        sort: Option<ListSort>,
+
+        /// Also show the components' file sizes and version IDs.
+        #[arg(short, long)]
+        long: bool,
+    },
+
+    /// Show the dependency tree of the pack's components.
+    Tree,
+
+    /// Look up the names, summaries, licenses and icons of all components'
+    /// projects again, and keep them in the components' files.
+    RefreshMetadata,
+
+    /// Print a shareable list of the pack's components, with their names,
+    /// versions, tags, sides and links, like for the pack's page.
+    ExportList {
+        /// The format to render the list in.
+        #[arg(default_value_t = ListFormat::default())]
+        format: ListFormat,
+
+        /// Show the projects' icons and summaries in HTML lists, looking up
+        /// the metadata of components that don't keep it.
+        #[arg(short, long)]
+        gallery: bool,
+    },
+
+    /// Show which components have newer versions available, exiting with 1
+    /// if there are any.
+    Outdated {
+        /// Render the report as Markdown, for posting it somewhere.
+        #[arg(short, long)]
+        markdown: bool,
+    },
+
+    /// Show the versions of a project that fit the pack, with their channels,
+    /// dependencies and sides, without adding anything.
+    #[command(arg_required_else_help = true)]
+    Probe {
+        /// The ID of the project to look up.
+        slug: String,
+
+        /// Where to look the project up.
+        #[arg(short, long, default_value_t = Source::Modrinth)]
+        source: Source,
+    },
 
     /// Add a new component to the pack.
     #[command(arg_required_else_help = true)]
@@ -87,15 +411,47 @@ pub enum ComponentAction {
         /// The IDs of components to be added.
         ids: Vec<String>,
 
+        /// Where to fetch the components from.
+        #[arg(short, long, default_value_t = Source::default())]
+        source: Source,
+
         /// Show the component's metadata before writing it to disk.
         #[arg(short('d'), long("debug"))]
         show_metadata: bool,
+
+        /// Create a single Git commit with all the added components, even if
+        /// the pack doesn't track its components.
+        #[arg(short, long)]
+        commit: bool,
     },
 
     /// Update one or more of the existing components.
+    #[command(arg_required_else_help = true)]
     Update {
-        /// The IDs of components to update (update all if not provided).
+        /// The IDs of components to update.
         slugs: Vec<String>,
+
+        /// Update all components in the pack.
+        #[arg(short, long, conflicts_with = "slugs")]
+        all: bool,
+
+        /// Interactively pick among newer versions instead of taking the
+        /// newest one.
+        #[arg(short, long)]
+        pick: bool,
+
+        /// Create a Git commit for each updated component.
+        #[arg(short, long)]
+        commit: bool,
+
+        /// Create a new Git branch and apply the updates on it.
+        #[arg(short, long, requires = "commit")]
+        branch: Option<String>,
+
+        /// Open the report of what changed in `$VISUAL` or `$EDITOR`, to
+        /// touch it up for release notes.
+        #[arg(short, long)]
+        edit: bool,
     },
 
     /// Remove one or more of the existing components.
@@ -104,13 +460,172 @@ pub enum ComponentAction {
     Remove {
         /// The IDs of components to remove.
         slugs: Vec<String>,
+
+        /// Also remove dependencies that nothing else needs afterwards.
+        #[arg(short, long)]
+        cascade: bool,
+
+        /// Also remove all libraries that nothing depends on.
+        #[arg(short, long)]
+        orphans: bool,
+
+        /// Also remove essential components, like the loader's API.
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Replace an existing component with another one, keeping its tags.
+    #[command(arg_required_else_help = true)]
+    Replace {
+        /// The ID of the component to remove.
+        old: String,
+
+        /// The ID of the component to add in its place.
+        new: String,
+
+        /// Where to get the new component from.
+        #[arg(short, long, default_value_t = Source::Modrinth)]
+        source: Source,
+    },
+
+    /// Give an existing component another ID, updating the pack's settings
+    /// that refer to it.
+    #[command(arg_required_else_help = true)]
+    Rename {
+        /// The current ID of the component.
+        old: String,
+
+        /// The ID to give it.
+        new: String,
+    },
+
+    /// Limit one or more of the existing components to a side, like `server`
+    /// for server-only mods.
+    #[command(arg_required_else_help = true)]
+    Side {
+        /// The IDs of components to limit.
+        slugs: Vec<String>,
+
+        /// The side they're needed on, `both` lifts the limit.
+        #[arg(short, long)]
+        side: OverrideSide,
+    },
+
+    /// Choose what an existing component's file is called at runtime.
+    #[command(arg_required_else_help = true)]
+    Naming {
+        /// The ID of the component.
+        slug: String,
+
+        /// `original` to keep the file's name, `id` to name it after the
+        /// component, or anything else to use as the name.
+        naming: FileNaming,
+    },
+
+    /// Put one or more of the existing components into a group, to be
+    /// toggled together by the pack's `features`.
+    #[command(arg_required_else_help = true)]
+    Group {
+        /// The IDs of components to group.
+        slugs: Vec<String>,
+
+        /// The group to put them into, or none to take them out of theirs.
+        #[arg(short, long)]
+        group: Option<String>,
+    },
+
+    /// Manage the tags of existing components.
+    Tag {
+        #[command(subcommand)]
+        action: TagAction,
+    },
+
+    /// Move one or more of the existing components into another pack.
+    #[command(arg_required_else_help = true)]
+    Move {
+        /// The IDs of components to move.
+        slugs: Vec<String>,
+
+        /// Path to the directory of the pack to move components into.
+        #[arg(short, long)]
+        to: PathBuf,
+    },
+
+    /// Add a file or directory from the pack's repository to be placed into
+    /// the overrides on export.
+    #[command(arg_required_else_help = true)]
+    AddLocal {
+        /// Path to the file or directory, relative to the pack root. Globs
+        /// like `config/yungsapi/**` pick all the matching files.
+        path: PathBuf,
+
+        /// Where it should end up in the instance, if not at the same path.
+        #[arg(short, long)]
+        target: Option<PathBuf>,
+
+        /// Zip a directory into a single file instead of copying it.
+        #[arg(short, long)]
+        zip: bool,
+
+        /// The sides it's needed on, which decides the overrides folder it's
+        /// exported into.
+        #[arg(short, long, default_value_t = OverrideSide::default())]
+        side: OverrideSide,
+    },
+
+    /// Check local components for changes since their hashes were recorded.
+    CheckLocal {
+        /// Record the current hashes of all local components.
+        #[arg(short, long)]
+        record: bool,
     },
 }
 
 #[derive(clap::Subcommand, Debug)]
 pub enum ServerAction {
+    #[command(flatten)]
+    Lifecycle(LifecycleAction),
+
+    /// Run a console command on the running server over RCON, like
+    /// `whitelist add Steve`.
+    #[command(arg_required_else_help = true)]
+    Exec {
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+    },
+
+    /// Apply the operators, whitelist and bans from the pack's settings to
+    /// the running server over RCON.
+    SyncPlayers,
+
+    /// Pack the compose file, the server-side `.mrpack` and the secrets into
+    /// a deployment bundle.
+    Bundle {
+        /// Include the secrets, encrypted with this tool.
+        #[arg(short, long)]
+        encrypt: Option<SecretsEncryption>,
+
+        /// The age public keys to encrypt the secrets for.
+        #[arg(short, long = "recipient")]
+        recipients: Vec<String>,
+    },
+
+    /// Manage backups of the server.
+    Backup {
+        #[command(subcommand)]
+        action: BackupAction,
+    },
+}
+
+/// The [`ServerAction`]s that are up to the server's backend.
+#[derive(clap::Subcommand, Debug)]
+pub enum LifecycleAction {
     /// Prepare for the first start of the server.
-    Setup,
+    Setup {
+        /// Set the server up with this profile of the pack applied.
+        #[arg(short, long)]
+        profile: Option<String>,
+    },
 
     /// Start the server, do nothing if it is already running.
     Start,
@@ -121,10 +636,37 @@ pub enum ServerAction {
     /// Report the status of the server.
     Status,
 
-    /// Manage backups of the server.
-    Backup {
-        #[command(subcommand)]
-        action: BackupAction,
+    /// Show the server's console output.
+    Logs {
+        /// Keep showing new output as it comes.
+        #[arg(short = 'F', long)]
+        follow: bool,
+    },
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum TagAction {
+    /// Change the tags of all components matching the filters at once, like
+    /// `--filter category=mod --add performance`.
+    #[command(arg_required_else_help = true)]
+    Bulk {
+        /// A `key=value` condition the components must meet, with `category`,
+        /// `source`, `tag`, `main` (or `main=none`) or `slug` as the key.
+        /// Components must meet all of them.
+        #[arg(long = "filter")]
+        filters: Vec<Filter>,
+
+        /// Tags to add to the other tags.
+        #[arg(short, long)]
+        add: Vec<Tag>,
+
+        /// Tags to remove, including the main one.
+        #[arg(short, long)]
+        remove: Vec<Tag>,
+
+        /// A tag to make the main one, moving the components' files.
+        #[arg(short, long)]
+        main: Option<Tag>,
     },
 }
 
@@ -134,16 +676,84 @@ pub enum BackupAction {
     List,
 
     /// Create a new backup at this point in time.
-    Create,
+    Create {
+        /// A label to tell the backup apart, put into its name.
+        #[arg(short, long, default_value = "ondemand")]
+        tag: String,
+
+        /// Keep all the older backups instead of garbage-collecting them
+        /// afterwards.
+        #[arg(long)]
+        no_gc: bool,
+
+        /// How to store the backup, overriding the pack's settings.
+        #[arg(long = "as", value_name = "FORMAT")]
+        format: Option<BackupFormat>,
+    },
 
     /// Garbage-collect backups.
     Gc,
+
+    /// Keep creating and garbage-collecting backups on the schedule set in the
+    /// pack's settings, for running next to the server as a service.
+    Daemon {
+        /// Answer `/healthz` and `/readyz` probes over HTTP on this address,
+        /// like `127.0.0.1:9090`, for systemd or container health checks.
+        #[arg(long, value_name = "ADDRESS")]
+        health: Option<std::net::SocketAddr>,
+    },
+
+    /// Replace the server's data with a backup, stopping the server for the
+    /// swap and starting it again if it was running.
+    #[command(arg_required_else_help = true)]
+    Restore {
+        /// The sequential number of the backup, as shown by `backup list`.
+        seq_number: usize,
+    },
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum MirrorAction {
+    /// Download all of the pack's remote files into the mirror's directory.
+    Push {
+        /// The mirror's local directory, overriding the one in the settings.
+        #[arg(short, long)]
+        to: Option<PathBuf>,
+    },
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum MetaAction {
+    /// Fetch the lists of Minecraft and loader versions that setup and
+    /// retargeting verify versions against.
+    Refresh {
+        /// Minecraft versions to fetch the loader versions for. Defaults to
+        /// the pack's one.
+        #[arg(short, long)]
+        minecraft_versions: Vec<Version>,
+    },
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum CacheAction {
+    /// Remove all downloaded files and API responses from the cache.
+    Clear,
+}
+
+/// Orders of `component list`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ListSort {
+    Name,
+    Size,
+    Date,
 }
 
-#[derive(Debug, Default, Clone, PartialEq, Eq, clap::ValueEnum, strum::Display)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum, strum::Display)]
 #[strum(serialize_all = "kebab-case")]
 pub enum OutputFormat {
     #[default]
+    #[value(alias("table"))]
     Human,
     Yaml,
+    Json,
 }
@@ -1,132 +1,691 @@
 use crate::cli::{ComponentAction, Options, PackAction, Subcommand};
+use crate::report::{ComponentList, PackOverview};
+use crate::summary::{Failures, Summary, PARTIAL_FAILURE_EXIT_CODE};
 use clap::Parser;
-use cli::{BackupAction, OutputFormat, ServerAction};
+use cli::{
+    BackupAction, CacheAction, LifecycleAction, ListSort, MetaAction, MirrorAction, OutputFormat,
+    ServerAction, TagAction,
+};
 use color_eyre::eyre::Report;
 use color_eyre::owo_colors::OwoColorize;
-use color_eyre::Section;
+use color_eyre::{Section, SectionExt};
 use eyre::Context;
 use inquire::validator::{StringValidator, Validation};
-use invar::local_storage::{Error, PersistedEntity};
-use invar::server::docker_compose::DockerCompose;
-use invar::server::{backup, Server};
-use invar::{Component, Instance, Loader, Pack, Settings};
+use invar::component::{
+    DependencyGraph, DependencyKind, FileNaming, Filter, ListFormat, ModList, Outdated, Probe,
+    Source, Tag, UpdateReport,
+};
+use invar::history::{Event, History, Operation, Outcome};
+use invar::index::OverrideSide;
+use invar::local_storage::{self, Error, PersistedEntity};
+use invar::plan::{Action, GitSignature};
+use invar::server::backup::{self, Restore};
+use invar::server::bare::BareServer;
+use invar::server::bundle::{self, SecretsEncryption};
+use invar::server::docker_compose::{DockerCompose, DATA_VOLUME_PATH};
+use invar::server::rcon::{Rcon, RconSettings};
+use invar::server::{health, Server};
+use invar::{
+    budget, cache, network, plan, terminal, Availability, BackupFormat, BackupMode, Component,
+    Diagnosis, ExportFormat, ExportPreset, Import, Instance, KnownVersions, Loader, LocalComponent,
+    OptionalComponents, Pack, PackSize, Plan, Problem, Resolution, ServerBackend, Settings,
+    UnusedConfigs, VcsMode, VersionCache, VersionPart, WatchedFiles, EXPORT_DIR,
+};
+use itertools::Itertools;
 use semver::Version;
-use std::collections::HashSet;
+use std::collections::HashMap;
 use std::fmt::Write as FmtWrite;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
 use std::{fs, io};
 use strum::IntoEnumIterator;
 use tracing::{info, instrument, Level};
 
 mod cli;
+mod progress;
+mod report;
+mod summary;
 
 const DEFAULT_PACK_VERSION: Version = Version::new(0, 1, 0);
-const VERSION_WARNING: &str = "Version verification is not implemented, so entering a non-existent version may result in an unusable modpack.";
+/// The exit code of `component outdated` when there are newer versions.
+const STALE_EXIT_CODE: i32 = 1;
+const VERSION_WARNING: &str = "The known versions couldn't be fetched, so entering a non-existent version may result in an unusable modpack.";
+const VERSION_CHECKED: &str = "Checked against the versions listed upstream.";
 
 fn main() -> Result<(), Report> {
     let options = Options::parse();
+    terminal::set_interactive(!options.non_interactive);
+    if let Some(seconds) = options.time_budget {
+        budget::set(Duration::from_secs(seconds));
+    }
     color_eyre::install()?;
     install_tracing()?;
+    progress::install();
+    local_storage::set_read_only(options.read_only);
+    local_storage::set_fsync(options.fsync);
+    cache::http::set_enabled(!options.no_cache);
+    network::set_offline(options.offline);
+    if let Ok(pack) = Pack::read() {
+        network::configure(pack.settings.network);
+    }
+    if !options.read_only && local_storage::detect_read_only(Path::new(Pack::FILE_PATH)) {
+        info!("The pack can't be written to, running in read-only mode");
+        local_storage::set_read_only(true);
+    }
 
     let span = tracing::span!(Level::DEBUG, "invar");
     let _guard = span.enter();
 
     let status = run_with_options(options);
-    if let Err(mut report) = status {
-        if let Some(error) = report.downcast_ref::<Error>() {
-            match error {
-                Error::Io { .. } => {
-                    report = report
-                        .with_note(|| "Invar encountered an I/O error.")
-                        .with_suggestion(|| {
-                            "Ensure you're in the right directory and have enough permissions."
-                        });
-                }
-                Error::SerdeYml(_) | Error::SerdeJson(_) => {
-                    report = report
-                        .with_note(|| "Invar had an error while (de)serializing data with Serde.")
-                        .with_note(|| "This really shouldn't happen, something is real broken.")
-                        .with_suggestion(|| {
-                            format!("Consider reporting this at {}", env!("CARGO_PKG_HOMEPAGE"))
-                        });
-                }
-                Error::Walkdir(_) => {
-                    report = report
-                        .with_note(|| "Invar had an error while scanning modpack's files.")
-                        .with_note(|| "Most likely there isn't a modpack in this directory.")
-                        .with_suggestion(|| {
-                            "Ensure you're in the right directory and have enough permissions."
-                        });
-                }
-                Error::Zip(_) => {
-                    report = report
-                        .with_note(|| "Invar had an error while dealing with Zip archives.")
-                        .with_note(|| "This really shouldn't happen, something is real broken.")
-                        .with_suggestion(|| {
-                            format!("Consider reporting this at {}", env!("CARGO_PKG_HOMEPAGE"))
-                        });
-                }
+    if let Err(report) = status {
+        if let Some(failures) = report.downcast_ref::<Failures>() {
+            if failures.is_partial() {
+                eprintln!("{report:?}");
+                std::process::exit(PARTIAL_FAILURE_EXIT_CODE);
             }
         }
 
-        return Err(report);
+        return Err(explain(report));
     }
 
     Ok(())
 }
 
+/// Add notes and suggestions on how to deal with the error to `report`.
+fn explain(mut report: Report) -> Report {
+    let local_storage_error =
+        report
+            .downcast_ref::<Error>()
+            .or_else(|| match report.downcast_ref::<plan::Error>() {
+                Some(plan::Error::LocalStorage(error)) => Some(error),
+                _ => None,
+            });
+    if let Some(error) = local_storage_error {
+        match error {
+            Error::Io { .. } => {
+                report = report
+                    .with_note(|| "Invar encountered an I/O error.")
+                    .with_suggestion(|| {
+                        "Ensure you're in the right directory and have enough permissions."
+                    });
+            }
+            Error::SerdeYml(_) | Error::SerdeJson(_) => {
+                report = report
+                    .with_note(|| "Invar had an error while (de)serializing data with Serde.")
+                    .with_note(|| "This really shouldn't happen, something is real broken.")
+                    .with_suggestion(|| {
+                        format!("Consider reporting this at {}", env!("CARGO_PKG_HOMEPAGE"))
+                    });
+            }
+            Error::Walkdir(_) => {
+                report = report
+                    .with_note(|| "Invar had an error while scanning modpack's files.")
+                    .with_note(|| "Most likely there isn't a modpack in this directory.")
+                    .with_suggestion(|| {
+                        "Ensure you're in the right directory and have enough permissions."
+                    });
+            }
+            Error::Zip(_) => {
+                report = report
+                    .with_note(|| "Invar had an error while dealing with Zip archives.")
+                    .with_note(|| "This really shouldn't happen, something is real broken.")
+                    .with_suggestion(|| {
+                        format!("Consider reporting this at {}", env!("CARGO_PKG_HOMEPAGE"))
+                    });
+            }
+            Error::Pattern(_) => {
+                report = report.with_suggestion(|| {
+                    "Fix the glob in the local component's `path` in pack.yml."
+                });
+            }
+            Error::ReadOnly => {
+                report = report.with_suggestion(|| {
+                    format!(
+                        "Run without --read-only or {}, from a writable checkout",
+                        local_storage::READ_ONLY_VAR
+                    )
+                });
+            }
+            Error::UnsafePath { .. } => {
+                report = report.with_suggestion(|| {
+                    "Fix the component's `file_name`, `file_naming` or `runtime_subdir`."
+                });
+            }
+        }
+    }
+
+    if network::is_offline() {
+        report = report
+            .with_note(|| "Invar ran in offline mode, using only cached data.")
+            .with_suggestion(|| {
+                format!(
+                    "Run without --offline or {} once the network is reachable",
+                    network::OFFLINE_VAR
+                )
+            });
+    }
+
+    report
+}
+
 fn run_with_options(options: Options) -> Result<(), Report> {
+    let dry_run = DryRun {
+        enabled: options.dry_run,
+        output_format: options.output_format,
+        no_commit: options.no_commit,
+    };
     match options.subcommand {
-        Subcommand::Pack { action } => match action {
-            PackAction::Show => {
-                println!("{}", serde_yml::to_string(&Pack::read()?)?);
-                Ok(())
-            }
-            PackAction::Export => Ok(Pack::read()?.export()?),
-            PackAction::Setup {
-                name,
-                minecraft_version,
-                loader,
-                loader_version,
-                overwrite,
-            } => setup_pack(name, minecraft_version, loader, loader_version, overwrite),
-        },
-
-        Subcommand::Component { action } => match action {
-            ComponentAction::List => list_components(),
-            ComponentAction::Add { ids, show_metadata } => add_component(&ids, show_metadata),
-            ComponentAction::Remove { slugs } => remove_component(&slugs),
-            ComponentAction::Update { .. } => {
-                let error = eyre::eyre!("Updating components isn't yet implemented")
-                    .with_note(|| "This will be implemented in a future version of Invar.")
-                    .with_suggestion(|| "Remove and re-add this component to update it.");
-                Err(error)
-            }
-        },
+        Subcommand::Pack { action } => run_pack_action(action, dry_run),
+        Subcommand::Component { action } => run_component_action(action, dry_run),
 
         Subcommand::Server { ref action, .. } => match action {
-            ServerAction::Setup => DockerCompose::setup()
-                .map(|_| ())
-                .wrap_err("Failed to setup the server"),
-            ServerAction::Start => DockerCompose::read()?
-                .start()
-                .wrap_err("Failed to start the server"),
-            ServerAction::Stop => DockerCompose::read()?
-                .stop()
-                .wrap_err("Failed to stop the server"),
-            ServerAction::Status => {
-                let error = eyre::eyre!("Checking the status of the server isn't yet implemented")
-                    .with_note(|| "This will be implemented in a future version of Invar.")
-                    .with_suggestion(|| "`docker compose ps` may have what you need.");
-                Err(error)
-            }
+            ServerAction::Lifecycle(action) => match Pack::read()?.settings.server_backend {
+                ServerBackend::DockerCompose => {
+                    run_server::<DockerCompose>(action, options.output_format)
+                }
+                ServerBackend::Bare => run_server::<BareServer>(action, options.output_format),
+            },
+
+            ServerAction::Exec { command } => server_exec(&command.join(" ")),
+            ServerAction::SyncPlayers => sync_players(),
+
+            ServerAction::Bundle {
+                encrypt,
+                recipients,
+            } => bundle_server(*encrypt, recipients, dry_run),
 
             ServerAction::Backup { action } => match action {
                 BackupAction::List => backup_list(&options),
-                BackupAction::Create => backup_create(),
+                BackupAction::Create { tag, no_gc, format } => backup_create(tag, *no_gc, *format),
                 BackupAction::Gc => backup_gc(&options),
+                BackupAction::Daemon { health } => backup_daemon(*health),
+                BackupAction::Restore { seq_number } => {
+                    match Pack::read()?.settings.server_backend {
+                        ServerBackend::DockerCompose => {
+                            backup_restore::<DockerCompose>(*seq_number)
+                        }
+                        ServerBackend::Bare => backup_restore::<BareServer>(*seq_number),
+                    }
+                }
             },
         },
+
+        Subcommand::Mirror { action } => match action {
+            MirrorAction::Push { to } => mirror_push(to, dry_run),
+        },
+
+        Subcommand::Cache { action } => match action {
+            CacheAction::Clear => clear_cache(dry_run),
+        },
+
+        Subcommand::Meta { action } => match action {
+            MetaAction::Refresh { minecraft_versions } => {
+                refresh_metadata(minecraft_versions, dry_run)
+            }
+        },
+    }
+}
+
+/// Set up, start or stop the server with the `S` backend.
+fn run_server<S>(action: &LifecycleAction, output_format: OutputFormat) -> Result<(), Report>
+where
+    S: Server + PersistedEntity,
+    S::SetupError: std::error::Error + Send + Sync + 'static,
+    S::StartStopError: std::error::Error + Send + Sync + 'static,
+    S::StatusError: std::error::Error + Send + Sync + 'static,
+{
+    match action {
+        LifecycleAction::Setup { profile } => S::setup(profile.as_deref())
+            .map(|_| ())
+            .wrap_err("Failed to setup the server"),
+        LifecycleAction::Start => S::read()?.start().wrap_err("Failed to start the server"),
+        LifecycleAction::Stop => S::read()?.stop().wrap_err("Failed to stop the server"),
+        LifecycleAction::Status => {
+            let status = S::read()?
+                .status()
+                .wrap_err("Failed to check the status of the server")?;
+            report::print(&status, output_format)
+        }
+        LifecycleAction::Logs { follow } => S::read()?
+            .logs(*follow)
+            .wrap_err("Failed to show the server's logs"),
+    }
+}
+
+fn connect_rcon() -> Result<Rcon, Report> {
+    let settings = RconSettings::read(Path::new(DATA_VOLUME_PATH)).map_err(|error| {
+        Report::from(error)
+            .with_note(|| "RCON settings are read from the server's `server.properties`")
+            .with_suggestion(|| "Start the server at least once after setting it up")
+    })?;
+    Rcon::connect(&settings).wrap_err("Failed to connect to the server's RCON")
+}
+
+fn server_exec(command: &str) -> Result<(), Report> {
+    let output = connect_rcon()?
+        .command(command)
+        .wrap_err("Failed to run the command over RCON")?;
+    match output.trim() {
+        "" => info!("The command produced no output"),
+        output => println!("{output}"),
+    }
+    Ok(())
+}
+
+fn sync_players() -> Result<(), Report> {
+    let commands = Pack::read()?.settings.player_commands();
+    if commands.is_empty() {
+        info!("The pack's settings list no operators, whitelisted or banned players");
+        return Ok(());
+    }
+    let mut rcon = connect_rcon()?;
+    for command in &commands {
+        let output = rcon
+            .command(command)
+            .wrap_err_with(|| format!("Failed to run `{command}` over RCON"))?;
+        info!(command, output = output.trim(), "Applied");
+    }
+    Ok(())
+}
+
+fn run_pack_action(action: PackAction, dry_run: DryRun) -> Result<(), Report> {
+    match action {
+        PackAction::Show => show_pack(dry_run.output_format),
+        PackAction::Size { side } => report::print(
+            &PackSize::measure(&Pack::read()?, side)?,
+            dry_run.output_format,
+        ),
+        PackAction::Export {
+            skip_verify,
+            force,
+            format,
+            side,
+            upload,
+            public_url,
+            profile,
+            preset,
+            watch,
+            restart_server,
+        } => {
+            let export = || {
+                let (pack, options) = match &preset {
+                    Some(preset) => Pack::read()?.with_preset(preset)?,
+                    None => (
+                        Pack::read()?.with_profile(profile.as_deref())?,
+                        ExportPreset {
+                            format,
+                            side,
+                            upload: upload.clone(),
+                            public_url: public_url.clone(),
+                            ..ExportPreset::default()
+                        },
+                    ),
+                };
+                recorded(
+                    Operation::Export,
+                    vec![options.format.to_string(), options.side.to_string()],
+                    dry_run,
+                    || export_pack(&pack, &options, skip_verify, force, dry_run),
+                )
+            };
+            match watch {
+                true => watch_exports(export, restart_server),
+                false => export(),
+            }
+        }
+        PackAction::Import { path, overwrite } => import_pack(&path, overwrite, dry_run),
+        PackAction::Install {
+            target,
+            ask_optional,
+            with_optional,
+        } => install_pack(&target, ask_optional, with_optional, dry_run),
+        PackAction::Retarget {
+            minecraft_version,
+            loader_version,
+            yes,
+        } => retarget_pack(minecraft_version, loader_version, yes, dry_run),
+        PackAction::ImportDefaults { instance } => import_defaults(&instance, dry_run),
+        PackAction::Bump {
+            part,
+            no_git,
+            export,
+        } => bump_pack(part, no_git, export, dry_run),
+        PackAction::Rename { name } => rename_pack(&name, dry_run),
+        PackAction::History { operation, last } => {
+            pack_history(operation, last, dry_run.output_format)
+        }
+        PackAction::Doctor { update_hashes } => doctor_pack(update_hashes, dry_run),
+        PackAction::PruneConfigs => prune_configs(dry_run),
+        PackAction::Fetch => Pack::read()?
+            .fetch_includes()
+            .wrap_err("Failed to fetch the pack's includes"),
+        PackAction::Setup {
+            name,
+            minecraft_version,
+            loader,
+            loader_version,
+            overwrite,
+        } => setup_pack(
+            name,
+            minecraft_version,
+            loader,
+            loader_version,
+            overwrite,
+            dry_run,
+        ),
+    }
+}
+
+fn run_component_action(action: ComponentAction, dry_run: DryRun) -> Result<(), Report> {
+    match action {
+        ComponentAction::List {
+            category,
+            tag,
+            env,
+            source,
+            sort,
+            long,
+        } => {
+            let filters = [
+                category.map(Filter::Category),
+                tag.map(Filter::Tag),
+                env.map(Filter::Side),
+                source.map(Filter::Source),
+            ];
+            let filters = filters.into_iter().flatten().collect::<Vec<_>>();
+            list_components(&filters, sort, long, dry_run.output_format)
+        }
+        ComponentAction::Tree => component_tree(),
+        ComponentAction::RefreshMetadata => refresh_component_metadata(dry_run),
+        ComponentAction::ExportList { format, gallery } => export_list(format, gallery),
+        ComponentAction::Outdated { markdown } => {
+            outdated_components(markdown, dry_run.output_format)
+        }
+        ComponentAction::Probe { slug, source } => {
+            probe_component(&slug, source, dry_run.output_format)
+        }
+        ComponentAction::Add {
+            ids,
+            source,
+            show_metadata,
+            commit,
+        } => recorded(Operation::Add, ids.clone(), dry_run, || {
+            add_component(&ids, source, show_metadata, commit, dry_run)
+        }),
+        ComponentAction::Remove {
+            slugs,
+            cascade,
+            orphans,
+            force,
+        } => recorded(Operation::Remove, slugs.clone(), dry_run, || {
+            remove_component(&slugs, cascade, orphans, force, dry_run)
+        }),
+        ComponentAction::Replace { old, new, source } => recorded(
+            Operation::Replace,
+            vec![old.clone(), new.clone()],
+            dry_run,
+            || replace_component(&old, &new, source, dry_run),
+        ),
+        ComponentAction::Rename { old, new } => rename_component(&old, &new, dry_run),
+        ComponentAction::Side { slugs, side } => set_component_side(&slugs, side, dry_run),
+        ComponentAction::Naming { slug, naming } => set_file_naming(&slug, naming, dry_run),
+        ComponentAction::Group { slugs, group } => {
+            set_component_group(&slugs, group.as_deref(), dry_run)
+        }
+        ComponentAction::Tag {
+            action:
+                TagAction::Bulk {
+                    filters,
+                    add,
+                    remove,
+                    main,
+                },
+        } => bulk_tag_components(&filters, &add, &remove, main.as_ref(), dry_run),
+        ComponentAction::Move { slugs, to } => move_component(&slugs, &to, dry_run),
+        ComponentAction::AddLocal {
+            path,
+            target,
+            zip,
+            side,
+        } => add_local_component(path, target, zip, side, dry_run),
+        ComponentAction::CheckLocal { record } => check_local_components(record, dry_run),
+        ComponentAction::Update {
+            slugs,
+            all,
+            pick,
+            commit,
+            branch,
+            edit,
+        } => {
+            let parameters = match all {
+                true => vec!["--all".to_string()],
+                false => slugs.clone(),
+            };
+            let slugs = (!all).then_some(slugs.as_slice());
+            recorded(Operation::Update, parameters, dry_run, || {
+                update_components(slugs, pick, commit, branch, edit, dry_run)
+            })
+        }
+    }
+}
+
+/// Run `export` again whenever the pack's files change, restarting the server
+/// after each successful one if `restart_server` is set. Failed exports are
+/// reported without ending the watch.
+fn watch_exports(
+    export: impl Fn() -> Result<(), Report>,
+    restart_server: bool,
+) -> Result<(), Report> {
+    let mut watched = WatchedFiles::scan(&Pack::read()?)?;
+    let mut result = export();
+    loop {
+        match result {
+            Ok(()) if restart_server => Pack::read()
+                .map_err(Report::from)
+                .and_then(|pack| match pack.settings.server_backend {
+                    ServerBackend::DockerCompose => restart::<DockerCompose>(),
+                    ServerBackend::Bare => restart::<BareServer>(),
+                })
+                .unwrap_or_else(|report| tracing::error!("{report:#}")),
+            Ok(()) => {}
+            Err(report) => tracing::error!("{report:#}"),
+        }
+        info!("Watching the pack for changes");
+        let changed = loop {
+            std::thread::sleep(WatchedFiles::POLL_INTERVAL);
+            let Ok(pack) = Pack::read() else {
+                continue;
+            };
+            let scanned = match WatchedFiles::scan(&pack) {
+                Ok(scanned) => scanned,
+                Err(error) => {
+                    tracing::error!(%error, "Failed to scan the pack for changes");
+                    continue;
+                }
+            };
+            if let Some(path) = watched.changes(&scanned).first() {
+                let path = path.to_path_buf();
+                watched = scanned;
+                break path;
+            }
+        };
+        info!("{} changed, exporting again", changed.display().bold());
+        result = export();
+    }
+}
+
+/// Stop and start the server with the `S` backend.
+fn restart<S>() -> Result<(), Report>
+where
+    S: Server + PersistedEntity,
+    S::StartStopError: std::error::Error + Send + Sync + 'static,
+{
+    let server = S::read()?;
+    server.stop().wrap_err("Failed to stop the server")?;
+    server.start().wrap_err("Failed to start the server")
+}
+
+/// Run a mutating operation and record it in the pack's [`History`], unless
+/// it's a dry run or there is no pack here.
+fn recorded(
+    operation: Operation,
+    parameters: Vec<String>,
+    dry_run: DryRun,
+    run: impl FnOnce() -> Result<(), Report>,
+) -> Result<(), Report> {
+    let result = run();
+    if !dry_run.enabled && Path::new(Pack::FILE_PATH).exists() {
+        let outcome = match &result {
+            Ok(()) => Outcome::Succeeded,
+            Err(report) => Outcome::Failed(format!("{report:#}")),
+        };
+        if let Err(error) = Event::new(operation, parameters, outcome).record() {
+            tracing::warn!(%error, "Failed to record the operation in the history");
+        }
+    }
+    result
+}
+
+fn pack_history(
+    operation: Option<Operation>,
+    last: Option<usize>,
+    output_format: OutputFormat,
+) -> Result<(), Report> {
+    let mut history = History::read()?;
+    if let Some(operation) = operation {
+        history.events.retain(|event| event.operation == operation);
+    }
+    if let Some(last) = last {
+        let skipped = history.events.len().saturating_sub(last);
+        history.events.drain(..skipped);
+    }
+    report::print(&history, output_format)
+}
+
+fn mirror_push(target: Option<PathBuf>, dry_run: DryRun) -> Result<(), Report> {
+    let pack = Pack::read()?;
+    let configured = pack
+        .settings
+        .mirror
+        .as_ref()
+        .and_then(|mirror| mirror.local_path.clone());
+    let Some(target) = target.or(configured) else {
+        let error = eyre::eyre!("Don't know where to push the mirror's files")
+            .with_suggestion(|| "Pass `--to <DIR>` or set `settings.mirror.local_path`.");
+        return Err(error);
+    };
+
+    let plan = pack.plan_mirror_push(&target)?;
+    info!(
+        message = "Pushing files to the mirror",
+        count = plan.actions.len(),
+        target = ?target.yellow().bold()
+    );
+    dry_run
+        .run(&plan)
+        .wrap_err("Failed to push files to the mirror")
+}
+
+#[instrument(level = "debug", ret)]
+fn clear_cache(dry_run: DryRun) -> Result<(), Report> {
+    let plan = cache::plan_clear()?;
+    if plan.is_empty() {
+        info!("The cache is already empty");
+        return Ok(());
+    }
+    dry_run.run(&plan)
+}
+
+#[instrument(level = "debug", ret)]
+fn refresh_metadata(mut minecraft_versions: Vec<Version>, dry_run: DryRun) -> Result<(), Report> {
+    if minecraft_versions.is_empty() {
+        if let Ok(pack) = Pack::read() {
+            minecraft_versions.push(pack.instance.minecraft_version);
+        }
+    }
+    let cache = VersionCache::refresh(&minecraft_versions)
+        .wrap_err("Failed to fetch the version metadata")?;
+    dry_run.run(&cache.plan_write()?)?;
+    if !dry_run.enabled {
+        info!(
+            "Done. Cached {count} Minecraft versions and {lists} loader version lists",
+            count = cache
+                .minecraft
+                .as_ref()
+                .map_or(0, |known| known.versions.len()),
+            lists = cache.loaders.len(),
+        );
+    }
+    Ok(())
+}
+
+fn show_pack(output_format: OutputFormat) -> Result<(), Report> {
+    let overview = PackOverview {
+        pack: Pack::read()?,
+        metadata_refreshed_at: VersionCache::read_or_default().refreshed_at,
+    };
+    report::print(&overview, output_format)
+}
+
+fn doctor_pack(update_hashes: bool, dry_run: DryRun) -> Result<(), Report> {
+    let mut pack = Pack::read()?;
+    if update_hashes {
+        check_local_components(true, dry_run)?;
+        if !dry_run.enabled {
+            pack = Pack::read()?;
+        }
+    }
+    let diagnosis = Diagnosis::check(&pack, &Component::load_all()?, network::is_offline());
+    report::print(&diagnosis, dry_run.output_format)?;
+    match diagnosis.is_healthy() {
+        true => Ok(()),
+        false => Err(eyre::eyre!("The pack has problems")),
+    }
+}
+
+fn prune_configs(dry_run: DryRun) -> Result<(), Report> {
+    let unused = UnusedConfigs::find(&Component::load_all()?)?;
+    report::print(&unused, dry_run.output_format)?;
+    if unused.is_empty() || !terminal::is_interactive() {
+        return Ok(());
+    }
+
+    let options = unused
+        .paths
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect();
+    let picked = terminal::suspend(|| {
+        inquire::MultiSelect::new("Delete which of them?", options)
+            .with_help_message("Nothing is deleted if none are picked.")
+            .prompt_skippable()
+    })?
+    .unwrap_or_default();
+    let picked = picked.into_iter().map(PathBuf::from).collect_vec();
+    let mut plan = Plan::new();
+    for path in UnusedConfigs::files(&picked)? {
+        plan.push(Action::RemoveFile { path });
+    }
+    dry_run.run(&plan)
+}
+
+fn outdated_components(markdown: bool, output_format: OutputFormat) -> Result<(), Report> {
+    let instance = Pack::read()?.instance;
+    let outdated = Outdated::check(&Component::load_all()?, &instance);
+    match (markdown, output_format) {
+        (true, _) => print!("{}", outdated.to_markdown()),
+        (false, OutputFormat::Human) => print!("{outdated}"),
+        (false, OutputFormat::Yaml) => print!("{}", serde_yml::to_string(&outdated)?),
+        (false, OutputFormat::Json) => {
+            println!("{}", serde_json::to_string_pretty(&outdated)?);
+        }
     }
+    if outdated.is_stale() {
+        std::process::exit(STALE_EXIT_CODE);
+    }
+    Ok(())
 }
 
 fn backup_list(options: &Options) -> Result<(), Report> {
@@ -140,12 +699,157 @@ fn backup_list(options: &Options) -> Result<(), Report> {
         OutputFormat::Yaml => {
             println!("{}", serde_yml::to_string(&backups)?);
         }
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&backups)?);
+        }
     };
     Ok(())
 }
 
-fn backup_create() -> Result<(), Report> {
-    backup::create_new(Some("ondemand"))?;
+fn backup_create(tag: &str, no_gc: bool, format: Option<BackupFormat>) -> Result<(), Report> {
+    let backup = match format {
+        Some(format) => backup::create_new_as(Some(tag), format),
+        None => backup::create_new(Some(tag)),
+    }
+    .wrap_err("Failed to create a backup")?;
+    info!("Created {backup}");
+    if !no_gc {
+        let gc_result = backup::gc().wrap_err("Failed to garbage-collect backups")?;
+        if !gc_result.removed.is_empty() {
+            info!("Garbage-collected {} old backups", gc_result.removed.len());
+        }
+    }
+    Ok(())
+}
+
+/// Create and garbage-collect backups on the pack's schedule until killed.
+fn backup_daemon(health: Option<SocketAddr>) -> Result<(), Report> {
+    let readiness = Arc::new(Mutex::new(DaemonReadiness::default()));
+    if let Some(address) = health {
+        let readiness = Arc::clone(&readiness);
+        let address = health::serve(address, move |probe| match probe {
+            health::Probe::Health => Pack::read()
+                .map(|_| cache_freshness())
+                .map_err(|error| format!("Failed to read the pack: {error}")),
+            health::Probe::Readiness => readiness
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .check()
+                .map(|()| "ok".to_string()),
+        })
+        .wrap_err("Failed to serve the health probes")?;
+        info!("Answering health probes at http://{address}");
+    }
+    let readiness = || readiness.lock().unwrap_or_else(PoisonError::into_inner);
+
+    loop {
+        // NOTE: Read the settings every time so that changes are picked up
+        // without restarting.
+        let BackupMode::Scheduled { schedule, .. } = Pack::read()?.settings.backup_mode else {
+            return Err(eyre::eyre!("The pack's backups aren't scheduled")
+                .with_suggestion(|| "Set `settings.backup_mode` to `!scheduled`."));
+        };
+        let Some(next) = schedule.upcoming(chrono::Local).next() else {
+            info!("No more backups are scheduled");
+            return Ok(());
+        };
+        readiness().scheduled = true;
+        info!("Next backup at {}", next.format("%d/%m/%Y %H:%M:%S").bold());
+        std::thread::sleep((next - chrono::Local::now()).to_std().unwrap_or_default());
+
+        match backup::create_new(Some("scheduled")) {
+            Ok(backup) => {
+                info!("Created {backup}");
+                readiness().last_failure = None;
+            }
+            Err(error) => {
+                tracing::error!(%error, "Failed to create a scheduled backup");
+                readiness().last_failure =
+                    Some(format!("The last scheduled backup failed: {error}"));
+                continue;
+            }
+        }
+        match backup::gc() {
+            Ok(gc_result) if !gc_result.removed.is_empty() => {
+                info!("Garbage-collected {} old backups", gc_result.removed.len());
+            }
+            Ok(_) => {}
+            Err(error) => tracing::error!(%error, "Failed to garbage-collect backups"),
+        }
+    }
+}
+
+/// What `/healthz` reports about the pack's cached version metadata.
+///
+/// It only informs, the daemon never refreshes the metadata and has no use
+/// for it, so stale metadata doesn't fail the probe.
+fn cache_freshness() -> String {
+    match VersionCache::read_or_default().refreshed_at {
+        Some(at) => format!("ok, version metadata refreshed at {}", at.to_rfc3339()),
+        None => "ok, version metadata was never refreshed".to_string(),
+    }
+}
+
+/// What `/readyz` reports for the [backup daemon](backup_daemon).
+#[derive(Debug, Default)]
+struct DaemonReadiness {
+    /// Whether the next backup is scheduled.
+    scheduled: bool,
+    /// Why the last backup failed, if it did.
+    last_failure: Option<String>,
+}
+
+impl DaemonReadiness {
+    fn check(&self) -> Result<(), String> {
+        match (&self.last_failure, self.scheduled) {
+            (Some(failure), _) => Err(failure.clone()),
+            (None, false) => Err("No backup is scheduled yet".to_string()),
+            (None, true) => Ok(()),
+        }
+    }
+}
+
+/// Restore the backup with this `seq_number`, stopping the server with the `S`
+/// backend while its data is swapped.
+fn backup_restore<S>(seq_number: usize) -> Result<(), Report>
+where
+    S: Server + PersistedEntity,
+    S::StartStopError: std::error::Error + Send + Sync + 'static,
+    S::StatusError: std::error::Error + Send + Sync + 'static,
+{
+    let restore = Restore::prepare(seq_number).wrap_err("Failed to prepare the backup")?;
+    let server = S::read()?;
+    let running = server
+        .status()
+        .wrap_err("Failed to check the status of the server")?
+        .running;
+    if running {
+        // NOTE: Stopping the server backs up the data about to be replaced.
+        server.stop().wrap_err("Failed to stop the server")?;
+    } else if let Some(backup) =
+        backup::create_safety("restore").wrap_err("Failed to back up the server")?
+    {
+        info!("Created {backup}");
+    }
+    let backup = restore.apply().wrap_err("Failed to restore the backup")?;
+    info!("Restored {backup}");
+    if running {
+        server.start().wrap_err("Failed to start the server")?;
+    }
+    Ok(())
+}
+
+/// Back up the server before an `operation` that can damage its world, see
+/// [`backup::create_safety`].
+fn safety_backup(operation: &str, dry_run: DryRun) -> Result<(), Report> {
+    if dry_run.enabled {
+        return Ok(());
+    }
+    if let Some(backup) =
+        backup::create_safety(operation).wrap_err("Failed to back up the server")?
+    {
+        info!("Created {backup}");
+    }
     Ok(())
 }
 
@@ -153,6 +857,7 @@ fn backup_gc(options: &Options) -> Result<(), Report> {
     let gc_result = backup::gc().wrap_err("Failed to garbage-collect backups")?;
     match options.output_format {
         OutputFormat::Yaml => println!("{}", serde_yml::to_string(&gc_result)?),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&gc_result)?),
         OutputFormat::Human => {
             if gc_result.removed.is_empty() {
                 println!("All backups are fresh enough to keep.");
@@ -178,61 +883,67 @@ fn setup_pack(
     mut loader: Option<Loader>,
     mut loader_version: Option<Version>,
     overwrite: bool,
+    dry_run: DryRun,
 ) -> Result<(), Report> {
     if !overwrite && fs::exists(<Pack as PersistedEntity>::FILE_PATH).is_ok_and(|exists| exists) {
-        let confirmed = inquire::Confirm::new(
+        if !terminal::is_interactive() {
+            return Err(eyre::eyre!("A pack already exists in this directory")
+                .suggestion("Pass --overwrite to replace it"));
+        }
+        let confirmed = terminal::suspend(|| inquire::Confirm::new(
             "A pack already exists in this directory, are you sure you wish to overwrite it with a new one?",
         )
         .with_placeholder("yeo")
-        .prompt()
+        .prompt())
         .unwrap_or(false);
 
         if !confirmed {
             std::process::exit(0);
         }
     }
-    let name = name.take().unwrap_or_else(|| {
-        inquire::Text::new("Modpack name:")
-            .with_validator(non_empty_validator("Please enter a non-empty name"))
-            .prompt()
-            .unwrap()
-            .trim()
-            .to_string()
-    });
-    let minecraft_version = minecraft_version.take().unwrap_or_else(|| {
-        inquire::CustomType::new("Minecraft version:")
-            .with_placeholder("X.X.X")
-            .with_help_message(VERSION_WARNING)
-            .with_error_message("That's not a valid semantic version.")
-            .prompt()
-            .unwrap()
-    });
-    let loader = loader.take().unwrap_or_else(|| {
-        inquire::Select::new("Modloader:", Loader::iter().collect::<Vec<_>>())
-            .prompt()
-            .unwrap()
-    });
-    let loader_version = match loader {
-        Loader::Minecraft => minecraft_version.clone(),
-        _ => loader_version.take().unwrap_or_else(|| {
-            inquire::CustomType::new("Modloader version:")
-                .with_placeholder("X.X.X")
-                .with_help_message(VERSION_WARNING)
-                .with_error_message("That's not a valid semantic version.")
+    let name = match name.take() {
+        Some(name) => name,
+        None => terminal::suspend(|| {
+            inquire::Text::new("Modpack name:")
+                .with_validator(non_empty_validator("Please enter a non-empty name"))
                 .prompt()
-                .unwrap()
-        }),
+        })
+        .wrap_err("Failed to get the modpack name")?
+        .trim()
+        .to_string(),
     };
-    let mut allowed_foreign_loaders = HashSet::from_iter([Loader::Minecraft]);
-    if loader == Loader::Forge || loader == Loader::Neoforge {
-        // Neoforge should be compatible with Forge mods.
-        allowed_foreign_loaders.extend([Loader::Forge, Loader::Neoforge]);
-        allowed_foreign_loaders.remove(&loader);
-    }
-    if loader == Loader::Quilt {
-        // Quilt should be compatible with Fabric mods.
-        allowed_foreign_loaders.insert(Loader::Fabric);
-    }
+    let cache = VersionCache::read_or_default();
+    let known = fetch_known_versions("Minecraft", cache.minecraft.as_ref(), || {
+        KnownVersions::minecraft().map(Some)
+    });
+    let minecraft_version = match minecraft_version.take() {
+        Some(version) => verify_version("Minecraft", version, known.as_ref())?,
+        None => ask_version("Minecraft version:", known.as_ref(), None)
+            .wrap_err("Failed to get the Minecraft version")?,
+    };
+    let loader = match loader.take() {
+        Some(loader) => loader,
+        None => terminal::suspend(|| {
+            inquire::Select::new("Modloader:", Loader::iter().collect::<Vec<_>>()).prompt()
+        })
+        .wrap_err("Failed to get the modloader")?,
+    };
+    let loader_version = match (loader, loader_version.take()) {
+        (Loader::Minecraft, _) => minecraft_version.clone(),
+        (_, version) => {
+            let known = fetch_known_versions(
+                &loader.to_string(),
+                cache.loader(loader, &minecraft_version),
+                || KnownVersions::loader(loader, &minecraft_version),
+            );
+            match version {
+                Some(version) => verify_version(&loader.to_string(), version, known.as_ref())?,
+                None => pick_version("Modloader version:", known.as_ref(), None)
+                    .wrap_err("Failed to get the modloader version")?,
+            }
+        }
+    };
+    let allowed_foreign_loaders = loader.default_foreign_loaders();
     let pack = Pack {
         name,
         version: DEFAULT_PACK_VERSION,
@@ -242,11 +953,27 @@ fn setup_pack(
             loader,
             loader_version,
             allowed_foreign_loaders, // None by default.
+            release_channel: None,
         },
         settings: Settings::default(),
+        includes: vec![],
+        local_components: vec![],
     };
-    pack.write()?;
-    Pack::setup_directories()?;
+    let mut plan = pack.plan_setup()?;
+    if !Path::new(".git").exists() {
+        plan.push(Action::GitInit);
+        if pack.settings.vcs_mode == VcsMode::TrackComponents {
+            plan.push(Action::GitCommit {
+                message: format!("Set up {}", pack.name),
+                paths: vec![],
+                author: commit_author(&pack.settings)?,
+            });
+        }
+    }
+    dry_run.run(&plan)?;
+    if dry_run.enabled {
+        return Ok(());
+    }
     info!(
         "Done. Check out `{pack_file}` for more options.",
         pack_file = Pack::FILE_PATH
@@ -254,80 +981,1456 @@ fn setup_pack(
     Ok(())
 }
 
-#[instrument(level = "debug", ret)]
-fn remove_component(slugs: &[String]) -> Result<(), Report> {
-    for slug in slugs {
-        Component::remove(slug).wrap_err(format!("Failed to remove the {slug:?} component"))?;
+/// Take the versions known to exist from the [`VersionCache`], or `fetch`
+/// them if they aren't cached, carrying on without verifying versions if they
+/// couldn't be fetched.
+fn fetch_known_versions(
+    what: &str,
+    cached: Option<&KnownVersions>,
+    fetch: impl FnOnce() -> Result<Option<KnownVersions>, network::Error>,
+) -> Option<KnownVersions> {
+    if let Some(cached) = cached {
+        return Some(cached.clone());
     }
+    fetch().unwrap_or_else(|error| {
+        tracing::warn!(%error, "Failed to fetch the known {what} versions, they won't be verified");
+        None
+    })
+}
 
-    Ok(())
+/// Make sure a version passed on the command line is one of the `known` ones.
+fn verify_version(
+    what: &str,
+    version: Version,
+    known: Option<&KnownVersions>,
+) -> Result<Version, Report> {
+    match known {
+        Some(known) if !known.contains(&version) => {
+            let error = eyre::eyre!("There is no {what} {version}")
+                .note("The known versions may be cached, `invar meta refresh` updates them");
+            Err(match known.latest() {
+                Some(latest) => error.suggestion(format!("The latest one is {latest}")),
+                None => error.note(format!("No {what} versions are known for this pack")),
+            })
+        }
+        _ => Ok(version),
+    }
+}
+
+/// Let the user pick one of the `known` versions, starting at `default` or the
+/// latest one. Without prompts, the latest one is taken.
+///
+/// Falls back to [asking](ask_version) for any version if the known ones
+/// couldn't be fetched.
+fn pick_version(
+    message: &str,
+    known: Option<&KnownVersions>,
+    default: Option<&Version>,
+) -> Result<Version, Report> {
+    let Some(known) = known else {
+        return ask_version(message, None, default);
+    };
+    let Some(latest) = known.latest() else {
+        return Err(eyre::eyre!("No versions are available for this pack")
+            .suggestion("Check that the loader supports the Minecraft version"));
+    };
+    if !terminal::is_interactive() {
+        return Ok(latest.clone());
+    }
+    let starting_cursor = known
+        .versions
+        .iter()
+        .position(|version| Some(version) == default)
+        .or_else(|| known.versions.iter().position(|version| version == latest))
+        .unwrap_or_default();
+    let version = terminal::suspend(|| {
+        inquire::Select::new(message, known.versions.clone())
+            .with_starting_cursor(starting_cursor)
+            .with_help_message(VERSION_CHECKED)
+            .prompt()
+    })?;
+    Ok(version)
+}
+
+/// Ask for a version, only accepting the `known` ones if they're available.
+fn ask_version(
+    message: &str,
+    known: Option<&KnownVersions>,
+    default: Option<&Version>,
+) -> Result<Version, Report> {
+    let placeholder = known
+        .and_then(KnownVersions::latest)
+        .map_or_else(|| "X.X.X".to_string(), Version::to_string);
+    let validator_known = known.cloned();
+    let version = terminal::suspend(|| {
+        let known = validator_known.clone();
+        let mut prompt = inquire::CustomType::new(message)
+            .with_placeholder(&placeholder)
+            .with_help_message(known.as_ref().map_or(VERSION_WARNING, |_| VERSION_CHECKED))
+            .with_error_message("That's not a valid semantic version.")
+            .with_validator(move |version: &Version| {
+                Ok(match &known {
+                    Some(known) if !known.contains(version) => {
+                        Validation::Invalid("No such version was released.".into())
+                    }
+                    _ => Validation::Valid,
+                })
+            });
+        if let Some(default) = default {
+            prompt = prompt.with_default(default.clone());
+        }
+        prompt.prompt()
+    })?;
+    Ok(version)
 }
 
+/// Export `pack` in the format, for the side and to the upload destination of
+/// `options`. The rest of the preset is expected to be applied to `pack`
+/// already.
 #[instrument(level = "debug", ret)]
-fn add_component(ids: &[String], show_metadata: bool) -> Result<(), Report> {
-    let instance = Pack::read()?.instance;
-    for id in ids {
-        let component = Component::fetch_from_modrinth(id, &instance).wrap_err(format!(
-            "Failed to fetch the {id:?} component from Modrinth"
-        ))?;
-
-        info!(message = "Adding:", slug = ?id, file_name = ?component.file_name.yellow().bold());
-        if show_metadata {
-            let yaml = serde_yml::to_string(&component)
-                .wrap_err("Failed to serialize the component's metadata")?
-                .lines()
-                .fold(String::new(), |mut acc, line| {
-                    let _ = writeln!(acc, "{prefix} {line}", prefix = "|>".yellow().bold());
-                    acc
-                });
-            info!(message = "Writing metadata,", path = ?component.local_storage_path().yellow().bold());
-            print!("{yaml}");
+fn export_pack(
+    pack: &Pack,
+    options: &ExportPreset,
+    skip_verify: bool,
+    force: bool,
+    dry_run: DryRun,
+) -> Result<(), Report> {
+    let ExportPreset {
+        format,
+        side,
+        upload,
+        public_url,
+        ..
+    } = options;
+    let (format, side) = (*format, *side);
+    if format == ExportFormat::Curseforge && side == OverrideSide::Server {
+        return Err(
+            eyre::eyre!("CurseForge packs can't be exported for servers")
+                .suggestion("Export a server `.mrpack` with `--as mrpack` instead"),
+        );
+    }
+    let components = pack.components()?;
+    if !force {
+        let mut diagnosis = Diagnosis::check(pack, &components, true);
+        // NOTE: Changed local components are only warned about on export, as
+        // they're meant to be edited.
+        diagnosis
+            .problems
+            .retain(|problem| !matches!(problem, Problem::ChangedLocalComponent { .. }));
+        if !diagnosis.is_healthy() {
+            let problems = diagnosis.problems.iter().join("\n");
+            return Err(eyre::eyre!("Refusing to export a pack with problems")
+                .section(problems.header("Problems:"))
+                .suggestion("Pass `--force` to export anyway"));
         }
+    }
+    if !dry_run.enabled {
+        pack.fetch_includes()
+            .wrap_err("Failed to fetch the pack's includes")?;
+    }
+    if !skip_verify && !dry_run.enabled {
+        invar::component::verify_all(&components)
+            .wrap_err("Refusing to export a pack with bad hashes")
+            .suggestion("Pass `--skip-verify` to export anyway")?;
+    }
+    let plan = match format {
+        ExportFormat::Mrpack => pack.plan_export(side)?,
+        ExportFormat::Curseforge => pack.plan_export_curseforge()?,
+    };
+    dry_run.run(&plan)?;
 
-        component
-            .save_to_metadata_dir()
-            .wrap_err("Failed to save component's metadata")?;
+    let Some(destination) = upload else {
+        return Ok(());
+    };
+    if dry_run.enabled {
+        info!("Would upload the artifact to {destination}");
+        return Ok(());
+    }
+    let latest = match format {
+        ExportFormat::Mrpack => pack.side_export_path(side),
+        ExportFormat::Curseforge => pack.export_path(format),
+    };
+    let artifact = latest
+        .canonicalize()
+        .wrap_err("Failed to find the exported artifact")?;
+    info!("Uploading the artifact to {destination}");
+    let url = destination
+        .upload(&artifact, public_url.as_ref())
+        .wrap_err("Failed to upload the artifact")?;
+    println!("{url}");
+    Ok(())
+}
+
+#[instrument(level = "debug", ret)]
+fn bundle_server(
+    encryption: Option<SecretsEncryption>,
+    recipients: &[String],
+    dry_run: DryRun,
+) -> Result<(), Report> {
+    let pack = Pack::read()?;
+    let (path, plan) = bundle::plan_bundle(&pack, encryption, recipients)
+        .wrap_err("Failed to bundle the server")?;
+    dry_run.run(&plan)?;
+    if !dry_run.enabled {
+        info!("Done. The bundle is at {}", path.display());
     }
+    Ok(())
+}
 
+#[instrument(level = "debug", ret)]
+fn rename_pack(name: &str, dry_run: DryRun) -> Result<(), Report> {
+    let pack = Pack::read()?;
+    let (renamed, mut plan) = pack
+        .plan_rename(name)
+        .wrap_err("Failed to plan the rename")?;
+    if Path::new(DockerCompose::FILE_PATH).exists() {
+        let compose = DockerCompose::read()?.renamed(&pack.name, &renamed.name);
+        plan.push(Action::write_entity(
+            DockerCompose::FILE_PATH,
+            serde_yml::to_string(&compose)?,
+        ));
+    }
+    dry_run.track(
+        &mut plan,
+        &format!("rename {} to {}", pack.name, renamed.name),
+    )?;
+    dry_run.run(&plan)?;
+    if !dry_run.enabled {
+        info!("Renamed {} to {}", pack.name, renamed.name);
+        if Path::new(DockerCompose::FILE_PATH).exists() {
+            info!(
+                "Recreate the server's container with `invar server stop` and `invar server start`"
+            );
+        }
+    }
     Ok(())
 }
 
 #[instrument(level = "debug", ret)]
-fn list_components() -> Result<(), Report> {
-    let components = invar::Component::load_all()?;
-    for c in &components {
-        println!(
-            "{type}: {prefix}{slug} [{version}]",
-            type = c.category,
-            slug = c.slug.yellow().bold(),
-            version = c.file_name.bold(),
-            prefix = match &c.tags.main {
-                Some(tag) => format!("{tag}/"),
-                None => String::new(),
-            }
-            .bright_yellow()
-            .bold(),
+fn bump_pack(part: VersionPart, no_git: bool, export: bool, dry_run: DryRun) -> Result<(), Report> {
+    let pack = Pack::read()?;
+    let author = match no_git {
+        true => None,
+        false => commit_author(&pack.settings)?,
+    };
+    let (bumped, plan) = pack.plan_bump(part, no_git, author)?;
+    dry_run.run(&plan)?;
+    if export && !dry_run.enabled {
+        recorded(
+            Operation::Export,
+            vec![
+                ExportFormat::Mrpack.to_string(),
+                OverrideSide::Both.to_string(),
+            ],
+            dry_run,
+            || export_pack(&bumped, &ExportPreset::default(), false, false, dry_run),
+        )?;
+    }
+    if !dry_run.enabled {
+        info!(
+            "Done. {name} is now at version {version}",
+            name = bumped.name,
+            version = bumped.version
         );
     }
-    println!(
-        "{count} components in total.",
-        count = components.len().red().bold()
-    );
     Ok(())
 }
 
-fn install_tracing() -> Result<(), Report> {
-    use tracing_error::ErrorLayer;
-    use tracing_subscriber::prelude::*;
-    use tracing_subscriber::{fmt, EnvFilter};
-    let format_layer = fmt::layer().pretty().without_time().with_writer(io::stderr);
-    let filter_layer = EnvFilter::try_from_default_env().or_else(|_| EnvFilter::try_new("info"))?;
-    tracing_subscriber::registry()
-        .with(filter_layer)
-        .with(format_layer)
-        .with(ErrorLayer::default())
-        .try_init()?;
-    Ok(())
+#[instrument(level = "debug", ret)]
+fn import_defaults(instance: &Path, dry_run: DryRun) -> Result<(), Report> {
+    let mut plan = Pack::plan_import_defaults(instance);
+    if plan.is_empty() {
+        tracing::warn!(
+            "No option files found in {instance}",
+            instance = instance.display()
+        );
+    }
+    dry_run.track(
+        &mut plan,
+        &format!("import default options from {}", instance.display()),
+    )?;
+    dry_run.run(&plan)
+}
+
+#[instrument(level = "debug", ret)]
+fn add_local_component(
+    path: PathBuf,
+    target: Option<PathBuf>,
+    zip: bool,
+    side: OverrideSide,
+    dry_run: DryRun,
+) -> Result<(), Report> {
+    let mut pack = Pack::read()?;
+    if pack.local_components.iter().any(|local| local.path == path) {
+        return Err(eyre::eyre!(
+            "{} is already a local component",
+            path.display()
+        ));
+    }
+    let mut local = LocalComponent {
+        path,
+        target,
+        zip,
+        hash: None,
+        environment: side.env(),
+    };
+    if !local.base().exists() {
+        return Err(eyre::eyre!("{} doesn't exist", local.base().display()));
+    }
+    local.hash = Some(local.tree_hash()?);
+    let mut plan = Plan::new();
+    let world = pack.settings.world_name();
+    if let Some(component) = Component::load_all()?
+        .into_iter()
+        .find(|component| local.collides_with(component, world))
+    {
+        let collision = format!(
+            "{} collides with the {} component",
+            local.path.display(),
+            component.slug
+        );
+        match Resolution::prompt(&format!("{collision}, what to do?"))? {
+            Resolution::Replace => {
+                plan.extend(Component::plan_remove(&component.slug)?);
+            }
+            Resolution::KeepBoth => {
+                let target = local.target();
+                let default = target.with_file_name(format!(
+                    "local-{}",
+                    target.file_name().unwrap_or_default().to_string_lossy()
+                ));
+                let target = terminal::suspend(|| {
+                    inquire::Text::new("Where should the new component end up?")
+                        .with_default(&default.to_string_lossy())
+                        .prompt()
+                })?;
+                local.target = Some(PathBuf::from(target));
+            }
+            Resolution::Abort => {
+                return Err(eyre::eyre!(collision).suggestion(
+                    "Resolve the collision interactively, or pass `--target` to place it elsewhere",
+                ))
+            }
+        }
+    }
+    let message = format!("add the local component {}", local.path.display());
+    pack.local_components.push(local);
+    plan.push(Action::write_entity(
+        <Pack as PersistedEntity>::FILE_PATH,
+        serde_yml::to_string(&pack)?,
+    ));
+    dry_run.track(&mut plan, &message)?;
+    dry_run.run(&plan)
+}
+
+#[instrument(level = "debug", ret)]
+fn check_local_components(record: bool, dry_run: DryRun) -> Result<(), Report> {
+    let mut pack = Pack::read()?;
+    let mut drifted = 0;
+    for local in &mut pack.local_components {
+        let hash = local.tree_hash()?;
+        if local.hash.as_ref() != Some(&hash) {
+            drifted += 1;
+            tracing::warn!(path = %local.path.display(), "Changed since its hash was recorded");
+        }
+        local.hash = Some(hash);
+    }
+    if drifted == 0 {
+        tracing::info!("All local components are unchanged");
+    } else if record {
+        let mut plan = Plan::new();
+        plan.push(Action::write_entity(
+            <Pack as PersistedEntity>::FILE_PATH,
+            serde_yml::to_string(&pack)?,
+        ));
+        dry_run.track(&mut plan, "record the hashes of local components")?;
+        dry_run.run(&plan)?;
+    }
+    Ok(())
+}
+
+#[instrument(level = "debug", ret)]
+fn import_pack(path: &Path, overwrite: bool, dry_run: DryRun) -> Result<(), Report> {
+    if !overwrite && fs::exists(<Pack as PersistedEntity>::FILE_PATH).is_ok_and(|exists| exists) {
+        if !terminal::is_interactive() {
+            return Err(eyre::eyre!("A pack already exists in this directory")
+                .suggestion("Pass --overwrite to replace it"));
+        }
+        let confirmed = terminal::suspend(|| inquire::Confirm::new(
+            "A pack already exists in this directory, are you sure you wish to overwrite it with the imported one?",
+        )
+        .with_placeholder("yeo")
+        .prompt())
+        .unwrap_or(false);
+
+        if !confirmed {
+            std::process::exit(0);
+        }
+    }
+
+    let import = Import::read(path).wrap_err("Failed to read the archive")?;
+    for (path, reason) in &import.skipped {
+        tracing::warn!(path = %path.display(), reason, "Skipped a file");
+    }
+    dry_run.run(&import.plan()?)?;
+    if !dry_run.enabled {
+        info!(
+            "Done. Imported {name} with {components} components and {overrides} override files",
+            name = import.pack.name,
+            components = import.components.len(),
+            overrides = import.overrides.len(),
+        );
+    }
+    Ok(())
+}
+
+#[instrument(level = "debug", ret)]
+fn retarget_pack(
+    minecraft_version: Version,
+    loader_version: Option<Version>,
+    yes: bool,
+    dry_run: DryRun,
+) -> Result<(), Report> {
+    let pack = Pack::read()?;
+    let cache = VersionCache::read_or_default();
+    let known = fetch_known_versions("Minecraft", cache.minecraft.as_ref(), || {
+        KnownVersions::minecraft().map(Some)
+    });
+    let minecraft_version = verify_version("Minecraft", minecraft_version, known.as_ref())?;
+    let loader = pack.instance.loader;
+    let loader_version = match loader {
+        Loader::Minecraft => minecraft_version.clone(),
+        _ => {
+            let known = fetch_known_versions(
+                &loader.to_string(),
+                cache.loader(loader, &minecraft_version),
+                || KnownVersions::loader(loader, &minecraft_version),
+            );
+            match loader_version {
+                Some(version) => verify_version(&loader.to_string(), version, known.as_ref())?,
+                None => pick_version(
+                    "Modloader version:",
+                    known.as_ref(),
+                    Some(&pack.instance.loader_version),
+                )?,
+            }
+        }
+    };
+    let instance = Instance {
+        minecraft_version,
+        loader_version,
+        ..pack.instance.clone()
+    };
+
+    info!(
+        "Checking components for Minecraft {version}...",
+        version = instance.minecraft_version
+    );
+    let retarget = pack.check_retarget(instance)?;
+    for (component, availability) in &retarget.components {
+        match availability {
+            Availability::Unchanged => {
+                println!(
+                    "{} {} {}",
+                    "=".green().bold(),
+                    component.slug.bold(),
+                    component.file_name
+                );
+            }
+            Availability::Updated(update) => println!(
+                "{} {} {} -> {}",
+                "+".green().bold(),
+                component.slug.bold(),
+                component.file_name,
+                update.file_name
+            ),
+            Availability::Unavailable(error) => {
+                println!(
+                    "{} {} {}",
+                    "!".red().bold(),
+                    component.slug.bold(),
+                    error.red()
+                );
+            }
+        }
+    }
+
+    let unavailable = retarget.unavailable().count();
+    if unavailable > 0 && !yes && !dry_run.enabled {
+        let confirmed = terminal::suspend(|| {
+            inquire::Confirm::new(&format!(
+                "{unavailable} components are unavailable and will be removed, continue?"
+            ))
+            .with_default(false)
+            .prompt()
+        })?;
+        if !confirmed {
+            return Ok(());
+        }
+    }
+
+    safety_backup("retarget", dry_run)?;
+    let mut plan = retarget.plan(&pack)?;
+    dry_run.track(
+        &mut plan,
+        &format!(
+            "retarget to Minecraft {}",
+            retarget.instance.minecraft_version
+        ),
+    )?;
+    dry_run.run(&plan)?;
+    if !dry_run.enabled {
+        info!(
+            "Done. {name} now targets Minecraft {version}",
+            name = pack.name,
+            version = retarget.instance.minecraft_version
+        );
+    }
+    Ok(())
+}
+
+#[instrument(level = "debug", ret)]
+fn install_pack(
+    target: &Path,
+    ask_optional: bool,
+    with_optional: bool,
+    dry_run: DryRun,
+) -> Result<(), Report> {
+    let pack = Pack::read()?;
+    let components = Component::load_all()?;
+    let manifest_path = target.join(OptionalComponents::FILE_NAME);
+    let mut optional = match fs::read_to_string(&manifest_path) {
+        Ok(text) => OptionalComponents::parse(&text),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => OptionalComponents::default(),
+        Err(source) => {
+            return Err(Error::Io {
+                source,
+                faulty_path: Some(manifest_path),
+            }
+            .into())
+        }
+    };
+
+    let optional_components = components
+        .iter()
+        .filter(|component| component.is_optional())
+        .collect::<Vec<_>>();
+    if ask_optional && !optional_components.is_empty() {
+        let options = optional_components
+            .iter()
+            .map(|component| component.slug.clone())
+            .collect::<Vec<_>>();
+        let defaults = optional_components
+            .iter()
+            .enumerate()
+            .filter(|(_, component)| optional.is_enabled(component))
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        let chosen = terminal::suspend(|| {
+            inquire::MultiSelect::new("Choose optional components to install:", options)
+                .with_default(&defaults)
+                .with_page_size(optional_components.len().min(20))
+                .prompt()
+        })?;
+        optional.entries = optional_components
+            .iter()
+            .map(|component| (component.slug.clone(), chosen.contains(&component.slug)))
+            .collect();
+    } else {
+        // NOTE: Without a way to ask, optional components are opt-in.
+        let enabled = with_optional || terminal::is_interactive();
+        for component in optional_components {
+            optional
+                .entries
+                .entry(component.slug.clone())
+                .or_insert(enabled);
+        }
+    }
+
+    if !dry_run.enabled {
+        pack.fetch_includes()
+            .wrap_err("Failed to fetch the pack's includes")?;
+    }
+    let plan = pack.plan_install(target, &optional)?;
+    dry_run.run(&plan).wrap_err("Failed to install the pack")?;
+    if !dry_run.enabled {
+        info!(
+            "Done. Installed {name} into {target}",
+            name = pack.name,
+            target = target.display()
+        );
+    }
+    Ok(())
+}
+
+#[instrument(level = "debug", ret)]
+fn remove_component(
+    slugs: &[String],
+    cascade: bool,
+    orphans: bool,
+    force: bool,
+    dry_run: DryRun,
+) -> Result<(), Report> {
+    let components = Component::load_all()?;
+    let graph = DependencyGraph::new(&components);
+    let mut removed = slugs
+        .iter()
+        .map(|slug| (slug.clone(), "removed"))
+        .collect::<Vec<_>>();
+    if orphans {
+        for orphan in graph.orphans() {
+            removed.push((orphan.slug.clone(), "removed (orphaned library)"));
+        }
+    }
+    if cascade {
+        let roots = removed
+            .iter()
+            .map(|(slug, _)| slug.clone())
+            .collect::<Vec<_>>();
+        for dependency in graph.cascade(&roots) {
+            removed.push((dependency.slug.clone(), "removed (no longer needed)"));
+        }
+    }
+    let removed = removed
+        .into_iter()
+        .unique_by(|(slug, _)| slug.clone())
+        .collect::<Vec<_>>();
+
+    let mut summary = Summary::default();
+    for (slug, outcome) in &removed {
+        let essential = components
+            .iter()
+            .any(|component| component.slug == *slug && component.essential);
+        if essential && !force {
+            summary.skipped(slug, "essential, pass `--force` to remove it");
+            continue;
+        }
+        for dependent in graph
+            .dependents(slug, &[DependencyKind::Required])
+            .into_iter()
+            .filter(|dependent| !removed.iter().any(|(slug, _)| *slug == dependent.slug))
+        {
+            tracing::warn!(
+                "Removing {slug} will break {dependent}, which requires it",
+                slug = slug.yellow().bold(),
+                dependent = dependent.slug.yellow().bold(),
+            );
+        }
+        let status = Component::plan_remove(slug)
+            .map_err(Report::from)
+            .and_then(|mut plan| {
+                dry_run.track(&mut plan, &format!("remove {slug}"))?;
+                dry_run.run(&plan)
+            });
+        match status {
+            Ok(()) => summary.succeeded(slug, *outcome),
+            Err(error) => summary.failed(slug, &error),
+        }
+    }
+
+    summary.finish(dry_run.output_format)
+}
+
+#[instrument(level = "debug", ret)]
+fn replace_component(old: &str, new: &str, source: Source, dry_run: DryRun) -> Result<(), Report> {
+    let Pack {
+        instance, settings, ..
+    } = Pack::read()?;
+    let mut components = Component::load_all()?;
+    let Some(index) = components
+        .iter()
+        .position(|component| component.slug == old)
+    else {
+        return Err(eyre::eyre!("There is no component {old} in the pack"));
+    };
+    let graph = DependencyGraph::new(&components);
+    for dependent in graph.dependents(old, &[DependencyKind::Required]) {
+        tracing::warn!(
+            "{dependent} requires {old}, make sure {new} works for it too",
+            dependent = dependent.slug.yellow().bold(),
+            old = old.yellow().bold(),
+            new = new.yellow().bold(),
+        );
+    }
+
+    let replaced = components.remove(index);
+    let graph = DependencyGraph::new(&components);
+    let tags = Some(replaced.tags);
+    let mut component = match source {
+        Source::Modrinth => Component::fetch_from_modrinth(new, &instance, &graph, tags),
+        Source::Curseforge => Component::fetch_from_curseforge(new, &instance, &graph, tags),
+    }
+    .wrap_err(format!("Failed to fetch the component from {source}"))?;
+    component.check_duplicate(&components, settings.world_name())?;
+    snapshot_dependencies(&mut component);
+    snapshot_metadata(&mut component);
+
+    let mut plan = Component::plan_remove(old)?;
+    plan.extend(component.plan_save()?);
+    dry_run.track(
+        &mut plan,
+        &format!("replace {old} with {}", component.pinned_id()),
+    )?;
+    dry_run.run(&plan)?;
+    if !dry_run.enabled {
+        info!(
+            "Done. Replaced {old} with {new} ({file_name})",
+            file_name = component.file_name
+        );
+    }
+    Ok(())
+}
+
+#[instrument(level = "debug", ret)]
+fn rename_component(old: &str, new: &str, dry_run: DryRun) -> Result<(), Report> {
+    let components = Component::load_all()?;
+    let component = components
+        .iter()
+        .find(|component| component.slug == old)
+        .ok_or_else(|| eyre::eyre!("There is no {old:?} component in the pack"))?;
+    if new.is_empty() || new.contains(['/', '\\']) || new.starts_with('.') {
+        return Err(eyre::eyre!("{new:?} can't be used as a component ID"));
+    }
+    if components.iter().any(|component| component.slug == new) {
+        return Err(
+            eyre::eyre!("There already is a {new:?} component in the pack")
+                .suggestion("Pick another ID, or remove the other component first"),
+        );
+    }
+
+    let (renamed, mut plan) = component.plan_rename(new)?;
+    let mut pack = Pack::read()?;
+    if pack.settings.rename_component(old, new) {
+        plan.push(Action::write_entity(
+            <Pack as PersistedEntity>::FILE_PATH,
+            serde_yml::to_string(&pack)?,
+        ));
+    }
+    dry_run.track(&mut plan, &format!("rename {old} to {new}"))?;
+    dry_run.run(&plan)?;
+    if !dry_run.enabled {
+        info!(
+            "Renamed {old} to {new}, now at {}",
+            renamed.local_storage_path().display()
+        );
+    }
+    Ok(())
+}
+
+#[instrument(level = "debug", ret)]
+fn set_component_side(slugs: &[String], side: OverrideSide, dry_run: DryRun) -> Result<(), Report> {
+    let components = Component::load_all()?;
+    let mut summary = Summary::default();
+    for slug in slugs {
+        let Some(component) = components.iter().find(|component| component.slug == *slug) else {
+            summary.failed(slug, &eyre::eyre!("There is no such component in the pack"));
+            continue;
+        };
+        let mut component = component.clone();
+        component.set_side(side);
+        let status = component
+            .plan_save()
+            .map_err(Report::from)
+            .and_then(|mut plan| {
+                dry_run.track(&mut plan, &format!("make {slug} needed on {side}"))?;
+                dry_run.run(&plan)
+            });
+        match status {
+            Ok(()) => summary.succeeded(slug, format!("needed on {side}")),
+            Err(error) => summary.failed(slug, &error),
+        }
+    }
+
+    summary.finish(dry_run.output_format)
+}
+
+fn set_component_group(
+    slugs: &[String],
+    group: Option<&str>,
+    dry_run: DryRun,
+) -> Result<(), Report> {
+    let components = Component::load_all()?;
+    let mut summary = Summary::default();
+    for slug in slugs {
+        let Some(component) = components.iter().find(|component| component.slug == *slug) else {
+            summary.failed(slug, &eyre::eyre!("There is no such component in the pack"));
+            continue;
+        };
+        let mut component = component.clone();
+        component.group = group.map(String::from);
+        let message = match group {
+            Some(group) => format!("group {slug} into {group}"),
+            None => format!("ungroup {slug}"),
+        };
+        let status = component
+            .plan_save()
+            .map_err(Report::from)
+            .and_then(|mut plan| {
+                dry_run.track(&mut plan, &message)?;
+                dry_run.run(&plan)
+            });
+        match (status, group) {
+            (Ok(()), Some(group)) => summary.succeeded(slug, format!("grouped into {group}")),
+            (Ok(()), None) => summary.succeeded(slug, "ungrouped"),
+            (Err(error), _) => summary.failed(slug, &error),
+        }
+    }
+
+    summary.finish(dry_run.output_format)
+}
+
+fn set_file_naming(slug: &str, naming: FileNaming, dry_run: DryRun) -> Result<(), Report> {
+    let mut component = Component::load_all()?
+        .into_iter()
+        .find(|component| component.slug == slug)
+        .ok_or_else(|| eyre::eyre!("There is no {slug:?} component in the pack"))?;
+    component.file_naming = naming;
+    let mut plan = component.plan_save()?;
+    dry_run.track(&mut plan, &format!("change how {slug}'s file is named"))?;
+    dry_run.run(&plan)?;
+    info!(
+        "{slug} will be placed at {}",
+        component
+            .runtime_path(Pack::read()?.settings.world_name())
+            .display()
+    );
+    Ok(())
+}
+
+fn bulk_tag_components(
+    filters: &[Filter],
+    add: &[Tag],
+    remove: &[Tag],
+    main: Option<&Tag>,
+    dry_run: DryRun,
+) -> Result<(), Report> {
+    if add.is_empty() && remove.is_empty() && main.is_none() {
+        return Err(eyre::eyre!("Nothing to do")
+            .with_suggestion(|| "Pass tags with --add, --remove or --main"));
+    }
+    let components = Component::load_all()?;
+    let mut summary = Summary::default();
+    let matching = components
+        .iter()
+        .filter(|component| filters.iter().all(|filter| filter.matches(component)))
+        .sorted_by(|a, b| a.slug.cmp(&b.slug));
+    for component in matching {
+        let mut tags = component.tags.clone();
+        for tag in remove {
+            tags.remove(tag);
+        }
+        if let Some(main) = main {
+            tags.set_main(main.clone());
+        }
+        for tag in add {
+            tags.add(tag.clone());
+        }
+        if tags == component.tags {
+            summary.skipped(&component.slug, "already tagged");
+            continue;
+        }
+        let status = component
+            .plan_retag(tags)
+            .map_err(Report::from)
+            .and_then(|mut plan| {
+                dry_run.track(&mut plan, &format!("retag {}", component.slug))?;
+                dry_run.run(&plan)
+            });
+        match status {
+            Ok(()) => summary.succeeded(&component.slug, "retagged"),
+            Err(error) => summary.failed(&component.slug, &error),
+        }
+    }
+
+    summary.finish(dry_run.output_format)
+}
+
+#[instrument(level = "debug", ret)]
+fn move_component(slugs: &[String], target_pack: &Path, dry_run: DryRun) -> Result<(), Report> {
+    let mut summary = Summary::default();
+    for slug in slugs {
+        let status = Component::plan_move_to_pack(slug, target_pack)
+            .map_err(Report::from)
+            .and_then(|(mut plan, destination)| {
+                let message = format!("move {slug} to {}", target_pack.display());
+                dry_run.track(&mut plan, &message)?;
+                dry_run.run(&plan).map(|()| destination)
+            });
+        match status {
+            Ok(destination) => {
+                summary.succeeded(slug, format!("moved to {}", destination.display()));
+            }
+            Err(error) => summary.failed(slug, &error),
+        }
+    }
+
+    summary.finish(dry_run.output_format)
+}
+
+/// Fetch the components called `ids` and add them to the pack.
+///
+/// All of them are written at once, after they're all fetched, so that a
+/// failure can't leave the pack with only some of their files.
+#[instrument(level = "debug", ret)]
+fn add_component(
+    ids: &[String],
+    source: Source,
+    show_metadata: bool,
+    commit: bool,
+    dry_run: DryRun,
+) -> Result<(), Report> {
+    let mut pack = Pack::read()?;
+    let (instance, settings) = (pack.instance.clone(), pack.settings.clone());
+    let mut installed = Component::load_all()?;
+    let mut summary = Summary::default();
+    let mut transaction = Plan::new();
+    let mut added = vec![];
+    for id in ids {
+        let graph = DependencyGraph::new(&installed);
+        let mut add = || -> Result<(Component, Plan), Report> {
+            let mut component = match source {
+                Source::Modrinth => Component::fetch_from_modrinth(id, &instance, &graph, None),
+                Source::Curseforge => Component::fetch_from_curseforge(id, &instance, &graph, None),
+            }
+            .wrap_err(format!("Failed to fetch the component from {source}"))?;
+            if let Some(defaults) = settings
+                .category_defaults
+                .get(&component.category)
+                .filter(|_| !terminal::is_interactive())
+            {
+                defaults.apply(&mut component);
+            }
+            component
+                .check_duplicate(&installed, settings.world_name())
+                .suggestion("Remove the existing component first, or `replace` it")?;
+            snapshot_dependencies(&mut component);
+            snapshot_metadata(&mut component);
+            let mut with_new = installed.clone();
+            with_new.push(component.clone());
+            for problem in Problem::incompatibilities(&with_new, &settings.conflicts) {
+                if matches!(&problem, Problem::Incompatible { slugs, .. } if slugs.contains(&component.slug))
+                {
+                    tracing::warn!("{problem}");
+                }
+            }
+
+            let mut plan = resolve_local_collision(&mut pack, &mut component, source)?;
+
+            info!(message = "Adding:", slug = ?id, file_name = ?component.file_name.yellow().bold());
+            if show_metadata {
+                let yaml = serde_yml::to_string(&component)
+                    .wrap_err("Failed to serialize the component's metadata")?
+                    .lines()
+                    .fold(String::new(), |mut acc, line| {
+                        let _ = writeln!(acc, "{prefix} {line}", prefix = "|>".yellow().bold());
+                        acc
+                    });
+                info!(message = "Writing metadata,", path = ?component.local_storage_path().yellow().bold());
+                print!("{yaml}");
+            }
+
+            plan.extend(
+                component
+                    .plan_save()
+                    .wrap_err("Failed to save component's metadata")?,
+            );
+            Ok((component, plan))
+        };
+
+        match add() {
+            Ok((component, plan)) => {
+                transaction.extend(plan);
+                added.push((id, component.clone()));
+                installed.push(component);
+            }
+            Err(error) => summary.failed(id, &error),
+        }
+    }
+
+    if (commit || dry_run.tracks(&settings)) && !added.is_empty() {
+        let paths = transaction.paths();
+        transaction.push(Action::GitCommit {
+            message: format!(
+                "invar: add {}",
+                added
+                    .iter()
+                    .map(|(_, component)| component.pinned_id())
+                    .join(", ")
+            ),
+            paths,
+            author: commit_author(&settings)?,
+        });
+    }
+    match dry_run
+        .run(&transaction)
+        .wrap_err("Failed to save the components, the pack was left as it was")
+    {
+        Ok(()) => {
+            for (id, component) in added {
+                summary.succeeded(id, &component.file_name);
+            }
+        }
+        Err(error) => {
+            for (id, _) in added {
+                summary.failed(id, &error);
+            }
+        }
+    }
+    summary.finish(dry_run.output_format)
+}
+
+/// Ask what to do if the new `component` would collide with one of the
+/// `pack`'s local components, returning the [`Plan`] to save the resolution.
+fn resolve_local_collision(
+    pack: &mut Pack,
+    component: &mut Component,
+    source: Source,
+) -> Result<Plan, Report> {
+    let mut plan = Plan::new();
+    let world = pack.settings.world_name();
+    if let Some(index) = pack
+        .local_components
+        .iter()
+        .position(|local| local.collides_with(component, world))
+    {
+        let path = pack.local_components[index].path.display().to_string();
+        let collision = format!(
+            "{} collides with the local component at {path}",
+            component.slug
+        );
+        match Resolution::prompt(&format!("{collision}, what to do?"))? {
+            Resolution::Replace => {
+                pack.local_components.remove(index);
+                plan.push(Action::write_entity(
+                    <Pack as PersistedEntity>::FILE_PATH,
+                    serde_yml::to_string(&pack)?,
+                ));
+            }
+            Resolution::KeepBoth => {
+                let default = format!("{source}-{}", component.runtime_file_name());
+                let name = terminal::suspend(|| {
+                    inquire::Text::new("Name of the new component's file:")
+                        .with_default(&default)
+                        .prompt()
+                })?;
+                component.file_naming = FileNaming::Custom(name);
+            }
+            Resolution::Abort => {
+                return Err(eyre::eyre!(collision).suggestion(
+                    "Resolve the collision interactively, or remove the local component from the pack file",
+                ));
+            }
+        }
+    }
+    Ok(plan)
+}
+
+/// Update the components called `slugs`, or all of them if [`None`].
+#[instrument(level = "debug", ret)]
+fn update_components(
+    slugs: Option<&[String]>,
+    pick: bool,
+    commit: bool,
+    branch: Option<String>,
+    edit: bool,
+    dry_run: DryRun,
+) -> Result<(), Report> {
+    let Pack {
+        instance, settings, ..
+    } = Pack::read()?;
+    let tracked = dry_run.tracks(&settings);
+    let commit = commit || tracked;
+    let author = match commit {
+        true => commit_author(&settings)?,
+        false => None,
+    };
+    let mut summary = Summary::default();
+    let mut installed = Component::load_all()?;
+    let mut components = installed.clone();
+    if let Some(slugs) = slugs {
+        for missing in slugs
+            .iter()
+            .filter(|slug| !components.iter().any(|c| &c.slug == *slug))
+        {
+            summary.failed(
+                missing,
+                &eyre::eyre!("There is no such component in the pack"),
+            );
+        }
+        components.retain(|component| slugs.contains(&component.slug));
+    }
+
+    let mut plan = Plan::new();
+    if let Some(name) = branch {
+        plan.push(Action::GitBranch { name });
+    }
+
+    let mut updated = vec![];
+    for component in components {
+        if budget::is_exhausted() {
+            summary.skipped(component.slug, budget::SKIPPED_REASON);
+            continue;
+        }
+        let graph = DependencyGraph::new(&installed);
+        let update = match pick {
+            true => pick_update(&component, &instance, &graph),
+            false => component
+                .fetch_update(&instance, &graph)
+                .map_err(Report::from),
+        };
+        match update {
+            Ok(Some(mut update)) => {
+                snapshot_dependencies(&mut update);
+                preview_impact(&graph, &update);
+                if let Some(entry) = installed.iter_mut().find(|c| c.slug == update.slug) {
+                    *entry = update.clone();
+                }
+                info!(message = "Updating:", slug = ?update.slug, file_name = ?update.file_name.yellow().bold());
+                plan.extend(update.plan_save()?);
+                if commit {
+                    let (slug, file_name) = (&update.slug, &update.file_name);
+                    plan.push(Action::GitCommit {
+                        message: match tracked {
+                            true => format!("invar: update {slug} to {file_name}"),
+                            false => format!("Update {slug} to {file_name}"),
+                        },
+                        paths: vec![update.local_storage_path()],
+                        author: author.clone(),
+                    });
+                }
+                updated.push((component, update));
+            }
+            Ok(None) if pick => summary.skipped(component.slug, "no newer version picked"),
+            Ok(None) => summary.skipped(component.slug, "already up to date"),
+            Err(error) => summary.failed(component.slug, &error),
+        }
+    }
+
+    dry_run.run(&plan)?;
+
+    let report = UpdateReport::new(&updated);
+    if !dry_run.enabled && !report.is_empty() {
+        if dry_run.output_format == OutputFormat::Human {
+            print!("{report}");
+        }
+        match report.save() {
+            Ok(path) if edit => open_in_editor(&path),
+            Ok(path) => info!("Saved the update report to {}", path.display()),
+            Err(error) => tracing::warn!(%error, "Failed to save the update report"),
+        }
+    }
+    for (old, new) in updated {
+        summary.succeeded(old.slug, format!("{} -> {}", old.file_name, new.file_name));
+    }
+    summary.finish(dry_run.output_format)
+}
+
+/// Open `path` in `$VISUAL` or `$EDITOR`, if there's one and a terminal to
+/// run it in.
+fn open_in_editor(path: &Path) {
+    let editor = std::env::var("VISUAL").or_else(|_| std::env::var("EDITOR"));
+    match editor {
+        Ok(editor) if terminal::is_interactive() => {
+            // NOTE: Editors are often set with arguments, like `code --wait`.
+            let mut words = editor.split_whitespace();
+            let status = terminal::suspend(|| {
+                std::process::Command::new(words.next().unwrap_or("vi"))
+                    .args(words)
+                    .arg(path)
+                    .status()
+                    .map_err(inquire::InquireError::IO)
+            });
+            if let Err(error) = status {
+                tracing::warn!(%error, editor, "Failed to open the update report");
+            }
+        }
+        _ => info!("Saved the update report to {}", path.display()),
+    }
+}
+
+/// Show the components that depend on the one being updated, and whether the
+/// `update` still satisfies them.
+fn preview_impact(graph: &DependencyGraph<'_>, update: &Component) {
+    let impact = graph.impact(update);
+    if impact.is_empty() {
+        return;
+    }
+    info!(
+        "{} is depended on by {} components:",
+        update.slug.magenta().bold(),
+        impact.len()
+    );
+    for dependent in impact {
+        match dependent.satisfied {
+            true => info!("  {dependent}"),
+            false => tracing::warn!("  {dependent}"),
+        }
+    }
+}
+
+/// Cache the metadata of the component's dependencies, carrying on without it
+/// if the source can't be reached.
+fn snapshot_dependencies(component: &mut Component) {
+    if let Err(error) = component.fetch_dependency_metadata() {
+        tracing::warn!(slug = ?component.slug, %error, "Failed to look up the dependencies' metadata");
+    }
+}
+
+/// Keep the metadata of the component's project, carrying on without it if
+/// the source can't be reached.
+fn snapshot_metadata(component: &mut Component) {
+    if let Err(error) = Component::fetch_project_metadata(std::slice::from_mut(component)) {
+        tracing::warn!(slug = ?component.slug, %error, "Failed to look up the project's metadata");
+    }
+}
+
+fn refresh_component_metadata(dry_run: DryRun) -> Result<(), Report> {
+    let mut components = Component::load_all()?;
+    Component::fetch_project_metadata(&mut components)?;
+    let mut summary = Summary::default();
+    for component in components {
+        let Some(metadata) = &component.metadata else {
+            summary.skipped(component.slug, "no metadata found");
+            continue;
+        };
+        let name = metadata.name.clone();
+        match component
+            .plan_save()
+            .map_err(Report::from)
+            .and_then(|mut plan| {
+                let message = format!("refresh the metadata of {}", component.slug);
+                dry_run.track(&mut plan, &message)?;
+                dry_run.run(&plan)
+            }) {
+            Ok(()) => summary.succeeded(component.slug, name),
+            Err(error) => summary.failed(component.slug, &error),
+        }
+    }
+    summary.finish(dry_run.output_format)
+}
+
+fn pick_update(
+    component: &Component,
+    instance: &Instance,
+    graph: &DependencyGraph<'_>,
+) -> Result<Option<Component>, Report> {
+    if component.source != Source::Modrinth {
+        tracing::warn!(slug = ?component.slug, "Picking versions is only supported for Modrinth, taking the newest one");
+        return Ok(component.fetch_update(instance, graph)?);
+    }
+    let versions = component.fetch_newer_versions(instance, graph)?;
+    if versions.is_empty() {
+        return Ok(None);
+    }
+
+    let message = format!(
+        "{count} newer versions of {slug} found, choose one:",
+        count = versions.len(),
+        slug = component.slug.magenta().bold()
+    );
+    let help = format!("Currently at {}", component.file_name);
+    let version = terminal::suspend(|| {
+        inquire::Select::new(&message, versions)
+            .with_help_message(&help)
+            .prompt_skippable()
+    })?;
+    match version {
+        Some(version) => Ok(Some(component.with_version(&version)?)),
+        None => Ok(None),
+    }
+}
+
+#[instrument(level = "debug", ret)]
+fn list_components(
+    filters: &[Filter],
+    sort: Option<ListSort>,
+    long: bool,
+    output_format: OutputFormat,
+) -> Result<(), Report> {
+    let mut components = Component::load_all()?;
+    components.retain(|component| filters.iter().all(|filter| filter.matches(component)));
+    match sort {
+        Some(ListSort::Name) => components.sort_by(|a, b| a.slug.cmp(&b.slug)),
+        Some(ListSort::Size) => {
+            components.sort_by_key(|component| std::cmp::Reverse(component.file_size));
+        }
+        Some(ListSort::Date) => {
+            // NOTE: Components don't record when they changed, but their files do.
+            let modified = local_storage::metadata_files(".")?
+                .filter_map(|entry| {
+                    let slug = entry
+                        .file_name()
+                        .to_str()?
+                        .strip_suffix(Component::LOCAL_STORAGE_SUFFIX)?
+                        .to_string();
+                    Some((slug, entry.metadata().ok()?.modified().ok()?))
+                })
+                .collect::<HashMap<_, _>>();
+            components.sort_by_key(|component| std::cmp::Reverse(modified.get(&component.slug)));
+        }
+        None => {}
+    }
+    let list = ComponentList {
+        long,
+        ..ComponentList::from(components.as_slice())
+    };
+    report::print(&list, output_format)
+}
+
+fn export_list(format: ListFormat, gallery: bool) -> Result<(), Report> {
+    let mut components = Pack::read()?.components()?;
+    if gallery {
+        let (mut missing, known): (Vec<_>, Vec<_>) = components
+            .into_iter()
+            .partition(|component| component.metadata.is_none());
+        if let Err(error) = Component::fetch_project_metadata(&mut missing) {
+            tracing::warn!(%error, "Failed to look up the projects' metadata");
+        }
+        components = known.into_iter().chain(missing).collect();
+    }
+    print!("{}", ModList::new(&components).render(format, gallery));
+    Ok(())
+}
+
+fn probe_component(slug: &str, source: Source, output_format: OutputFormat) -> Result<(), Report> {
+    let instance = Pack::read()?.instance;
+    let probe = Probe::fetch(slug, source, &instance, &Component::load_all()?)?;
+    report::print(&probe, output_format)
+}
+
+fn component_tree() -> Result<(), Report> {
+    let components = Component::load_all()?;
+    let graph = DependencyGraph::new(&components);
+    print!("{graph}");
+    for (component, dependency) in graph.missing() {
+        tracing::warn!(
+            "{slug} requires {project}, which is not in the pack",
+            slug = component.slug.yellow().bold(),
+            project = dependency.name().bold(),
+        );
+    }
+    for orphan in graph.orphans() {
+        tracing::warn!(
+            "{slug} is a library nothing depends on, consider removing it",
+            slug = orphan.slug.yellow().bold(),
+        );
+    }
+    Ok(())
+}
+
+/// Whether mutating commands should only print their [`Plan`] instead of
+/// applying it, and how to print it.
+#[derive(Debug, Clone, Copy)]
+struct DryRun {
+    enabled: bool,
+    output_format: OutputFormat,
+    /// Don't commit changes even if the pack
+    /// [tracks its components](VcsMode::TrackComponents).
+    no_commit: bool,
+}
+
+impl DryRun {
+    /// Whether changes should be committed automatically with these pack
+    /// `settings`.
+    fn tracks(self, settings: &Settings) -> bool {
+        !self.no_commit
+            && settings.vcs_mode == VcsMode::TrackComponents
+            && Path::new(".git").exists()
+    }
+
+    /// Have `plan` commit the paths it changes as `invar: {message}` if the
+    /// pack [tracks its components](Self::tracks). Exported artifacts are
+    /// left out, they're build outputs, and so are paths outside the pack,
+    /// like the ones a component is moved to.
+    fn track(self, plan: &mut Plan, message: &str) -> Result<(), Report> {
+        let settings = Pack::read()?.settings;
+        if plan.is_empty() || !self.tracks(&settings) {
+            return Ok(());
+        }
+        let paths = plan
+            .paths()
+            .into_iter()
+            .filter(|path| !path.starts_with(EXPORT_DIR))
+            .filter(|path| {
+                path.components().all(|component| {
+                    matches!(
+                        component,
+                        std::path::Component::CurDir | std::path::Component::Normal(_)
+                    )
+                })
+            })
+            .collect_vec();
+        if paths.is_empty() {
+            return Ok(());
+        }
+        plan.push(Action::GitCommit {
+            message: format!("invar: {message}"),
+            paths,
+            author: commit_author(&settings)?,
+        });
+        Ok(())
+    }
+
+    fn run(self, plan: &Plan) -> Result<(), Report> {
+        if !self.enabled {
+            return Ok(plan.apply()?);
+        }
+
+        match self.output_format {
+            OutputFormat::Human => print!("{plan}"),
+            OutputFormat::Yaml => print!("{}", serde_yml::to_string(plan)?),
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(plan)?),
+        }
+        Ok(())
+    }
+}
+
+fn install_tracing() -> Result<(), Report> {
+    use tracing_error::ErrorLayer;
+    use tracing_subscriber::prelude::*;
+    use tracing_subscriber::{fmt, EnvFilter};
+    let format_layer = fmt::layer()
+        .pretty()
+        .without_time()
+        .with_writer(terminal::Writer);
+    let filter_layer = EnvFilter::try_from_default_env().or_else(|_| EnvFilter::try_new("info"))?;
+    tracing_subscriber::registry()
+        .with(filter_layer)
+        .with(format_layer)
+        .with(ErrorLayer::default())
+        .try_init()?;
+    Ok(())
+}
+
+/// Figure out who to commit as: nobody in particular if Git has an identity
+/// configured, the pack's [`Settings::git_signature`] otherwise, and whoever
+/// the user says as a last resort.
+fn commit_author(settings: &Settings) -> Result<Option<GitSignature>, Report> {
+    if GitSignature::from_git_config().is_some() {
+        return Ok(None);
+    }
+    if let Some(signature) = &settings.git_signature {
+        return Ok(Some(signature.clone()));
+    }
+    tracing::warn!("Git has no identity (`user.name` and `user.email`) configured");
+    let name = terminal::suspend(|| {
+        inquire::Text::new("Name to commit as:")
+            .with_validator(non_empty_validator("Please enter a non-empty name"))
+            .prompt()
+    })?;
+    let email = terminal::suspend(|| {
+        inquire::Text::new("Email to commit as:")
+            .with_validator(non_empty_validator("Please enter a non-empty email"))
+            .prompt()
+    })?;
+    info!(
+        "Set `settings.git_signature` in `{pack_file}` to skip these questions",
+        pack_file = Pack::FILE_PATH
+    );
+    Ok(Some(GitSignature {
+        name: name.trim().to_string(),
+        email: email.trim().to_string(),
+    }))
 }
 
 fn non_empty_validator(error_msg: &str) -> impl StringValidator + '_ {
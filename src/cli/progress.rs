@@ -0,0 +1,58 @@
+use invar::progress::{self, Event};
+use invar::terminal::Status;
+use std::sync::{Mutex, PoisonError};
+
+/// How many characters wide task progress bars are.
+const BAR_WIDTH: usize = 20;
+
+/// Render the library's [progress events](Event): tasks as a status line with
+/// a progress bar, everything else as debug logs.
+pub fn install() {
+    let status = Mutex::new(None::<Status>);
+    progress::set_handler(move |event| {
+        let lock = || status.lock().unwrap_or_else(PoisonError::into_inner);
+        match *event {
+            Event::TaskStarted { task, .. } => {
+                let mut status = lock();
+                // NOTE: The old status has to go first, as dropping it clears
+                // the line.
+                status.take();
+                *status = Some(Status::new(task));
+            }
+            Event::TaskAdvanced {
+                task,
+                current,
+                total,
+                item,
+            } => {
+                let filled = (current * BAR_WIDTH).checked_div(total).unwrap_or(0);
+                let mut line = format!(
+                    "[{}{}] {current}/{total} {task}",
+                    "#".repeat(filled.min(BAR_WIDTH)),
+                    "-".repeat(BAR_WIDTH.saturating_sub(filled)),
+                );
+                if let Some(item) = item {
+                    line = format!("{line}: {item}");
+                }
+                if let Some(status) = lock().as_ref() {
+                    status.set(line);
+                }
+            }
+            Event::TaskFinished { .. } => {
+                lock().take();
+            }
+            Event::FetchStarted { url } => tracing::debug!(%url, "Downloading"),
+            Event::VersionResolved { component } => tracing::debug!(
+                slug = component.slug,
+                file_name = component.file_name,
+                "Resolved a version"
+            ),
+            Event::DependencyFound {
+                slug,
+                project_id,
+                kind,
+            } => tracing::debug!(slug, project_id, %kind, "Found a dependency"),
+            Event::FileSaved { path } => tracing::debug!(path = %path.display(), "Saved"),
+        }
+    });
+}
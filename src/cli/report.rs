@@ -0,0 +1,147 @@
+use crate::cli::OutputFormat;
+use chrono::{DateTime, Local, Utc};
+use color_eyre::eyre::Report;
+use color_eyre::owo_colors::OwoColorize;
+use invar::component::{Category, Source, Tag};
+use invar::{format_size, Component, Pack};
+use serde::Serialize;
+use std::fmt;
+
+/// Print a report in the requested [`OutputFormat`].
+pub fn print<T: Serialize + fmt::Display>(
+    report: &T,
+    output_format: OutputFormat,
+) -> Result<(), Report> {
+    match output_format {
+        OutputFormat::Human => print!("{report}"),
+        OutputFormat::Yaml => print!("{}", serde_yml::to_string(report)?),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(report)?),
+    }
+    Ok(())
+}
+
+/// A pack, as shown by `pack show`.
+#[derive(Debug, Serialize)]
+pub struct PackOverview {
+    #[serde(flatten)]
+    pub pack: Pack,
+    /// When the cached version lists were last refreshed.
+    pub metadata_refreshed_at: Option<DateTime<Utc>>,
+}
+
+impl fmt::Display for PackOverview {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // NOTE: The pack file itself is the most readable view of the pack.
+        let yaml = serde_yml::to_string(&self.pack).map_err(|_| fmt::Error)?;
+        write!(f, "{yaml}")?;
+        match self.metadata_refreshed_at {
+            Some(at) => writeln!(
+                f,
+                "# Version metadata refreshed at {}",
+                at.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+            ),
+            None => writeln!(f, "# Version metadata was never refreshed"),
+        }
+    }
+}
+
+/// The components of a pack, as shown by `component list`.
+#[derive(Debug, Serialize)]
+pub struct ComponentList {
+    pub total: usize,
+    /// The listed components' file sizes, summed.
+    pub total_size: usize,
+    pub components: Vec<ListedComponent>,
+    /// Show file sizes and version IDs too.
+    #[serde(skip)]
+    pub long: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListedComponent {
+    pub slug: String,
+    /// The project's display name, if its metadata was looked up.
+    pub name: Option<String>,
+    pub source: Source,
+    pub category: Category,
+    pub main_tag: Option<Tag>,
+    pub other_tags: Vec<Tag>,
+    pub version_id: String,
+    pub file_name: String,
+    pub file_size: usize,
+    pub essential: bool,
+}
+
+impl From<&[Component]> for ComponentList {
+    fn from(components: &[Component]) -> Self {
+        let components = components
+            .iter()
+            .map(|component| ListedComponent {
+                slug: component.slug.clone(),
+                name: component
+                    .metadata
+                    .as_ref()
+                    .map(|metadata| metadata.name.clone()),
+                source: component.source,
+                category: component.category,
+                main_tag: component.tags.main.clone(),
+                other_tags: component.tags.others.clone(),
+                version_id: component.version_id.clone(),
+                file_name: component.file_name.clone(),
+                file_size: component.file_size,
+                essential: component.essential,
+            })
+            .collect::<Vec<_>>();
+        Self {
+            total: components.len(),
+            total_size: components.iter().map(|component| component.file_size).sum(),
+            components,
+            long: false,
+        }
+    }
+}
+
+impl fmt::Display for ComponentList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for component in &self.components {
+            writeln!(
+                f,
+                "{type}: {prefix}{slug}{name}{essential} [{version}]",
+                type = component.category,
+                name = match &component.name {
+                    Some(name) => format!(" ({name})"),
+                    None => String::new(),
+                }
+                .dimmed(),
+                slug = component.slug.yellow().bold(),
+                essential = match component.essential {
+                    true => " (essential)",
+                    false => "",
+                }
+                .red()
+                .bold(),
+                version = component.file_name.bold(),
+                prefix = match &component.main_tag {
+                    Some(tag) => format!("{tag}/"),
+                    None => String::new(),
+                }
+                .bright_yellow()
+                .bold(),
+            )?;
+            if self.long {
+                writeln!(
+                    f,
+                    "    {} KiB, version {}",
+                    component.file_size / 1024,
+                    component.version_id.dimmed()
+                )?;
+            }
+        }
+        writeln!(
+            f,
+            "{count} components in total, {size}.",
+            count = self.total.red().bold(),
+            size = format_size(self.total_size).bold()
+        )
+    }
+}
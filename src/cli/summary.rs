@@ -0,0 +1,130 @@
+use crate::cli::OutputFormat;
+use color_eyre::eyre::Report;
+use color_eyre::owo_colors::OwoColorize;
+use serde::Serialize;
+use std::fmt;
+
+/// The exit code used when some, but not all, items of a bulk operation
+/// failed.
+pub const PARTIAL_FAILURE_EXIT_CODE: i32 = 2;
+
+/// Outcomes of a multi-item operation, printed once the operation is done.
+#[derive(Debug, Default, Serialize)]
+pub struct Summary {
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Entry {
+    pub item: String,
+    #[serde(flatten)]
+    pub outcome: Outcome,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "outcome", content = "details", rename_all = "snake_case")]
+pub enum Outcome {
+    Succeeded(String),
+    Skipped(String),
+    Failed(String),
+}
+
+impl Summary {
+    pub fn succeeded(&mut self, item: impl Into<String>, details: impl Into<String>) {
+        self.push(item, Outcome::Succeeded(details.into()));
+    }
+
+    pub fn skipped(&mut self, item: impl Into<String>, reason: impl Into<String>) {
+        self.push(item, Outcome::Skipped(reason.into()));
+    }
+
+    pub fn failed(&mut self, item: impl Into<String>, error: &Report) {
+        self.push(item, Outcome::Failed(format!("{error:#}")));
+    }
+
+    fn push(&mut self, item: impl Into<String>, outcome: Outcome) {
+        self.entries.push(Entry {
+            item: item.into(),
+            outcome,
+        });
+    }
+
+    fn count(&self, predicate: impl Fn(&Outcome) -> bool) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| predicate(&entry.outcome))
+            .count()
+    }
+
+    /// Print this [`Summary`] and turn it into an error if anything failed.
+    pub fn finish(self, output_format: OutputFormat) -> Result<(), Report> {
+        match output_format {
+            OutputFormat::Human => print!("{self}"),
+            OutputFormat::Yaml => print!("{}", serde_yml::to_string(&self)?),
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&self)?),
+        }
+
+        let failed = self.count(|outcome| matches!(outcome, Outcome::Failed(_)));
+        match failed {
+            0 => Ok(()),
+            failed => Err(Failures {
+                failed,
+                total: self.entries.len(),
+            }
+            .into()),
+        }
+    }
+}
+
+/// The error returned when some items of a multi-item operation failed.
+#[derive(Debug, thiserror::Error)]
+#[error("{failed} of {total} items failed")]
+pub struct Failures {
+    pub failed: usize,
+    pub total: usize,
+}
+
+impl Failures {
+    pub const fn is_partial(&self) -> bool {
+        self.failed < self.total
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .entries
+            .iter()
+            .map(|entry| entry.item.len())
+            .max()
+            .unwrap_or_default();
+        for Entry { item, outcome } in &self.entries {
+            let item = format!("{item:<width$}");
+            match outcome {
+                Outcome::Succeeded(details) => {
+                    writeln!(f, "{} {} {details}", "+".green().bold(), item.bold())?;
+                }
+                Outcome::Skipped(reason) => {
+                    writeln!(f, "{} {} {reason}", "-".yellow().bold(), item.bold())?;
+                }
+                Outcome::Failed(reason) => {
+                    writeln!(f, "{} {} {}", "!".red().bold(), item.bold(), reason.red())?;
+                }
+            }
+        }
+        writeln!(
+            f,
+            "{total} in total: {succeeded} succeeded, {skipped} skipped, {failed} failed.",
+            total = self.entries.len().bold(),
+            succeeded = self
+                .count(|o| matches!(o, Outcome::Succeeded(_)))
+                .green()
+                .bold(),
+            skipped = self
+                .count(|o| matches!(o, Outcome::Skipped(_)))
+                .yellow()
+                .bold(),
+            failed = self.count(|o| matches!(o, Outcome::Failed(_))).red().bold(),
+        )
+    }
+}
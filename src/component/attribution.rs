@@ -0,0 +1,101 @@
+use super::{curseforge, modrinth, AddError, Component, Source};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// Who made a [`Component`] and under which license, for the
+/// [`Notices`](crate::pack::Notices) shipped with exports.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attribution {
+    pub slug: String,
+    /// The project's display name.
+    pub name: String,
+    pub authors: Vec<String>,
+    /// The project's page.
+    pub url: Option<Url>,
+    /// The license the project declares, if the source knows about it.
+    pub license: Option<License>,
+}
+
+/// A license declared by a project.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct License {
+    /// An SPDX identifier, or a `LicenseRef-` for custom licenses.
+    pub id: String,
+    pub name: String,
+    /// Where the license text lives, for custom licenses.
+    pub url: Option<Url>,
+}
+
+/// A snapshot of a [`Component`]'s project, kept in its file so lists and
+/// pack pages can show more than slugs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectMetadata {
+    /// The project's display name.
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    /// The SPDX identifier of the project's license, if the source knows it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    /// The project's page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<Url>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon_url: Option<Url>,
+}
+
+impl Component {
+    /// Fetch the [`ProjectMetadata`] of all `components` that have a project
+    /// ID, querying each [`Source`] once, and replace what they had.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it fails to query a source's
+    /// API.
+    pub fn fetch_project_metadata(components: &mut [Self]) -> Result<(), AddError> {
+        for source in [Source::Modrinth, Source::Curseforge] {
+            let ids = components
+                .iter()
+                .filter(|component| component.source == source)
+                .filter_map(|component| component.project_id.clone())
+                .collect::<Vec<_>>();
+            if ids.is_empty() {
+                continue;
+            }
+            let mut found = match source {
+                Source::Modrinth => modrinth::fetch_project_metadata(&ids)?,
+                Source::Curseforge => {
+                    curseforge::fetch_project_metadata(&curseforge::client()?, &ids)?
+                }
+            };
+            for component in components.iter_mut().filter(|c| c.source == source) {
+                if let Some(metadata) = component
+                    .project_id
+                    .as_ref()
+                    .and_then(|id| found.remove(id))
+                {
+                    component.metadata = Some(metadata);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Fetch the [`Attribution`] of this component from its source.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it fails to query the source's
+    /// API, or the component has no project ID.
+    pub fn fetch_attribution(&self) -> Result<Attribution, AddError> {
+        let project_id = self.project_id.as_ref().ok_or(AddError::NotFound)?;
+        let mut attribution = match self.source {
+            Source::Modrinth => modrinth::fetch_attribution(project_id)?,
+            Source::Curseforge => {
+                curseforge::fetch_attribution(&curseforge::client()?, project_id)?
+            }
+        };
+        attribution.slug.clone_from(&self.slug);
+        Ok(attribution)
+    }
+}
@@ -0,0 +1,123 @@
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use strum::Display;
+
+/// Limits on which versions `component update` may move a
+/// [`Component`](super::Component) to.
+///
+/// All the set limits have to be satisfied.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Constraint {
+    /// Stay on this exact version ID.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pin: Option<String>,
+
+    /// The least stable release channel to accept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<Channel>,
+
+    /// A semver-style range the version number has to match, like `>=6.0,
+    /// <7`. Versions whose number can't be parsed don't match.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub versions: Option<VersionReq>,
+}
+
+/// Release channels of a version, from the most to the least stable.
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Display,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Channel {
+    #[default]
+    Release,
+    Beta,
+    Alpha,
+}
+
+impl Channel {
+    /// A note to put next to versions from this channel, empty for releases.
+    #[must_use]
+    pub fn annotation(self) -> String {
+        match self {
+            Self::Release => String::new(),
+            _ => format!(" ({self})"),
+        }
+    }
+}
+
+impl Constraint {
+    /// Whether there are no limits at all.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.pin.is_none() && self.channel.is_none() && self.versions.is_none()
+    }
+
+    /// This [`Constraint`], falling back to `channel` if it doesn't limit the
+    /// release channel itself.
+    #[must_use]
+    pub fn or_channel(&self, channel: Option<Channel>) -> Self {
+        Self {
+            channel: self.channel.or(channel),
+            ..self.clone()
+        }
+    }
+
+    /// Whether a version is allowed by this [`Constraint`].
+    #[must_use]
+    pub fn allows(&self, version_id: &str, channel: Channel, version_number: &str) -> bool {
+        let pinned = self.pin.as_deref().is_none_or(|pin| pin == version_id);
+        let stable_enough = self.channel.is_none_or(|least| channel <= least);
+        let in_range = self.versions.as_ref().is_none_or(|range| {
+            lenient_version(version_number).is_some_and(|version| range.matches(&version))
+        });
+        pinned && stable_enough && in_range
+    }
+}
+
+/// Find a version in a version number like `mc1.20.1-0.5.3`, which is
+/// anything but semver more often than not.
+///
+/// The last part that looks like a version wins, since the game version
+/// usually comes first. Missing minor and patch numbers are taken as zeroes.
+fn lenient_version(version_number: &str) -> Option<Version> {
+    version_number
+        .split(['-', '+', '_', ' '])
+        .filter_map(|part| {
+            let part = part.trim_start_matches(|c: char| !c.is_ascii_digit());
+            let padded = match part.matches('.').count() {
+                0 => format!("{part}.0.0"),
+                1 => format!("{part}.0"),
+                _ => part.to_string(),
+            };
+            padded.parse().ok()
+        })
+        .last()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lenient_version, Channel, Constraint};
+
+    #[test]
+    fn constraints_limit_versions() {
+        let constraint = Constraint {
+            pin: None,
+            channel: Some(Channel::Beta),
+            versions: Some(">=0.5, <0.6".parse().unwrap()),
+        };
+        assert!(constraint.allows("a", Channel::Release, "mc1.20.1-0.5.3"));
+        assert!(constraint.allows("b", Channel::Beta, "0.5"));
+        assert!(!constraint.allows("c", Channel::Alpha, "0.5.3"));
+        assert!(!constraint.allows("d", Channel::Release, "0.6.0"));
+        assert!(!constraint.allows("e", Channel::Release, "latest"));
+
+        let pinned = Constraint {
+            pin: Some("6R069CcK".to_string()),
+            ..Constraint::default()
+        };
+        assert!(pinned.allows("6R069CcK", Channel::Alpha, "whatever"));
+        assert!(!pinned.allows("other", Channel::Release, "1.0.0"));
+        assert_eq!(lenient_version("v2"), Some("2.0.0".parse().unwrap()));
+    }
+}
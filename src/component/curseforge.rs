@@ -0,0 +1,476 @@
+use super::{
+    AddError, Attribution, Category, Channel, Dependency, DependencyKind, DependencyMetadata,
+    ProjectMetadata,
+};
+use crate::index::file::{Env, Hashes, Requirement};
+use crate::instance::{minecraft_id, Instance, Loader};
+use crate::network;
+use color_eyre::owo_colors::OwoColorize;
+use itertools::Itertools;
+use reqwest::blocking::Client;
+use reqwest::header::{HeaderMap, HeaderValue};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use url::Url;
+
+/// Base URL of the **CurseForge API**.
+pub const API_URL: &str = "https://api.curseforge.com/v1";
+
+/// The environment variable the CurseForge API key is read from.
+pub const API_KEY_VAR: &str = "CURSEFORGE_API_KEY";
+
+/// CurseForge's ID of Minecraft.
+const MINECRAFT_GAME_ID: u32 = 432;
+
+/// How many files are fetched per page, the most CurseForge allows.
+const PAGE_SIZE: usize = 50;
+
+#[derive(Deserialize, Debug)]
+struct Response<T> {
+    data: T,
+}
+
+#[derive(Deserialize, Debug)]
+struct PagedResponse<T> {
+    data: Vec<T>,
+    pagination: Pagination,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Pagination {
+    total_count: usize,
+}
+
+/// A CurseForge project (a "mod", in CurseForge's terms).
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Mod {
+    pub id: u32,
+    pub slug: String,
+    pub name: String,
+    #[serde(default)]
+    pub summary: Option<String>,
+    pub class_id: Option<u32>,
+    #[serde(default)]
+    pub authors: Vec<Author>,
+    #[serde(default)]
+    pub links: Option<Links>,
+    #[serde(default)]
+    pub logo: Option<Logo>,
+}
+
+/// The icon of a CurseForge [`Mod`].
+#[derive(Deserialize, Debug, Clone)]
+pub struct Logo {
+    pub url: Option<Url>,
+}
+
+/// An author of a CurseForge [`Mod`].
+#[derive(Deserialize, Debug, Clone)]
+pub struct Author {
+    pub name: String,
+}
+
+/// Web links of a CurseForge [`Mod`].
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Links {
+    pub website_url: Option<Url>,
+}
+
+/// A single file (version) of a CurseForge [`Mod`].
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct File {
+    pub id: u32,
+    pub display_name: String,
+    pub file_name: String,
+    pub file_length: usize,
+    pub download_url: Option<Url>,
+    /// CurseForge mixes game versions, loaders and environments in here.
+    pub game_versions: Vec<String>,
+    pub hashes: Vec<FileHash>,
+    pub file_date: chrono::DateTime<chrono::Utc>,
+    #[serde(default)]
+    pub dependencies: Vec<FileDependency>,
+    /// `1` for releases, `2` for betas and `3` for alphas.
+    #[serde(default)]
+    pub release_type: u8,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FileDependency {
+    pub mod_id: u32,
+    pub relation_type: u8,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct FileHash {
+    pub value: String,
+    pub algo: u8,
+}
+
+impl FileHash {
+    const SHA1: u8 = 1;
+}
+
+impl Mod {
+    /// Map CurseForge's project class onto a [`Category`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the class isn't one Invar knows
+    /// how to handle.
+    pub fn category(&self) -> Result<Category, AddError> {
+        match self.class_id {
+            Some(6) => Ok(Category::Mod),
+            Some(12) => Ok(Category::Resourcepack),
+            Some(6552) => Ok(Category::Shader),
+            Some(6945) => Ok(Category::Datapack),
+            class_id => Err(AddError::UnsupportedClass(class_id.unwrap_or_default())),
+        }
+    }
+}
+
+impl File {
+    /// The release [`Channel`] of this file.
+    #[must_use]
+    pub const fn channel(&self) -> Channel {
+        match self.release_type {
+            2 => Channel::Beta,
+            3 => Channel::Alpha,
+            _ => Channel::Release,
+        }
+    }
+
+    /// The [`Loader`]s this file declares support for.
+    #[must_use]
+    pub fn loaders(&self) -> Vec<Loader> {
+        self.game_versions
+            .iter()
+            .filter_map(|tag| match tag.to_lowercase().as_str() {
+                "forge" => Some(Loader::Forge),
+                "neoforge" => Some(Loader::Neoforge),
+                "fabric" => Some(Loader::Fabric),
+                "quilt" => Some(Loader::Quilt),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The environment this file is meant for.
+    ///
+    /// CurseForge only tells us about this for some files, so if neither side
+    /// is mentioned, the file is assumed to be required on both.
+    #[must_use]
+    pub fn environment(&self) -> Env {
+        let client = self.game_versions.iter().any(|tag| tag == "Client");
+        let server = self.game_versions.iter().any(|tag| tag == "Server");
+        let requirement = |present: bool| match present || client == server {
+            true => Requirement::Required,
+            false => Requirement::Unsupported,
+        };
+        Env {
+            client: requirement(client),
+            server: requirement(server),
+        }
+    }
+
+    /// The [`Dependency`]s of this file on other projects.
+    ///
+    /// Tools (relation type 4) aren't dependencies in Invar's sense, so they
+    /// are skipped.
+    #[must_use]
+    pub fn dependencies(&self) -> Vec<Dependency> {
+        self.dependencies
+            .iter()
+            .filter_map(|dependency| {
+                let kind = match dependency.relation_type {
+                    1 | 6 => DependencyKind::Embedded,
+                    2 => DependencyKind::Optional,
+                    3 => DependencyKind::Required,
+                    5 => DependencyKind::Incompatible,
+                    _ => return None,
+                };
+                Some(Dependency {
+                    project_id: dependency.mod_id.to_string(),
+                    version_id: None,
+                    kind,
+                    metadata: None,
+                })
+            })
+            .collect()
+    }
+
+    /// Whether this file can be loaded by the [`Instance`]'s loader. Files
+    /// that don't declare any loaders are assumed to be.
+    #[must_use]
+    pub fn supports_loader(&self, instance: &Instance) -> bool {
+        let loaders = self.loaders();
+        loaders.is_empty()
+            || loaders
+                .iter()
+                .any(|l| *l == instance.loader || instance.allowed_foreign_loaders.contains(l))
+    }
+
+    /// Whether this file is made for the [`Instance`]'s Minecraft version.
+    ///
+    /// Resourcepacks and shaders may be loaded even if they are made for a
+    /// different version, so they always are.
+    #[must_use]
+    pub fn supports_minecraft_version(&self, category: Category, instance: &Instance) -> bool {
+        let version_insensitive = [Category::Resourcepack, Category::Shader].contains(&category);
+        let minecraft_id = minecraft_id(&instance.minecraft_version);
+        version_insensitive || self.game_versions.contains(&minecraft_id)
+    }
+
+    fn is_compatible(&self, category: Category, instance: &Instance) -> bool {
+        self.supports_loader(instance) && self.supports_minecraft_version(category, instance)
+    }
+
+    /// Download this file and compute its [`Hashes`].
+    ///
+    /// CurseForge only provides SHA1 (and MD5) hashes, but the `.mrpack` format
+    /// requires a SHA512 one as well, so the file has to be downloaded. The
+    /// SHA1 hash is checked against the one reported by CurseForge.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file can't be downloaded or
+    /// its SHA1 hash doesn't match the one reported by CurseForge.
+    pub fn fetch_hashes(&self) -> Result<Hashes, AddError> {
+        let url = self.download_url.clone().ok_or(AddError::NoFile)?;
+        let bytes = network::send(network::client().get(url))?
+            .error_for_status()?
+            .bytes()?;
+        let hashes = Hashes::compute(&bytes);
+        let expected = self
+            .hashes
+            .iter()
+            .find(|hash| hash.algo == FileHash::SHA1)
+            .map(|hash| hash.value.to_lowercase());
+        match expected {
+            Some(expected) if expected != hashes.sha1_hex() => Err(AddError::HashMismatch),
+            _ => Ok(hashes),
+        }
+    }
+}
+
+/// Build an HTTP client authenticated against the **CurseForge API**.
+///
+/// # Errors
+///
+/// This function will return an error if [`API_KEY_VAR`] is not set (or isn't
+/// a valid header value) or the client fails to build.
+pub fn client() -> Result<Client, AddError> {
+    let key = std::env::var(API_KEY_VAR).map_err(|_| AddError::MissingApiKey(API_KEY_VAR))?;
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "x-api-key",
+        HeaderValue::from_str(&key).map_err(|_| AddError::MissingApiKey(API_KEY_VAR))?,
+    );
+    Ok(network::client_builder().default_headers(headers).build()?)
+}
+
+/// Fetch a CurseForge project by its slug or numeric ID.
+///
+/// # Errors
+///
+/// This function will return an error if it fails to query the CurseForge API
+/// or there is no such project.
+pub fn fetch_project(client: &Client, slug: &str) -> Result<Mod, AddError> {
+    if let Ok(id) = slug.parse::<u32>() {
+        let url = format!("{API_URL}/mods/{id}");
+        let response: Response<Mod> = network::send(client.get(url))?.error_for_status()?.json()?;
+        return Ok(response.data);
+    }
+
+    let url = format!("{API_URL}/mods/search");
+    let request = client.get(url).query(&[
+        ("gameId", MINECRAFT_GAME_ID.to_string()),
+        ("slug", slug.into()),
+    ]);
+    let response: Response<Vec<Mod>> = network::send(request)?.error_for_status()?.json()?;
+    response
+        .data
+        .into_iter()
+        .find(|project| project.slug == slug)
+        .ok_or(AddError::NotFound)
+}
+
+/// Look up the [`DependencyMetadata`] of several projects by their IDs at
+/// once.
+///
+/// # Errors
+///
+/// This function will return an error if it fails to query the CurseForge API.
+pub fn fetch_metadata(
+    client: &Client,
+    ids: &[String],
+) -> Result<HashMap<String, DependencyMetadata>, AddError> {
+    Ok(fetch_projects(client, ids)?
+        .into_iter()
+        .map(|project| {
+            let metadata = DependencyMetadata {
+                slug: project.slug,
+                name: project.name,
+                summary: project.summary,
+            };
+            (project.id.to_string(), metadata)
+        })
+        .collect())
+}
+
+/// Look up the [`ProjectMetadata`] of several projects by their IDs at once.
+///
+/// CurseForge doesn't expose licenses through its API, so they're left out.
+///
+/// # Errors
+///
+/// This function will return an error if it fails to query the CurseForge API.
+pub fn fetch_project_metadata(
+    client: &Client,
+    ids: &[String],
+) -> Result<HashMap<String, ProjectMetadata>, AddError> {
+    Ok(fetch_projects(client, ids)?
+        .into_iter()
+        .map(|project| {
+            let metadata = ProjectMetadata {
+                name: project.name,
+                summary: project.summary,
+                license: None,
+                url: project.links.and_then(|links| links.website_url),
+                icon_url: project.logo.and_then(|logo| logo.url),
+            };
+            (project.id.to_string(), metadata)
+        })
+        .collect())
+}
+
+fn fetch_projects(client: &Client, ids: &[String]) -> Result<Vec<Mod>, AddError> {
+    let mod_ids = ids
+        .iter()
+        .filter_map(|id| id.parse::<u32>().ok())
+        .collect::<Vec<_>>();
+    let request = client
+        .post(format!("{API_URL}/mods"))
+        .json(&serde_json::json!({ "modIds": mod_ids }));
+    let response: Response<Vec<Mod>> = network::send(request)?.error_for_status()?.json()?;
+    Ok(response.data)
+}
+
+/// Fetch the [`Attribution`] of a project by its numeric ID.
+///
+/// CurseForge doesn't expose licenses through its API, so only the authors
+/// and the project page are known.
+///
+/// # Errors
+///
+/// This function will return an error if it fails to query the CurseForge API
+/// or there is no such project.
+pub fn fetch_attribution(client: &Client, project_id: &str) -> Result<Attribution, AddError> {
+    let project = fetch_project(client, project_id)?;
+    Ok(Attribution {
+        slug: project.slug,
+        name: project.name,
+        authors: project
+            .authors
+            .into_iter()
+            .map(|author| author.name)
+            .collect(),
+        url: project.links.and_then(|links| links.website_url),
+        license: None,
+    })
+}
+
+/// Fetch all [`File`]s of a project compatible with the provided
+/// [`Instance`], newest first.
+///
+/// The API is asked to filter the files by the instance's Minecraft version
+/// and loader where it can, and all pages of them are fetched.
+///
+/// # Errors
+///
+/// This function will return an error if it fails to query the CurseForge API.
+pub fn fetch_compatible_files(
+    client: &Client,
+    project: &Mod,
+    instance: &Instance,
+) -> Result<Vec<File>, AddError> {
+    let category = project.category()?;
+    let url = format!("{API_URL}/mods/{id}/files", id = project.id);
+    let mut query = vec![("pageSize", PAGE_SIZE.to_string())];
+    if ![Category::Resourcepack, Category::Shader].contains(&category) {
+        query.push(("gameVersion", minecraft_id(&instance.minecraft_version)));
+    }
+    if let Some(loader_type) = mod_loader_type(category, instance) {
+        query.push(("modLoaderType", loader_type.to_string()));
+    }
+    let mut files = vec![];
+    loop {
+        let request = client
+            .get(&url)
+            .query(&query)
+            .query(&[("index", files.len())]);
+        let page: PagedResponse<File> = network::send(request)?.error_for_status()?.json()?;
+        let done =
+            page.data.is_empty() || files.len() + page.data.len() >= page.pagination.total_count;
+        files.extend(page.data);
+        if done {
+            break;
+        }
+    }
+    files.retain(|file| file.download_url.is_some() && file.is_compatible(category, instance));
+    files.sort_unstable_by_key(|file| file.file_date);
+    files.reverse();
+    Ok(files)
+}
+
+/// CurseForge's `ModLoaderType` to filter `category`'s files for `instance`
+/// by, if they can only use a single loader's.
+fn mod_loader_type(category: Category, instance: &Instance) -> Option<u8> {
+    let loader_type = |loader: &Loader| match loader {
+        Loader::Forge => Some(1),
+        Loader::Fabric => Some(4),
+        Loader::Quilt => Some(5),
+        Loader::Neoforge => Some(6),
+        Loader::Minecraft | Loader::Other => None,
+    };
+    let types = std::iter::once(&instance.loader)
+        .chain(&instance.allowed_foreign_loaders)
+        .filter_map(loader_type)
+        .unique()
+        .collect_vec();
+    match (category, types.as_slice()) {
+        (Category::Mod, [loader_type]) => Some(*loader_type),
+        _ => None,
+    }
+}
+
+/// Fetch a single [`File`] of a project by their IDs.
+///
+/// # Errors
+///
+/// This function will return an error if it fails to query the CurseForge API.
+pub fn fetch_file(client: &Client, project_id: &str, file_id: &str) -> Result<File, AddError> {
+    let url = format!("{API_URL}/mods/{project_id}/files/{file_id}");
+    let response: Response<File> = network::send(client.get(url))?.error_for_status()?.json()?;
+    Ok(response.data)
+}
+
+impl fmt::Display for File {
+    fn fmt(&self, stream: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            stream,
+            "{name}{channel} [ID: {id}] - Supported loaders: {loaders:?}, released: {date}",
+            name = self.display_name.yellow().bold(),
+            channel = self.channel().annotation().red().bold(),
+            id = self.id.bold(),
+            loaders = self.loaders().bright_red(),
+            date = self.file_date.format("%b %e, %Y").bright_blue().bold()
+        )
+    }
+}
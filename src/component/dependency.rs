@@ -0,0 +1,588 @@
+use super::{Component, Tag};
+use color_eyre::owo_colors::OwoColorize;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+
+/// A dependency of a [`Component`] on another project, as reported by the
+/// component's [`Source`](super::Source).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Dependency {
+    /// The ID of the project depended upon, on the same source.
+    pub project_id: String,
+    /// The exact version of the project required, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version_id: Option<String>,
+    pub kind: DependencyKind,
+    /// What the project was called when the dependency was resolved.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<DependencyMetadata>,
+}
+
+/// A snapshot of a [`Dependency`]'s project, cached in the [`Component`]'s
+/// file so it can be shown without querying the source again.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DependencyMetadata {
+    pub slug: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+}
+
+impl Dependency {
+    /// The name of the project depended upon, or its ID if it was never
+    /// resolved.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        self.metadata
+            .as_ref()
+            .map_or(&self.project_id, |metadata| &metadata.name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, strum::Display)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum DependencyKind {
+    Required,
+    Optional,
+    Incompatible,
+    /// The dependency is bundled into the component's file.
+    Embedded,
+}
+
+/// A version of a project that is about to be added to the pack or replace
+/// one of its [`Component`]s, to be [checked](DependencyGraph::check) against
+/// the rest of the pack.
+#[derive(Debug, Clone, Copy)]
+pub struct Candidate<'c> {
+    pub slug: &'c str,
+    pub project_id: Option<&'c str>,
+    pub version_id: &'c str,
+    pub dependencies: &'c [Dependency],
+}
+
+/// A requirement of the [`Candidate`] that the rest of the pack can't satisfy,
+/// or the other way around.
+///
+/// Both sides are chains of `project@version` links, starting with whatever
+/// declares the requirement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub chain: Vec<String>,
+    pub clashes_with: Vec<String>,
+}
+
+/// A [`Component`] that depends on one about to be updated, and whether the
+/// new version still satisfies it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Impact {
+    pub slug: String,
+    pub kind: DependencyKind,
+    /// The exact version the dependent requires, if any.
+    pub pinned: Option<String>,
+    pub satisfied: bool,
+}
+
+impl Component {
+    /// This component, as a [`Candidate`] for
+    /// [checking](DependencyGraph::check).
+    #[must_use]
+    pub fn as_candidate(&self) -> Candidate<'_> {
+        Candidate {
+            slug: &self.slug,
+            project_id: self.project_id.as_deref(),
+            version_id: &self.version_id,
+            dependencies: &self.dependencies,
+        }
+    }
+}
+
+/// The dependency relationships between all the [`Component`]s of a pack.
+#[derive(Debug, Clone)]
+pub struct DependencyGraph<'a> {
+    components: &'a [Component],
+}
+
+impl<'a> DependencyGraph<'a> {
+    #[must_use]
+    pub const fn new(components: &'a [Component]) -> Self {
+        Self { components }
+    }
+
+    /// Find the [`Component`] a [`Dependency`] points to, if it's in the pack.
+    #[must_use]
+    pub fn resolve(&self, dependency: &Dependency) -> Option<&'a Component> {
+        self.components
+            .iter()
+            .find(|component| component.project_id.as_ref() == Some(&dependency.project_id))
+    }
+
+    /// The [`Component`]s that depend on the one with this slug in one of the
+    /// provided ways.
+    #[must_use]
+    pub fn dependents(&self, slug: &str, kinds: &[DependencyKind]) -> Vec<&'a Component> {
+        self.components
+            .iter()
+            .filter(|component| {
+                component.dependencies.iter().any(|dependency| {
+                    kinds.contains(&dependency.kind)
+                        && self
+                            .resolve(dependency)
+                            .is_some_and(|target| target.slug == slug)
+                })
+            })
+            .collect()
+    }
+
+    /// How replacing the [`Component`] with the `update`'s slug by the
+    /// `update` affects the components that depend on it.
+    #[must_use]
+    pub fn impact(&self, update: &Component) -> Vec<Impact> {
+        let kinds = [DependencyKind::Required, DependencyKind::Optional];
+        self.dependents(&update.slug, &kinds)
+            .into_iter()
+            .filter(|dependent| dependent.slug != update.slug)
+            .flat_map(|dependent| {
+                dependent
+                    .dependencies
+                    .iter()
+                    .filter(|dependency| kinds.contains(&dependency.kind))
+                    .filter(|dependency| {
+                        self.resolve(dependency)
+                            .is_some_and(|target| target.slug == update.slug)
+                    })
+                    .map(|dependency| Impact {
+                        slug: dependent.slug.clone(),
+                        kind: dependency.kind,
+                        pinned: dependency.version_id.clone(),
+                        satisfied: dependency
+                            .version_id
+                            .as_ref()
+                            .is_none_or(|pinned| *pinned == update.version_id),
+                    })
+            })
+            .collect()
+    }
+
+    /// Check whether a [`Candidate`] fits into the pack, replacing the
+    /// [`Component`] with the same slug if there is one.
+    ///
+    /// The candidate conflicts with the pack if:
+    /// - It requires an exact version of a component, and the pack has another
+    ///   version of it, or some other component requires another version of it;
+    /// - Another component requires an exact version of the candidate's
+    ///   project, and it's not the candidate's version;
+    /// - It's incompatible with a component, or the other way around.
+    #[must_use]
+    pub fn check(&self, candidate: &Candidate<'_>) -> Vec<Conflict> {
+        let link = |name: &str, version: &str| format!("{name}@{version}");
+        let candidate_link = link(candidate.slug, candidate.version_id);
+        let others = self
+            .components
+            .iter()
+            .filter(|component| component.slug != candidate.slug)
+            .collect::<Vec<_>>();
+        let mut conflicts = vec![];
+
+        for dependency in candidate.dependencies {
+            let target = self
+                .resolve(dependency)
+                .filter(|target| target.slug != candidate.slug);
+            let target_name = target.map_or(dependency.project_id.as_str(), |t| &t.slug);
+            match (dependency.kind, target) {
+                (DependencyKind::Incompatible, Some(target)) => conflicts.push(Conflict {
+                    chain: vec![
+                        candidate_link.clone(),
+                        format!("{target_name} (incompatible)"),
+                    ],
+                    clashes_with: vec![link(target_name, &target.version_id)],
+                }),
+                (DependencyKind::Required, _) => {
+                    let Some(pinned) = &dependency.version_id else {
+                        continue;
+                    };
+                    if let Some(target) = target.filter(|target| &target.version_id != pinned) {
+                        conflicts.push(Conflict {
+                            chain: vec![candidate_link.clone(), link(target_name, pinned)],
+                            clashes_with: vec![link(target_name, &target.version_id)],
+                        });
+                    }
+                    for other in &others {
+                        let other_pins = other.dependencies.iter().filter(|theirs| {
+                            theirs.kind == DependencyKind::Required
+                                && theirs.project_id == dependency.project_id
+                                && theirs.version_id.as_ref().is_some_and(|v| v != pinned)
+                        });
+                        for theirs in other_pins {
+                            conflicts.push(Conflict {
+                                chain: vec![candidate_link.clone(), link(target_name, pinned)],
+                                clashes_with: vec![
+                                    link(&other.slug, &other.version_id),
+                                    link(target_name, theirs.version_id.as_deref().unwrap_or("?")),
+                                ],
+                            });
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let Some(project_id) = candidate.project_id else {
+            return conflicts;
+        };
+        for other in &others {
+            for theirs in other
+                .dependencies
+                .iter()
+                .filter(|theirs| theirs.project_id == project_id)
+            {
+                let clash = match (theirs.kind, &theirs.version_id) {
+                    (DependencyKind::Incompatible, _) => {
+                        format!("{} (incompatible)", candidate.slug)
+                    }
+                    (DependencyKind::Required, Some(pinned)) if pinned != candidate.version_id => {
+                        link(candidate.slug, pinned)
+                    }
+                    _ => continue,
+                };
+                conflicts.push(Conflict {
+                    chain: vec![link(&other.slug, &other.version_id), clash],
+                    clashes_with: vec![candidate_link.clone()],
+                });
+            }
+        }
+
+        conflicts
+    }
+
+    /// The dependencies that would no longer be needed by anything if the
+    /// [`Component`]s with these slugs were removed, transitively.
+    ///
+    /// The returned components don't include the ones in `slugs`.
+    #[must_use]
+    pub fn cascade(&self, slugs: &[String]) -> Vec<&'a Component> {
+        let kinds = [DependencyKind::Required, DependencyKind::Optional];
+        let mut removed = slugs.iter().map(String::as_str).collect::<HashSet<_>>();
+        let mut cascaded = vec![];
+        loop {
+            let newly_unneeded = self
+                .components
+                .iter()
+                .filter(|component| removed.contains(component.slug.as_str()))
+                .flat_map(|component| &component.dependencies)
+                .filter(|dependency| kinds.contains(&dependency.kind))
+                .filter_map(|dependency| self.resolve(dependency))
+                .filter(|target| !removed.contains(target.slug.as_str()))
+                .filter(|target| {
+                    self.dependents(&target.slug, &kinds)
+                        .iter()
+                        .all(|dependent| removed.contains(dependent.slug.as_str()))
+                })
+                .unique_by(|target| &target.slug)
+                .collect::<Vec<_>>();
+            if newly_unneeded.is_empty() {
+                return cascaded;
+            }
+            removed.extend(newly_unneeded.iter().map(|target| target.slug.as_str()));
+            cascaded.extend(newly_unneeded);
+        }
+    }
+
+    /// Libraries nothing in the pack depends on anymore.
+    #[must_use]
+    pub fn orphans(&self) -> Vec<&'a Component> {
+        self.components
+            .iter()
+            .filter(|component| {
+                component.tags.main == Some(Tag::Library)
+                    || component.tags.others.contains(&Tag::Library)
+            })
+            .filter(|library| {
+                self.dependents(
+                    &library.slug,
+                    &[DependencyKind::Required, DependencyKind::Optional],
+                )
+                .is_empty()
+            })
+            .collect()
+    }
+
+    /// Pairs of [`Component`]s in the pack where the first declares itself
+    /// incompatible with the second.
+    #[must_use]
+    pub fn incompatible(&self) -> Vec<(&'a Component, &'a Component)> {
+        self.components
+            .iter()
+            .flat_map(|component| {
+                component
+                    .dependencies
+                    .iter()
+                    .filter(|dependency| dependency.kind == DependencyKind::Incompatible)
+                    .filter_map(|dependency| self.resolve(dependency))
+                    .filter(|target| target.slug != component.slug)
+                    .map(move |target| (component, target))
+            })
+            .collect()
+    }
+
+    /// Required dependencies that aren't in the pack, together with the
+    /// [`Component`]s requiring them.
+    #[must_use]
+    pub fn missing(&self) -> Vec<(&'a Component, &'a Dependency)> {
+        self.components
+            .iter()
+            .flat_map(|component| {
+                component
+                    .dependencies
+                    .iter()
+                    .map(move |dependency| (component, dependency))
+            })
+            .filter(|(_, dependency)| {
+                dependency.kind == DependencyKind::Required && self.resolve(dependency).is_none()
+            })
+            .collect()
+    }
+
+    fn write_subtree(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        component: &Component,
+        depth: usize,
+        ancestors: &mut HashSet<&'a str>,
+    ) -> fmt::Result {
+        for dependency in &component.dependencies {
+            let indent = "  ".repeat(depth);
+            let optional = match dependency.kind {
+                DependencyKind::Required => "",
+                DependencyKind::Optional => " (optional)",
+                DependencyKind::Incompatible | DependencyKind::Embedded => continue,
+            };
+            match self.resolve(dependency) {
+                Some(target) if ancestors.contains(target.slug.as_str()) => {
+                    writeln!(f, "{indent}└ {}{optional} (cycle)", target.slug)?;
+                }
+                Some(target) => {
+                    writeln!(f, "{indent}└ {}{optional}", target.slug)?;
+                    ancestors.insert(&target.slug);
+                    self.write_subtree(f, target, depth + 1, ancestors)?;
+                    ancestors.remove(target.slug.as_str());
+                }
+                None => writeln!(
+                    f,
+                    "{indent}└ {}{optional} {}",
+                    dependency.name(),
+                    "(missing)".red().bold()
+                )?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} conflicts with {}",
+            self.chain.iter().join(" -> "),
+            self.clashes_with.iter().join(" -> "),
+        )
+    }
+}
+
+impl fmt::Display for Impact {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let optional = match self.kind {
+            DependencyKind::Optional => " (optional)",
+            _ => "",
+        };
+        match (&self.pinned, self.satisfied) {
+            (None, _) => write!(f, "{}{optional}: any version", self.slug.bold()),
+            (Some(pinned), true) => write!(f, "{}{optional}: pins {pinned}", self.slug.bold()),
+            (Some(pinned), false) => write!(
+                f,
+                "{}{optional}: pins {pinned}, {}",
+                self.slug.bold(),
+                "no longer satisfied".red().bold()
+            ),
+        }
+    }
+}
+
+/// Prints every [`Component`] nothing else depends on, with its dependencies
+/// nested below it.
+impl fmt::Display for DependencyGraph<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut roots = self
+            .components
+            .iter()
+            .filter(|component| {
+                self.dependents(
+                    &component.slug,
+                    &[DependencyKind::Required, DependencyKind::Optional],
+                )
+                .is_empty()
+            })
+            .collect::<Vec<_>>();
+        roots.sort_unstable_by_key(|component| &component.slug);
+        for root in roots {
+            writeln!(f, "{}", root.slug.bold())?;
+            let mut ancestors = HashSet::from([root.slug.as_str()]);
+            self.write_subtree(f, root, 1, &mut ancestors)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Candidate, Dependency, DependencyGraph, DependencyKind};
+    use crate::component::{
+        Category, Component, Constraint, FileNaming, Source, Tag, TagInformation,
+    };
+    use crate::index::file::{Env, Hashes, Requirement};
+
+    fn component(slug: &str, library: bool, requires: &[&str]) -> Component {
+        Component {
+            slug: slug.to_string(),
+            source: Source::Modrinth,
+            project_id: Some(format!("{slug}-id")),
+            category: Category::Mod,
+            tags: TagInformation {
+                main: library.then_some(Tag::Library),
+                others: vec![],
+            },
+            environment: Env {
+                client: Requirement::Required,
+                server: Requirement::Required,
+            },
+            version_id: String::new(),
+            file_name: format!("{slug}.jar"),
+            file_size: 0,
+            download_url: "https://cdn.modrinth.com".parse().unwrap(),
+            hashes: Hashes::compute(&[]),
+            dependencies: requires
+                .iter()
+                .map(|slug| Dependency {
+                    project_id: format!("{slug}-id"),
+                    version_id: None,
+                    kind: DependencyKind::Required,
+                    metadata: None,
+                })
+                .collect(),
+            runtime_subdir: None,
+            constraint: Constraint::default(),
+            essential: false,
+            file_naming: FileNaming::default(),
+            group: None,
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn orphans_and_dependents() {
+        let components = [
+            component("create", false, &["flywheel", "ponder"]),
+            component("flywheel", true, &[]),
+            component("cloth-config", true, &[]),
+        ];
+        let graph = DependencyGraph::new(&components);
+        let slugs = |components: Vec<&Component>| {
+            components
+                .into_iter()
+                .map(|component| component.slug.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            slugs(graph.dependents("flywheel", &[DependencyKind::Required])),
+            ["create"]
+        );
+        assert_eq!(slugs(graph.orphans()), ["cloth-config"]);
+        assert_eq!(slugs(graph.cascade(&["create".to_string()])), ["flywheel"]);
+        assert_eq!(graph.missing().len(), 1);
+    }
+
+    #[test]
+    fn declared_incompatibilities_are_found() {
+        let mut optifine = component("optifine", false, &[]);
+        optifine.dependencies.push(Dependency {
+            project_id: "sodium-id".to_string(),
+            version_id: None,
+            kind: DependencyKind::Incompatible,
+            metadata: None,
+        });
+        let components = [optifine, component("sodium", false, &[])];
+        let graph = DependencyGraph::new(&components);
+        let pairs = graph.incompatible();
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(
+            (&*pairs[0].0.slug, &*pairs[0].1.slug),
+            ("optifine", "sodium")
+        );
+        assert!(DependencyGraph::new(&components[1..])
+            .incompatible()
+            .is_empty());
+    }
+
+    #[test]
+    fn pinned_versions_conflict() {
+        let pin = |version: &str| Dependency {
+            project_id: "fabric-api-id".to_string(),
+            version_id: Some(version.to_string()),
+            kind: DependencyKind::Required,
+            metadata: None,
+        };
+        let mut sodium = component("sodium", false, &[]);
+        sodium.dependencies.push(pin("0.92"));
+        let components = [sodium];
+        let graph = DependencyGraph::new(&components);
+
+        let iris_dependencies = [pin("0.100")];
+        let conflicts = graph.check(&Candidate {
+            slug: "iris",
+            project_id: Some("iris-id"),
+            version_id: "1.8",
+            dependencies: &iris_dependencies,
+        });
+        assert_eq!(
+            conflicts
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            ["iris@1.8 -> fabric-api-id@0.100 conflicts with sodium@ -> fabric-api-id@0.92"]
+        );
+    }
+
+    #[test]
+    fn updates_break_pinning_dependents() {
+        let mut fabric_api = component("fabric-api", true, &[]);
+        fabric_api.version_id = "0.92".to_string();
+        let mut sodium = component("sodium", false, &[]);
+        sodium.dependencies.push(Dependency {
+            project_id: "fabric-api-id".to_string(),
+            version_id: Some("0.92".to_string()),
+            kind: DependencyKind::Required,
+            metadata: None,
+        });
+        let components = [
+            fabric_api.clone(),
+            sodium,
+            component("iris", false, &["fabric-api"]),
+        ];
+        let graph = DependencyGraph::new(&components);
+
+        fabric_api.version_id = "0.100".to_string();
+        let impact = graph.impact(&fabric_api);
+        assert_eq!(
+            impact
+                .iter()
+                .map(|impact| (impact.slug.as_str(), impact.satisfied))
+                .collect::<Vec<_>>(),
+            [("sodium", false), ("iris", true)]
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use super::{Category, Component, Source, Tag};
+use crate::index::OverrideSide;
+use clap::ValueEnum;
+use serde_with::{DeserializeFromStr, SerializeDisplay};
+use std::fmt;
+use std::str::FromStr;
+
+/// A condition on [`Component`]s, written as `key=value`, to pick the ones a
+/// bulk operation applies to.
+#[derive(Debug, Clone, PartialEq, Eq, SerializeDisplay, DeserializeFromStr)]
+pub enum Filter {
+    /// `category=mod`
+    Category(Category),
+    /// `source=modrinth`
+    Source(Source),
+    /// `tag=performance`, matching both the main and the other tags.
+    Tag(Tag),
+    /// `main=performance`, or `main=none` for components without one.
+    Main(Option<Tag>),
+    /// `slug=sodium`
+    Slug(String),
+    /// `group=optional-visuals`, or `group=none` for components without one.
+    Group(Option<String>),
+    /// `side=server` (or `env=server`) for the components needed only on that
+    /// side, `side=both` for those needed everywhere.
+    Side(OverrideSide),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum FilterError {
+    #[error("Expected a `key=value` filter, got {0:?}")]
+    Malformed(String),
+    #[error("Unknown filter key {0:?}, expected category, source, tag, main, slug, group or side")]
+    UnknownKey(String),
+    #[error("Invalid value for the {key} filter: {message}")]
+    InvalidValue { key: String, message: String },
+}
+
+impl Filter {
+    /// Whether `component` satisfies this [`Filter`].
+    #[must_use]
+    pub fn matches(&self, component: &Component) -> bool {
+        match self {
+            Self::Category(category) => component.category == *category,
+            Self::Source(source) => component.source == *source,
+            Self::Tag(tag) => {
+                component.tags.main.as_ref() == Some(tag) || component.tags.others.contains(tag)
+            }
+            Self::Main(main) => component.tags.main == *main,
+            Self::Slug(slug) => component.slug == *slug,
+            Self::Group(group) => component.group == *group,
+            Self::Side(side) => OverrideSide::from_env(&component.environment) == *side,
+        }
+    }
+}
+
+impl FromStr for Filter {
+    type Err = FilterError;
+
+    fn from_str(filter: &str) -> Result<Self, Self::Err> {
+        let (key, value) = filter
+            .split_once('=')
+            .ok_or_else(|| FilterError::Malformed(filter.to_string()))?;
+        let (key, value) = (key.trim(), value.trim());
+        let invalid = |message: String| FilterError::InvalidValue {
+            key: key.to_string(),
+            message,
+        };
+        let filter = match key {
+            "category" => Self::Category(Category::from_str(value, true).map_err(invalid)?),
+            "source" => Self::Source(Source::from_str(value, true).map_err(invalid)?),
+            "tag" => Self::Tag(value.parse().unwrap_or_else(|never| match never {})),
+            "main" => Self::Main(match value {
+                "none" => None,
+                tag => Some(tag.parse().unwrap_or_else(|never| match never {})),
+            }),
+            "slug" => Self::Slug(value.to_string()),
+            "group" => Self::Group(match value {
+                "none" => None,
+                group => Some(group.to_string()),
+            }),
+            "side" | "env" => Self::Side(OverrideSide::from_str(value, true).map_err(invalid)?),
+            _ => return Err(FilterError::UnknownKey(key.to_string())),
+        };
+        Ok(filter)
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Category(category) => {
+                write!(f, "category={}", category.to_string().to_lowercase())
+            }
+            Self::Source(source) => write!(f, "source={source}"),
+            Self::Tag(tag) => write!(f, "tag={}", tag.to_string().to_lowercase()),
+            Self::Main(Some(tag)) => write!(f, "main={}", tag.to_string().to_lowercase()),
+            Self::Main(None) => write!(f, "main=none"),
+            Self::Slug(slug) => write!(f, "slug={slug}"),
+            Self::Group(Some(group)) => write!(f, "group={group}"),
+            Self::Group(None) => write!(f, "group=none"),
+            Self::Side(side) => write!(f, "side={side}"),
+        }
+    }
+}
@@ -1,30 +1,61 @@
-use crate::index::file::{Env, Hashes};
-use crate::instance::{Instance, Loader};
-use crate::local_storage;
+use crate::index::file::{Env, Hashes, Requirement};
+use crate::index::OverrideSide;
+use crate::instance::Instance;
+use crate::local_storage::{self, ComponentIndex, PersistedEntity};
+use crate::pack::Pack;
+use crate::plan::{self, Action, Plan};
+use crate::{network, progress, terminal};
+use clap::ValueEnum;
 use color_eyre::owo_colors::OwoColorize;
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use std::io::ErrorKind;
-use std::path::PathBuf;
-use std::str::FromStr;
+use std::path::{Path, PathBuf};
 use std::{fs, io};
 use strum::Display;
 use url::Url;
 
+mod attribution;
+mod constraint;
+mod dependency;
+mod filter;
+mod mod_list;
+mod outdated;
+mod probe;
+mod report;
 mod tag;
+mod verify;
+pub use attribution::*;
+pub use constraint::*;
+pub use dependency::*;
+pub use filter::*;
+pub use mod_list::*;
+pub use outdated::*;
+pub use probe::*;
+pub use report::*;
 pub use tag::*;
+pub use verify::*;
 
 /// [Modrinth](https://modrinth.com)-specific code.
 pub mod modrinth;
 
+/// [CurseForge](https://curseforge.com/minecraft)-specific code.
+pub mod curseforge;
+
 /// A (runtime) modpack component.
 ///
 /// A component is one of the elements that go into the `files` array of the
 /// `.mrpack` index. These usually represent mods, resourcepacks, shaderpacks,
 /// datapacks, but can be anything, if needed. New components are obtained from
-/// the **Modrinth API**.
+/// the **Modrinth API** or the **CurseForge API**.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Component {
     pub slug: String,
+    #[serde(default)]
+    pub source: Source,
+    /// The ID of the project on the [`Source`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<String>,
     pub category: Category,
     pub tags: tag::TagInformation,
     pub environment: Env,
@@ -33,15 +64,89 @@ pub struct Component {
     pub file_size: usize,
     pub download_url: Url,
     pub hashes: Hashes,
+    /// Other projects this component's current version depends on.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<Dependency>,
+    /// A directory nested in the [`Category`]'s one that the file is placed
+    /// into at runtime, like `yosbr` for `config/yosbr/options.txt`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub runtime_subdir: Option<PathBuf>,
+    /// Limits on the versions this component may be updated to.
+    #[serde(default, skip_serializing_if = "Constraint::is_empty")]
+    pub constraint: Constraint,
+    /// Whether the pack can't work without this component, so removing it
+    /// takes `--force`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub essential: bool,
+    /// What the file is called at runtime, since some shaders and
+    /// resourcepacks break when renamed (or need to be).
+    #[serde(default, skip_serializing_if = "FileNaming::is_original")]
+    pub file_naming: FileNaming,
+    /// The group of components this one is toggled with by the pack's
+    /// [`features`](crate::pack::Settings::features), like `optional-visuals`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// What the project is called and about, as its [`Source`] describes it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<ProjectMetadata>,
+}
+
+/// What a [`Component`]'s file is called at runtime.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileNaming {
+    /// Keep the name the file has on the [`Source`].
+    #[default]
+    Original,
+    /// Name the file after the component's slug, keeping its extension, like
+    /// `complementary-reimagined.zip`.
+    Id,
+    /// Use exactly this name.
+    Custom(String),
+}
+
+impl FileNaming {
+    #[must_use]
+    pub const fn is_original(&self) -> bool {
+        matches!(self, Self::Original)
+    }
+}
+
+/// `original` and `id` pick those policies, anything else is a custom name.
+impl std::str::FromStr for FileNaming {
+    type Err = std::convert::Infallible;
+
+    fn from_str(naming: &str) -> Result<Self, Self::Err> {
+        Ok(match naming {
+            "original" => Self::Original,
+            "id" => Self::Id,
+            name => Self::Custom(name.to_string()),
+        })
+    }
+}
+
+/// Remote APIs [`Component`]s can be fetched from.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Display, ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Source {
+    #[default]
+    Modrinth,
+    Curseforge,
 }
 
 /// Possible types (categories) of [`Component`]s.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Display)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Display, ValueEnum,
+)]
 #[serde(rename_all = "lowercase")]
 pub enum Category {
     Mod,
     Resourcepack,
     #[serde(alias = "shaderpack")]
+    #[value(alias = "shaderpack")]
     Shader,
     Datapack,
     Config,
@@ -59,7 +164,9 @@ impl Component {
     /// # Errors
     ///
     /// This function will propagate errors occurring while reading
-    /// files or deserialing [`Component`]s from their contents.
+    /// files or deserialing [`Component`]s from their contents, and return an
+    /// error if a component's file would end up outside its directory (see
+    /// [`unsafe_path`](Self::unsafe_path)).
     #[tracing::instrument]
     pub fn load_all() -> Result<Vec<Self>, local_storage::Error> {
         let mut components = vec![];
@@ -70,7 +177,13 @@ impl Component {
                 source,
                 faulty_path: Some(path.to_path_buf()),
             })?;
-            let component = serde_yml::from_str(&yaml)?;
+            let component: Self = serde_yml::from_str(&yaml)?;
+            if let Some(unsafe_path) = component.unsafe_path() {
+                return Err(local_storage::Error::UnsafePath {
+                    metadata: path.to_path_buf(),
+                    path: unsafe_path,
+                });
+            }
             components.push(component);
         }
 
@@ -83,30 +196,96 @@ impl Component {
     ///
     /// This function will return an error if there are no components with this
     /// slug or an error occurs when deleting it.
-    pub fn remove(slug: &str) -> Result<(), local_storage::Error> {
-        let target_filename = format!("{slug}{}", Self::LOCAL_STORAGE_SUFFIX);
-        let candidate = local_storage::metadata_files(".")?.find(|dir_entry| {
-            dir_entry
-                .file_name()
-                .to_str()
-                .is_some_and(|name| name == target_filename)
-        });
-        match candidate {
-            Some(file) => {
-                fs::remove_file(file.path()).map_err(|source| local_storage::Error::Io {
-                    source,
-                    faulty_path: Some(file.path().to_path_buf()),
-                })?;
-            }
-            None => {
-                return Err(local_storage::Error::Io {
-                    source: io::Error::new(ErrorKind::NotFound, "Failed to find file"),
-                    faulty_path: None,
-                })
-            }
+    pub fn remove(slug: &str) -> Result<(), plan::Error> {
+        Self::plan_remove(slug)?.apply()
+    }
+
+    /// Compute the [`Plan`] for [removing](Self::remove) a [`Component`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there are no components with this
+    /// slug or an error occurs while scanning the local storage.
+    pub fn plan_remove(slug: &str) -> Result<Plan, local_storage::Error> {
+        let path = Self::find_metadata_file(".", slug)?;
+        let mut plan = Plan::new();
+        plan.push(Action::RemoveFile { path });
+        Ok(plan)
+    }
+
+    /// Move a [`Component`] by slug into another pack's local storage.
+    ///
+    /// The metadata file keeps its relative location (and thus its tags), so
+    /// the component ends up at the same [local storage
+    /// path](Self::local_storage_path) inside `target_pack`. Returns the new
+    /// path of the metadata file.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there are no components with this
+    /// slug, if `target_pack` doesn't contain a pack, if the target pack
+    /// already has a component at that path, or if moving the file fails.
+    pub fn move_to_pack(slug: &str, target_pack: &Path) -> Result<PathBuf, plan::Error> {
+        let (plan, destination) = Self::plan_move_to_pack(slug, target_pack)?;
+        plan.apply()?;
+        Ok(destination)
+    }
+
+    /// Compute the [`Plan`] for [moving](Self::move_to_pack) a [`Component`],
+    /// together with the path the metadata file will end up at.
+    ///
+    /// # Errors
+    ///
+    /// See [`Component::move_to_pack`].
+    pub fn plan_move_to_pack(
+        slug: &str,
+        target_pack: &Path,
+    ) -> Result<(Plan, PathBuf), local_storage::Error> {
+        let target_manifest = target_pack.join(<Pack as PersistedEntity>::FILE_PATH);
+        if !target_manifest.is_file() {
+            return Err(local_storage::Error::Io {
+                source: io::Error::new(ErrorKind::NotFound, "No pack in the target directory"),
+                faulty_path: Some(target_manifest),
+            });
         }
 
-        Ok(())
+        let source = Self::find_metadata_file(".", slug)?;
+        let relative = source.strip_prefix(".").unwrap_or(&source);
+        let destination = target_pack.join(relative);
+        if destination.exists() {
+            return Err(local_storage::Error::Io {
+                source: io::Error::new(ErrorKind::AlreadyExists, "Component already exists"),
+                faulty_path: Some(destination),
+            });
+        }
+
+        // NOTE: `fs::rename` won't work across filesystems, and packs may well
+        // live on different ones, so copy first and only then remove the original.
+        let mut plan = Plan::new();
+        plan.push(Action::CopyFile {
+            from: source.clone(),
+            to: destination.clone(),
+        })
+        .push(Action::RemoveFile { path: source });
+
+        Ok((plan, destination))
+    }
+
+    /// Find the metadata file of a [`Component`] by slug under `root`,
+    /// looking it up in the [`ComponentIndex`] first.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there are no components with this
+    /// slug or an error occurs while scanning the local storage.
+    pub fn find_metadata_file<P>(root: P, slug: &str) -> Result<PathBuf, local_storage::Error>
+    where
+        P: AsRef<Path>,
+    {
+        ComponentIndex::find(root.as_ref(), slug)?.ok_or_else(|| local_storage::Error::Io {
+            source: io::Error::new(ErrorKind::NotFound, "Failed to find file"),
+            faulty_path: None,
+        })
     }
 
     /// Saves this [`Component`] in its metadata directory.
@@ -114,25 +293,101 @@ impl Component {
     /// # Errors
     ///
     /// This function will return an error if a [`local_storage::Error`] occurs.
+    pub fn save_to_metadata_dir(&self) -> Result<(), plan::Error> {
+        self.plan_save()?.apply()
+    }
+
+    /// Check that this new [`Component`] isn't in the pack yet under another
+    /// slug, like an alias or as a dependency pulled in earlier: as the same
+    /// project of the same [`Source`], or as a file at the same runtime path.
     ///
-    /// # Panics
+    /// Components with this one's slug are expected to be replaced by it, so
+    /// they don't count.
     ///
-    /// This function will panic if the [parent](std::path::Path::parent) of
-    /// this [`Component`]'s [local storage path](Self::local_storage_path)
-    /// ends up being [`None`], which shouldn't happen.
-    pub fn save_to_metadata_dir(&self) -> Result<(), local_storage::Error> {
+    /// # Errors
+    ///
+    /// Returns [`AddError::Duplicate`] naming the component already in the
+    /// pack.
+    pub fn check_duplicate(&self, installed: &[Self], world: &str) -> Result<(), AddError> {
+        let runtime_path = self.runtime_path(world);
+        for other in installed.iter().filter(|other| other.slug != self.slug) {
+            let same_project = other.source == self.source
+                && other.project_id.is_some()
+                && other.project_id == self.project_id;
+            let duplicate = match (same_project, other.runtime_path(world) == runtime_path) {
+                (true, _) => "project",
+                (false, true) => "file",
+                (false, false) => continue,
+            };
+            return Err(AddError::Duplicate {
+                existing: other.slug.clone(),
+                by: duplicate,
+            });
+        }
+        Ok(())
+    }
+
+    /// Compute the [`Plan`] for [saving](Self::save_to_metadata_dir) this
+    /// [`Component`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if serializing the component fails.
+    pub fn plan_save(&self) -> Result<Plan, local_storage::Error> {
         let yaml = serde_yml::to_string(self)?;
-        let path = self.local_storage_path();
-        fs::create_dir_all(path.parent().unwrap()).map_err(|source| local_storage::Error::Io {
-            source,
-            faulty_path: Some(path.clone()),
-        })?;
-        fs::write(&path, yaml).map_err(|source| local_storage::Error::Io {
-            source,
-            faulty_path: Some(path.clone()),
-        })?;
+        let mut plan = Plan::new();
+        plan.push(Action::write_file(self.local_storage_path(), yaml));
+        Ok(plan)
+    }
 
-        Ok(())
+    /// Compute the [`Plan`] for giving this [`Component`] other `tags`.
+    ///
+    /// A new main tag moves the component's metadata file, since the main tag
+    /// is a part of its [local storage path](Self::local_storage_path).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the component's metadata file
+    /// can't be found or serializing the component fails.
+    pub fn plan_retag(&self, tags: TagInformation) -> Result<Plan, local_storage::Error> {
+        let current_path = Self::find_metadata_file(".", &self.slug)?;
+        let retagged = Self {
+            tags,
+            ..self.clone()
+        };
+        let mut plan = Plan::new();
+        let relative_path = current_path.strip_prefix(".").unwrap_or(&current_path);
+        if retagged.local_storage_path() != relative_path {
+            plan.push(Action::RemoveFile { path: current_path });
+        }
+        plan.extend(retagged.plan_save()?);
+        Ok(plan)
+    }
+
+    /// Compute the [`Plan`] for renaming this [`Component`] to `new_slug`,
+    /// moving its metadata file along, and return the renamed component.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the component's metadata file
+    /// can't be found or serializing the component fails.
+    pub fn plan_rename(&self, new_slug: &str) -> Result<(Self, Plan), local_storage::Error> {
+        let current_path = Self::find_metadata_file(".", &self.slug)?;
+        let renamed = Self {
+            slug: new_slug.to_string(),
+            ..self.clone()
+        };
+        let mut plan = Plan::new();
+        plan.push(Action::RemoveFile { path: current_path });
+        plan.extend(renamed.plan_save()?);
+        Ok((renamed, plan))
+    }
+
+    /// This component's slug along with the version it's pinned to, like
+    /// `create@6R069CcK`.
+    #[must_use]
+    pub fn pinned_id(&self) -> String {
+        format!("{}@{}", self.slug, self.version_id)
     }
 
     /// Construct a path where this component should be stored.
@@ -146,11 +401,63 @@ impl Component {
         path
     }
 
+    /// Limit this component to `side`, like [`OverrideSide::Server`] for
+    /// server-only mods. [`OverrideSide::Both`] makes it required everywhere.
+    pub fn set_side(&mut self, side: OverrideSide) {
+        self.environment = side.env().unwrap_or(Env {
+            client: Requirement::Required,
+            server: Requirement::Required,
+        });
+    }
+
+    /// The name of this component's file at runtime, according to its
+    /// [`FileNaming`].
+    #[must_use]
+    pub fn runtime_file_name(&self) -> String {
+        match &self.file_naming {
+            FileNaming::Original => self.file_name.clone(),
+            FileNaming::Id => match Path::new(&self.file_name).extension() {
+                Some(extension) => format!("{}.{}", self.slug, extension.to_string_lossy()),
+                None => self.slug.clone(),
+            },
+            FileNaming::Custom(name) => name.clone(),
+        }
+    }
+
+    /// The part of this component's runtime location that isn't a plain
+    /// relative path, like a file name with a `/` in it or a `..` subdir, if
+    /// there is one. Such paths could place the file anywhere on disk.
+    #[must_use]
+    pub fn unsafe_path(&self) -> Option<PathBuf> {
+        let is_plain = |path: &Path| {
+            path.components()
+                .all(|component| matches!(component, std::path::Component::Normal(_)))
+        };
+        let file_name = PathBuf::from(self.runtime_file_name());
+        if file_name.components().count() != 1 || !is_plain(&file_name) {
+            return Some(file_name);
+        }
+        self.runtime_subdir
+            .clone()
+            .filter(|subdir| !is_plain(subdir))
+    }
+
     /// Construct a path where this component should be at runtime.
+    ///
+    /// Datapacks go into the `world` directory, since that's where the game
+    /// loads them from. The path is nested into the
+    /// [`runtime_subdir`](Self::runtime_subdir), if there is one, and ends
+    /// with the [runtime file name](Self::runtime_file_name).
     #[must_use]
-    pub fn runtime_path(&self) -> PathBuf {
-        let mut path = PathBuf::from(self.category);
-        path.push(&self.file_name);
+    pub fn runtime_path(&self, world: &str) -> PathBuf {
+        let mut path = match self.category {
+            Category::Datapack => Path::new(world).join(PathBuf::from(self.category)),
+            category => PathBuf::from(category),
+        };
+        if let Some(subdir) = &self.runtime_subdir {
+            path.push(subdir);
+        }
+        path.push(self.runtime_file_name());
         path
     }
 
@@ -160,7 +467,13 @@ impl Component {
     /// 1. Get the component's available versions from [`/project/{id|slug}/version`](https://docs.modrinth.com/#tag/versions/operation/getProjectVersions).
     /// 2. Filter the versions based on the `loaders` and `game_versions`
     ///    fields.
-    /// 3. Pick the latest from the compatible ones.
+    /// 3. Drop the versions that [conflict](DependencyGraph::check) with the
+    ///    rest of the pack.
+    /// 4. Let the user pick one of the remaining ones, or take the latest when
+    ///    running [non-interactively](terminal::set_interactive).
+    ///
+    /// The component gets the provided `tags`, or the ones the user picks if
+    /// there are none.
     ///
     /// # Errors
     ///
@@ -168,43 +481,25 @@ impl Component {
     /// - It fails to query the Modrinth API;
     /// - None of the versions of the component are compatible with the provided
     ///   [`Instance`];
+    /// - All the compatible versions conflict with the pack;
     /// - There are no URLs to where the component's file can be downloaded
     ///   (unlikely...)
-    #[tracing::instrument]
-    pub fn fetch_from_modrinth(slug: &str, instance: &Instance) -> Result<Self, AddError> {
-        let metadata_url = format!("https://api.modrinth.com/v2/project/{slug}");
-        let versions_url = format!("https://api.modrinth.com/v2/project/{slug}/version");
-        let metadata: modrinth::Metadata = reqwest::blocking::get(metadata_url)?.json()?;
-        let mut versions: Vec<modrinth::Version> = reqwest::blocking::get(versions_url)?.json()?;
-
-        // Only leave versions that are both loader- and version-compatible with the
-        // instance.
-        versions.retain(|v| {
-            // Resourcepacks and shaders may be loaded even if they are made for a different
-            // version.
-            let version_insensitive =
-                [Category::Resourcepack, Category::Shader].contains(&metadata.category);
-            let version_compatible = v.game_versions.iter().any(|v| {
-                semver::Version::from_str(v).is_ok_and(|v| v == instance.minecraft_version)
-            });
-            let version_compatible = version_insensitive || version_compatible;
-            let loader_compatible = v.loaders.iter().any(|l| {
-                *l == instance.loader
-                    || instance.allowed_foreign_loaders.contains(l)
-                    || *l == Loader::Other
-            });
-            loader_compatible && version_compatible
-        });
-
-        for version in &mut versions {
-            version.loaders.dedup();
-        }
-        versions.sort_unstable_by_key(|version| version.date_published);
-        versions.reverse();
-
+    #[tracing::instrument(skip(graph))]
+    pub fn fetch_from_modrinth(
+        slug: &str,
+        instance: &Instance,
+        graph: &DependencyGraph<'_>,
+        tags: Option<TagInformation>,
+    ) -> Result<Self, AddError> {
+        let (metadata, mut versions) = modrinth::fetch_compatible_versions(slug, instance)?;
+        versions.retain(|version| instance.accepts_channel(version.version_type));
+        let versions = retain_fitting(graph, slug, &metadata.id, versions, |version| {
+            (version.id.clone(), version.dependencies())
+        })?;
         let version = match versions.len() {
-            0 => return Err(AddError::Incompatible),
-            1 => versions.first().unwrap_or_else(|| unreachable!()),
+            count if count < 2 || !terminal::is_interactive() => {
+                versions.first().ok_or(AddError::Incompatible)?
+            }
             count => {
                 let message = format!(
                     "{count} compatible versions of {} found, choose one:",
@@ -214,22 +509,25 @@ impl Component {
                     "NOTE: this component will be added as a '{}', so pick a version with the right loaders",
                     metadata.category
                 );
-                &inquire::Select::new(&message, versions)
-                    .with_help_message(&help)
-                    .prompt()?
+                &terminal::suspend(|| {
+                    inquire::Select::new(&message, versions)
+                        .with_help_message(&help)
+                        .prompt()
+                })?
             }
         };
 
         let file = version.files.first().ok_or(AddError::NoFile)?;
-        let main_tag = self::tag::pick_main_tag()?;
-        let other_tags = self::tag::pick_secondary_tags(main_tag.as_ref())?;
+        let tags = match tags {
+            Some(tags) => tags,
+            None => tag::pick_tags()?,
+        };
         let component = Self {
             slug: slug.to_owned(),
+            source: Source::Modrinth,
+            project_id: Some(metadata.id),
             category: metadata.category,
-            tags: tag::TagInformation {
-                main: main_tag,
-                others: other_tags,
-            },
+            tags,
             environment: Env {
                 client: metadata.client_side,
                 server: metadata.server_side,
@@ -239,10 +537,370 @@ impl Component {
             file_size: file.size,
             download_url: file.url.clone(),
             hashes: file.hashes.clone(),
+            dependencies: version.dependencies(),
+            runtime_subdir: None,
+            constraint: Constraint::default(),
+            essential: instance.loader.is_essential(slug),
+            file_naming: FileNaming::default(),
+            group: None,
+            metadata: None,
         };
+        progress::resolved(&component);
 
         Ok(component)
     }
+
+    /// Fetch a [`Component`] from the **CurseForge API**.
+    ///
+    /// The API key is read from [`curseforge::API_KEY_VAR`]. Picking the
+    /// version and the tags works the same way as in
+    /// [`Component::fetch_from_modrinth`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - The API key isn't set;
+    /// - It fails to query the CurseForge API or download the file;
+    /// - The project's class isn't supported by Invar;
+    /// - None of the files of the component are compatible with the provided
+    ///   [`Instance`];
+    /// - All the compatible files conflict with the pack.
+    #[tracing::instrument(skip(graph))]
+    pub fn fetch_from_curseforge(
+        slug: &str,
+        instance: &Instance,
+        graph: &DependencyGraph<'_>,
+        tags: Option<TagInformation>,
+    ) -> Result<Self, AddError> {
+        let client = curseforge::client()?;
+        let project = curseforge::fetch_project(&client, slug)?;
+        let category = project.category()?;
+        let mut files = curseforge::fetch_compatible_files(&client, &project, instance)?;
+        files.retain(|file| instance.accepts_channel(file.channel()));
+        let files = retain_fitting(
+            graph,
+            &project.slug,
+            &project.id.to_string(),
+            files,
+            |file| (file.id.to_string(), file.dependencies()),
+        )?;
+        let file = match files.len() {
+            count if count < 2 || !terminal::is_interactive() => {
+                files.into_iter().next().ok_or(AddError::Incompatible)?
+            }
+            count => {
+                let message = format!(
+                    "{count} compatible files of {} found, choose one:",
+                    project.name.magenta().bold()
+                );
+                terminal::suspend(|| inquire::Select::new(&message, files).prompt())?
+            }
+        };
+
+        let tags = match tags {
+            Some(tags) => tags,
+            None => tag::pick_tags()?,
+        };
+        let component = Self {
+            slug: project.slug.clone(),
+            source: Source::Curseforge,
+            project_id: Some(project.id.to_string()),
+            category,
+            tags,
+            environment: file.environment(),
+            version_id: file.id.to_string(),
+            hashes: file.fetch_hashes()?,
+            dependencies: file.dependencies(),
+            file_name: file.file_name,
+            file_size: file.file_length,
+            download_url: file.download_url.ok_or(AddError::NoFile)?,
+            runtime_subdir: None,
+            constraint: Constraint::default(),
+            essential: instance.loader.is_essential(&project.slug),
+            file_naming: FileNaming::default(),
+            group: None,
+            metadata: None,
+        };
+        progress::resolved(&component);
+
+        Ok(component)
+    }
+
+    /// Fetch the newest compatible version of this [`Component`] from its
+    /// [`Source`].
+    ///
+    /// Returns [`None`] if this component is already at the newest compatible
+    /// version. Versions that [conflict](DependencyGraph::check) with the rest
+    /// of the pack are skipped. Tags and the environment are kept as they are.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - It fails to query the Modrinth API;
+    /// - None of the versions of the component are compatible with the provided
+    ///   [`Instance`];
+    /// - All the newer versions conflict with the pack;
+    /// - The newest fitting version has no files associated.
+    #[tracing::instrument(skip(self, graph), fields(slug = self.slug))]
+    pub fn fetch_update(
+        &self,
+        instance: &Instance,
+        graph: &DependencyGraph<'_>,
+    ) -> Result<Option<Self>, AddError> {
+        self.fetch_update_for(instance, graph, false)
+    }
+
+    /// Fetch the newest version of this [`Component`] compatible with another
+    /// [`Instance`], like [`fetch_update`](Self::fetch_update) does, but for
+    /// moving the pack to it.
+    ///
+    /// Unless the current version is compatible with `instance`, every
+    /// compatible version is a candidate, even ones older than it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error in the same cases as
+    /// [`fetch_update`](Self::fetch_update).
+    #[tracing::instrument(skip(self, graph), fields(slug = self.slug))]
+    pub fn fetch_retarget(
+        &self,
+        instance: &Instance,
+        graph: &DependencyGraph<'_>,
+    ) -> Result<Option<Self>, AddError> {
+        self.fetch_update_for(instance, graph, true)
+    }
+
+    fn fetch_update_for(
+        &self,
+        instance: &Instance,
+        graph: &DependencyGraph<'_>,
+        retarget: bool,
+    ) -> Result<Option<Self>, AddError> {
+        let update = match self.source {
+            Source::Curseforge => self.fetch_curseforge_update(instance, graph, retarget)?,
+            Source::Modrinth => match self.newer_versions(instance, graph, retarget)?.first() {
+                Some(newest) => Some(self.with_version(newest)?),
+                None => None,
+            },
+        };
+        if let Some(update) = &update {
+            progress::resolved(update);
+        }
+        Ok(update)
+    }
+
+    /// Fetch all compatible versions of this [`Component`] that were published
+    /// after its current version, newest first.
+    ///
+    /// If the current version is no longer among the compatible ones, it is
+    /// fetched to only keep the ones published after it. Versions not allowed
+    /// by the component's [`Constraint`] are left out; a pinned version is
+    /// returned even if it's older than the current one. Versions that
+    /// [conflict](DependencyGraph::check) with the rest of the pack are left
+    /// out. Only [`Source::Modrinth`] components are supported.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the component isn't from
+    /// Modrinth, it fails to query the Modrinth API, if none of the
+    /// versions of the component are compatible with the provided
+    /// [`Instance`], or if all the newer ones conflict with the pack.
+    #[tracing::instrument(skip(self, graph), fields(slug = self.slug))]
+    pub fn fetch_newer_versions(
+        &self,
+        instance: &Instance,
+        graph: &DependencyGraph<'_>,
+    ) -> Result<Vec<modrinth::Version>, AddError> {
+        self.newer_versions(instance, graph, false)
+    }
+
+    /// See [`fetch_newer_versions`](Self::fetch_newer_versions). On `retarget`,
+    /// versions aren't compared to an incompatible current one.
+    fn newer_versions(
+        &self,
+        instance: &Instance,
+        graph: &DependencyGraph<'_>,
+        retarget: bool,
+    ) -> Result<Vec<modrinth::Version>, AddError> {
+        if self.source != Source::Modrinth {
+            return Err(AddError::Unsupported(self.source));
+        }
+        let (_, mut versions) = modrinth::fetch_compatible_versions(&self.slug, instance)?;
+        if versions.is_empty() {
+            return Err(AddError::Incompatible);
+        }
+        let current = versions.iter().position(|v| v.id == self.version_id);
+        match (current, &self.constraint.pin) {
+            (_, Some(_)) => {}
+            (Some(current), None) => versions.truncate(current),
+            (None, None) if retarget => {}
+            (None, None) => {
+                let published = modrinth::fetch_version(&self.version_id)?.date_published;
+                versions.retain(|version| version.date_published > published);
+            }
+        }
+        let constraint = self.constraint.or_channel(instance.release_channel);
+        versions.retain(|version| {
+            version.id != self.version_id
+                && constraint.allows(&version.id, version.version_type, &version.version_number)
+        });
+        let project_id = self.project_id.clone().unwrap_or_default();
+        retain_fitting(graph, &self.slug, &project_id, versions, |version| {
+            (version.id.clone(), version.dependencies())
+        })
+    }
+
+    fn fetch_curseforge_update(
+        &self,
+        instance: &Instance,
+        graph: &DependencyGraph<'_>,
+        retarget: bool,
+    ) -> Result<Option<Self>, AddError> {
+        let client = curseforge::client()?;
+        let project =
+            curseforge::fetch_project(&client, self.project_id.as_ref().unwrap_or(&self.slug))?;
+        let mut files = curseforge::fetch_compatible_files(&client, &project, instance)?;
+        if files.is_empty() {
+            return Err(AddError::Incompatible);
+        }
+        let current = files
+            .iter()
+            .position(|f| f.id.to_string() == self.version_id);
+        match (current, &self.constraint.pin) {
+            (_, Some(_)) => {}
+            (Some(current), None) => files.truncate(current),
+            (None, None) if retarget => {}
+            (None, None) => {
+                let published =
+                    curseforge::fetch_file(&client, &project.id.to_string(), &self.version_id)?
+                        .file_date;
+                files.retain(|file| file.file_date > published);
+            }
+        }
+        let constraint = self.constraint.or_channel(instance.release_channel);
+        files.retain(|file| {
+            let id = file.id.to_string();
+            id != self.version_id && constraint.allows(&id, file.channel(), &file.display_name)
+        });
+        let files = retain_fitting(graph, &self.slug, &project.id.to_string(), files, |file| {
+            (file.id.to_string(), file.dependencies())
+        })?;
+        let Some(newest) = files.into_iter().next() else {
+            return Ok(None);
+        };
+
+        Ok(Some(Self {
+            version_id: newest.id.to_string(),
+            hashes: newest.fetch_hashes()?,
+            dependencies: self.keep_dependency_metadata(newest.dependencies()),
+            file_name: newest.file_name,
+            file_size: newest.file_length,
+            download_url: newest.download_url.ok_or(AddError::NoFile)?,
+            ..self.clone()
+        }))
+    }
+
+    /// Construct a copy of this [`Component`] pinned to another (Modrinth)
+    /// version.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the version has no files.
+    pub fn with_version(&self, version: &modrinth::Version) -> Result<Self, AddError> {
+        let file = version.files.first().ok_or(AddError::NoFile)?;
+        Ok(Self {
+            version_id: version.id.clone(),
+            file_name: file.filename.clone(),
+            file_size: file.size,
+            download_url: file.url.clone(),
+            hashes: file.hashes.clone(),
+            dependencies: self.keep_dependency_metadata(version.dependencies()),
+            ..self.clone()
+        })
+    }
+
+    /// Look up the [`DependencyMetadata`] of the dependencies that don't have
+    /// it yet and cache it in this [`Component`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it fails to query the component's
+    /// [`Source`].
+    pub fn fetch_dependency_metadata(&mut self) -> Result<(), AddError> {
+        let ids = self
+            .dependencies
+            .iter()
+            .filter(|dependency| dependency.metadata.is_none())
+            .map(|dependency| dependency.project_id.clone())
+            .unique()
+            .collect::<Vec<_>>();
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let mut found = match self.source {
+            Source::Modrinth => modrinth::fetch_metadata(&ids)?,
+            Source::Curseforge => curseforge::fetch_metadata(&curseforge::client()?, &ids)?,
+        };
+        for dependency in &mut self.dependencies {
+            if dependency.metadata.is_none() {
+                dependency.metadata = found.remove(&dependency.project_id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Carry the cached [`DependencyMetadata`] of this [`Component`] over to
+    /// the `dependencies` of another one of its versions.
+    fn keep_dependency_metadata(&self, mut dependencies: Vec<Dependency>) -> Vec<Dependency> {
+        for dependency in &mut dependencies {
+            dependency.metadata = self
+                .dependencies
+                .iter()
+                .find(|known| known.project_id == dependency.project_id)
+                .and_then(|known| known.metadata.clone());
+        }
+        dependencies
+    }
+}
+
+/// Drop the versions of a project that [conflict](DependencyGraph::check) with
+/// the rest of the pack, keeping their order.
+///
+/// `describe` provides the version ID and dependencies of each version.
+///
+/// # Errors
+///
+/// This function will return [`AddError::Conflicts`] with the conflicts of the
+/// first version if there were some versions, but all of them conflict.
+fn retain_fitting<T>(
+    graph: &DependencyGraph<'_>,
+    slug: &str,
+    project_id: &str,
+    versions: Vec<T>,
+    describe: impl Fn(&T) -> (String, Vec<Dependency>),
+) -> Result<Vec<T>, AddError> {
+    let mut first_conflicts = None;
+    let fitting = versions
+        .into_iter()
+        .filter(|version| {
+            let (version_id, dependencies) = describe(version);
+            let conflicts = graph.check(&Candidate {
+                slug,
+                project_id: Some(project_id).filter(|id| !id.is_empty()),
+                version_id: &version_id,
+                dependencies: &dependencies,
+            });
+            if conflicts.is_empty() {
+                return true;
+            }
+            first_conflicts.get_or_insert(conflicts);
+            false
+        })
+        .collect::<Vec<_>>();
+    match first_conflicts {
+        Some(conflicts) if fitting.is_empty() => Err(AddError::Conflicts(conflicts)),
+        _ => Ok(fitting),
+    }
 }
 
 /// This [`From`] implementation represents the [`Category`] to `folder
@@ -261,15 +919,43 @@ impl From<Category> for PathBuf {
     }
 }
 
-/// Errors that may arise when adding a new [`Component`].
+/// Errors that may arise when adding a new [`Component`] or updating an
+/// existing one.
 #[derive(thiserror::Error, Debug)]
 pub enum AddError {
     #[error("API error: {0:?}")]
     Api(#[from] reqwest::Error),
+    #[error("The API can't be queried in offline mode")]
+    Offline,
+    #[error("Failed to parse the API's response")]
+    Parse(#[from] serde_json::Error),
     #[error("Could not find a compatible version of this component")]
     Incompatible,
     #[error("The latest compatible version of this component has no files associated")]
     NoFile,
     #[error("Failed to get required input from user")]
     User(#[from] inquire::error::InquireError),
+    #[error("Could not find this component")]
+    NotFound,
+    #[error("The {0} environment variable must be set to use this API")]
+    MissingApiKey(&'static str),
+    #[error("Unsupported CurseForge project class: {0}")]
+    UnsupportedClass(u32),
+    #[error("The downloaded file's hash does not match the expected one")]
+    HashMismatch,
+    #[error("This operation is not supported for components from {0}")]
+    Unsupported(Source),
+    #[error("Dependency conflicts: {}", .0.iter().join("; "))]
+    Conflicts(Vec<Conflict>),
+    #[error("The pack already has this {by} as {existing}")]
+    Duplicate { existing: String, by: &'static str },
+}
+
+impl From<network::Error> for AddError {
+    fn from(error: network::Error) -> Self {
+        match error {
+            network::Error::Offline => Self::Offline,
+            network::Error::Request(error) => Self::Api(error),
+        }
+    }
 }
@@ -0,0 +1,241 @@
+use super::{curseforge, modrinth, Category, Component, DependencyMetadata, Source};
+use crate::index::OverrideSide;
+use clap::ValueEnum;
+use itertools::Itertools;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Write;
+use url::Url;
+
+/// Formats a [`ModList`] can be rendered in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, strum::Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum ListFormat {
+    #[default]
+    Markdown,
+    Csv,
+    Html,
+}
+
+/// A shareable list of a pack's [`Component`]s, like for the pack's page.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ModList {
+    pub entries: Vec<ListEntry>,
+}
+
+/// A [`Component`] as shown in a [`ModList`].
+#[derive(Debug, Clone, Serialize)]
+pub struct ListEntry {
+    pub slug: String,
+    /// The project's display name, or its slug if it couldn't be looked up.
+    pub name: String,
+    pub category: Category,
+    pub file_name: String,
+    pub tags: Vec<String>,
+    pub side: OverrideSide,
+    pub url: Option<Url>,
+    /// The project's short description, if its metadata was looked up.
+    pub summary: Option<String>,
+    pub icon_url: Option<Url>,
+}
+
+impl ModList {
+    /// List `components`, sorted by category and name.
+    ///
+    /// Display names are taken from the
+    /// [`ProjectMetadata`](super::ProjectMetadata) and dependency metadata
+    /// the components already keep, and looked up on their [`Source`]s for
+    /// the rest. Names that can't be looked up fall back to slugs, with a
+    /// warning logged.
+    #[must_use]
+    pub fn new(components: &[Component]) -> Self {
+        let mut names = components
+            .iter()
+            .flat_map(|component| &component.dependencies)
+            .filter_map(|dependency| {
+                let metadata = dependency.metadata.as_ref()?;
+                Some((dependency.project_id.clone(), metadata.name.clone()))
+            })
+            .collect::<HashMap<_, _>>();
+        for source in [Source::Modrinth, Source::Curseforge] {
+            let missing = components
+                .iter()
+                .filter(|component| component.source == source && component.metadata.is_none())
+                .filter_map(|component| component.project_id.clone())
+                .filter(|id| !names.contains_key(id))
+                .unique()
+                .collect::<Vec<_>>();
+            if missing.is_empty() {
+                continue;
+            }
+            let found = match source {
+                Source::Modrinth => modrinth::fetch_metadata(&missing),
+                Source::Curseforge => curseforge::client()
+                    .and_then(|client| curseforge::fetch_metadata(&client, &missing)),
+            };
+            match found {
+                Ok(found) => names.extend(
+                    found
+                        .into_iter()
+                        .map(|(id, DependencyMetadata { name, .. })| (id, name)),
+                ),
+                Err(error) => tracing::warn!(%source, %error, "Failed to look up the names"),
+            }
+        }
+
+        let entries = components
+            .iter()
+            .map(|component| ListEntry {
+                slug: component.slug.clone(),
+                name: component
+                    .metadata
+                    .as_ref()
+                    .map(|metadata| &metadata.name)
+                    .or_else(|| component.project_id.as_ref().and_then(|id| names.get(id)))
+                    .unwrap_or(&component.slug)
+                    .clone(),
+                category: component.category,
+                file_name: component.file_name.clone(),
+                tags: component
+                    .tags
+                    .main
+                    .iter()
+                    .chain(&component.tags.others)
+                    .map(ToString::to_string)
+                    .collect(),
+                side: OverrideSide::from_env(&component.environment),
+                url: component
+                    .metadata
+                    .as_ref()
+                    .and_then(|metadata| metadata.url.clone())
+                    .or_else(|| component.project_url()),
+                summary: component
+                    .metadata
+                    .as_ref()
+                    .and_then(|metadata| metadata.summary.clone()),
+                icon_url: component
+                    .metadata
+                    .as_ref()
+                    .and_then(|metadata| metadata.icon_url.clone()),
+            })
+            .sorted_by(|a, b| {
+                (a.category, a.name.to_lowercase()).cmp(&(b.category, b.name.to_lowercase()))
+            })
+            .collect();
+        Self { entries }
+    }
+
+    /// Render this list in `format`. A `gallery` HTML list also shows the
+    /// projects' icons and summaries, for a presentable pack page.
+    #[must_use]
+    pub fn render(&self, format: ListFormat, gallery: bool) -> String {
+        let mut out = String::new();
+        match format {
+            ListFormat::Markdown => {
+                let escape = |text: &str| text.replace('|', "\\|");
+                let _ = writeln!(out, "| Name | Category | Version | Tags | Side |");
+                let _ = writeln!(out, "| --- | --- | --- | --- | --- |");
+                for entry in &self.entries {
+                    let name = match &entry.url {
+                        Some(url) => format!("[{}]({url})", escape(&entry.name)),
+                        None => escape(&entry.name),
+                    };
+                    let _ = writeln!(
+                        out,
+                        "| {name} | {} | `{}` | {} | {} |",
+                        entry.category,
+                        entry.file_name,
+                        escape(&entry.tags.join(", ")),
+                        entry.side
+                    );
+                }
+            }
+            ListFormat::Csv => {
+                let _ = writeln!(out, "slug,name,category,file_name,tags,side,url");
+                for entry in &self.entries {
+                    let fields = [
+                        entry.slug.clone(),
+                        entry.name.clone(),
+                        entry.category.to_string(),
+                        entry.file_name.clone(),
+                        entry.tags.join(";"),
+                        entry.side.to_string(),
+                        entry.url.as_ref().map(Url::to_string).unwrap_or_default(),
+                    ];
+                    let _ = writeln!(out, "{}", fields.iter().map(|f| csv_field(f)).join(","));
+                }
+            }
+            ListFormat::Html => {
+                let _ = writeln!(out, "<table>");
+                let _ = writeln!(
+                    out,
+                    "  <tr>{}<th>Name</th><th>Category</th><th>Version</th><th>Tags</th><th>Side</th></tr>",
+                    match gallery {
+                        true => "<th></th>",
+                        false => "",
+                    }
+                );
+                for entry in &self.entries {
+                    let name = html_escape(&entry.name);
+                    let mut name = match &entry.url {
+                        Some(url) => {
+                            format!("<a href=\"{}\">{name}</a>", html_escape(url.as_str()))
+                        }
+                        None => name,
+                    };
+                    if let Some(summary) = entry.summary.as_ref().filter(|_| gallery) {
+                        let _ = write!(name, "<br><small>{}</small>", html_escape(summary));
+                    }
+                    let icon = match (gallery, &entry.icon_url) {
+                        (false, _) => String::new(),
+                        (true, Some(url)) => format!(
+                            "<td><img src=\"{}\" alt=\"\" width=\"48\" height=\"48\" loading=\"lazy\"></td>",
+                            html_escape(url.as_str())
+                        ),
+                        (true, None) => "<td></td>".to_string(),
+                    };
+                    let _ = writeln!(
+                        out,
+                        "  <tr>{icon}<td>{name}</td><td>{}</td><td><code>{}</code></td><td>{}</td><td>{}</td></tr>",
+                        entry.category,
+                        html_escape(&entry.file_name),
+                        html_escape(&entry.tags.join(", ")),
+                        entry.side
+                    );
+                }
+                let _ = writeln!(out, "</table>");
+            }
+        }
+        out
+    }
+}
+
+/// Quote `field` for CSV if it has anything that would break the row.
+fn csv_field(field: &str) -> String {
+    match field.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string(),
+    }
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields_are_escaped() {
+        assert_eq!(csv_field("Sodium"), "Sodium");
+        assert_eq!(
+            csv_field("Create: \"Steam, Rails\""),
+            "\"Create: \"\"Steam, Rails\"\"\""
+        );
+        assert_eq!(html_escape("<Tom & Jerry>"), "&lt;Tom &amp; Jerry&gt;");
+    }
+}
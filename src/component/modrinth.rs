@@ -1,11 +1,36 @@
-use super::Category;
+use super::{
+    AddError, Attribution, Category, Channel, Dependency, DependencyKind, DependencyMetadata,
+    License, ProjectMetadata,
+};
+use crate::cache::{http, DEFAULT_CONCURRENCY};
 use crate::index::file::{Hashes, Requirement};
-use crate::instance::Loader;
+use crate::instance::{Instance, Loader};
+use crate::network;
 use color_eyre::owo_colors::OwoColorize;
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
-use std::fmt;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::{fmt, panic, thread};
 use url::Url;
 
+/// Base URL of the **Modrinth API**.
+pub const API_URL: &str = "https://api.modrinth.com/v2";
+
+/// Environment variable that overrides [`API_URL`], for self-hosted proxies
+/// and [mock servers](crate::test_support::MockModrinth).
+pub const API_URL_VAR: &str = "INVAR_MODRINTH_API_URL";
+
+fn api_url() -> String {
+    std::env::var(API_URL_VAR).unwrap_or_else(|_| API_URL.to_string())
+}
+
+/// How many IDs go into a single request to a bulk endpoint, to keep the URLs
+/// well within what servers accept.
+const BULK_CHUNK_SIZE: usize = 100;
+
 #[derive(Deserialize, Debug)]
 pub struct File {
     pub hashes: Hashes,
@@ -14,8 +39,9 @@ pub struct File {
     pub size: usize,
 }
 
-#[derive(Deserialize, Debug, Clone, Copy)]
+#[derive(Deserialize, Debug, Clone)]
 pub struct Metadata {
+    pub id: String,
     #[serde(rename = "project_type")]
     pub category: Category,
     pub client_side: Requirement,
@@ -26,18 +52,75 @@ pub struct Metadata {
 pub struct Version {
     pub id: String,
     pub name: String,
+    #[serde(default)]
+    pub version_number: String,
+    #[serde(default)]
+    pub version_type: Channel,
     pub game_versions: Vec<String>,
     pub loaders: Vec<Loader>,
     pub date_published: chrono::DateTime<chrono::Utc>,
     pub files: Vec<File>,
+    #[serde(default)]
+    pub dependencies: Vec<VersionDependency>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct VersionDependency {
+    pub project_id: Option<String>,
+    pub version_id: Option<String>,
+    pub dependency_type: DependencyKind,
+}
+
+impl Version {
+    /// Whether this version can be loaded by the [`Instance`]'s loader.
+    #[must_use]
+    pub fn supports_loader(&self, instance: &Instance) -> bool {
+        self.loaders.iter().any(|l| {
+            *l == instance.loader
+                || instance.allowed_foreign_loaders.contains(l)
+                || *l == Loader::Other
+        })
+    }
+
+    /// Whether this version is made for the [`Instance`]'s Minecraft version.
+    ///
+    /// Resourcepacks and shaders may be loaded even if they are made for a
+    /// different version, so they always are.
+    #[must_use]
+    pub fn supports_minecraft_version(&self, category: Category, instance: &Instance) -> bool {
+        let version_insensitive = [Category::Resourcepack, Category::Shader].contains(&category);
+        version_insensitive
+            || self.game_versions.iter().any(|v| {
+                semver::Version::from_str(v).is_ok_and(|v| v == instance.minecraft_version)
+            })
+    }
+
+    /// The [`Dependency`]s of this version on other projects.
+    ///
+    /// Dependencies on specific versions without a project are skipped.
+    #[must_use]
+    pub fn dependencies(&self) -> Vec<Dependency> {
+        self.dependencies
+            .iter()
+            .filter_map(|dependency| {
+                Some(Dependency {
+                    project_id: dependency.project_id.clone()?,
+                    version_id: dependency.version_id.clone(),
+                    kind: dependency.dependency_type,
+                    metadata: None,
+                })
+            })
+            .collect()
+    }
 }
 
 impl fmt::Display for Version {
     fn fmt(&self, stream: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             stream,
-            "{name} [ID: {id}] - Supported loaders: {loaders:?}, released: {date}",
+            "{name}{channel} [ID: {id}] - Supported loaders: {loaders:?}, released: {date}",
             name = self.name.yellow().bold(),
+            channel = self.version_type.annotation().red().bold(),
             id = self.id.bold(),
             loaders = self.loaders.bright_red(),
             date = self.date_published.format("%b %e, %Y").bright_blue().bold()
@@ -45,3 +128,253 @@ impl fmt::Display for Version {
         Ok(())
     }
 }
+
+/// Fetch a project's [`Metadata`] and all of its [`Version`]s compatible with
+/// the provided [`Instance`], newest first.
+///
+/// # Errors
+///
+/// This function will return an error if it fails to query the Modrinth API.
+pub fn fetch_compatible_versions(
+    slug: &str,
+    instance: &Instance,
+) -> Result<(Metadata, Vec<Version>), AddError> {
+    let metadata_url = format!("{}/project/{slug}", api_url());
+    let versions_url = format!("{}/project/{slug}/version", api_url());
+    let client = network::client();
+    // NOTE: The two don't depend on each other, so they're fetched at once.
+    let (metadata, versions) = thread::scope(|scope| {
+        let versions = scope.spawn(|| -> Result<Vec<Version>, AddError> {
+            Ok(serde_json::from_str(&http::get(&client, &versions_url)?)?)
+        });
+        let metadata = http::get(&client, &metadata_url)
+            .map_err(AddError::from)
+            .and_then(|body| Ok(serde_json::from_str::<Metadata>(&body)?));
+        let versions = versions
+            .join()
+            .unwrap_or_else(|panic| panic::resume_unwind(panic));
+        (metadata, versions)
+    });
+    let (metadata, mut versions) = (metadata?, versions?);
+
+    // Only leave versions that are both loader- and version-compatible with the
+    // instance.
+    versions.retain(|v| {
+        v.supports_loader(instance) && v.supports_minecraft_version(metadata.category, instance)
+    });
+
+    for version in &mut versions {
+        version.loaders.dedup();
+    }
+    versions.sort_unstable_by_key(|version| version.date_published);
+    versions.reverse();
+
+    Ok((metadata, versions))
+}
+
+/// Fetch a single [`Version`] by its ID.
+///
+/// # Errors
+///
+/// This function will return an error if it fails to query the Modrinth API.
+pub fn fetch_version(version_id: &str) -> Result<Version, AddError> {
+    let url = format!("{}/version/{version_id}", api_url());
+    Ok(serde_json::from_str(&http::get(&network::client(), &url)?)?)
+}
+
+/// Fetch several [`Version`]s by their IDs at once, keyed by ID. Versions that
+/// don't exist are left out.
+///
+/// # Errors
+///
+/// This function will return an error if it fails to query the Modrinth API.
+pub fn fetch_versions(ids: &[String]) -> Result<HashMap<String, Version>, AddError> {
+    Ok(fetch_bulk::<Version>("versions", ids)?
+        .into_iter()
+        .map(|version| (version.id.clone(), version))
+        .collect())
+}
+
+#[derive(Deserialize, Debug)]
+struct ProjectLicense {
+    slug: String,
+    title: String,
+    license: Option<LicenseInfo>,
+}
+
+#[derive(Deserialize, Debug)]
+struct LicenseInfo {
+    id: String,
+    name: String,
+    url: Option<Url>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TeamMember {
+    user: User,
+}
+
+#[derive(Deserialize, Debug)]
+struct User {
+    username: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct LicenseText {
+    body: String,
+}
+
+/// Fetch the [`Attribution`] of a project: its license and team members.
+///
+/// # Errors
+///
+/// This function will return an error if it fails to query the Modrinth API.
+pub fn fetch_attribution(project_id: &str) -> Result<Attribution, AddError> {
+    let url = format!("{}/project/{project_id}", api_url());
+    let client = network::client();
+    let project: ProjectLicense = network::send(client.get(url))?.error_for_status()?.json()?;
+    let url = format!("{}/project/{project_id}/members", api_url());
+    let members: Vec<TeamMember> = network::send(client.get(url))?.error_for_status()?.json()?;
+    Ok(Attribution {
+        url: format!("https://modrinth.com/project/{}", project.slug)
+            .parse()
+            .ok(),
+        slug: project.slug,
+        name: project.title,
+        authors: members
+            .into_iter()
+            .map(|member| member.user.username)
+            .collect(),
+        license: project.license.map(|license| License {
+            id: license.id,
+            name: license.name,
+            url: license.url,
+        }),
+    })
+}
+
+/// Fetch the full text of a license by its SPDX identifier, or [`None`] if
+/// Modrinth doesn't know it (which is the case for custom licenses).
+///
+/// # Errors
+///
+/// This function will return an error if it fails to query the Modrinth API.
+pub fn fetch_license_text(id: &str) -> Result<Option<String>, AddError> {
+    let url = format!("{}/tag/license/{id}", api_url());
+    let response = network::send(network::client().get(url))?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let license: LicenseText = response.error_for_status()?.json()?;
+    Ok(Some(license.body))
+}
+
+#[derive(Deserialize, Debug)]
+struct ProjectSummary {
+    id: String,
+    slug: String,
+    title: String,
+    description: Option<String>,
+    #[serde(default)]
+    license: Option<LicenseInfo>,
+    #[serde(default)]
+    icon_url: Option<Url>,
+}
+
+/// Look up the slugs of several projects by their IDs at once.
+///
+/// # Errors
+///
+/// This function will return an error if it fails to query the Modrinth API.
+pub fn fetch_slugs(ids: &[String]) -> Result<HashMap<String, String>, AddError> {
+    Ok(fetch_metadata(ids)?
+        .into_iter()
+        .map(|(id, metadata)| (id, metadata.slug))
+        .collect())
+}
+
+/// Look up the [`DependencyMetadata`] of several projects by their IDs at
+/// once.
+///
+/// # Errors
+///
+/// This function will return an error if it fails to query the Modrinth API.
+pub fn fetch_metadata(ids: &[String]) -> Result<HashMap<String, DependencyMetadata>, AddError> {
+    Ok(fetch_projects(ids)?
+        .into_iter()
+        .map(|project| {
+            let metadata = DependencyMetadata {
+                slug: project.slug,
+                name: project.title,
+                summary: project.description,
+            };
+            (project.id, metadata)
+        })
+        .collect())
+}
+
+/// Look up the [`ProjectMetadata`] of several projects by their IDs at once.
+///
+/// # Errors
+///
+/// This function will return an error if it fails to query the Modrinth API.
+pub fn fetch_project_metadata(
+    ids: &[String],
+) -> Result<HashMap<String, ProjectMetadata>, AddError> {
+    Ok(fetch_projects(ids)?
+        .into_iter()
+        .map(|project| {
+            let metadata = ProjectMetadata {
+                url: format!("https://modrinth.com/project/{}", project.slug)
+                    .parse()
+                    .ok(),
+                name: project.title,
+                summary: project.description,
+                license: project.license.map(|license| license.id),
+                icon_url: project.icon_url,
+            };
+            (project.id, metadata)
+        })
+        .collect())
+}
+
+fn fetch_projects(ids: &[String]) -> Result<Vec<ProjectSummary>, AddError> {
+    fetch_bulk("projects", ids)
+}
+
+/// Query a bulk `endpoint` (like `projects`) for all `ids`, in chunks of
+/// [`BULK_CHUNK_SIZE`] with up to [`DEFAULT_CONCURRENCY`] requests at once.
+fn fetch_bulk<T>(endpoint: &str, ids: &[String]) -> Result<Vec<T>, AddError>
+where
+    T: DeserializeOwned + Send,
+{
+    let client = network::client();
+    let chunks = ids.chunks(BULK_CHUNK_SIZE).collect::<Vec<_>>();
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(vec![]);
+    let worker = || {
+        while let Some(chunk) = chunks.get(next.fetch_add(1, Ordering::Relaxed)) {
+            let ids = serde_json::to_string(chunk).unwrap_or_default();
+            let request = client
+                .get(format!("{}/{endpoint}", api_url()))
+                .query(&[("ids", ids)]);
+            let result = network::send(request)
+                .map_err(AddError::from)
+                .and_then(|response| Ok(response.error_for_status()?.json::<Vec<T>>()?));
+            results
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(result);
+        }
+    };
+    thread::scope(|scope| {
+        for _ in 0..DEFAULT_CONCURRENCY.min(chunks.len()) {
+            scope.spawn(worker);
+        }
+    });
+    let mut found = vec![];
+    for result in results.into_inner().unwrap_or_else(PoisonError::into_inner) {
+        found.extend(result?);
+    }
+    Ok(found)
+}
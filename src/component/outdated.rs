@@ -0,0 +1,221 @@
+use super::{Component, DependencyGraph, Source};
+use crate::budget;
+use crate::cache::DEFAULT_CONCURRENCY;
+use crate::instance::Instance;
+use crate::progress::Task;
+use chrono::{DateTime, Utc};
+use color_eyre::owo_colors::OwoColorize;
+use semver::Version;
+use serde::Serialize;
+use std::fmt::{self, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+
+/// Available updates for a pack's [`Component`]s.
+///
+/// This is serialized in a stable shape meant for scripts and bots: fields are
+/// only ever added, and anything else bumps [`Outdated::SCHEMA_VERSION`].
+#[derive(Debug, Clone, Serialize)]
+pub struct Outdated {
+    pub schema_version: u32,
+    pub checked_at: DateTime<Utc>,
+    pub minecraft_version: Version,
+    pub updates: Vec<AvailableUpdate>,
+    /// Slugs of the components that are already up to date.
+    pub up_to_date: Vec<String>,
+    pub failed: Vec<FailedCheck>,
+    /// Slugs of the components that weren't checked because the
+    /// [time budget](crate::budget) ran out.
+    pub skipped: Vec<String>,
+}
+
+/// A newer version of a [`Component`] that fits the pack.
+#[derive(Debug, Clone, Serialize)]
+pub struct AvailableUpdate {
+    pub slug: String,
+    pub source: Source,
+    pub current_version_id: String,
+    pub current_file_name: String,
+    pub latest_version_id: String,
+    pub latest_file_name: String,
+}
+
+/// A [`Component`] whose updates couldn't be checked.
+#[derive(Debug, Clone, Serialize)]
+pub struct FailedCheck {
+    pub slug: String,
+    pub error: String,
+}
+
+impl Outdated {
+    pub const SCHEMA_VERSION: u32 = 1;
+
+    /// [Fetch the updates](Component::fetch_update) of all `components`,
+    /// checking up to [`DEFAULT_CONCURRENCY`] of them at once.
+    ///
+    /// Errors of specific components are recorded in
+    /// [`failed`](Self::failed) instead of stopping the check, and components
+    /// left once the [time budget](crate::budget) runs out in
+    /// [`skipped`](Self::skipped).
+    #[must_use]
+    pub fn check(components: &[Component], instance: &Instance) -> Self {
+        let graph = DependencyGraph::new(components);
+        let task = Task::start("Checking for updates", components.len());
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new((0..components.len()).map(|_| None).collect::<Vec<_>>());
+        let worker = || loop {
+            let index = next.fetch_add(1, Ordering::Relaxed);
+            let Some(component) = components.get(index) else {
+                break;
+            };
+            if budget::is_exhausted() {
+                break;
+            }
+            let result = component.fetch_update(instance, &graph);
+            results.lock().unwrap_or_else(PoisonError::into_inner)[index] = Some(result);
+            task.advance(Some(&component.slug));
+        };
+        thread::scope(|scope| {
+            for _ in 0..DEFAULT_CONCURRENCY.min(components.len()) {
+                scope.spawn(worker);
+            }
+        });
+
+        let mut outdated = Self {
+            schema_version: Self::SCHEMA_VERSION,
+            checked_at: Utc::now(),
+            minecraft_version: instance.minecraft_version.clone(),
+            updates: vec![],
+            up_to_date: vec![],
+            failed: vec![],
+            skipped: vec![],
+        };
+        let results = results.into_inner().unwrap_or_else(PoisonError::into_inner);
+        for (component, result) in components.iter().zip(results) {
+            let Some(result) = result else {
+                outdated.skipped.push(component.slug.clone());
+                continue;
+            };
+            match result {
+                Ok(Some(update)) => outdated.updates.push(AvailableUpdate {
+                    slug: component.slug.clone(),
+                    source: component.source,
+                    current_version_id: component.version_id.clone(),
+                    current_file_name: component.file_name.clone(),
+                    latest_version_id: update.version_id,
+                    latest_file_name: update.file_name,
+                }),
+                Ok(None) => outdated.up_to_date.push(component.slug.clone()),
+                Err(error) => outdated.failed.push(FailedCheck {
+                    slug: component.slug.clone(),
+                    error: error.to_string(),
+                }),
+            }
+        }
+        outdated
+    }
+
+    /// Whether any of the components has a newer version available.
+    #[must_use]
+    pub const fn is_stale(&self) -> bool {
+        !self.updates.is_empty()
+    }
+
+    /// Render this report as Markdown, ready to be posted to a chat.
+    #[must_use]
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::new();
+        let _ = writeln!(
+            markdown,
+            "### Available updates ({count})\n",
+            count = self.updates.len()
+        );
+        if self.updates.is_empty() {
+            let _ = writeln!(markdown, "Everything is up to date.");
+        } else {
+            let _ = writeln!(markdown, "| Component | Current | Latest |");
+            let _ = writeln!(markdown, "| --- | --- | --- |");
+            for update in &self.updates {
+                let _ = writeln!(
+                    markdown,
+                    "| {} | `{}` | `{}` |",
+                    update.slug, update.current_file_name, update.latest_file_name
+                );
+            }
+        }
+        if !self.failed.is_empty() {
+            let _ = writeln!(
+                markdown,
+                "\n<details><summary>Failed to check {} components</summary>\n",
+                self.failed.len()
+            );
+            for failed in &self.failed {
+                let _ = writeln!(markdown, "- **{}**: {}", failed.slug, failed.error);
+            }
+            let _ = writeln!(markdown, "\n</details>");
+        }
+        if !self.skipped.is_empty() {
+            let _ = writeln!(
+                markdown,
+                "\n_{} components weren't checked in time._",
+                self.skipped.len()
+            );
+        }
+        markdown
+    }
+}
+
+/// Prints the available updates as a table of the current and the latest
+/// versions.
+impl fmt::Display for Outdated {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_stale() {
+            let width = |header: &str, column: fn(&AvailableUpdate) -> &str| {
+                self.updates
+                    .iter()
+                    .map(|update| column(update).len())
+                    .chain([header.len()])
+                    .max()
+                    .unwrap_or_default()
+            };
+            let slug_width = width("Component", |update| &update.slug);
+            let current_width = width("Current", |update| &update.current_file_name);
+            writeln!(
+                f,
+                "{:<slug_width$}  {:<current_width$}  {}",
+                "Component".bold(),
+                "Current".bold(),
+                "Latest".bold()
+            )?;
+            for update in &self.updates {
+                writeln!(
+                    f,
+                    "{}  {:<current_width$}  {}",
+                    format!("{:<slug_width$}", update.slug).magenta().bold(),
+                    update.current_file_name,
+                    update.latest_file_name.yellow().bold()
+                )?;
+            }
+        }
+        for failed in &self.failed {
+            writeln!(
+                f,
+                "{}: failed to check ({})",
+                failed.slug.magenta().bold(),
+                failed.error.red()
+            )?;
+        }
+        write!(
+            f,
+            "{} outdated, {} up to date, {} failed",
+            self.updates.len(),
+            self.up_to_date.len(),
+            self.failed.len()
+        )?;
+        match self.skipped.len() {
+            0 => writeln!(f),
+            count => writeln!(f, ", {} skipped (out of time)", count.yellow().bold()),
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use super::{
+    curseforge, modrinth, AddError, Candidate, Category, Channel, Component, Dependency,
+    DependencyGraph, DependencyKind, Source,
+};
+use crate::index::file::Env;
+use crate::instance::Instance;
+use chrono::{DateTime, Utc};
+use color_eyre::owo_colors::OwoColorize;
+use itertools::Itertools;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+
+/// What a project offers to a pack, looked up without adding anything to it.
+///
+/// Made for scouting: every version that fits the [`Instance`] is listed, even
+/// those from channels the instance doesn't accept.
+#[derive(Debug, Clone, Serialize)]
+pub struct Probe {
+    pub slug: String,
+    pub source: Source,
+    pub project_id: String,
+    pub category: Category,
+    /// Newest first.
+    pub versions: Vec<ProbedVersion>,
+}
+
+/// A version of a [`Probe`]d project that fits the [`Instance`].
+#[derive(Debug, Clone, Serialize)]
+pub struct ProbedVersion {
+    pub version_id: String,
+    pub name: String,
+    pub channel: Channel,
+    /// Whether the instance accepts versions from this
+    /// [`channel`](Self::channel).
+    pub accepted: bool,
+    pub published: DateTime<Utc>,
+    pub environment: Env,
+    pub dependencies: Vec<ProbedDependency>,
+    /// How this version clashes with the pack, as chains of `project@version`
+    /// links.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub conflicts: Vec<String>,
+}
+
+/// A [`Dependency`] of a [`ProbedVersion`], and whether the pack has it.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProbedDependency {
+    pub project_id: String,
+    pub name: String,
+    pub kind: DependencyKind,
+    pub in_pack: bool,
+}
+
+impl Probe {
+    /// Look up the versions of the project with this `slug` on `source` that
+    /// fit the `instance`, checking each one against the pack's `components`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the project or its versions can't be fetched.
+    pub fn fetch(
+        slug: &str,
+        source: Source,
+        instance: &Instance,
+        components: &[Component],
+    ) -> Result<Self, AddError> {
+        let (project_id, category, versions): (_, _, Vec<Fetched>) = match source {
+            Source::Modrinth => {
+                let (metadata, versions) = modrinth::fetch_compatible_versions(slug, instance)?;
+                let environment = Env {
+                    client: metadata.client_side,
+                    server: metadata.server_side,
+                };
+                let versions = versions
+                    .into_iter()
+                    .map(|version| Fetched {
+                        dependencies: version.dependencies(),
+                        version_id: version.id,
+                        name: version.name,
+                        channel: version.version_type,
+                        published: version.date_published,
+                        environment: environment.clone(),
+                    })
+                    .collect();
+                (metadata.id, metadata.category, versions)
+            }
+            Source::Curseforge => {
+                let client = curseforge::client()?;
+                let project = curseforge::fetch_project(&client, slug)?;
+                let files = curseforge::fetch_compatible_files(&client, &project, instance)?;
+                let versions = files
+                    .into_iter()
+                    .map(|file| Fetched {
+                        version_id: file.id.to_string(),
+                        channel: file.channel(),
+                        environment: file.environment(),
+                        dependencies: file.dependencies(),
+                        name: file.display_name,
+                        published: file.file_date,
+                    })
+                    .collect();
+                (project.id.to_string(), project.category()?, versions)
+            }
+        };
+
+        let ids = versions
+            .iter()
+            .flat_map(|version| &version.dependencies)
+            .map(|dependency| dependency.project_id.clone())
+            .unique()
+            .collect::<Vec<_>>();
+        let names = match (ids.is_empty(), source) {
+            (true, _) => HashMap::new(),
+            (false, Source::Modrinth) => modrinth::fetch_metadata(&ids)?,
+            (false, Source::Curseforge) => {
+                curseforge::fetch_metadata(&curseforge::client()?, &ids)?
+            }
+        };
+
+        let graph = DependencyGraph::new(components);
+        let versions = versions
+            .into_iter()
+            .map(|version| {
+                let conflicts = graph.check(&Candidate {
+                    slug,
+                    project_id: Some(&project_id),
+                    version_id: &version.version_id,
+                    dependencies: &version.dependencies,
+                });
+                let dependencies = version
+                    .dependencies
+                    .into_iter()
+                    .map(|mut dependency| {
+                        dependency.metadata = names.get(&dependency.project_id).cloned();
+                        ProbedDependency {
+                            name: dependency.name().to_string(),
+                            in_pack: graph.resolve(&dependency).is_some(),
+                            project_id: dependency.project_id,
+                            kind: dependency.kind,
+                        }
+                    })
+                    .collect();
+                ProbedVersion {
+                    version_id: version.version_id,
+                    name: version.name,
+                    channel: version.channel,
+                    accepted: instance.accepts_channel(version.channel),
+                    published: version.published,
+                    environment: version.environment,
+                    dependencies,
+                    conflicts: conflicts.iter().map(ToString::to_string).collect(),
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            slug: slug.to_string(),
+            source,
+            project_id,
+            category,
+            versions,
+        })
+    }
+}
+
+/// A version as fetched from its [`Source`], before being checked.
+struct Fetched {
+    version_id: String,
+    name: String,
+    channel: Channel,
+    published: DateTime<Utc>,
+    environment: Env,
+    dependencies: Vec<Dependency>,
+}
+
+impl fmt::Display for Probe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} ({}, {}): {} compatible versions",
+            self.slug.magenta().bold(),
+            self.category,
+            self.source,
+            self.versions.len().bold()
+        )?;
+        for version in &self.versions {
+            let channel = match version.accepted {
+                true => version.channel.annotation().yellow().bold().to_string(),
+                false => format!("{} (not accepted)", version.channel.annotation())
+                    .red()
+                    .bold()
+                    .to_string(),
+            };
+            writeln!(
+                f,
+                "- {name}{channel} [ID: {id}] released {date}, client: {client}, server: {server}",
+                name = version.name.yellow().bold(),
+                id = version.version_id.bold(),
+                date = version.published.format("%b %e, %Y").bright_blue().bold(),
+                client = version.environment.client,
+                server = version.environment.server,
+            )?;
+            for dependency in &version.dependencies {
+                writeln!(
+                    f,
+                    "    {} {}{}",
+                    dependency.kind,
+                    dependency.name.bold(),
+                    match dependency.in_pack {
+                        true => " (in the pack)".green().to_string(),
+                        false => String::new(),
+                    }
+                )?;
+            }
+            for conflict in &version.conflicts {
+                writeln!(f, "    {} {conflict}", "!".red().bold())?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,199 @@
+use super::{Category, Component, Source};
+use crate::local_storage;
+use chrono::{DateTime, Utc};
+use color_eyre::owo_colors::OwoColorize;
+use serde::Serialize;
+use std::fmt::{self, Write};
+use std::fs;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+/// Directory (relative to the pack's root) [`UpdateReport`]s are saved in.
+pub const REPORTS_DIR: &str = ".invar/reports";
+
+/// What changed in a `component update` run, meant to be pasted into release
+/// notes.
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateReport {
+    pub generated_at: DateTime<Utc>,
+    pub updates: Vec<UpdateEntry>,
+}
+
+/// A [`Component`] moved from one version to another.
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateEntry {
+    pub slug: String,
+    pub source: Source,
+    pub old_file_name: String,
+    pub new_file_name: String,
+    pub old_file_size: usize,
+    pub new_file_size: usize,
+    /// The page of the new version on its [`Source`], which has its
+    /// changelog.
+    pub changelog: Option<Url>,
+}
+
+impl UpdateReport {
+    /// Report on the `updated` components, given as old and new pairs.
+    #[must_use]
+    pub fn new(updated: &[(Component, Component)]) -> Self {
+        Self {
+            generated_at: Utc::now(),
+            updates: updated
+                .iter()
+                .map(|(old, new)| UpdateEntry {
+                    slug: new.slug.clone(),
+                    source: new.source,
+                    old_file_name: old.file_name.clone(),
+                    new_file_name: new.file_name.clone(),
+                    old_file_size: old.file_size,
+                    new_file_size: new.file_size,
+                    changelog: new.changelog_url(),
+                })
+                .collect(),
+        }
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.updates.is_empty()
+    }
+
+    /// Save this report as Markdown into [`REPORTS_DIR`], named after when it
+    /// was generated, and return its path.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the report can't be written.
+    pub fn save(&self) -> local_storage::Result<PathBuf> {
+        let dir = Path::new(REPORTS_DIR);
+        let path = dir.join(format!(
+            "update-{}.md",
+            self.generated_at.format("%Y%m%d-%H%M%S")
+        ));
+        let io_error = |source| local_storage::Error::Io {
+            source,
+            faulty_path: Some(path.clone()),
+        };
+        fs::create_dir_all(dir).map_err(io_error)?;
+        fs::write(&path, self.to_markdown()).map_err(io_error)?;
+        Ok(path)
+    }
+
+    /// Render this report as Markdown.
+    #[must_use]
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::new();
+        let _ = writeln!(
+            markdown,
+            "### Updated components ({count})\n",
+            count = self.updates.len()
+        );
+        let _ = writeln!(markdown, "| Component | Old | New | Size | Changelog |");
+        let _ = writeln!(markdown, "| --- | --- | --- | --- | --- |");
+        for update in &self.updates {
+            let changelog = update
+                .changelog
+                .as_ref()
+                .map(|url| format!("[{}]({url})", update.source))
+                .unwrap_or_default();
+            let _ = writeln!(
+                markdown,
+                "| {} | `{}` | `{}` | {} | {changelog} |",
+                update.slug,
+                update.old_file_name,
+                update.new_file_name,
+                update.size_delta()
+            );
+        }
+        markdown
+    }
+}
+
+impl UpdateEntry {
+    /// How much bigger (or smaller) the new file is, like `+12.5 KiB`.
+    #[must_use]
+    pub fn size_delta(&self) -> String {
+        let sign = match self.new_file_size >= self.old_file_size {
+            true => '+',
+            false => '-',
+        };
+        let tenths = self.new_file_size.abs_diff(self.old_file_size) * 10 / 1024;
+        format!("{sign}{}.{} KiB", tenths / 10, tenths % 10)
+    }
+}
+
+impl Component {
+    /// The page of this component's project on its [`Source`].
+    #[must_use]
+    pub fn project_url(&self) -> Option<Url> {
+        let url = match self.source {
+            Source::Modrinth => format!("https://modrinth.com/project/{}", self.slug),
+            Source::Curseforge => {
+                let section = match self.category {
+                    Category::Mod => "mc-mods",
+                    Category::Resourcepack => "texture-packs",
+                    Category::Shader => "shaders",
+                    Category::Datapack => "data-packs",
+                    Category::Config => "customization",
+                };
+                format!(
+                    "https://www.curseforge.com/minecraft/{section}/{}",
+                    self.slug
+                )
+            }
+        };
+        url.parse().ok()
+    }
+
+    /// The page of this component's version on its [`Source`], where its
+    /// changelog is shown.
+    #[must_use]
+    pub fn changelog_url(&self) -> Option<Url> {
+        let project = self.project_url()?;
+        let path = match self.source {
+            Source::Modrinth => "version",
+            Source::Curseforge => "files",
+        };
+        format!("{project}/{path}/{}", self.version_id).parse().ok()
+    }
+}
+
+impl fmt::Display for UpdateReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for update in &self.updates {
+            writeln!(
+                f,
+                "{}: {} -> {} ({})",
+                update.slug.magenta().bold(),
+                update.old_file_name,
+                update.new_file_name.yellow().bold(),
+                update.size_delta()
+            )?;
+            if let Some(changelog) = &update.changelog {
+                writeln!(f, "  {}", changelog.dimmed())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn size_deltas_are_signed() {
+        let entry = |old_file_size, new_file_size| UpdateEntry {
+            slug: "sodium".into(),
+            source: Source::Modrinth,
+            old_file_name: "sodium-0.5.jar".into(),
+            new_file_name: "sodium-0.6.jar".into(),
+            old_file_size,
+            new_file_size,
+            changelog: None,
+        };
+        assert_eq!(entry(1024, 13824).size_delta(), "+12.5 KiB");
+        assert_eq!(entry(2048, 1024).size_delta(), "-1.0 KiB");
+    }
+}
@@ -1,5 +1,8 @@
 use super::AddError;
+use crate::terminal;
 use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::str::FromStr;
 use strum::{Display, EnumIter, IntoEnumIterator};
 
 /// Possible tags that can be associated with a
@@ -53,6 +56,20 @@ pub enum Tag {
     Wildlife,
 }
 
+/// Names of the predefined tags are matched regardless of case, anything else
+/// becomes a [`Tag::Custom`] one.
+impl FromStr for Tag {
+    type Err = Infallible;
+
+    fn from_str(tag: &str) -> Result<Self, Self::Err> {
+        let tag = tag.trim().to_lowercase();
+        let predefined = Self::iter()
+            .filter(|known| !matches!(known, Self::Custom(_)))
+            .find(|known| known.to_string().to_lowercase() == tag);
+        Ok(predefined.unwrap_or(Self::Custom(tag)))
+    }
+}
+
 /// Helper struct to group together [`Component`](crate::component::Component)
 /// tagging information.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -61,22 +78,51 @@ pub struct TagInformation {
     pub others: Vec<Tag>,
 }
 
+impl TagInformation {
+    /// Add `tag` to the other tags, unless it's already there or the main one.
+    pub fn add(&mut self, tag: Tag) {
+        if self.main.as_ref() != Some(&tag) && !self.others.contains(&tag) {
+            self.others.push(tag);
+        }
+    }
+
+    /// Remove `tag`, whether it's the main one or one of the others.
+    pub fn remove(&mut self, tag: &Tag) {
+        if self.main.as_ref() == Some(tag) {
+            self.main = None;
+        }
+        self.others.retain(|other| other != tag);
+    }
+
+    /// Make `tag` the main one, dropping it from the other tags. The previous
+    /// main tag, if any, is dropped altogether.
+    pub fn set_main(&mut self, tag: Tag) {
+        self.others.retain(|other| *other != tag);
+        self.main = Some(tag);
+    }
+}
+
+/// Ask for the main tag of a new component. Tags are left out when running
+/// [non-interactively](terminal::set_interactive).
 pub(super) fn pick_main_tag() -> Result<Option<Tag>, AddError> {
+    if !terminal::is_interactive() {
+        return Ok(None);
+    }
     let main_tag: Option<Tag> = {
         let message = "Choose the main tag for this component:";
         let options = Tag::iter()
             .filter(|tag| !matches!(tag, Tag::Custom(_)))
             .collect();
-        match inquire::Select::new(message, options)
-            .with_page_size(Tag::iter().count())
-            .with_help_message("Skip with [Escape] to provide a custom tag")
-            .prompt_skippable()?
-        {
+        match terminal::suspend(|| {
+            inquire::Select::new(message, options)
+                .with_page_size(Tag::iter().count())
+                .with_help_message("Skip with [Escape] to provide a custom tag")
+                .prompt_skippable()
+        })? {
             tag @ Some(_) => tag,
             None => {
                 let message = "Provide a custom tag for this component:";
-                inquire::Text::new(message)
-                    .prompt_skippable()?
+                terminal::suspend(|| inquire::Text::new(message).prompt_skippable())?
                     .map(|tag| tag.trim().to_lowercase())
                     .map(Tag::Custom)
             }
@@ -85,17 +131,29 @@ pub(super) fn pick_main_tag() -> Result<Option<Tag>, AddError> {
     Ok(main_tag)
 }
 
+/// Ask for both the main and the secondary tags of a new component.
+pub(super) fn pick_tags() -> Result<TagInformation, AddError> {
+    let main = pick_main_tag()?;
+    let others = pick_secondary_tags(main.as_ref())?;
+    Ok(TagInformation { main, others })
+}
+
 pub(super) fn pick_secondary_tags(main_tag: Option<&Tag>) -> Result<Vec<Tag>, AddError> {
+    if !terminal::is_interactive() {
+        return Ok(vec![]);
+    }
     let other_tags: Vec<Tag> = {
         let message = "Add some additional tags for this component?";
         let options = Tag::iter()
             .filter(|tag| !matches!(tag, Tag::Custom(_)) && main_tag != Some(tag))
             .collect();
-        inquire::MultiSelect::new(message, options)
-            .with_page_size(Tag::iter().count())
-            .with_help_message("This step can be freely skipped.")
-            .prompt_skippable()?
-            .unwrap_or_default()
+        terminal::suspend(|| {
+            inquire::MultiSelect::new(message, options)
+                .with_page_size(Tag::iter().count())
+                .with_help_message("This step can be freely skipped.")
+                .prompt_skippable()
+        })?
+        .unwrap_or_default()
     };
     Ok(other_tags)
 }
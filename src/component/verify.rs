@@ -0,0 +1,103 @@
+use super::Component;
+use crate::cache::{self, DEFAULT_CONCURRENCY};
+use crate::index::file::Algorithm;
+use crate::local_storage;
+use std::fs;
+
+impl Component {
+    /// The [`cache::Request`] for this [`Component`]'s file.
+    #[must_use]
+    pub fn cache_request(&self) -> cache::Request {
+        cache::Request {
+            url: self.download_url.clone(),
+            sha512: self.hashes.hex(Algorithm::Sha512).unwrap_or_default(),
+        }
+    }
+
+    /// Get the contents of this [`Component`]'s file, from the
+    /// [cache](cache::CACHE_DIR) if it's there or by downloading it
+    /// otherwise.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file can't be downloaded, its
+    /// hashes don't match the stored ones, or it can't be read from the cache.
+    pub fn fetch_file(&self) -> Result<Vec<u8>, VerifyError> {
+        let path = cache::fetch(&reqwest::blocking::Client::new(), &self.cache_request()).map_err(
+            |source| VerifyError::Cache {
+                slug: self.slug.clone(),
+                source,
+            },
+        )?;
+        fs::read(&path).map_err(|source| {
+            local_storage::Error::Io {
+                source,
+                faulty_path: Some(path),
+            }
+            .into()
+        })
+    }
+
+    /// Check that the hashes stored in this [`Component`]'s metadata match its
+    /// actual file.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file can't be fetched.
+    pub fn verify(&self) -> Result<bool, VerifyError> {
+        match cache::fetch(&reqwest::blocking::Client::new(), &self.cache_request()) {
+            Ok(_) => Ok(true),
+            Err(cache::Error::Mismatch { .. }) => Ok(false),
+            Err(source) => Err(VerifyError::Cache {
+                slug: self.slug.clone(),
+                source,
+            }),
+        }
+    }
+}
+
+/// [Verify](Component::verify) all the provided [`Component`]s, downloading
+/// their files into the [cache](cache::CACHE_DIR) in parallel.
+///
+/// # Errors
+///
+/// This function will return an error if any of the files can't be fetched, or
+/// [`VerifyError::Mismatch`] listing all the components whose hashes don't
+/// match.
+pub fn verify_all(components: &[Component]) -> Result<(), VerifyError> {
+    let requests = components
+        .iter()
+        .map(Component::cache_request)
+        .collect::<Vec<_>>();
+    let mut mismatched = vec![];
+    for (component, result) in components
+        .iter()
+        .zip(cache::fetch_all(&requests, DEFAULT_CONCURRENCY))
+    {
+        match result {
+            Ok(_) => {}
+            Err(cache::Error::Mismatch { .. }) => mismatched.push(component.slug.clone()),
+            Err(source) => {
+                return Err(VerifyError::Cache {
+                    slug: component.slug.clone(),
+                    source,
+                })
+            }
+        }
+    }
+    match mismatched.is_empty() {
+        true => Ok(()),
+        false => Err(VerifyError::Mismatch(mismatched)),
+    }
+}
+
+/// Errors that may arise while verifying [`Component`]s' hashes.
+#[derive(thiserror::Error, Debug)]
+pub enum VerifyError {
+    #[error(transparent)]
+    LocalStorage(#[from] local_storage::Error),
+    #[error("Failed to fetch the file of {slug}")]
+    Cache { slug: String, source: cache::Error },
+    #[error("Stored hashes don't match the actual files of: {}", .0.join(", "))]
+    Mismatch(Vec<String>),
+}
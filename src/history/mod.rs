@@ -0,0 +1,147 @@
+use crate::local_storage;
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use color_eyre::owo_colors::OwoColorize;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Append-only log (relative to the pack root) of the operations run on the
+/// pack, one JSON [`Event`] per line.
+pub const HISTORY_FILE: &str = ".invar/history.jsonl";
+
+/// A single operation run on the pack.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    pub at: DateTime<Utc>,
+    pub operation: Operation,
+    /// What the operation was run with, like the slugs of added components.
+    pub parameters: Vec<String>,
+    pub outcome: Outcome,
+}
+
+/// Operations recorded in the [history](HISTORY_FILE).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum, strum::Display)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Operation {
+    Add,
+    Remove,
+    Replace,
+    Update,
+    Export,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", content = "error", rename_all = "lowercase")]
+pub enum Outcome {
+    Succeeded,
+    Failed(String),
+}
+
+/// All the [`Event`]s of the [history](HISTORY_FILE), oldest first.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct History {
+    pub events: Vec<Event>,
+}
+
+impl Event {
+    /// An [`Event`] of an operation that just finished.
+    #[must_use]
+    pub fn new(operation: Operation, parameters: Vec<String>, outcome: Outcome) -> Self {
+        Self {
+            at: Utc::now(),
+            operation,
+            parameters,
+            outcome,
+        }
+    }
+
+    /// Append this [`Event`] to the [history](HISTORY_FILE).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the history can't be written.
+    pub fn record(&self) -> local_storage::Result<()> {
+        if local_storage::is_read_only() {
+            return Ok(());
+        }
+        let path = Path::new(HISTORY_FILE);
+        let io_error = |source| local_storage::Error::Io {
+            source,
+            faulty_path: Some(path.to_path_buf()),
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(io_error)?;
+        }
+        let mut line = serde_json::to_string(self)?;
+        line.push('\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .map_err(io_error)
+    }
+}
+
+impl History {
+    /// Read the [history](HISTORY_FILE). A missing file is an empty history,
+    /// and lines that can't be parsed are skipped.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the history can't be read.
+    pub fn read() -> local_storage::Result<Self> {
+        let text = match fs::read_to_string(HISTORY_FILE) {
+            Ok(text) => text,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(source) => {
+                return Err(local_storage::Error::Io {
+                    source,
+                    faulty_path: Some(PathBuf::from(HISTORY_FILE)),
+                })
+            }
+        };
+        let events = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .filter_map(|(index, line)| match serde_json::from_str(line) {
+                Ok(event) => Some(event),
+                Err(error) => {
+                    tracing::warn!(line = index + 1, %error, "Skipping a malformed history entry");
+                    None
+                }
+            })
+            .collect();
+        Ok(Self { events })
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{at} {operation} {parameters}",
+            at = self.at.format("%Y-%m-%d %H:%M:%S").bright_blue(),
+            operation = self.operation.yellow().bold(),
+            parameters = self.parameters.join(" "),
+        )?;
+        match &self.outcome {
+            Outcome::Succeeded => Ok(()),
+            Outcome::Failed(error) => write!(f, " ({})", error.red()),
+        }
+    }
+}
+
+impl fmt::Display for History {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for event in &self.events {
+            writeln!(f, "{event}")?;
+        }
+        Ok(())
+    }
+}
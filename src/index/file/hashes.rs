@@ -1,19 +1,208 @@
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
+use sha1::Digest;
+use std::collections::BTreeMap;
+use std::fmt::Write;
 
-#[serde_as]
+/// A hash algorithm a file's [`Hashes`] may be computed with.
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    strum::Display,
+    strum::EnumString,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Algorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+    Blake3,
+}
+
+impl Algorithm {
+    /// The algorithms the `.mrpack` format requires every file to have.
+    pub const REQUIRED: [Self; 2] = [Self::Sha1, Self::Sha512];
+
+    /// Length of this algorithm's digests, in bytes.
+    #[must_use]
+    pub const fn digest_len(self) -> usize {
+        match self {
+            Self::Sha1 => 20,
+            Self::Sha256 | Self::Blake3 => 32,
+            Self::Sha512 => 64,
+        }
+    }
+
+    /// Compute the digest of some data.
+    #[must_use]
+    pub fn digest(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Sha1 => sha1::Sha1::digest(data).to_vec(),
+            Self::Sha256 => sha2::Sha256::digest(data).to_vec(),
+            Self::Sha512 => sha2::Sha512::digest(data).to_vec(),
+            Self::Blake3 => blake3::hash(data).as_bytes().to_vec(),
+        }
+    }
+}
+
+/// A set of digests of a file, one per [`Algorithm`].
+///
+/// The [required](Algorithm::REQUIRED) ones are always present, which is
+/// checked upon deserialization. Digests made with algorithms Invar doesn't
+/// know are skipped.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
-#[serde(rename_all = "camelCase")]
+#[serde(try_from = "RawDigests", into = "Digests")]
 pub struct Hashes {
-    #[serde_as(as = "serde_with::hex::Hex")]
-    sha1: [u8; 20],
-    #[serde_as(as = "serde_with::hex::Hex")]
-    sha512: [u8; 64],
+    digests: BTreeMap<Algorithm, Vec<u8>>,
+}
+
+// NOTE: The serialized form of [`Hashes`].
+#[serde_as]
+#[derive(Serialize)]
+struct Digests(#[serde_as(as = "BTreeMap<_, serde_with::hex::Hex>")] BTreeMap<Algorithm, Vec<u8>>);
+
+// NOTE: The deserialized form of [`Hashes`], not validated yet, with
+// algorithms that may be unknown.
+#[derive(Deserialize)]
+struct RawDigests(BTreeMap<String, String>);
+
+/// Errors that may arise when validating [`Hashes`].
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum InvalidHashes {
+    #[error("The required {0} hash is missing")]
+    Missing(Algorithm),
+    #[error("The {0} hash isn't a hex string")]
+    NotHex(Algorithm),
+    #[error("The {algorithm} hash is {actual} bytes long, expected {expected}")]
+    Length {
+        algorithm: Algorithm,
+        expected: usize,
+        actual: usize,
+    },
+}
+
+impl Hashes {
+    /// Compute the [required](Algorithm::REQUIRED) hashes of some data.
+    #[must_use]
+    pub fn compute(data: &[u8]) -> Self {
+        Self::compute_with(data, &[])
+    }
+
+    /// Compute the [required](Algorithm::REQUIRED) hashes of some data, plus
+    /// the `extra` ones.
+    #[must_use]
+    pub fn compute_with(data: &[u8], extra: &[Algorithm]) -> Self {
+        let digests = Algorithm::REQUIRED
+            .iter()
+            .chain(extra)
+            .map(|algorithm| (*algorithm, algorithm.digest(data)))
+            .collect();
+        Self { digests }
+    }
+
+    /// The digest computed with an [`Algorithm`], if there is one.
+    #[must_use]
+    pub fn get(&self, algorithm: Algorithm) -> Option<&[u8]> {
+        self.digests.get(&algorithm).map(Vec::as_slice)
+    }
+
+    /// The digest computed with an [`Algorithm`] as a lowercase hex string, if
+    /// there is one.
+    #[must_use]
+    pub fn hex(&self, algorithm: Algorithm) -> Option<String> {
+        self.get(algorithm).map(|digest| {
+            digest.iter().fold(String::new(), |mut hex, byte| {
+                let _ = write!(hex, "{byte:02x}");
+                hex
+            })
+        })
+    }
+
+    /// The SHA1 hash as a lowercase hex string.
+    #[must_use]
+    pub fn sha1_hex(&self) -> String {
+        self.hex(Algorithm::Sha1).unwrap_or_default()
+    }
+
+    /// Only the hashes the `.mrpack` format knows about.
+    #[must_use]
+    pub fn mrpack(&self) -> Self {
+        let digests = self
+            .digests
+            .iter()
+            .filter(|(algorithm, _)| Algorithm::REQUIRED.contains(algorithm))
+            .map(|(algorithm, digest)| (*algorithm, digest.clone()))
+            .collect();
+        Self { digests }
+    }
+}
+
+impl TryFrom<RawDigests> for Hashes {
+    type Error = InvalidHashes;
+
+    fn try_from(RawDigests(raw): RawDigests) -> Result<Self, Self::Error> {
+        let mut digests = BTreeMap::new();
+        for (name, hex) in raw {
+            let Ok(algorithm) = name.parse::<Algorithm>() else {
+                tracing::debug!(
+                    algorithm = name,
+                    "Skipping a hash made with an unknown algorithm"
+                );
+                continue;
+            };
+            let digest = decode_hex(&hex).ok_or(InvalidHashes::NotHex(algorithm))?;
+            digests.insert(algorithm, digest);
+        }
+        if let Some(missing) = Algorithm::REQUIRED
+            .into_iter()
+            .find(|algorithm| !digests.contains_key(algorithm))
+        {
+            return Err(InvalidHashes::Missing(missing));
+        }
+        for (algorithm, digest) in &digests {
+            if digest.len() != algorithm.digest_len() {
+                return Err(InvalidHashes::Length {
+                    algorithm: *algorithm,
+                    expected: algorithm.digest_len(),
+                    actual: digest.len(),
+                });
+            }
+        }
+        Ok(Self { digests })
+    }
+}
+
+impl From<Hashes> for Digests {
+    fn from(hashes: Hashes) -> Self {
+        Self(hashes.digests)
+    }
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|index| {
+            let byte = hex.get(index..index + 2)?;
+            u8::from_str_radix(byte, 16).ok()
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Hashes;
+    use super::{Algorithm, Hashes};
 
     #[test]
     fn yaml_roundtrip() {
@@ -25,4 +214,24 @@ mod tests {
         let hashes = dbg!(serde_yml::from_str::<Hashes>(YAML).unwrap());
         let _ = dbg!(serde_yml::to_string(&hashes).unwrap());
     }
+
+    #[test]
+    fn required_pair() {
+        let hashes = Hashes::compute_with(b"invar", &[Algorithm::Blake3]);
+        assert!(hashes.get(Algorithm::Blake3).is_some());
+        assert!(hashes.mrpack().get(Algorithm::Blake3).is_none());
+
+        let yaml = serde_yml::to_string(&hashes).unwrap();
+        assert_eq!(serde_yml::from_str::<Hashes>(&yaml).unwrap(), hashes);
+        assert!(serde_yml::from_str::<Hashes>(
+            "sha1: \"cc297357ff0031f805a744ca3a1378a112c2ddf4\""
+        )
+        .is_err());
+
+        let with_unknown = format!("{yaml}md5: \"d41d8cd98f00b204e9800998ecf8427e\"\n");
+        assert_eq!(
+            serde_yml::from_str::<Hashes>(&with_unknown).unwrap(),
+            hashes
+        );
+    }
 }
@@ -7,7 +7,7 @@ mod env;
 mod hashes;
 mod requirement;
 pub use env::Env;
-pub use hashes::Hashes;
+pub use hashes::{Algorithm, Hashes, InvalidHashes};
 pub use requirement::Requirement;
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -21,19 +21,22 @@ pub struct File {
     pub(crate) hashes: Hashes,
     /// For files that only exist on a specific environment, this field allows
     /// that to be specified.
-    pub(crate) env: Env,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) env: Option<Env>,
     /// An array containing HTTPS URLs where this file may be downloaded.
     pub(crate) downloads: Vec<Url>,
     /// An integer containing the size of the file, in bytes.
     pub file_size: usize,
 }
 
-impl From<Component> for File {
-    fn from(component: Component) -> Self {
+impl File {
+    /// Describe a [`Component`]'s file, placing datapacks into `world`.
+    #[must_use]
+    pub fn new(component: Component, world: &str) -> Self {
         Self {
-            path: component.runtime_path(),
-            hashes: component.hashes,
-            env: component.environment,
+            path: component.runtime_path(world),
+            hashes: component.hashes.mrpack(),
+            env: Some(component.environment),
             downloads: vec![component.download_url],
             file_size: component.file_size,
         }
@@ -1,40 +1,97 @@
 use crate::instance::Loader;
 use crate::pack::Pack;
 use file::File;
-use semver::Version;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 /// An entity representing a single project in the `files` array.
 pub mod file;
 
+mod mrpack;
+pub use mrpack::*;
+
 /// [Modrinth's `.mrpack`](https://support.modrinth.com/en/articles/8802351-modrinth-modpack-format-mrpack) format structure.
-#[derive(Debug, Clone, Serialize)]
+///
+/// Versions are kept as they are written, since packs made by other tools
+/// don't always use semantic versions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct Index<'pack, 'files> {
-    pub dependencies: HashMap<Loader, Version>,
-    pub files: &'files [File],
+pub struct Index {
+    pub dependencies: HashMap<String, String>,
+    pub files: Vec<File>,
     pub format_version: u8,
-    pub game: &'static str,
-    pub name: &'pack str,
-    pub version_id: &'pack Version,
+    pub game: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    pub version_id: String,
 }
 
-impl Index<'_, '_> {
+impl Index {
     const GAME_LITERAL: &'static str = "minecraft";
     const FORMAT_VERSION: u8 = 1;
-}
 
-impl<'pack, 'files> Index<'pack, 'files> {
     #[must_use]
-    pub fn from_pack_and_files(pack: &'pack Pack, files: &'files [File]) -> Self {
+    pub fn from_pack_and_files(pack: &Pack, files: Vec<File>) -> Self {
         Self {
-            game: Self::GAME_LITERAL,
+            game: Self::GAME_LITERAL.to_string(),
             format_version: Self::FORMAT_VERSION,
-            version_id: &pack.version,
-            name: &pack.name,
+            version_id: pack.version.to_string(),
+            name: pack.name.clone(),
+            summary: None,
             dependencies: pack.instance.index_dependencies(),
             files,
         }
     }
+
+    /// The Minecraft version this pack is made for.
+    #[must_use]
+    pub fn minecraft_version(&self) -> Option<&str> {
+        self.dependencies
+            .get(Loader::Minecraft.index_key())
+            .map(String::as_str)
+    }
+
+    /// The modloader this pack depends on and its version, if there is one.
+    #[must_use]
+    pub fn loader(&self) -> Option<(Loader, &str)> {
+        self.dependencies.iter().find_map(|(key, version)| {
+            Loader::from_index_key(key)
+                .filter(|loader| *loader != Loader::Minecraft)
+                .map(|loader| (loader, version.as_str()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Index;
+    use crate::instance::Loader;
+
+    #[test]
+    fn reads_foreign_indexes() {
+        let json = r#"{
+            "formatVersion": 1,
+            "game": "minecraft",
+            "versionId": "v2-beta",
+            "name": "Example",
+            "files": [{
+                "path": "mods/sodium.jar",
+                "hashes": { "sha1": "SHA1", "sha512": "SHA512" },
+                "downloads": ["https://cdn.modrinth.com/data/AANobbMI/versions/4Gs1yj5V/sodium.jar"],
+                "fileSize": 1
+            }],
+            "dependencies": { "minecraft": "1.21", "fabric-loader": "0.16.9" }
+        }"#
+        .replace("SHA512", &"00".repeat(64))
+        .replace("SHA1", &"00".repeat(20));
+        let index: Index = serde_json::from_str(&json).unwrap();
+        assert_eq!(index.minecraft_version(), Some("1.21"));
+        assert_eq!(index.loader(), Some((Loader::Fabric, "0.16.9")));
+        assert!(index.files[0].env.is_none());
+
+        let reread: Index = serde_json::from_str(&serde_json::to_string(&index).unwrap()).unwrap();
+        assert_eq!(reread.version_id, "v2-beta");
+        assert_eq!(reread.files.len(), 1);
+    }
 }
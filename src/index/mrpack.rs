@@ -0,0 +1,187 @@
+use super::file::{Env, Requirement};
+use super::Index;
+use crate::local_storage;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Read, Seek};
+use std::path::{Path, PathBuf};
+use zip::ZipArchive;
+
+/// A whole `.mrpack` archive: its [`Index`] and the override files.
+///
+/// Override paths are relative to the instance directory, with the
+/// `overrides/` (or side-specific) prefix stripped.
+#[derive(Debug, Clone)]
+pub struct Mrpack {
+    pub index: Index,
+    pub overrides: Vec<(PathBuf, Vec<u8>)>,
+    pub client_overrides: Vec<(PathBuf, Vec<u8>)>,
+    pub server_overrides: Vec<(PathBuf, Vec<u8>)>,
+}
+
+/// The sides an override file is needed on, which decides the folder of an
+/// `.mrpack` it goes into.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum, strum::Display,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum OverrideSide {
+    #[default]
+    Both,
+    Client,
+    Server,
+}
+
+impl OverrideSide {
+    pub const ALL: [Self; 3] = [Self::Both, Self::Client, Self::Server];
+
+    /// The side of files with this [`Env`]: files unsupported on one side
+    /// only go to the other.
+    #[must_use]
+    pub const fn from_env(env: &Env) -> Self {
+        match (env.client, env.server) {
+            (Requirement::Unsupported, Requirement::Unsupported) => Self::Both,
+            (_, Requirement::Unsupported) => Self::Client,
+            (Requirement::Unsupported, _) => Self::Server,
+            _ => Self::Both,
+        }
+    }
+
+    /// The [`Env`] of files needed only on this side.
+    #[must_use]
+    pub const fn env(self) -> Option<Env> {
+        match self {
+            Self::Both => None,
+            Self::Client => Some(Env {
+                client: Requirement::Required,
+                server: Requirement::Unsupported,
+            }),
+            Self::Server => Some(Env {
+                client: Requirement::Unsupported,
+                server: Requirement::Required,
+            }),
+        }
+    }
+
+    /// The folder of an `.mrpack` these override files go into.
+    #[must_use]
+    pub const fn folder(self) -> &'static str {
+        match self {
+            Self::Both => "overrides",
+            Self::Client => "client-overrides",
+            Self::Server => "server-overrides",
+        }
+    }
+
+    /// Whether files with this [`Env`] belong in an export for this side.
+    /// Everything does for [`Both`](Self::Both).
+    #[must_use]
+    pub fn wants(self, env: &Env) -> bool {
+        match self {
+            Self::Both => true,
+            Self::Client => env.client != Requirement::Unsupported,
+            Self::Server => env.server != Requirement::Unsupported,
+        }
+    }
+
+    /// Whether override files of the `other` side belong in an export for
+    /// this side.
+    #[must_use]
+    pub fn covers(self, other: Self) -> bool {
+        self == Self::Both || other == Self::Both || self == other
+    }
+
+    /// Whether files of this side are needed on the client.
+    #[must_use]
+    pub const fn on_client(self) -> bool {
+        matches!(self, Self::Both | Self::Client)
+    }
+}
+
+impl Mrpack {
+    pub const INDEX_FILE_NAME: &'static str = "modrinth.index.json";
+
+    /// Read a `.mrpack` archive from a file.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file can't be read or isn't
+    /// a valid `.mrpack`.
+    pub fn read(path: &Path) -> Result<Self, ReadError> {
+        let file = fs::File::open(path).map_err(|source| local_storage::Error::Io {
+            source,
+            faulty_path: Some(path.to_path_buf()),
+        })?;
+        Self::from_reader(file)
+    }
+
+    /// Read a `.mrpack` archive from anything seekable, like an in-memory
+    /// buffer.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the archive can't be read, or
+    /// has no valid [`INDEX_FILE_NAME`](Self::INDEX_FILE_NAME).
+    pub fn from_reader(reader: impl Read + Seek) -> Result<Self, ReadError> {
+        let mut archive = ZipArchive::new(reader).map_err(local_storage::Error::from)?;
+        let index = {
+            let entry = archive
+                .by_name(Self::INDEX_FILE_NAME)
+                .map_err(|_| ReadError::MissingIndex)?;
+            serde_json::from_reader(entry).map_err(local_storage::Error::from)?
+        };
+
+        let mut mrpack = Self {
+            index,
+            overrides: vec![],
+            client_overrides: vec![],
+            server_overrides: vec![],
+        };
+        for index in 0..archive.len() {
+            let mut entry = archive
+                .by_index(index)
+                .map_err(local_storage::Error::from)?;
+            let Some(name) = entry.enclosed_name() else {
+                continue;
+            };
+            if entry.is_dir() {
+                continue;
+            }
+            let Some(side) = OverrideSide::ALL
+                .into_iter()
+                .find(|side| name.starts_with(side.folder()))
+            else {
+                continue;
+            };
+            let overrides = match side {
+                OverrideSide::Both => &mut mrpack.overrides,
+                OverrideSide::Client => &mut mrpack.client_overrides,
+                OverrideSide::Server => &mut mrpack.server_overrides,
+            };
+            let mut contents = vec![];
+            entry
+                .read_to_end(&mut contents)
+                .map_err(|source| local_storage::Error::Io {
+                    source,
+                    faulty_path: Some(name.clone()),
+                })?;
+            let relative = name
+                .strip_prefix(side.folder())
+                .unwrap_or(&name)
+                .to_path_buf();
+            overrides.push((relative, contents));
+        }
+        Ok(mrpack)
+    }
+}
+
+/// Errors that may arise when [reading](Mrpack::read) a `.mrpack`.
+#[derive(thiserror::Error, Debug)]
+pub enum ReadError {
+    #[error(transparent)]
+    LocalStorage(#[from] local_storage::Error),
+    #[error("The archive has no `modrinth.index.json`")]
+    MissingIndex,
+}
@@ -1,9 +1,13 @@
+use crate::component::Channel;
 use clap::ValueEnum;
 use semver::Version;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use strum::{Display, EnumIter};
 
+mod versions;
+pub use versions::*;
+
 /// A struct representing a Minecraft instance.
 ///
 /// An instance does NOT take into account the associated `Component`s. Those
@@ -23,14 +27,34 @@ pub struct Instance {
     /// require a compatibility layer without getting bombarded with
     /// incompatibility warnings.
     pub allowed_foreign_loaders: HashSet<Loader>,
+
+    /// The least stable release channel components are added and updated
+    /// from. All channels are allowed if this is not set.
+    ///
+    /// Components can override this with their own
+    /// [`Constraint`](crate::component::Constraint).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub release_channel: Option<Channel>,
 }
 
 impl Instance {
+    /// Whether versions from `channel` may be added to this instance.
+    #[must_use]
+    pub fn accepts_channel(&self, channel: Channel) -> bool {
+        self.release_channel.is_none_or(|least| channel <= least)
+    }
+
     #[must_use = "Unused instance dependencies"]
-    pub fn index_dependencies(&self) -> HashMap<Loader, Version> {
+    pub fn index_dependencies(&self) -> HashMap<String, String> {
         let mut dependencies = HashMap::new();
-        dependencies.insert(self.loader, self.loader_version.clone());
-        dependencies.insert(Loader::Minecraft, self.minecraft_version.clone());
+        dependencies.insert(
+            self.loader.index_key().to_string(),
+            self.loader_version.to_string(),
+        );
+        dependencies.insert(
+            Loader::Minecraft.index_key().to_string(),
+            self.minecraft_version.to_string(),
+        );
         dependencies
     }
 }
@@ -81,3 +105,70 @@ pub enum Loader {
     #[serde(other)]
     Other,
 }
+
+impl Loader {
+    /// The [`Instance::allowed_foreign_loaders`] a new instance with this
+    /// loader starts with: the loaders known to be compatible with it.
+    #[must_use]
+    pub fn default_foreign_loaders(self) -> HashSet<Self> {
+        let mut loaders = HashSet::from_iter([Self::Minecraft]);
+        if self == Self::Forge || self == Self::Neoforge {
+            // Neoforge should be compatible with Forge mods.
+            loaders.extend([Self::Forge, Self::Neoforge]);
+            loaders.remove(&self);
+        }
+        if self == Self::Quilt {
+            // Quilt should be compatible with Fabric mods.
+            loaders.insert(Self::Fabric);
+        }
+        loaders
+    }
+
+    /// Slugs of the API mods and compatibility layers packs made for this
+    /// loader can't work without, which are marked as
+    /// [essential](crate::Component::essential) when added.
+    #[must_use]
+    pub const fn essential_slugs(self) -> &'static [&'static str] {
+        match self {
+            Self::Fabric => &["fabric-api"],
+            Self::Quilt => &["qsl", "quilted-fabric-api", "fabric-api"],
+            Self::Forge | Self::Neoforge => &["connector", "forgified-fabric-api"],
+            Self::Minecraft | Self::Other => &[],
+        }
+    }
+
+    /// Whether the component with this slug is one of the
+    /// [`essential_slugs`](Self::essential_slugs).
+    #[must_use]
+    pub fn is_essential(self, slug: &str) -> bool {
+        self.essential_slugs().contains(&slug)
+    }
+
+    /// The key of this loader in the `dependencies` of a `modrinth.index.json`.
+    #[must_use]
+    pub const fn index_key(self) -> &'static str {
+        match self {
+            Self::Minecraft => "minecraft",
+            Self::Forge => "forge",
+            Self::Neoforge => "neoforge",
+            Self::Fabric => "fabric-loader",
+            Self::Quilt => "quilt-loader",
+            Self::Other => "other",
+        }
+    }
+
+    /// The loader with this key in the `dependencies` of a
+    /// `modrinth.index.json`. Bare loader names, which some tools write
+    /// instead, are accepted too.
+    #[must_use]
+    pub fn from_index_key(key: &str) -> Option<Self> {
+        match key {
+            "minecraft" => Some(Self::Minecraft),
+            "forge" => Some(Self::Forge),
+            "neoforge" => Some(Self::Neoforge),
+            "fabric-loader" | "fabric" => Some(Self::Fabric),
+            "quilt-loader" | "quilt" => Some(Self::Quilt),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use super::Loader;
+use crate::local_storage::{self, PersistedEntity};
+use crate::network;
+use crate::plan::{Action, Plan};
+use chrono::{DateTime, Utc};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use strum::IntoEnumIterator;
+use url::Url;
+
+/// Mojang's list of all Minecraft versions.
+pub const MINECRAFT_MANIFEST_URL: &str =
+    "https://piston-meta.mojang.com/mc/game/version_manifest_v2.json";
+pub const FABRIC_META_URL: &str = "https://meta.fabricmc.net/v2";
+pub const QUILT_META_URL: &str = "https://meta.quiltmc.org/v3";
+pub const FORGE_PROMOTIONS_URL: &str =
+    "https://files.minecraftforge.net/net/minecraftforge/forge/promotions_slim.json";
+pub const NEOFORGE_VERSIONS_URL: &str =
+    "https://maven.neoforged.net/api/maven/versions/releases/net/neoforged/neoforge";
+
+/// Versions of Minecraft or a [`Loader`] known to exist, as listed by their
+/// upstream, newest first.
+///
+/// Only versions that can be read as semantic versions are kept, so snapshots
+/// are left out.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KnownVersions {
+    pub versions: Vec<Version>,
+}
+
+/// [`KnownVersions`] kept around by `invar meta refresh`, so that versions
+/// can be verified without reaching the upstreams every time.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VersionCache {
+    pub refreshed_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minecraft: Option<KnownVersions>,
+    /// Loader versions, keyed by [`VersionCache::loader_key`].
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub loaders: BTreeMap<String, KnownVersions>,
+}
+
+impl PersistedEntity for VersionCache {
+    const FILE_PATH: &'static str = ".invar/meta/versions.yml";
+}
+
+#[derive(Deserialize)]
+struct MinecraftManifest {
+    versions: Vec<MinecraftManifestEntry>,
+}
+
+#[derive(Deserialize)]
+struct MinecraftManifestEntry {
+    id: String,
+    url: Url,
+}
+
+#[derive(Deserialize)]
+struct MinecraftVersionInfo {
+    downloads: HashMap<String, MinecraftDownload>,
+}
+
+#[derive(Deserialize)]
+struct MinecraftDownload {
+    url: Url,
+}
+
+#[derive(Deserialize)]
+struct MetaLoaderEntry {
+    loader: MetaLoader,
+}
+
+#[derive(Deserialize)]
+struct MetaLoader {
+    version: String,
+}
+
+#[derive(Deserialize)]
+struct ForgePromotions {
+    promos: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct NeoforgeVersions {
+    versions: Vec<String>,
+}
+
+impl KnownVersions {
+    /// Fetch all released Minecraft versions from Mojang's version manifest.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the manifest can't be fetched.
+    pub fn minecraft() -> Result<Self, network::Error> {
+        let manifest: MinecraftManifest = network::get_json(MINECRAFT_MANIFEST_URL)?;
+        Ok(Self::from_ids(
+            manifest.versions.into_iter().map(|entry| entry.id),
+        ))
+    }
+
+    /// Fetch the versions of `loader` made for `minecraft_version` from the
+    /// loader's metadata endpoint. Forge only lists its promoted versions.
+    ///
+    /// Returns [`None`] for loaders without one.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the metadata can't be fetched.
+    pub fn loader(
+        loader: Loader,
+        minecraft_version: &Version,
+    ) -> Result<Option<Self>, network::Error> {
+        let minecraft_id = minecraft_id(minecraft_version);
+        let ids = match loader {
+            Loader::Fabric | Loader::Quilt => {
+                let base = match loader {
+                    Loader::Quilt => QUILT_META_URL,
+                    _ => FABRIC_META_URL,
+                };
+                let url = format!("{base}/versions/loader/{minecraft_id}");
+                let entries: Vec<MetaLoaderEntry> = network::get_json(url)?;
+                entries
+                    .into_iter()
+                    .map(|entry| entry.loader.version)
+                    .collect()
+            }
+            Loader::Forge => {
+                let promotions: ForgePromotions = network::get_json(FORGE_PROMOTIONS_URL)?;
+                promotions
+                    .promos
+                    .into_iter()
+                    .filter(|(key, _)| {
+                        key.rsplit_once('-')
+                            .is_some_and(|(minecraft, _)| minecraft == minecraft_id)
+                    })
+                    .map(|(_, version)| version)
+                    .collect()
+            }
+            Loader::Neoforge => {
+                // NOTE: NeoForge versions start with Minecraft's minor and patch
+                // versions, like `20.4.237` for `1.20.4`.
+                let prefix = format!("{}.{}.", minecraft_version.minor, minecraft_version.patch);
+                let response: NeoforgeVersions = network::get_json(NEOFORGE_VERSIONS_URL)?;
+                response
+                    .versions
+                    .into_iter()
+                    .filter(|version| version.starts_with(&prefix))
+                    .collect::<Vec<_>>()
+            }
+            Loader::Minecraft | Loader::Other => return Ok(None),
+        };
+        Ok(Some(Self::from_ids(ids)))
+    }
+
+    /// Read versions as their upstream writes them, padding missing minor and
+    /// patch parts with zeroes.
+    pub fn from_ids(ids: impl IntoIterator<Item = String>) -> Self {
+        let mut versions = ids
+            .into_iter()
+            .filter_map(|id| {
+                let padded = match id.matches('.').count() {
+                    0 => format!("{id}.0.0"),
+                    1 => format!("{id}.0"),
+                    _ => id,
+                };
+                padded.parse().ok()
+            })
+            .collect::<Vec<Version>>();
+        versions.sort_unstable_by(|a, b| b.cmp(a));
+        versions.dedup();
+        Self { versions }
+    }
+
+    /// Whether `version` is one of the known ones.
+    #[must_use]
+    pub fn contains(&self, version: &Version) -> bool {
+        self.versions.contains(version)
+    }
+
+    /// The newest known version, skipping pre-releases if there are others.
+    #[must_use]
+    pub fn latest(&self) -> Option<&Version> {
+        self.versions
+            .iter()
+            .find(|version| version.pre.is_empty())
+            .or_else(|| self.versions.first())
+    }
+}
+
+impl VersionCache {
+    /// Read the cache, or an empty one if it's missing or unreadable.
+    #[must_use]
+    pub fn read_or_default() -> Self {
+        Self::read().unwrap_or_default()
+    }
+
+    /// Fetch the Minecraft versions, and the versions of every [`Loader`] made
+    /// for each of the `minecraft_versions`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any of the lists can't be
+    /// fetched.
+    pub fn refresh(minecraft_versions: &[Version]) -> Result<Self, network::Error> {
+        let mut loaders = BTreeMap::new();
+        for minecraft_version in minecraft_versions {
+            for loader in Loader::iter() {
+                if let Some(known) = KnownVersions::loader(loader, minecraft_version)? {
+                    loaders.insert(Self::loader_key(loader, minecraft_version), known);
+                }
+            }
+        }
+        Ok(Self {
+            refreshed_at: Some(Utc::now()),
+            minecraft: Some(KnownVersions::minecraft()?),
+            loaders,
+        })
+    }
+
+    /// Compute the [`Plan`] for writing this cache out.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the cache can't be serialized.
+    pub fn plan_write(&self) -> local_storage::Result<Plan> {
+        let yaml = serde_yml::to_string(self)?;
+        let mut plan = Plan::new();
+        plan.push(Action::write_entity(Self::FILE_PATH, yaml));
+        Ok(plan)
+    }
+
+    /// The cached versions of `loader` made for `minecraft_version`.
+    #[must_use]
+    pub fn loader(&self, loader: Loader, minecraft_version: &Version) -> Option<&KnownVersions> {
+        self.loaders
+            .get(&Self::loader_key(loader, minecraft_version))
+    }
+
+    /// The key loader versions are cached under, like `fabric-1.20.1`.
+    fn loader_key(loader: Loader, minecraft_version: &Version) -> String {
+        let loader = loader.to_string().to_lowercase();
+        format!("{loader}-{}", minecraft_id(minecraft_version))
+    }
+}
+
+/// Find the download URL of the vanilla server of `minecraft_version` in
+/// Mojang's version manifest, or [`None`] if there is no such version or it
+/// has no server.
+///
+/// # Errors
+///
+/// This function will return an error if the manifest can't be fetched.
+pub fn minecraft_server_url(minecraft_version: &Version) -> Result<Option<Url>, network::Error> {
+    let manifest: MinecraftManifest = network::get_json(MINECRAFT_MANIFEST_URL)?;
+    let id = minecraft_id(minecraft_version);
+    let Some(entry) = manifest.versions.into_iter().find(|entry| entry.id == id) else {
+        return Ok(None);
+    };
+    let mut info: MinecraftVersionInfo = network::get_json(entry.url)?;
+    Ok(info.downloads.remove("server").map(|server| server.url))
+}
+
+/// How Mojang writes a Minecraft version: `1.21` rather than `1.21.0`.
+#[must_use]
+pub fn minecraft_id(version: &Version) -> String {
+    match version.patch {
+        0 => format!("{}.{}", version.major, version.minor),
+        _ => version.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{minecraft_id, KnownVersions};
+    use semver::Version;
+
+    #[test]
+    fn reads_upstream_ids() {
+        let known =
+            KnownVersions::from_ids(["1.21", "1.20.6", "24w14a", "1.21.1-rc1"].map(String::from));
+        assert!(known.contains(&Version::new(1, 21, 0)));
+        assert_eq!(known.versions.len(), 3);
+        assert_eq!(known.latest(), Some(&Version::new(1, 21, 0)));
+        assert_eq!(minecraft_id(&Version::new(1, 21, 0)), "1.21");
+        assert_eq!(minecraft_id(&Version::new(1, 20, 1)), "1.20.1");
+    }
+}
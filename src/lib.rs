@@ -1,4 +1,3 @@
-#![feature(never_type)]
 #![feature(error_generic_member_access)]
 #![feature(let_chains)]
 #![doc = include_str!("../README.md")]
@@ -11,6 +10,15 @@ pub use component::Component;
 pub mod index;
 pub use index::Index;
 
+/// Limit on the time bulk operations spend querying remote APIs.
+pub mod budget;
+
+/// Content-addressed cache for downloaded files.
+pub mod cache;
+
+/// Append-only log of the operations run on a pack.
+pub mod history;
+
 /// The Minecraft instance entity.
 mod instance;
 pub use instance::*;
@@ -22,5 +30,23 @@ pub mod local_storage;
 mod pack;
 pub use pack::*;
 
+/// Two-phase plan/apply API for mutating operations.
+pub mod plan;
+pub use plan::Plan;
+
 /// Interface for self-hosting a server with the pack.
 pub mod server;
+
+/// Retries, rate limits and timeouts for requests to remote APIs.
+pub mod network;
+
+/// Events for rendering the progress of operations.
+pub mod progress;
+
+/// Coordinated terminal output: logs, status lines and prompts.
+pub mod terminal;
+
+/// A mock **Modrinth API** and fixtures for tests that shouldn't touch the
+/// network.
+#[cfg(feature = "test-support")]
+pub mod test_support;
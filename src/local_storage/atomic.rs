@@ -0,0 +1,125 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Directory (relative to the pack root) the previous versions of persisted
+/// entities are kept in, see [`write_atomic`].
+pub const BAK_DIR: &str = ".invar/bak";
+
+/// Environment variable that makes writes flush files to disk, like
+/// `--fsync`.
+pub const FSYNC_VAR: &str = "INVAR_FSYNC";
+
+static FSYNC: AtomicBool = AtomicBool::new(false);
+
+/// Flush every file to disk before it replaces the previous version (or not)
+/// for the rest of the process. Slower, but survives power loss and not just
+/// crashes.
+pub fn set_fsync(fsync: bool) {
+    FSYNC.store(fsync, Ordering::Relaxed);
+}
+
+/// Whether files are flushed to disk when written, see [`set_fsync`].
+#[must_use]
+pub fn is_fsync() -> bool {
+    FSYNC.load(Ordering::Relaxed)
+}
+
+/// Where the previous version of the file at `path` is kept when it's
+/// written with a backup, like `.invar/bak/pack.yml.bak`.
+///
+/// Returns [`None`] unless `path` is a plain relative path, without any `..`,
+/// since the backup would end up outside of [`BAK_DIR`] otherwise.
+#[must_use]
+pub fn bak_path(path: &Path) -> Option<PathBuf> {
+    let mut relative = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    let mut name = relative.into_os_string();
+    if name.is_empty() {
+        return None;
+    }
+    name.push(".bak");
+    Some(Path::new(BAK_DIR).join(name))
+}
+
+/// Write `contents` to the file at `path` without ever leaving it half
+/// written.
+///
+/// They go into a temporary file next to it first, which is then renamed over
+/// it, so a crash leaves either the old or the new version.
+///
+/// With `backup`, the previous version is copied to its [`bak_path`] first.
+///
+/// # Errors
+///
+/// This function will return an error if any of the files can't be written,
+/// or if `path` should be backed up but has no [`bak_path`].
+pub fn write_atomic(path: &Path, contents: &[u8], backup: bool) -> io::Result<()> {
+    let bak = match backup && path.is_file() {
+        true => Some(bak_path(path).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} can't be backed up in {BAK_DIR}", path.display()),
+            )
+        })?),
+        false => None,
+    };
+    let mut name = path.file_name().unwrap_or_default().to_owned();
+    name.push(".tmp");
+    let temporary = path.with_file_name(name);
+    let mut file = File::create(&temporary)?;
+    file.write_all(contents)?;
+    if is_fsync() {
+        file.sync_all()?;
+    }
+    drop(file);
+
+    if let Some(bak) = bak {
+        if let Some(parent) = bak.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let gitignore = Path::new(BAK_DIR).join(".gitignore");
+        if !gitignore.exists() {
+            fs::write(gitignore, "*\n")?;
+        }
+        fs::copy(path, bak)?;
+    }
+    fs::rename(&temporary, path).inspect_err(|_| {
+        let _ = fs::remove_file(&temporary);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_replace_files_whole() {
+        let root = std::env::temp_dir().join(format!("invar-atomic-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let path = root.join("pack.yml");
+        write_atomic(&path, b"old", false).unwrap();
+        write_atomic(&path, b"new", false).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"new");
+        assert_eq!(fs::read_dir(&root).unwrap().count(), 1);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn backups_stay_in_the_backup_directory() {
+        let bak = Path::new(BAK_DIR);
+        assert_eq!(
+            bak_path(Path::new("./mods/sodium.invar.yaml")),
+            Some(bak.join("mods/sodium.invar.yaml.bak"))
+        );
+        assert_eq!(bak_path(Path::new("/etc/passwd")), None);
+        assert_eq!(bak_path(Path::new("mods/../../pack.yml")), None);
+    }
+}
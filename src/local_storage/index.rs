@@ -0,0 +1,121 @@
+use super::{metadata_files, Error, Result};
+use crate::component::Component;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File (relative to the pack's root) the [`ComponentIndex`] is kept in.
+pub const INDEX_FILE: &str = ".invar/index.yml";
+
+/// Where the metadata file of each [`Component`] is, by slug, so finding one
+/// doesn't take walking the whole pack.
+///
+/// The index is only a cache: entries are checked before being trusted, and
+/// it's rebuilt from a full scan whenever one is missing or stale, like after
+/// a component is retagged, moved or edited by hand.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComponentIndex {
+    /// Paths relative to the pack's root.
+    pub paths: BTreeMap<String, PathBuf>,
+}
+
+impl ComponentIndex {
+    /// Read the index of the pack at `root`, empty if there's none or it
+    /// can't be parsed.
+    #[must_use]
+    pub fn read(root: &Path) -> Self {
+        fs::read_to_string(root.join(INDEX_FILE))
+            .ok()
+            .and_then(|yaml| serde_yml::from_str(&yaml).ok())
+            .unwrap_or_default()
+    }
+
+    /// Scan the pack at `root` for metadata files and index all of them.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the pack can't be scanned.
+    pub fn rebuild(root: &Path) -> Result<Self> {
+        let paths = metadata_files(root)?
+            .filter_map(|entry| {
+                let slug = entry
+                    .file_name()
+                    .to_str()?
+                    .strip_suffix(Component::LOCAL_STORAGE_SUFFIX)?
+                    .to_string();
+                let path = entry.path().strip_prefix(root).ok()?.to_path_buf();
+                Some((slug, path))
+            })
+            .collect();
+        Ok(Self { paths })
+    }
+
+    /// Write the index into the pack at `root`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the index can't be written.
+    pub fn write(&self, root: &Path) -> Result<()> {
+        super::ensure_writable()?;
+        let path = root.join(INDEX_FILE);
+        let io_error = |source| Error::Io {
+            source,
+            faulty_path: Some(path.clone()),
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(io_error)?;
+        }
+        super::write_atomic(&path, serde_yml::to_string(self)?.as_bytes(), false).map_err(io_error)
+    }
+
+    /// Find the metadata file of the component called `slug` in the pack at
+    /// `root`, through the index if its entry is still right, or else through
+    /// a full scan that refreshes the index (unless in
+    /// [read-only mode](super::set_read_only)).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the pack can't be scanned.
+    pub fn find(root: &Path, slug: &str) -> Result<Option<PathBuf>> {
+        let file_name = format!("{slug}{}", Component::LOCAL_STORAGE_SUFFIX);
+        let indexed = Self::read(root).paths.get(slug).map(|path| root.join(path));
+        if let Some(path) = indexed.filter(|path| {
+            path.is_file() && path.file_name().is_some_and(|name| *name == *file_name)
+        }) {
+            return Ok(Some(path));
+        }
+        let index = Self::rebuild(root)?;
+        if !super::is_read_only() {
+            if let Err(error) = index.write(root) {
+                tracing::warn!(%error, "Failed to update the component index");
+            }
+        }
+        Ok(index.paths.get(slug).map(|path| root.join(path)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stale_entries_are_rebuilt() {
+        let root = std::env::temp_dir().join(format!("invar-index-{}", std::process::id()));
+        fs::create_dir_all(root.join("mod/performance")).unwrap();
+        fs::write(root.join("mod/performance/sodium.invar.yaml"), "").unwrap();
+        let found = ComponentIndex::find(&root, "sodium").unwrap();
+        assert_eq!(found, Some(root.join("mod/performance/sodium.invar.yaml")));
+
+        fs::create_dir_all(root.join("mod/visual")).unwrap();
+        fs::rename(
+            root.join("mod/performance/sodium.invar.yaml"),
+            root.join("mod/visual/sodium.invar.yaml"),
+        )
+        .unwrap();
+        let found = ComponentIndex::find(&root, "sodium").unwrap();
+        assert_eq!(found, Some(root.join("mod/visual/sodium.invar.yaml")));
+        assert_eq!(ComponentIndex::find(&root, "lithium").unwrap(), None);
+        fs::remove_dir_all(root).unwrap();
+    }
+}
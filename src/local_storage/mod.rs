@@ -1,10 +1,18 @@
 use crate::component::Component;
 use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::{fs, io};
 use tracing::instrument;
 use walkdir::WalkDir;
 
+mod atomic;
+pub use atomic::*;
+
+mod index;
+pub use index::*;
+
 pub type Result<T> = std::result::Result<T, self::Error>;
 
 /// Possible errors that may arise while interacting with local storage.
@@ -27,6 +35,65 @@ pub enum Error {
 
     #[error(transparent)]
     Walkdir(#[from] walkdir::Error),
+
+    #[error(transparent)]
+    Pattern(#[from] glob::PatternError),
+
+    #[error("The component in {metadata:?} would be placed outside its directory, at {path:?}")]
+    UnsafePath { metadata: PathBuf, path: PathBuf },
+
+    #[error("Nothing can be written in read-only mode")]
+    ReadOnly,
+}
+
+/// Environment variable that turns on read-only mode, like `--read-only`.
+pub const READ_ONLY_VAR: &str = "INVAR_READ_ONLY";
+
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+
+/// Forbid (or allow again) all writes to the pack for the rest of the process,
+/// for inspecting packs in CI containers or on shared mounts.
+///
+/// In read-only mode, [plans](crate::plan::Plan) and persisted entities fail
+/// with [`Error::ReadOnly`] instead of being written, files missing from the
+/// [cache](crate::cache) aren't downloaded into it, and bookkeeping like the
+/// history and the [`ComponentIndex`] is left as it is.
+pub fn set_read_only(read_only: bool) {
+    READ_ONLY.store(read_only, Ordering::Relaxed);
+}
+
+/// Whether writes are forbidden, see [`set_read_only`].
+#[must_use]
+pub fn is_read_only() -> bool {
+    READ_ONLY.load(Ordering::Relaxed)
+}
+
+/// Fail with [`Error::ReadOnly`] in [read-only mode](set_read_only).
+///
+/// # Errors
+///
+/// See above.
+pub fn ensure_writable() -> Result<()> {
+    match is_read_only() {
+        true => Err(Error::ReadOnly),
+        false => Ok(()),
+    }
+}
+
+/// Whether the pack file at `path` can't be written to, like on a read-only
+/// mount or in a checkout owned by someone else.
+///
+/// The file is opened for writing without being changed, and a missing file
+/// isn't read-only.
+#[must_use]
+pub fn detect_read_only(path: &Path) -> bool {
+    match OpenOptions::new().write(true).open(path) {
+        Ok(_) => false,
+        Err(error) => matches!(
+            error.kind(),
+            io::ErrorKind::ReadOnlyFilesystem | io::ErrorKind::PermissionDenied
+        ),
+    }
 }
 
 /// A trait that represents an entity (type) that can be persisted in a file.
@@ -52,19 +119,21 @@ pub trait PersistedEntity: Serialize + for<'de> Deserialize<'de> {
         Ok(entity)
     }
 
-    /// Serialize `self` into a string and write it to [`Self::FILE_PATH`].
+    /// Serialize `self` into a string and write it to [`Self::FILE_PATH`]
+    /// [atomically](write_atomic), keeping the previous version as a backup.
     ///
     /// # Errors
     ///
     /// This function will return an error if an error occurs while serializing
     /// [`self`](Self) to a string or while writing that string to
-    /// [`Self::FILE_PATH`].
+    /// [`Self::FILE_PATH`], or in [read-only mode](set_read_only).
     #[must_use = "You haven't checked if the entity was successfully persisted"]
     #[instrument(skip(self))]
     fn write(&self) -> Result<()> {
+        ensure_writable()?;
         let path = PathBuf::from(Self::FILE_PATH);
         let yaml = serde_yml::to_string(self)?;
-        fs::write(&path, yaml).map_err(|source| Error::Io {
+        write_atomic(&path, yaml.as_bytes(), true).map_err(|source| Error::Io {
             source,
             faulty_path: Some(path.clone()),
         })?;
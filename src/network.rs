@@ -0,0 +1,263 @@
+use crate::budget;
+use reqwest::blocking::{Client, ClientBuilder, Request, RequestBuilder, Response};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{IntoUrl, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Environment variable that turns on offline mode, like `--offline`.
+pub const OFFLINE_VAR: &str = "INVAR_OFFLINE";
+
+/// The longest a single wait between attempts may be, however long the API
+/// asks to wait.
+pub const MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// How requests to remote APIs are retried and timed out, see
+/// [`Settings::network`](crate::Settings::network).
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct NetworkSettings {
+    /// How many times a request failing with a transient error (a timeout,
+    /// `429 Too Many Requests` or a `5xx` status) is retried.
+    pub retries: u32,
+    /// How long to wait before the first retry, in milliseconds. The wait
+    /// doubles with every retry, unless the API says how long to wait.
+    pub backoff_ms: u64,
+    /// How long a request may take as a whole, in seconds.
+    pub timeout_secs: u64,
+    /// How long connecting to the API may take, in seconds.
+    pub connect_timeout_secs: u64,
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        Self {
+            retries: 3,
+            backoff_ms: 500,
+            timeout_secs: 30,
+            connect_timeout_secs: 10,
+        }
+    }
+}
+
+impl NetworkSettings {
+    #[must_use]
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// How long to wait before retrying for the `attempt`th time (counting
+    /// from zero) if the API doesn't say.
+    #[must_use]
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2_u64.saturating_pow(attempt);
+        Duration::from_millis(self.backoff_ms.saturating_mul(factor)).min(MAX_DELAY)
+    }
+}
+
+static SETTINGS: OnceLock<NetworkSettings> = OnceLock::new();
+
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Until when every request waits, because an API said its rate limit ran
+/// out.
+static PAUSED_UNTIL: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Use `settings` for requests made for the rest of the process. Only the
+/// first call has an effect, the [defaults](NetworkSettings::default) are used
+/// if there is none.
+pub fn configure(settings: NetworkSettings) {
+    let _ = SETTINGS.set(settings);
+}
+
+fn settings() -> NetworkSettings {
+    SETTINGS.get().copied().unwrap_or_default()
+}
+
+/// Don't make any requests for the rest of the process, failing them with
+/// [`Error::Offline`] instead. Cached data is still used where there is some.
+///
+/// Offline mode is also turned on by itself once an API can't be reached.
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+/// Whether offline mode is on, see [`set_offline`].
+#[must_use]
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+/// Errors that may arise while sending a request.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("The network can't be used in offline mode")]
+    Offline,
+
+    #[error(transparent)]
+    Request(#[from] reqwest::Error),
+}
+
+impl Error {
+    /// Whether the request failed because the API couldn't be reached, so
+    /// cached data may stand in for its response.
+    #[must_use]
+    pub fn is_unreachable(&self) -> bool {
+        match self {
+            Self::Offline => true,
+            Self::Request(error) => error.is_connect(),
+        }
+    }
+}
+
+/// A [`ClientBuilder`] with the [configured](configure) timeouts, for clients
+/// that need more setup, like default headers.
+pub fn client_builder() -> ClientBuilder {
+    let settings = settings();
+    Client::builder()
+        .timeout(Duration::from_secs(settings.timeout_secs))
+        .connect_timeout(Duration::from_secs(settings.connect_timeout_secs))
+}
+
+/// Build an HTTP client with the [configured](configure) timeouts.
+#[must_use]
+pub fn client() -> Client {
+    client_builder().build().unwrap_or_default()
+}
+
+/// Send `request`, retrying it with exponential backoff while it fails with
+/// a transient error and respecting the API's `Retry-After` and
+/// `X-Ratelimit-*` headers.
+///
+/// Once the retries (or the [time budget](budget)) run out, the last response
+/// is returned as is, so its status still has to be checked.
+///
+/// If the API can't be reached at all, [offline mode](set_offline) is turned
+/// on for the rest of the process.
+///
+/// # Errors
+///
+/// This function will return an error if the request can't be sent, after
+/// retrying timeouts and failed connections, or right away in offline mode.
+pub fn send(request: RequestBuilder) -> Result<Response, Error> {
+    let (client, request) = request.build_split();
+    let request = request?;
+    if is_offline() {
+        tracing::debug!(url = %request.url(), "Not sending a request in offline mode");
+        return Err(Error::Offline);
+    }
+    let settings = settings();
+    let mut attempt = 0;
+    loop {
+        wait_for_rate_limit();
+        // NOTE: Requests with streamed bodies can't be cloned, and so are
+        // only ever sent once.
+        let retry = match attempt < settings.retries && !budget::is_exhausted() {
+            true => request.try_clone(),
+            false => None,
+        };
+        let Some(next) = retry else {
+            return execute_last(&client, request);
+        };
+        let delay = match client.execute(next) {
+            Ok(response) => {
+                note_rate_limit(&response);
+                let status = response.status();
+                if !is_transient(status) {
+                    return Ok(response);
+                }
+                tracing::debug!(url = %response.url(), %status, attempt, "Retrying");
+                requested_delay(response.headers()).unwrap_or_else(|| settings.backoff(attempt))
+            }
+            Err(error) if error.is_timeout() || error.is_connect() => {
+                tracing::debug!(%error, attempt, "Retrying");
+                settings.backoff(attempt)
+            }
+            Err(error) => return Err(error.into()),
+        };
+        thread::sleep(delay.min(MAX_DELAY));
+        attempt += 1;
+    }
+}
+
+fn execute_last(client: &Client, request: Request) -> Result<Response, Error> {
+    let host = request.url().host_str().unwrap_or_default().to_string();
+    match client.execute(request) {
+        Ok(response) => {
+            note_rate_limit(&response);
+            Ok(response)
+        }
+        Err(error) => {
+            if error.is_connect() && !is_offline() {
+                tracing::warn!(host, "Couldn't reach the network, carrying on offline");
+                set_offline(true);
+            }
+            Err(error.into())
+        }
+    }
+}
+
+/// [Send](send) a `GET` request to `url` with a [client](client) and parse
+/// the response as JSON.
+///
+/// # Errors
+///
+/// This function will return an error if the request fails, the server
+/// responds with an error status, or the response can't be parsed.
+pub fn get_json<T: DeserializeOwned>(url: impl IntoUrl) -> Result<T, Error> {
+    Ok(send(client().get(url))?.error_for_status()?.json()?)
+}
+
+fn is_transient(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+fn header_secs(headers: &HeaderMap, name: &str) -> Option<Duration> {
+    let value = headers.get(name)?.to_str().ok()?.trim().parse().ok()?;
+    Some(Duration::from_secs(value))
+}
+
+/// How long the API asked to wait before trying again, if it did.
+fn requested_delay(headers: &HeaderMap) -> Option<Duration> {
+    header_secs(headers, RETRY_AFTER.as_str()).or_else(|| header_secs(headers, "x-ratelimit-reset"))
+}
+
+/// Hold off all requests until the rate limit resets if `response` used up
+/// the last request of it.
+fn note_rate_limit(response: &Response) {
+    let headers = response.headers();
+    if header_secs(headers, "x-ratelimit-remaining") != Some(Duration::ZERO) {
+        return;
+    }
+    let Some(reset) = header_secs(headers, "x-ratelimit-reset") else {
+        return;
+    };
+    tracing::debug!(url = %response.url(), ?reset, "Rate limit reached, pausing requests");
+    let until = Instant::now() + reset.min(MAX_DELAY);
+    let mut paused = PAUSED_UNTIL.lock().unwrap_or_else(PoisonError::into_inner);
+    *paused = Some(paused.map_or(until, |paused| paused.max(until)));
+}
+
+fn wait_for_rate_limit() {
+    let paused = *PAUSED_UNTIL.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(wait) = paused.and_then(|until| until.checked_duration_since(Instant::now())) {
+        thread::sleep(wait);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let settings = NetworkSettings::default();
+        assert_eq!(settings.backoff(0), Duration::from_millis(500));
+        assert_eq!(settings.backoff(2), Duration::from_secs(2));
+        assert_eq!(settings.backoff(40), MAX_DELAY);
+    }
+}
@@ -0,0 +1,86 @@
+use super::Pack;
+use crate::local_storage::{self, PersistedEntity};
+use crate::plan::{Action, GitSignature, Plan};
+use clap::ValueEnum;
+use semver::{BuildMetadata, Prerelease, Version};
+
+/// The part of a [`Pack::version`] to increment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, strum::Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum VersionPart {
+    Major,
+    Minor,
+    Patch,
+}
+
+impl VersionPart {
+    /// Increment this part of `version`, resetting the lower ones and dropping
+    /// any pre-release or build metadata.
+    #[must_use]
+    pub fn bump(self, version: &Version) -> Version {
+        let mut bumped = match self {
+            Self::Major => Version::new(version.major + 1, 0, 0),
+            Self::Minor => Version::new(version.major, version.minor + 1, 0),
+            Self::Patch => Version::new(version.major, version.minor, version.patch + 1),
+        };
+        bumped.pre = Prerelease::EMPTY;
+        bumped.build = BuildMetadata::EMPTY;
+        bumped
+    }
+}
+
+impl Pack {
+    /// Compute the [`Plan`] for moving this [`Pack`] to the next `part`
+    /// version: write the pack file with the new version, and unless
+    /// `no_git` is set, commit it and create an annotated `v<version>` tag.
+    ///
+    /// Returns the bumped [`Pack`] along with the [`Plan`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the pack can't be serialized.
+    pub fn plan_bump(
+        &self,
+        part: VersionPart,
+        no_git: bool,
+        author: Option<GitSignature>,
+    ) -> local_storage::Result<(Self, Plan)> {
+        let bumped = Self {
+            version: part.bump(&self.version),
+            ..self.clone()
+        };
+        let mut plan = Plan::new();
+        plan.push(Action::write_entity(
+            <Self as PersistedEntity>::FILE_PATH,
+            serde_yml::to_string(&bumped)?,
+        ));
+        if !no_git {
+            let message = format!("Release {} {}", bumped.name, bumped.version);
+            plan.push(Action::GitCommit {
+                message: message.clone(),
+                paths: vec![<Self as PersistedEntity>::FILE_PATH.into()],
+                author: author.clone(),
+            });
+            plan.push(Action::GitTag {
+                name: format!("v{}", bumped.version),
+                message,
+                author,
+            });
+        }
+        Ok((bumped, plan))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VersionPart;
+    use semver::Version;
+
+    #[test]
+    fn bumps_reset_lower_parts() {
+        let version: Version = "1.4.2-rc.1".parse().unwrap();
+        assert_eq!(VersionPart::Major.bump(&version), Version::new(2, 0, 0));
+        assert_eq!(VersionPart::Minor.bump(&version), Version::new(1, 5, 0));
+        assert_eq!(VersionPart::Patch.bump(&version), Version::new(1, 4, 3));
+    }
+}
@@ -0,0 +1,221 @@
+use super::Pack;
+use crate::component::{Category, Component};
+use crate::local_storage;
+use color_eyre::owo_colors::OwoColorize;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::{fmt, fs, io};
+use walkdir::WalkDir;
+use zip::ZipArchive;
+
+/// IDs that the loaders and the game itself write configs under.
+pub const BUILTIN_MOD_IDS: &[&str] = &[
+    "minecraft",
+    "fabric",
+    "fabricloader",
+    "quilt",
+    "forge",
+    "neoforge",
+];
+
+/// Entries of the pack's [`CONFIG_DIR`](Pack::CONFIG_DIR) that seem to belong
+/// to mods no longer in the pack.
+///
+/// Entries are matched to mods by name: `sodium-options.json` and
+/// `sodium/` belong to a mod with the `sodium` slug or ID. The IDs a mod
+/// declares in its file are used too, if the file is in the download
+/// [cache](crate::cache). Matching is lenient, so that configs are never
+/// reported unless nothing in the pack could have written them.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UnusedConfigs {
+    /// Files and directories right inside the config directory.
+    pub paths: Vec<PathBuf>,
+}
+
+impl UnusedConfigs {
+    /// Look for entries of the config directory owned by none of the
+    /// `components`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the config directory can't be read.
+    pub fn find(components: &[Component]) -> local_storage::Result<Self> {
+        let config_dir = Path::new(Pack::CONFIG_DIR);
+        if !config_dir.is_dir() {
+            return Ok(Self::default());
+        }
+        let mut owners = BUILTIN_MOD_IDS
+            .iter()
+            .map(|id| normalize(id))
+            .collect::<BTreeSet<_>>();
+        for component in components {
+            match component.category {
+                Category::Mod => {
+                    owners.insert(normalize(&component.slug));
+                    let jar = component.cache_request().path();
+                    owners.extend(declared_mod_ids(&jar).iter().map(|id| normalize(id)));
+                }
+                Category::Config => {
+                    owners.insert(normalize(&component.runtime_file_name()));
+                }
+                _ => {}
+            }
+        }
+        owners.retain(|owner| !owner.is_empty());
+
+        let mut paths = vec![];
+        let entries = fs::read_dir(config_dir).map_err(|source| local_storage::Error::Io {
+            source,
+            faulty_path: Some(config_dir.to_path_buf()),
+        })?;
+        for entry in entries {
+            let entry = entry.map_err(|source| local_storage::Error::Io {
+                source,
+                faulty_path: Some(config_dir.to_path_buf()),
+            })?;
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.starts_with('.') || holds_metadata(&path) {
+                continue;
+            }
+            let key = normalize(&name);
+            let owned = owners
+                .iter()
+                .any(|owner| key.starts_with(owner.as_str()) || owner.starts_with(&key));
+            if !owned {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        Ok(Self { paths })
+    }
+
+    /// All the files to remove to prune the `paths`, those in directories
+    /// included.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a directory can't be walked.
+    pub fn files(paths: &[PathBuf]) -> local_storage::Result<Vec<PathBuf>> {
+        let mut files = vec![];
+        for path in paths {
+            for entry in WalkDir::new(path).sort_by_file_name() {
+                let entry = entry?;
+                if !entry.file_type().is_dir() {
+                    files.push(entry.into_path());
+                }
+            }
+        }
+        Ok(files)
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+}
+
+/// Lowercase `name` and keep only its letters and digits, up to the first dot
+/// to drop extensions.
+fn normalize(name: &str) -> String {
+    name.split('.')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|char| char.to_ascii_lowercase())
+        .collect()
+}
+
+/// Whether `path` holds [`Component`] metadata rather than configs, like the
+/// directories of the config components' main tags.
+fn holds_metadata(path: &Path) -> bool {
+    WalkDir::new(path).into_iter().flatten().any(|entry| {
+        entry
+            .file_name()
+            .to_string_lossy()
+            .ends_with(Component::LOCAL_STORAGE_SUFFIX)
+    })
+}
+
+/// The mod IDs declared by the Fabric, Quilt, Forge or NeoForge metadata of
+/// the mod file at `path`. Empty if the file can't be read.
+fn declared_mod_ids(path: &Path) -> Vec<String> {
+    let read = || -> io::Result<Vec<String>> {
+        let mut archive = ZipArchive::new(fs::File::open(path)?)?;
+        let mut read_entry = |name: &str| -> Option<String> {
+            let mut contents = String::new();
+            archive
+                .by_name(name)
+                .ok()?
+                .read_to_string(&mut contents)
+                .ok()?;
+            Some(contents)
+        };
+        let mut ids = vec![];
+        if let Some(json) = read_entry("fabric.mod.json") {
+            let json = serde_json::from_str::<Value>(&json).unwrap_or_default();
+            ids.extend(json["id"].as_str().map(String::from));
+        }
+        if let Some(json) = read_entry("quilt.mod.json") {
+            let json = serde_json::from_str::<Value>(&json).unwrap_or_default();
+            ids.extend(json["quilt_loader"]["id"].as_str().map(String::from));
+        }
+        for toml in ["META-INF/mods.toml", "META-INF/neoforge.mods.toml"] {
+            if let Some(toml) = read_entry(toml) {
+                ids.extend(toml_mod_ids(&toml));
+            }
+        }
+        Ok(ids)
+    };
+    read().unwrap_or_default()
+}
+
+/// The `modId`s of a `mods.toml`, without parsing the rest of it.
+fn toml_mod_ids(toml: &str) -> Vec<String> {
+    toml.lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            (key.trim() == "modId").then(|| {
+                let value = value.split('#').next().unwrap_or_default();
+                value.trim().trim_matches('"').to_string()
+            })
+        })
+        .collect()
+}
+
+impl fmt::Display for UnusedConfigs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.paths.is_empty() {
+            return writeln!(f, "{}", "No unused configs found.".green().bold());
+        }
+        for path in &self.paths {
+            writeln!(f, "- {}", path.display().bold())?;
+        }
+        writeln!(
+            f,
+            "{} configs belong to none of the pack's mods",
+            self.paths.len().yellow().bold()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_names_are_normalized() {
+        assert_eq!(normalize("Sodium-Options.json"), "sodiumoptions");
+        assert_eq!(normalize("fabric_loader"), "fabricloader");
+        let toml = indoc::indoc! {r#"
+            [[mods]]
+            modId = "create" # The mod's ID
+            version = "${file.jarVersion}"
+        "#};
+        assert_eq!(toml_mod_ids(toml), ["create"]);
+    }
+}
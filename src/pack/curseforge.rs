@@ -0,0 +1,195 @@
+use super::{Artifact, ExportFormat, Notices, Pack};
+use crate::component::{Component, Source, VerifyError};
+use crate::index::OverrideSide;
+use crate::instance::Loader;
+use crate::local_storage;
+use crate::plan::Plan;
+use serde::Serialize;
+use std::io::{Cursor, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+/// [CurseForge's modpack](https://docs.curseforge.com/) `manifest.json`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest<'pack> {
+    pub minecraft: ManifestMinecraft,
+    pub manifest_type: &'static str,
+    pub manifest_version: u8,
+    pub name: &'pack str,
+    pub version: String,
+    pub author: String,
+    pub files: Vec<ManifestFile>,
+    pub overrides: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestMinecraft {
+    pub version: String,
+    pub mod_loaders: Vec<ManifestLoader>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ManifestLoader {
+    /// Like `forge-47.2.0`.
+    pub id: String,
+    pub primary: bool,
+}
+
+/// A CurseForge file the launcher downloads by itself.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ManifestFile {
+    #[serde(rename = "projectID")]
+    pub project_id: u32,
+    #[serde(rename = "fileID")]
+    pub file_id: u32,
+    pub required: bool,
+}
+
+impl<'pack> Manifest<'pack> {
+    const MANIFEST_TYPE: &'static str = "minecraftModpack";
+    const MANIFEST_VERSION: u8 = 1;
+    const OVERRIDES: &'static str = "overrides";
+
+    /// Describe `pack` with the provided CurseForge `files`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the pack's loader can't be
+    /// expressed in the manifest.
+    pub fn new(pack: &'pack Pack, files: Vec<ManifestFile>) -> Result<Self, ExportError> {
+        let instance = &pack.instance;
+        let loader_name = match instance.loader {
+            Loader::Minecraft => None,
+            Loader::Forge => Some("forge"),
+            Loader::Neoforge => Some("neoforge"),
+            Loader::Fabric => Some("fabric"),
+            Loader::Quilt => Some("quilt"),
+            Loader::Other => return Err(ExportError::UnsupportedLoader(instance.loader)),
+        };
+        let mod_loaders = loader_name
+            .map(|name| ManifestLoader {
+                id: format!("{name}-{}", instance.loader_version),
+                primary: true,
+            })
+            .into_iter()
+            .collect();
+        Ok(Self {
+            minecraft: ManifestMinecraft {
+                version: instance.minecraft_version.to_string(),
+                mod_loaders,
+            },
+            manifest_type: Self::MANIFEST_TYPE,
+            manifest_version: Self::MANIFEST_VERSION,
+            name: &pack.name,
+            version: pack.version.to_string(),
+            author: pack.authors.join(", "),
+            files,
+            overrides: Self::OVERRIDES,
+        })
+    }
+}
+
+impl Component {
+    /// This [`Component`] as a file of a CurseForge [`Manifest`], if it comes
+    /// from CurseForge and its IDs are known.
+    #[must_use]
+    pub fn manifest_file(&self) -> Option<ManifestFile> {
+        if self.source != Source::Curseforge {
+            return None;
+        }
+        Some(ManifestFile {
+            project_id: self.project_id.as_ref()?.parse().ok()?,
+            file_id: self.version_id.parse().ok()?,
+            required: !self.is_optional(),
+        })
+    }
+}
+
+impl Pack {
+    /// Compute the [`Plan`] for exporting this [`Pack`] in CurseForge's
+    /// format, as an [`Artifact`] like [`plan_export`](Self::plan_export)
+    /// does.
+    ///
+    /// Components from CurseForge are listed in the `manifest.json`. The rest
+    /// can't be referenced by a CurseForge pack, so their files are
+    /// [fetched](Component::fetch_file) and bundled into the overrides.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if loading the components or reading
+    /// the overrides fails, a bundled file can't be fetched, or the pack's
+    /// loader isn't supported by CurseForge.
+    pub fn plan_export_curseforge(&self) -> Result<Plan, ExportError> {
+        let components = self.components()?;
+        let name = self.artifact_name(OverrideSide::Both);
+        let artifact = Artifact::new(&name, &self.version, ExportFormat::Curseforge);
+        let path = artifact.path.clone();
+        let io_error = |source| local_storage::Error::Io {
+            source,
+            faulty_path: Some(path.clone()),
+        };
+
+        let mut files = vec![];
+        let mut bundled = vec![];
+        for component in &components {
+            match component.manifest_file() {
+                Some(file) => files.push(file),
+                None => {
+                    tracing::debug!(slug = component.slug, "Bundling into the overrides");
+                    let path = component.runtime_path(self.settings.world_name());
+                    bundled.push((path, component.fetch_file()?));
+                }
+            }
+        }
+        let manifest = Manifest::new(self, files)?;
+        let json = serde_json::to_string_pretty(&manifest).map_err(local_storage::Error::from)?;
+
+        let mut archive = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated)
+            .compression_level(self.settings.export_compression_level);
+        archive
+            .start_file("manifest.json", options)
+            .map_err(local_storage::Error::from)?;
+        archive.write_all(json.as_bytes()).map_err(io_error)?;
+        if self.settings.notices {
+            archive
+                .start_file(Notices::FILE_NAME, options)
+                .map_err(local_storage::Error::from)?;
+            archive
+                .write_all(Notices::fetch(&components).to_string().as_bytes())
+                .map_err(io_error)?;
+        }
+        let overrides = self
+            .override_files()?
+            .into_iter()
+            // NOTE: CurseForge packs are only ever installed on clients.
+            .filter(|(side, ..)| side.on_client())
+            .map(|(_, relative, contents)| (relative, contents));
+        for (relative, contents) in bundled.into_iter().chain(overrides) {
+            archive
+                .start_file_from_path(Path::new(Manifest::OVERRIDES).join(relative), options)
+                .map_err(local_storage::Error::from)?;
+            archive.write_all(&contents).map_err(io_error)?;
+        }
+        let archive = archive
+            .finish()
+            .map_err(local_storage::Error::from)?
+            .into_inner();
+        Ok(self.plan_artifact(&name, artifact, ExportFormat::Curseforge, archive)?)
+    }
+}
+
+/// Errors that may arise while exporting a [`Pack`] in CurseForge's format.
+#[derive(thiserror::Error, Debug)]
+pub enum ExportError {
+    #[error(transparent)]
+    LocalStorage(#[from] local_storage::Error),
+    #[error(transparent)]
+    Fetch(#[from] VerifyError),
+    #[error("CurseForge packs can't use the {0} loader")]
+    UnsupportedLoader(Loader),
+}
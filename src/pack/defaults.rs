@@ -0,0 +1,54 @@
+use super::Pack;
+use crate::plan::{Action, Plan};
+use std::path::Path;
+
+/// Files with client options (including keybinds) that a pack may ship
+/// defaults for, relative to the instance directory.
+pub const DEFAULT_OPTION_FILES: &[&str] = &[
+    // Vanilla options, keybinds included.
+    "options.txt",
+    // OptiFine and its shader options.
+    "optionsof.txt",
+    "optionsshaders.txt",
+    // Iris shader options.
+    "config/iris.properties",
+];
+
+impl Pack {
+    /// Compute the [`Plan`] for importing default client options from an
+    /// existing instance into [`Pack::DEFAULTS_DIR`].
+    ///
+    /// Only the [`DEFAULT_OPTION_FILES`] present in `instance` are imported,
+    /// so the plan may well be empty.
+    #[must_use]
+    pub fn plan_import_defaults(instance: &Path) -> Plan {
+        let mut plan = Plan::new();
+        for file in DEFAULT_OPTION_FILES {
+            let from = instance.join(file);
+            if from.is_file() {
+                plan.push(Action::CopyFile {
+                    from,
+                    to: Path::new(Self::DEFAULTS_DIR).join(file),
+                });
+            }
+        }
+        plan
+    }
+
+    /// Compute the [`Plan`] for placing default client options into an
+    /// instance being [installed](Pack::plan_install).
+    ///
+    /// Options the player already has are left alone.
+    #[must_use]
+    pub fn plan_install_defaults(target: &Path) -> Plan {
+        let mut plan = Plan::new();
+        for file in DEFAULT_OPTION_FILES {
+            let from = Path::new(Self::DEFAULTS_DIR).join(file);
+            let to = target.join(file);
+            if from.is_file() && !to.exists() {
+                plan.push(Action::CopyFile { from, to });
+            }
+        }
+        plan
+    }
+}
@@ -0,0 +1,314 @@
+use super::{KnownConflict, Pack};
+use crate::component::{curseforge, modrinth, AddError, Component, DependencyGraph, Source};
+use crate::instance::{Instance, Loader};
+use crate::progress::Task;
+use crate::{budget, network};
+use color_eyre::owo_colors::OwoColorize;
+use itertools::Itertools;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+
+/// The outcome of checking a whole [`Pack`] for problems, made to gate CI.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Diagnosis {
+    pub problems: Vec<Problem>,
+    /// Slugs of the components whose versions weren't checked because the
+    /// [time budget](crate::budget) ran out or the network went
+    /// [offline](crate::network::is_offline).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unchecked: Vec<String>,
+}
+
+/// A single problem found in a [`Pack`].
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Problem {
+    /// The component's version can't be loaded by the instance's loader.
+    WrongLoader { slug: String, loaders: Vec<Loader> },
+    /// The component's version isn't made for the instance's Minecraft
+    /// version.
+    WrongMinecraftVersion {
+        slug: String,
+        game_versions: Vec<String>,
+    },
+    /// A required dependency of the component isn't in the pack.
+    MissingDependency {
+        slug: String,
+        project_id: String,
+        name: String,
+    },
+    /// Components that don't work together are all in the pack, as one of
+    /// them declares or the pack's [conflicts](super::Settings::conflicts)
+    /// say.
+    Incompatible { slugs: Vec<String>, reason: String },
+    /// Several components end up at the same runtime path.
+    DuplicateFile { path: PathBuf, slugs: Vec<String> },
+    /// A local component's path no longer exists.
+    MissingLocalComponent { path: PathBuf },
+    /// A local component changed since its hash was recorded, or never had
+    /// one recorded.
+    ChangedLocalComponent { path: PathBuf },
+    /// The component's version couldn't be checked against the instance.
+    CheckFailed { slug: String, error: String },
+}
+
+impl Diagnosis {
+    /// Check `pack` and its `components` for problems.
+    ///
+    /// Unless `offline` is set, the current version of every component is
+    /// fetched to check that it fits the pack's [`Instance`].
+    #[must_use]
+    pub fn check(pack: &Pack, components: &[Component], offline: bool) -> Self {
+        let mut problems = vec![];
+        let mut unchecked = vec![];
+        if !offline {
+            // NOTE: All the Modrinth versions are fetched at once, those that
+            // fail to are fetched one by one below.
+            let ids = components
+                .iter()
+                .filter(|component| component.source == Source::Modrinth)
+                .map(|component| component.version_id.clone())
+                .collect::<Vec<_>>();
+            let mut versions = match ids.is_empty() {
+                true => HashMap::new(),
+                false => modrinth::fetch_versions(&ids).unwrap_or_else(|error| {
+                    tracing::warn!(%error, "Failed to fetch the versions in bulk");
+                    HashMap::new()
+                }),
+            };
+            let task = Task::start("Checking components", components.len());
+            for component in components {
+                if budget::is_exhausted() || network::is_offline() {
+                    unchecked.push(component.slug.clone());
+                    continue;
+                }
+                task.advance(Some(&component.slug));
+                let prefetched = versions.remove(&component.version_id);
+                match check_remote(component, prefetched, &pack.instance) {
+                    Ok(found) => problems.extend(found),
+                    Err(error) => problems.push(Problem::CheckFailed {
+                        slug: component.slug.clone(),
+                        error: error.to_string(),
+                    }),
+                }
+            }
+        }
+
+        let graph = DependencyGraph::new(components);
+        problems.extend(graph.missing().into_iter().map(|(component, dependency)| {
+            Problem::MissingDependency {
+                slug: component.slug.clone(),
+                project_id: dependency.project_id.clone(),
+                name: dependency.name().to_string(),
+            }
+        }));
+        problems.extend(Problem::incompatibilities(
+            components,
+            &pack.settings.conflicts,
+        ));
+        problems.extend(
+            components
+                .iter()
+                .into_group_map_by(|component| component.runtime_path(pack.settings.world_name()))
+                .into_iter()
+                .filter(|(_, components)| components.len() > 1)
+                .sorted_by(|(a, _), (b, _)| a.cmp(b))
+                .map(|(path, components)| Problem::DuplicateFile {
+                    path,
+                    slugs: components.iter().map(|c| c.slug.clone()).collect(),
+                }),
+        );
+        problems.extend(
+            pack.local_components
+                .iter()
+                .filter(|local| !local.base().exists())
+                .map(|local| Problem::MissingLocalComponent {
+                    path: local.path.clone(),
+                }),
+        );
+        problems.extend(
+            pack.local_components
+                .iter()
+                .filter(|local| local.base().exists() && local.has_drifted().unwrap_or(true))
+                .map(|local| Problem::ChangedLocalComponent {
+                    path: local.path.clone(),
+                }),
+        );
+        Self {
+            problems,
+            unchecked,
+        }
+    }
+
+    /// Whether no problems were found.
+    #[must_use]
+    pub const fn is_healthy(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+impl Problem {
+    /// The [incompatibilities](Self::Incompatible) between `components`,
+    /// both those the components declare and the `known` ones.
+    #[must_use]
+    pub fn incompatibilities(components: &[Component], known: &[KnownConflict]) -> Vec<Self> {
+        let mut problems = vec![];
+        let mut seen = vec![];
+        for (component, target) in DependencyGraph::new(components).incompatible() {
+            let mut pair = [&component.slug, &target.slug];
+            pair.sort();
+            if seen.contains(&pair) {
+                continue;
+            }
+            seen.push(pair);
+            problems.push(Self::Incompatible {
+                slugs: vec![component.slug.clone(), target.slug.clone()],
+                reason: format!("{} declares it", component.slug),
+            });
+        }
+        for conflict in known {
+            let all_present = conflict
+                .slugs
+                .iter()
+                .all(|slug| components.iter().any(|component| component.slug == *slug));
+            if conflict.slugs.len() > 1 && all_present {
+                problems.push(Self::Incompatible {
+                    slugs: conflict.slugs.clone(),
+                    reason: conflict
+                        .reason
+                        .clone()
+                        .unwrap_or_else(|| "listed in the pack's conflicts".to_string()),
+                });
+            }
+        }
+        problems
+    }
+}
+
+/// Check the current version of a [`Component`] against the [`Instance`],
+/// fetching it unless it's already `prefetched` (from Modrinth).
+fn check_remote(
+    component: &Component,
+    prefetched: Option<modrinth::Version>,
+    instance: &Instance,
+) -> Result<Vec<Problem>, AddError> {
+    let slug = component.slug.clone();
+    let (loader_ok, loaders, version_ok, game_versions) = match component.source {
+        Source::Modrinth => {
+            let version = match prefetched {
+                Some(version) => version,
+                None => modrinth::fetch_version(&component.version_id)?,
+            };
+            (
+                version.supports_loader(instance),
+                version.loaders.clone(),
+                version.supports_minecraft_version(component.category, instance),
+                version.game_versions,
+            )
+        }
+        Source::Curseforge => {
+            let project_id = component.project_id.as_ref().ok_or(AddError::NotFound)?;
+            let file =
+                curseforge::fetch_file(&curseforge::client()?, project_id, &component.version_id)?;
+            (
+                file.supports_loader(instance),
+                file.loaders(),
+                file.supports_minecraft_version(component.category, instance),
+                file.game_versions,
+            )
+        }
+    };
+
+    let mut problems = vec![];
+    if !loader_ok {
+        problems.push(Problem::WrongLoader {
+            slug: slug.clone(),
+            loaders,
+        });
+    }
+    if !version_ok {
+        problems.push(Problem::WrongMinecraftVersion {
+            slug,
+            game_versions,
+        });
+    }
+    Ok(problems)
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WrongLoader { slug, loaders } => write!(
+                f,
+                "{}: only supports the {} loaders",
+                slug.magenta().bold(),
+                loaders.iter().join(", ")
+            ),
+            Self::WrongMinecraftVersion {
+                slug,
+                game_versions,
+            } => write!(
+                f,
+                "{}: made for Minecraft {}",
+                slug.magenta().bold(),
+                game_versions.join(", ")
+            ),
+            Self::MissingDependency { slug, name, .. } => write!(
+                f,
+                "{}: requires {name}, which isn't in the pack",
+                slug.magenta().bold()
+            ),
+            Self::Incompatible { slugs, reason } => write!(
+                f,
+                "{} don't work together ({reason})",
+                slugs
+                    .iter()
+                    .map(|slug| slug.magenta().bold().to_string())
+                    .join(" and ")
+            ),
+            Self::DuplicateFile { path, slugs } => write!(
+                f,
+                "{} is provided by several components: {}",
+                path.display().bold(),
+                slugs.join(", ")
+            ),
+            Self::MissingLocalComponent { path } => {
+                write!(f, "local component {} doesn't exist", path.display().bold())
+            }
+            Self::ChangedLocalComponent { path } => write!(
+                f,
+                "local component {} changed since its hash was recorded",
+                path.display().bold()
+            ),
+            Self::CheckFailed { slug, error } => write!(
+                f,
+                "{}: failed to check ({})",
+                slug.magenta().bold(),
+                error.red()
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Diagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for problem in &self.problems {
+            writeln!(f, "{} {problem}", "!".red().bold())?;
+        }
+        match self.problems.len() {
+            0 => writeln!(f, "{}", "No problems found.".green().bold())?,
+            count => writeln!(f, "{} problems found", count.red().bold())?,
+        }
+        if !self.unchecked.is_empty() {
+            writeln!(
+                f,
+                "{} components weren't checked: {}",
+                self.unchecked.len().yellow().bold(),
+                self.unchecked.join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
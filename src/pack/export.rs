@@ -0,0 +1,200 @@
+use super::ExportRetention;
+use crate::local_storage;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use clap::ValueEnum;
+use itertools::Itertools;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory (relative to the pack root) where exported packs go.
+pub const EXPORT_DIR: &str = "exports";
+
+const TIMESTAMP_FORMAT: &str = "%Y%m%d%H%M%S";
+
+/// The pack formats an export can produce.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum, strum::Display,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum ExportFormat {
+    /// Modrinth's `.mrpack`, see [`crate::index`].
+    #[default]
+    Mrpack,
+    /// CurseForge's `manifest.json` in a `.zip`, see
+    /// [`Manifest`](super::Manifest).
+    Curseforge,
+}
+
+impl ExportFormat {
+    /// The file extension of this format's archives.
+    #[must_use]
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::Mrpack => "mrpack",
+            Self::Curseforge => "zip",
+        }
+    }
+}
+
+/// A timestamped archive produced by an export, living in [`EXPORT_DIR`].
+///
+/// Artifacts are named `<pack>-<version>-<timestamp>.<extension>`, and the
+/// most recent one of each [`ExportFormat`] is also available as
+/// `<pack>-latest.<extension>`. Exports for a single side use `<pack>-<side>`
+/// in place of `<pack>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Artifact {
+    pub path: PathBuf,
+    pub version: Version,
+    pub created_at: DateTime<Local>,
+}
+
+impl Artifact {
+    /// Describe a new artifact of a pack, to be written now.
+    #[must_use]
+    pub fn new(pack_name: &str, version: &Version, format: ExportFormat) -> Self {
+        let created_at = Local::now();
+        let file_name = format!(
+            "{pack_name}-{version}-{timestamp}.{extension}",
+            timestamp = created_at.format(TIMESTAMP_FORMAT),
+            extension = format.extension(),
+        );
+        Self {
+            path: Path::new(EXPORT_DIR).join(file_name),
+            version: version.clone(),
+            created_at,
+        }
+    }
+
+    /// Path to the `-latest` symlink of a pack's artifacts in `format`.
+    #[must_use]
+    pub fn latest_link(pack_name: &str, format: ExportFormat) -> PathBuf {
+        Path::new(EXPORT_DIR).join(format!("{pack_name}-latest.{}", format.extension()))
+    }
+
+    /// Parse an artifact's file name, returning [`None`] for anything that
+    /// isn't an artifact of `pack_name` in `format`.
+    fn parse(pack_name: &str, format: ExportFormat, path: &Path) -> Option<Self> {
+        let stem = path
+            .file_name()?
+            .to_str()?
+            .strip_suffix(format.extension())?
+            .strip_suffix('.')?
+            .strip_prefix(pack_name)?
+            .strip_prefix('-')?;
+        let (version, timestamp) = stem.rsplit_once('-')?;
+        let created_at = NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT).ok()?;
+        Some(Self {
+            path: path.to_path_buf(),
+            version: version.parse().ok()?,
+            created_at: Local.from_local_datetime(&created_at).earliest()?,
+        })
+    }
+
+    /// Find all the existing artifacts of a pack in `format`, newest first.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if [`EXPORT_DIR`] exists but can't
+    /// be read.
+    pub fn find_all(pack_name: &str, format: ExportFormat) -> local_storage::Result<Vec<Self>> {
+        let io_error = |source| local_storage::Error::Io {
+            source,
+            faulty_path: Some(PathBuf::from(EXPORT_DIR)),
+        };
+        if !Path::new(EXPORT_DIR).is_dir() {
+            return Ok(vec![]);
+        }
+        let artifacts = fs::read_dir(EXPORT_DIR)
+            .map_err(io_error)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(io_error)?
+            .into_iter()
+            .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_file()))
+            .filter_map(|entry| Self::parse(pack_name, format, &entry.path()))
+            .sorted_unstable_by_key(|artifact| artifact.created_at)
+            .rev()
+            .collect();
+        Ok(artifacts)
+    }
+}
+
+impl ExportRetention {
+    /// Select the artifacts that these rules don't keep.
+    ///
+    /// `artifacts` must be sorted newest first, like [`Artifact::find_all`]
+    /// returns them.
+    #[must_use]
+    pub fn select_stale<'a>(&self, artifacts: &'a [Artifact]) -> Vec<&'a Artifact> {
+        let mut seen_per_version = HashMap::<&Version, usize>::new();
+        artifacts
+            .iter()
+            .enumerate()
+            .filter(|(index, artifact)| {
+                let seen = seen_per_version.entry(&artifact.version).or_default();
+                *seen += 1;
+                let kept_as_recent = self.keep_last.is_some_and(|keep| *index < keep);
+                let kept_for_version = self.keep_per_version.is_some_and(|keep| *seen <= keep);
+                let unrestricted = self.keep_last.is_none() && self.keep_per_version.is_none();
+                !(unrestricted || kept_as_recent || kept_for_version)
+            })
+            .map(|(_, artifact)| artifact)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Artifact, ExportFormat, ExportRetention};
+    use std::path::Path;
+
+    #[test]
+    fn retention_keeps_recent_and_per_version() {
+        let artifacts = [
+            "pack-1.1.0-20240103000000.mrpack",
+            "pack-1.1.0-20240102000000.mrpack",
+            "pack-1.0.0-20240101120000.mrpack",
+            "pack-1.0.0-20240101000000.mrpack",
+        ]
+        .map(|name| Artifact::parse("pack", ExportFormat::Mrpack, Path::new(name)).unwrap());
+        let stale = |retention: ExportRetention| {
+            retention
+                .select_stale(&artifacts)
+                .into_iter()
+                .map(|artifact| artifact.path.to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+        };
+
+        assert!(stale(ExportRetention::default()).is_empty());
+        assert_eq!(
+            stale(ExportRetention {
+                keep_last: Some(1),
+                keep_per_version: Some(1),
+            }),
+            [
+                "pack-1.1.0-20240102000000.mrpack",
+                "pack-1.0.0-20240101000000.mrpack",
+            ]
+        );
+        assert_eq!(
+            Artifact::parse(
+                "other",
+                ExportFormat::Mrpack,
+                Path::new("pack-latest.mrpack")
+            ),
+            None
+        );
+        assert_eq!(
+            Artifact::parse(
+                "pack",
+                ExportFormat::Curseforge,
+                Path::new("pack-1.0.0-20240101000000.mrpack")
+            ),
+            None
+        );
+    }
+}
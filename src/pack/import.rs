@@ -0,0 +1,298 @@
+use super::{Overrides, Pack, Settings};
+use crate::component::{
+    modrinth, Category, Component, Constraint, FileNaming, Source, TagInformation,
+};
+use crate::index::file::{Env, File, Requirement};
+use crate::index::{Index, Mrpack, ReadError};
+use crate::instance::{Instance, Loader};
+use crate::local_storage;
+use crate::plan::Plan;
+use semver::Version;
+use std::collections::HashMap;
+use std::path::{Component as PathComponent, Path, PathBuf};
+use url::Url;
+
+/// The contents of a `.mrpack` archive, turned into Invar's entities.
+#[derive(Debug, Clone)]
+pub struct Import {
+    pub pack: Pack,
+    pub components: Vec<Component>,
+    /// Files from the archive's override folders, written to the pack's own
+    /// ones.
+    pub overrides: Overrides,
+    /// Files of the archive that couldn't be imported, with the reason why.
+    pub skipped: Vec<(PathBuf, &'static str)>,
+}
+
+impl Import {
+    /// Read a `.mrpack` archive.
+    ///
+    /// The slugs of Modrinth-hosted components are looked up on the **Modrinth
+    /// API**. If that fails, they are derived from file names instead.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the archive can't be read, has no
+    /// valid `modrinth.index.json`, or targets versions Invar can't parse.
+    pub fn read(path: &Path) -> Result<Self, ImportError> {
+        let mrpack = Mrpack::read(path)?;
+        let overrides = Overrides::from_mrpack(&mrpack);
+        let mut skipped = vec![];
+        let (pack, components) = into_entities(mrpack.index, &mut skipped)?;
+        Ok(Self {
+            pack,
+            components,
+            overrides,
+            skipped,
+        })
+    }
+
+    /// Compute the [`Plan`] for materializing this [`Import`] as a pack in the
+    /// current directory.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if serializing the pack or any of
+    /// the components fails.
+    pub fn plan(&self) -> local_storage::Result<Plan> {
+        let mut plan = self.pack.plan_setup()?;
+        for component in &self.components {
+            plan.extend(component.plan_save()?);
+        }
+        plan.extend(self.overrides.plan_write());
+        Ok(plan)
+    }
+}
+
+fn into_entities(
+    index: Index,
+    skipped: &mut Vec<(PathBuf, &'static str)>,
+) -> Result<(Pack, Vec<Component>), ImportError> {
+    let minecraft_version = index
+        .minecraft_version()
+        .ok_or(ImportError::MissingMinecraft)?;
+    let minecraft_version = parse_version(minecraft_version)?;
+    let (loader, loader_version) = index.loader().map_or(
+        Ok((Loader::Minecraft, minecraft_version.clone())),
+        |(loader, version)| parse_version(version).map(|version| (loader, version)),
+    )?;
+
+    let pack = Pack {
+        name: index.name,
+        version: parse_version(&index.version_id)?,
+        authors: vec![],
+        instance: Instance {
+            minecraft_version,
+            loader,
+            loader_version,
+            allowed_foreign_loaders: loader.default_foreign_loaders(),
+            release_channel: None,
+        },
+        settings: Settings::default(),
+        includes: vec![],
+        local_components: vec![],
+    };
+
+    let modrinth_ids = index
+        .files
+        .iter()
+        .filter_map(|file| modrinth_ids(file.downloads.first()?))
+        .map(|(project_id, _)| project_id)
+        .collect::<Vec<_>>();
+    let slugs = match modrinth_ids.is_empty() {
+        true => HashMap::new(),
+        false => modrinth::fetch_slugs(&modrinth_ids).unwrap_or_else(|error| {
+            tracing::warn!(%error, "Failed to look up slugs, using file names instead");
+            HashMap::new()
+        }),
+    };
+
+    let mut components = vec![];
+    for file in index.files {
+        match into_component(file, &slugs, pack.settings.world_name()) {
+            Ok(mut component) => {
+                component.essential = loader.is_essential(&component.slug);
+                components.push(component);
+            }
+            Err((path, reason)) => skipped.push((path, reason)),
+        }
+    }
+
+    Ok((pack, components))
+}
+
+fn into_component(
+    file: File,
+    slugs: &HashMap<String, String>,
+    world: &str,
+) -> Result<Component, (PathBuf, &'static str)> {
+    // NOTE: Datapacks live in the world's directory, but older exports have them
+    // at the top level.
+    let runtime_path = match file.path.strip_prefix(world) {
+        Ok(rest) if rest.starts_with("datapacks") => rest.to_path_buf(),
+        _ => file.path.clone(),
+    };
+    let mut parts = runtime_path.components();
+    let category = match parts.next() {
+        Some(PathComponent::Normal(dir)) if dir == "mods" => Category::Mod,
+        Some(PathComponent::Normal(dir)) if dir == "resourcepacks" => Category::Resourcepack,
+        Some(PathComponent::Normal(dir)) if dir == "shaderpacks" => Category::Shader,
+        Some(PathComponent::Normal(dir)) if dir == "datapacks" => Category::Datapack,
+        Some(PathComponent::Normal(dir)) if dir == "config" => Category::Config,
+        _ => return Err((file.path, "unknown runtime directory")),
+    };
+    let rest = parts.as_path();
+    if !rest
+        .components()
+        .all(|part| matches!(part, PathComponent::Normal(_)))
+    {
+        return Err((file.path, "invalid runtime path"));
+    }
+    let runtime_subdir = rest
+        .parent()
+        .filter(|subdir| !subdir.as_os_str().is_empty())
+        .map(Path::to_path_buf);
+    if runtime_subdir.is_some() && category != Category::Config {
+        return Err((
+            file.path,
+            "nested runtime paths are only supported for configs",
+        ));
+    }
+    let Some(file_name) = rest
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+    else {
+        return Err((file.path, "no file name"));
+    };
+    let Some(download_url) = file.downloads.into_iter().next() else {
+        return Err((file.path, "no download URLs"));
+    };
+
+    let (source, project_id, version_id) = match modrinth_ids(&download_url) {
+        Some((project_id, version_id)) => (Source::Modrinth, Some(project_id), version_id),
+        None => match curseforge_file_id(&download_url) {
+            Some(file_id) => (Source::Curseforge, None, file_id),
+            None => return Err((file.path, "downloads from an unknown source")),
+        },
+    };
+    let slug = project_id
+        .as_ref()
+        .and_then(|id| slugs.get(id).cloned())
+        .unwrap_or_else(|| slug_from_file_name(&file_name));
+
+    Ok(Component {
+        slug,
+        source,
+        project_id,
+        category,
+        tags: TagInformation {
+            main: None,
+            others: vec![],
+        },
+        environment: file.env.unwrap_or(Env {
+            client: Requirement::Required,
+            server: Requirement::Required,
+        }),
+        version_id,
+        file_name,
+        file_size: file.file_size,
+        download_url,
+        hashes: file.hashes,
+        // NOTE: The index doesn't carry dependency information, these get filled in
+        // on the next update.
+        dependencies: vec![],
+        runtime_subdir,
+        constraint: Constraint::default(),
+        essential: false,
+        file_naming: FileNaming::default(),
+        group: None,
+        metadata: None,
+    })
+}
+
+/// Errors that may arise when [importing](Import::read) a `.mrpack`.
+#[derive(thiserror::Error, Debug)]
+pub enum ImportError {
+    #[error(transparent)]
+    LocalStorage(#[from] local_storage::Error),
+    #[error(transparent)]
+    Read(#[from] ReadError),
+    #[error("The index doesn't specify a Minecraft version")]
+    MissingMinecraft,
+    #[error("Can't parse {0:?} as a version")]
+    InvalidVersion(String),
+}
+
+/// Parse a version that may be missing its minor or patch parts, like
+/// Minecraft's `1.21`.
+fn parse_version(version: &str) -> Result<Version, ImportError> {
+    let padded = match version.matches('.').count() {
+        0 => format!("{version}.0.0"),
+        1 => format!("{version}.0"),
+        _ => version.to_string(),
+    };
+    padded
+        .parse()
+        .map_err(|_| ImportError::InvalidVersion(version.to_string()))
+}
+
+/// Extract the project and version IDs from a Modrinth CDN URL, which looks
+/// like `https://cdn.modrinth.com/data/<project>/versions/<version>/<file>`.
+fn modrinth_ids(url: &Url) -> Option<(String, String)> {
+    if url.host_str() != Some("cdn.modrinth.com") {
+        return None;
+    }
+    match url.path_segments()?.collect::<Vec<_>>()[..] {
+        ["data", project_id, "versions", version_id, ..] => {
+            Some((project_id.to_string(), version_id.to_string()))
+        }
+        _ => None,
+    }
+}
+
+/// Extract the file ID from a CurseForge CDN URL, which looks like
+/// `https://edge.forgecdn.net/files/<id / 1000>/<id % 1000>/<file>`.
+fn curseforge_file_id(url: &Url) -> Option<String> {
+    if !url.host_str()?.ends_with("forgecdn.net") {
+        return None;
+    }
+    match url.path_segments()?.collect::<Vec<_>>()[..] {
+        ["files", high, low, ..] => {
+            let id = high.parse::<u32>().ok()? * 1000 + low.parse::<u32>().ok()?;
+            Some(id.to_string())
+        }
+        _ => None,
+    }
+}
+
+fn slug_from_file_name(file_name: &str) -> String {
+    Path::new(file_name).file_stem().map_or_else(
+        || file_name.to_string(),
+        |stem| stem.to_string_lossy().to_lowercase(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{curseforge_file_id, modrinth_ids, parse_version};
+    use url::Url;
+
+    #[test]
+    fn ids_from_urls() {
+        let url = |url: &str| Url::parse(url).unwrap();
+        assert_eq!(
+            modrinth_ids(&url(
+                "https://cdn.modrinth.com/data/AANobbMI/versions/4Gs1yj5V/sodium.jar"
+            )),
+            Some(("AANobbMI".to_string(), "4Gs1yj5V".to_string()))
+        );
+        assert_eq!(
+            curseforge_file_id(&url("https://edge.forgecdn.net/files/4567/89/jei.jar")),
+            Some("4567089".to_string())
+        );
+        assert_eq!(
+            parse_version("1.21").unwrap(),
+            semver::Version::new(1, 21, 0)
+        );
+    }
+}
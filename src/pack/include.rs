@@ -0,0 +1,175 @@
+use crate::local_storage;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+use std::{fs, io};
+
+/// Directory (relative to the pack root) where [`Include`]s are fetched to.
+pub const INCLUDES_DIR: &str = ".invar/includes";
+
+/// A Git repository of shared files (configs, scripts, etc.) merged into the
+/// pack's overrides on export.
+///
+/// Includes are pinned to a specific commit, so every pack that uses one gets
+/// exactly the same files until the commit is bumped in `pack.yml`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Include {
+    /// Where to clone the repository from.
+    pub url: String,
+
+    /// The commit to check out.
+    pub commit: String,
+
+    /// A subdirectory of the repository to use as the overrides root. The
+    /// whole repository is used if this is not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+
+    /// Name of the local checkout. Derived from the URL if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+impl Include {
+    /// The name of this include's local checkout.
+    #[must_use]
+    pub fn name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| {
+            self.url
+                .trim_end_matches('/')
+                .rsplit(['/', ':'])
+                .next()
+                .unwrap_or(&self.url)
+                .trim_end_matches(".git")
+                .to_string()
+        })
+    }
+
+    /// Path to this include's local checkout.
+    #[must_use]
+    pub fn checkout_path(&self) -> PathBuf {
+        Path::new(INCLUDES_DIR).join(self.name())
+    }
+
+    /// Path to the directory whose contents are merged into the overrides.
+    #[must_use]
+    pub fn overrides_root(&self) -> PathBuf {
+        let checkout = self.checkout_path();
+        match &self.path {
+            Some(path) => checkout.join(path),
+            None => checkout,
+        }
+    }
+
+    /// Clone this include (if needed) and check out the pinned commit.
+    ///
+    /// Does nothing if the checkout is already at the pinned commit.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if running `git` fails or if it
+    /// exits unsuccessfully.
+    pub fn fetch(&self) -> Result<(), FetchError> {
+        let checkout = self.checkout_path();
+        if checkout.join(".git").is_dir() {
+            if self.head(&checkout)?.starts_with(&self.commit) {
+                tracing::debug!(name = self.name(), "Include is up to date");
+                return Ok(());
+            }
+        } else {
+            local_storage::ensure_writable()?;
+            fs::create_dir_all(INCLUDES_DIR).map_err(|source| local_storage::Error::Io {
+                source,
+                faulty_path: Some(PathBuf::from(INCLUDES_DIR)),
+            })?;
+            fs::write(Path::new(INCLUDES_DIR).join(".gitignore"), "*\n").map_err(|source| {
+                local_storage::Error::Io {
+                    source,
+                    faulty_path: Some(PathBuf::from(INCLUDES_DIR)),
+                }
+            })?;
+            let checkout = checkout.to_string_lossy();
+            self.git(None, &["clone", "--no-checkout", &self.url, &checkout])?;
+        }
+
+        tracing::info!(name = self.name(), commit = self.commit, "Fetching include");
+        self.git(Some(&checkout), &["fetch", "origin", &self.commit])?;
+        self.git(Some(&checkout), &["checkout", "--detach", &self.commit])?;
+
+        Ok(())
+    }
+
+    fn head(&self, checkout: &Path) -> Result<String, FetchError> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(checkout)
+            .args(["rev-parse", "HEAD"])
+            .output()
+            .map_err(|source| io_error(source, checkout))?;
+        if !output.status.success() {
+            return Err(FetchError::Git {
+                url: self.url.clone(),
+                status: output.status,
+            });
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    fn git(&self, checkout: Option<&Path>, args: &[&str]) -> Result<(), FetchError> {
+        let mut command = Command::new("git");
+        if let Some(checkout) = checkout {
+            command.arg("-C").arg(checkout);
+        }
+        let status = command
+            .args(args)
+            .status()
+            .map_err(|source| io_error(source, checkout.unwrap_or(Path::new(INCLUDES_DIR))))?;
+        match status.success() {
+            true => Ok(()),
+            false => Err(FetchError::Git {
+                url: self.url.clone(),
+                status,
+            }),
+        }
+    }
+}
+
+/// Errors that may arise when fetching an [`Include`].
+#[derive(thiserror::Error, Debug)]
+pub enum FetchError {
+    #[error(transparent)]
+    LocalStorage(#[from] local_storage::Error),
+    #[error("`git` exited unsuccessfully ({status}) while fetching {url}")]
+    Git { url: String, status: ExitStatus },
+}
+
+fn io_error(source: io::Error, path: &Path) -> local_storage::Error {
+    local_storage::Error::Io {
+        source,
+        faulty_path: Some(path.to_path_buf()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Include;
+
+    #[test]
+    fn name_from_url() {
+        let include = |url: &str| Include {
+            url: url.to_string(),
+            commit: "deadbeef".to_string(),
+            path: None,
+            name: None,
+        };
+        assert_eq!(
+            include("https://github.com/team/configs.git").name(),
+            "configs"
+        );
+        assert_eq!(
+            include("https://github.com/team/configs/").name(),
+            "configs"
+        );
+        assert_eq!(include("git@github.com:configs.git").name(), "configs");
+    }
+}
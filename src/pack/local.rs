@@ -0,0 +1,295 @@
+use crate::component::Component;
+use crate::index::file::Env;
+use crate::index::OverrideSide;
+use crate::{local_storage, terminal};
+use glob::{MatchOptions, Pattern};
+use inquire::InquireError;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+use strum::IntoEnumIterator;
+use walkdir::WalkDir;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+/// A file or a whole directory (like a `kubejs/` tree) that lives in the pack
+/// repository itself and is placed into the overrides on export.
+///
+/// Directories are either copied recursively or zipped into a single file,
+/// which is what resourcepacks and datapacks are usually expected to be. The
+/// path may also be a glob, like `config/yungsapi/**`, which picks the
+/// matching files under its [base](Self::base) with their layout kept.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LocalComponent {
+    /// Path to the file or directory (or a glob), relative to the pack root.
+    pub path: PathBuf,
+
+    /// Where the component ends up in the instance. Same as
+    /// [`base`](Self::base) if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<PathBuf>,
+
+    /// Zip a directory into a single file instead of copying it.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub zip: bool,
+
+    /// The [tree hash](Self::tree_hash) recorded when the component was last
+    /// reviewed, to detect changes made since then.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+
+    /// The sides this component is needed on, both if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<Env>,
+}
+
+impl LocalComponent {
+    /// Whether [`path`](Self::path) is a glob rather than a plain path.
+    #[must_use]
+    pub fn is_glob(&self) -> bool {
+        self.path.to_string_lossy().contains(['*', '?', '['])
+    }
+
+    /// The directory a glob [`path`](Self::path) is matched under: its
+    /// leading components without wildcards. Plain paths are their own base.
+    #[must_use]
+    pub fn base(&self) -> PathBuf {
+        self.path
+            .components()
+            .take_while(|component| {
+                !component
+                    .as_os_str()
+                    .to_string_lossy()
+                    .contains(['*', '?', '['])
+            })
+            .collect()
+    }
+
+    /// Where this component ends up, relative to the instance directory.
+    #[must_use]
+    pub fn target(&self) -> PathBuf {
+        let target = self.target.clone().unwrap_or_else(|| self.base());
+        match self.zip && target.extension().is_none() {
+            true => target.with_extension("zip"),
+            false => target,
+        }
+    }
+
+    /// Whether this component and the remote `component` seem to be the same
+    /// thing: they end up at the same path, or this one is named after the
+    /// other's slug, like `mods/sodium.jar` for `sodium`.
+    #[must_use]
+    pub fn collides_with(&self, component: &Component, world: &str) -> bool {
+        let target = self.target();
+        let named_after = target
+            .file_stem()
+            .is_some_and(|stem| stem.to_string_lossy().eq_ignore_ascii_case(&component.slug));
+        target == component.runtime_path(world) || named_after
+    }
+
+    /// Which overrides folder this component goes into.
+    #[must_use]
+    pub fn side(&self) -> OverrideSide {
+        self.environment
+            .as_ref()
+            .map_or(OverrideSide::Both, OverrideSide::from_env)
+    }
+
+    /// Compute a BLAKE3 hash over the whole file tree of this component: the
+    /// relative path and contents of every file, in a stable order.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the tree can't be read.
+    pub fn tree_hash(&self) -> local_storage::Result<String> {
+        let mut hasher = blake3::Hasher::new();
+        for (relative, contents) in self.files()? {
+            let relative = relative.to_string_lossy();
+            hasher.update(&(relative.len() as u64).to_le_bytes());
+            hasher.update(relative.as_bytes());
+            hasher.update(&(contents.len() as u64).to_le_bytes());
+            hasher.update(&contents);
+        }
+        Ok(hasher.finalize().to_hex().to_string())
+    }
+
+    /// Whether this component changed since its [hash](Self::hash) was
+    /// recorded. Components without a recorded hash are considered drifted.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the tree can't be read.
+    pub fn has_drifted(&self) -> local_storage::Result<bool> {
+        Ok(self.hash.as_ref() != Some(&self.tree_hash()?))
+    }
+
+    /// The files this component contributes to the overrides, as paths
+    /// relative to the instance directory and their contents.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the tree can't be read or zipped.
+    pub fn override_files(&self) -> local_storage::Result<Vec<(PathBuf, Vec<u8>)>> {
+        let files = self.files()?;
+        let target = self.target();
+        if self.path.is_file() {
+            return Ok(files
+                .into_iter()
+                .map(|(_, contents)| (target.clone(), contents))
+                .collect());
+        }
+        if !self.zip {
+            return Ok(files
+                .into_iter()
+                .map(|(relative, contents)| (target.join(relative), contents))
+                .collect());
+        }
+
+        let mut archive = ZipWriter::new(Cursor::new(Vec::new()));
+        let options =
+            SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        for (relative, contents) in files {
+            archive.start_file_from_path(&relative, options)?;
+            archive
+                .write_all(&contents)
+                .map_err(|source| local_storage::Error::Io {
+                    source,
+                    faulty_path: Some(self.path.join(relative)),
+                })?;
+        }
+        Ok(vec![(target, archive.finish()?.into_inner())])
+    }
+
+    /// All the files of this component, sorted by their path relative to
+    /// [`base`](Self::base). A single file has an empty relative path.
+    fn files(&self) -> local_storage::Result<Vec<(PathBuf, Vec<u8>)>> {
+        let pattern = match self.is_glob() {
+            true => Some(Pattern::new(&self.path.to_string_lossy())?),
+            false => None,
+        };
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+        let base = self.base();
+        let entries = WalkDir::new(&base)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|entry| entry.file_name() != ".git")
+            .collect::<Result<Vec<_>, _>>()?;
+        entries
+            .iter()
+            .filter(|entry| entry.file_type().is_file())
+            .filter(|entry| {
+                pattern
+                    .as_ref()
+                    .is_none_or(|pattern| pattern.matches_path_with(entry.path(), options))
+            })
+            .map(|entry| {
+                let relative = entry.path().strip_prefix(&base).unwrap_or(Path::new(""));
+                let contents =
+                    fs::read(entry.path()).map_err(|source| local_storage::Error::Io {
+                        source,
+                        faulty_path: Some(entry.path().to_path_buf()),
+                    })?;
+                Ok((relative.to_path_buf(), contents))
+            })
+            .collect()
+    }
+}
+
+/// What to do when a component being added
+/// [collides](LocalComponent::collides_with) with one already in the pack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display, strum::EnumIter)]
+pub enum Resolution {
+    /// Remove the existing component and add the new one.
+    #[strum(to_string = "Replace the existing component")]
+    Replace,
+    /// Add the new component under another name.
+    #[strum(to_string = "Keep both, renaming the new one")]
+    KeepBoth,
+    #[strum(to_string = "Abort")]
+    Abort,
+}
+
+impl Resolution {
+    /// Ask what to do about a collision described by `message`. Always
+    /// [aborts](Self::Abort) when running
+    /// [non-interactively](terminal::set_interactive).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if prompting fails.
+    pub fn prompt(message: &str) -> Result<Self, InquireError> {
+        if !terminal::is_interactive() {
+            return Ok(Self::Abort);
+        }
+        terminal::suspend(|| inquire::Select::new(message, Self::iter().collect()).prompt())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tree_hash_tracks_contents_and_layout() {
+        let root = std::env::temp_dir().join(format!("invar-local-{}", std::process::id()));
+        fs::create_dir_all(root.join("scripts")).unwrap();
+        fs::write(root.join("scripts/a.js"), "a").unwrap();
+        let mut local = LocalComponent {
+            path: root.clone(),
+            target: Some(PathBuf::from("kubejs")),
+            zip: false,
+            hash: None,
+            environment: None,
+        };
+        let hash = local.tree_hash().unwrap();
+        assert!(local.has_drifted().unwrap());
+        local.hash = Some(hash.clone());
+        assert!(!local.has_drifted().unwrap());
+
+        fs::rename(root.join("scripts/a.js"), root.join("scripts/b.js")).unwrap();
+        assert_ne!(local.tree_hash().unwrap(), hash);
+        assert_eq!(
+            local.override_files().unwrap()[0].0,
+            Path::new("kubejs/scripts/b.js")
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn globs_keep_the_layout_under_their_base() {
+        let root = std::env::temp_dir().join(format!("invar-glob-{}", std::process::id()));
+        fs::create_dir_all(root.join("yungsapi/structures")).unwrap();
+        fs::write(root.join("yungsapi/common.json"), "{}").unwrap();
+        fs::write(root.join("yungsapi/structures/mineshaft.json"), "{}").unwrap();
+        fs::write(root.join("yungsapi/notes.txt"), "").unwrap();
+        let local = LocalComponent {
+            path: root.join("yungsapi/**/*.json"),
+            target: Some(PathBuf::from("config/yungsapi")),
+            zip: false,
+            hash: None,
+            environment: None,
+        };
+        assert!(local.is_glob());
+        assert_eq!(local.base(), root.join("yungsapi"));
+        let paths = local
+            .override_files()
+            .unwrap()
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            [
+                PathBuf::from("config/yungsapi/common.json"),
+                PathBuf::from("config/yungsapi/structures/mineshaft.json"),
+            ]
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
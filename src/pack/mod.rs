@@ -1,18 +1,56 @@
-use crate::index::{self, Index};
+use crate::component::Component;
+use crate::index::file::{Algorithm, Requirement};
+use crate::index::{self, Index, Mrpack, OverrideSide};
 use crate::instance::Instance;
 use crate::local_storage::{self, PersistedEntity};
+use crate::plan::{self, Action, Plan};
 use crate::server::backup::BACKUP_FOLDER;
 use color_eyre::owo_colors::OwoColorize;
 use semver::Version;
 use serde::{Deserialize, Serialize};
-use std::fs::{self, File};
-use std::io::{self, Write};
-use std::path::PathBuf;
+use std::io::{self, Cursor, Write};
+use std::path::{Path, PathBuf};
 use zip::write::SimpleFileOptions;
 use zip::ZipWriter;
 
+mod bump;
+mod configs;
+mod curseforge;
+mod defaults;
+mod doctor;
+mod export;
+mod import;
+mod include;
+mod local;
+mod notices;
+mod optional;
+mod overrides;
+mod profile;
+mod rename;
+mod retarget;
 mod settings;
+mod size;
+mod upload;
+mod watch;
+pub use bump::*;
+pub use configs::*;
+pub use curseforge::*;
+pub use defaults::*;
+pub use doctor::*;
+pub use export::*;
+pub use import::*;
+pub use include::*;
+pub use local::*;
+pub use notices::*;
+pub use optional::*;
+pub use overrides::*;
+pub use profile::*;
+pub use rename::*;
+pub use retarget::*;
 pub use settings::*;
+pub use size::*;
+pub use upload::*;
+pub use watch::*;
 
 /// The top-level "modpack" entity.
 ///
@@ -29,6 +67,15 @@ pub struct Pack {
     pub instance: Instance,
 
     pub settings: Settings,
+
+    /// Git repositories of shared files merged into the overrides on export.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub includes: Vec<Include>,
+
+    /// Files and directories from the pack repository placed into the
+    /// overrides on export.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub local_components: Vec<LocalComponent>,
 }
 
 impl PersistedEntity for Pack {
@@ -51,61 +98,405 @@ impl Pack {
     /// Local path to the directory that stores the configuration files.
     pub const CONFIG_DIR: &'static str = "config";
 
+    /// Local path to the directory that stores default client options (see
+    /// [`DEFAULT_OPTION_FILES`]), exported to the root of the overrides.
+    pub const DEFAULTS_DIR: &'static str = "defaults";
+
     /// Create the data subdirectories in the current directory.
     ///
     /// # Errors
     ///
     /// This function will return an error if an I/O error occurs.
-    pub fn setup_directories() -> io::Result<()> {
+    pub fn setup_directories() -> Result<(), plan::Error> {
+        Self::plan_directories().apply()
+    }
+
+    /// Compute the [`Plan`] for [setting up](Self::setup_directories) the data
+    /// subdirectories.
+    #[must_use]
+    pub fn plan_directories() -> Plan {
+        let mut plan = Plan::new();
         for subdir in [
             Self::MOD_DIR,
             Self::RESOURCEPACK_DIR,
             Self::SHADERPACK_DIR,
             Self::DATAPACK_DIR,
             Self::CONFIG_DIR,
+            Self::DEFAULTS_DIR,
         ] {
-            fs::create_dir_all(subdir)?;
-            let _ = File::create(format!("{subdir}/.gitkeep"))?;
+            plan.push(Action::CreateDir {
+                path: PathBuf::from(subdir),
+            })
+            .push(Action::write_file(format!("{subdir}/.gitkeep"), ""));
         }
 
-        fs::create_dir_all(BACKUP_FOLDER)?;
-        fs::write(format!("{BACKUP_FOLDER}/.gitignore"), "*\n")?;
+        plan.push(Action::CreateDir {
+            path: PathBuf::from(BACKUP_FOLDER),
+        })
+        .push(Action::write_file(
+            format!("{BACKUP_FOLDER}/.gitignore"),
+            "*\n",
+        ));
 
-        Ok(())
+        plan
+    }
+
+    /// Compute the [`Plan`] for setting up a new pack from `self`: persisting
+    /// it and [creating the data subdirectories](Self::setup_directories).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if serializing the pack fails.
+    pub fn plan_setup(&self) -> local_storage::Result<Plan> {
+        let yaml = serde_yml::to_string(self)?;
+        let mut plan = Plan::new();
+        plan.push(Action::write_entity(Self::FILE_PATH, yaml))
+            .extend(Self::plan_directories());
+        Ok(plan)
     }
 
     /// Export this [`Pack`]. See [`crate::index`] for details.
     ///
+    /// The `.mrpack` is written as a new timestamped [`Artifact`], and stale
+    /// ones are pruned according to [`Settings::export_retention`].
+    ///
+    /// [`LocalComponent`]s that changed since their hash was recorded are
+    /// exported anyway, but a warning is logged for each.
+    ///
+    /// # Errors
+    ///
+    /// This function may return a [`local_storage::Error`]. Look there for
+    /// possible causes.
+    pub fn export(&self) -> Result<(), plan::Error> {
+        let plan = self.plan_export(OverrideSide::Both)?;
+        tracing::info!(message = "Writing index", target = ?self.export_path(ExportFormat::Mrpack).yellow().bold());
+        plan.apply()
+    }
+
+    /// Compute the [`Plan`] for [exporting](Self::export) this [`Pack`].
+    ///
+    /// The archive is built in memory, so the returned plan only has to write
+    /// it out, update the `-latest` symlink and prune stale artifacts.
+    ///
+    /// Exports for a single `side` leave out the components and override files
+    /// unsupported on it, and are named after the side to keep them apart from
+    /// the full ones.
+    ///
     /// # Errors
     ///
     /// This function may return a [`local_storage::Error`]. Look there for
     /// possible causes.
-    pub fn export(&self) -> local_storage::Result<()> {
-        let files: Vec<index::file::File> = crate::component::Component::load_all()?
+    pub fn plan_export(&self, side: OverrideSide) -> local_storage::Result<Plan> {
+        let name = self.artifact_name(side);
+        let artifact = Artifact::new(&name, &self.version, ExportFormat::Mrpack);
+        let archive = self.mrpack_archive(side)?;
+        self.plan_artifact(&name, artifact, ExportFormat::Mrpack, archive)
+    }
+
+    /// Build the `.mrpack` archive of this [`Pack`] for `side` in memory, as
+    /// [`plan_export`](Self::plan_export) writes it.
+    ///
+    /// # Errors
+    ///
+    /// This function may return a [`local_storage::Error`]. Look there for
+    /// possible causes.
+    pub fn mrpack_archive(&self, side: OverrideSide) -> local_storage::Result<Vec<u8>> {
+        let mirror = self.settings.mirror.as_ref();
+        let mut components = self.components()?;
+        components.retain(|component| side.wants(&component.environment));
+        let optional = OptionalComponents::from_components(&components);
+        let notices = self.settings.notices.then(|| Notices::fetch(&components));
+        let files: Vec<index::file::File> = components
             .into_iter()
-            .map(Into::into)
+            .map(|component| {
+                let world = self.settings.world_name();
+                let mirror_url = mirror.and_then(|mirror| mirror.url_for(&component, world));
+                let mut file = index::file::File::new(component, world);
+                if let Some(url) = mirror_url {
+                    file.downloads.insert(0, url);
+                }
+                file
+            })
             .collect();
-        let index = Index::from_pack_and_files(self, &files);
+        let index = Index::from_pack_and_files(self, files);
         let json = serde_json::to_string_pretty(&index)?;
-        let path = format!("{}.mrpack", self.name);
-
-        tracing::info!(message = "Writing index", target = ?path.yellow().bold());
-        let file = File::create(&path).map_err(|source| local_storage::Error::Io {
-            source,
-            faulty_path: Some(PathBuf::from(path.clone())),
-        })?;
-        let mut mrpack = ZipWriter::new(file);
-        let options =
-            SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
-        mrpack.start_file("modrinth.index.json", options)?;
+        let io_error = |path: &Path| {
+            let path = path.to_path_buf();
+            move |source| local_storage::Error::Io {
+                source,
+                faulty_path: Some(path),
+            }
+        };
+
+        let mut mrpack = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated)
+            .compression_level(self.settings.export_compression_level);
+        mrpack.start_file(Mrpack::INDEX_FILE_NAME, options)?;
         mrpack
             .write_all(json.as_bytes())
-            .map_err(|source| local_storage::Error::Io {
-                source,
-                faulty_path: Some(PathBuf::from(path)),
-            })?;
-        mrpack.finish()?;
+            .map_err(io_error(Path::new(Mrpack::INDEX_FILE_NAME)))?;
+        if !optional.entries.is_empty() {
+            mrpack.start_file(OptionalComponents::FILE_NAME, options)?;
+            mrpack
+                .write_all(optional.to_string().as_bytes())
+                .map_err(io_error(Path::new(OptionalComponents::FILE_NAME)))?;
+        }
+        if let Some(notices) = notices {
+            mrpack.start_file(Notices::FILE_NAME, options)?;
+            mrpack
+                .write_all(notices.to_string().as_bytes())
+                .map_err(io_error(Path::new(Notices::FILE_NAME)))?;
+        }
+        let override_files = self.override_files()?;
+        for (side, relative, contents) in override_files
+            .into_iter()
+            .filter(|(file_side, ..)| side.covers(*file_side))
+        {
+            let path = Path::new(side.folder()).join(relative);
+            mrpack.start_file_from_path(&path, options)?;
+            mrpack.write_all(&contents).map_err(io_error(&path))?;
+        }
+
+        Ok(mrpack.finish()?.into_inner())
+    }
+
+    /// The name artifacts of exports for `side` are filed under: the pack's
+    /// own name, suffixed with the active [`Profile`] if any, and with the side
+    /// unless it's [`OverrideSide::Both`].
+    fn artifact_name(&self, side: OverrideSide) -> String {
+        let name = match &self.settings.profile {
+            Some(profile) => format!("{}-{profile}", self.name),
+            None => self.name.clone(),
+        };
+        match side {
+            OverrideSide::Both => name,
+            side => format!("{name}-{side}"),
+        }
+    }
+
+    /// Compute the [`Plan`] for writing out an exported `archive` as
+    /// `artifact`, updating the `-latest` symlink and pruning stale artifacts
+    /// of the same `format` filed under `name`.
+    fn plan_artifact(
+        &self,
+        name: &str,
+        artifact: Artifact,
+        format: ExportFormat,
+        archive: Vec<u8>,
+    ) -> local_storage::Result<Plan> {
+        let path = artifact.path.clone();
+        let mut plan = Plan::new();
+        plan.push(Action::write_file(&path, archive));
+        plan.push(Action::Symlink {
+            target: path.file_name().map(PathBuf::from).unwrap_or_default(),
+            link: Artifact::latest_link(name, format),
+        });
+        if let Some(retention) = self.settings.export_retention {
+            let mut artifacts = Artifact::find_all(name, format)?;
+            artifacts.insert(0, artifact);
+            for stale in retention.select_stale(&artifacts) {
+                if stale.path != path {
+                    plan.push(Action::RemoveFile {
+                        path: stale.path.clone(),
+                    });
+                }
+            }
+        }
+        Ok(plan)
+    }
+
+    /// Collect all the files placed into the overrides on export, with paths
+    /// relative to the instance directory: those of the [`Include`]s, the
+    /// [`DEFAULTS_DIR`](Self::DEFAULTS_DIR), the pack's own [`Overrides`] and
+    /// the [`LocalComponent`]s.
+    ///
+    /// Only the pack's own overrides and local components can be limited to a
+    /// [side](OverrideSide).
+    fn override_files(&self) -> local_storage::Result<Vec<(OverrideSide, PathBuf, Vec<u8>)>> {
+        let mut override_roots = vec![];
+        for include in &self.includes {
+            let root = include.overrides_root();
+            if !root.is_dir() {
+                return Err(local_storage::Error::Io {
+                    source: io::Error::new(io::ErrorKind::NotFound, "Include is not fetched"),
+                    faulty_path: Some(root),
+                });
+            }
+            override_roots.push(root);
+        }
+        if Path::new(Self::DEFAULTS_DIR).is_dir() {
+            override_roots.push(PathBuf::from(Self::DEFAULTS_DIR));
+        }
+
+        let mut override_files = vec![];
+        for root in override_roots {
+            override_files.extend(
+                overrides::read_tree(&root)?
+                    .into_iter()
+                    .map(|(relative, contents)| (OverrideSide::Both, relative, contents)),
+            );
+        }
+        override_files.extend(Overrides::scan(Path::new("."))?.files);
+        for local in &self.local_components {
+            if local.has_drifted()? {
+                tracing::warn!(
+                    path = %local.path.display(),
+                    "Local component changed since its hash was recorded"
+                );
+            }
+            let side = local.side();
+            override_files.extend(
+                local
+                    .override_files()?
+                    .into_iter()
+                    .map(|(relative, contents)| (side, relative, contents)),
+            );
+        }
+        Ok(override_files)
+    }
+
+    /// Compute the [`Plan`] for populating a local [`Mirror`] directory with
+    /// all the files of this [`Pack`]'s components.
+    ///
+    /// Files that are already present in the mirror are skipped.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if loading the components fails.
+    pub fn plan_mirror_push(&self, target: &Path) -> local_storage::Result<Plan> {
+        let mut plan = Plan::new();
+        for component in Component::load_all()? {
+            let path = target.join(component.runtime_path(self.settings.world_name()));
+            if !path.exists() {
+                plan.push(Action::Download {
+                    sha512: component.hashes.hex(Algorithm::Sha512),
+                    url: component.download_url,
+                    path,
+                });
+            }
+        }
+        Ok(plan)
+    }
+
+    /// Compute the [`Plan`] for installing this [`Pack`] as a client instance
+    /// into `target`.
+    ///
+    /// All client-side components are downloaded to their [runtime
+    /// paths](Component::runtime_path), except for optional ones disabled in
+    /// `optional`, which are removed if previously installed. The overrides
+    /// (includes, local components and default options) are then written over
+    /// the instance, and the manifest is saved into it.
+    ///
+    /// Like with [`plan_install_defaults`](Self::plan_install_defaults),
+    /// options the player already has are left alone.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if loading the components or reading
+    /// the overrides fails.
+    pub fn plan_install(
+        &self,
+        target: &Path,
+        optional: &OptionalComponents,
+    ) -> local_storage::Result<Plan> {
+        let mut plan = Plan::new();
+        plan.push(Action::CreateDir {
+            path: target.to_path_buf(),
+        });
+        for component in self.components()? {
+            let path = target.join(component.runtime_path(self.settings.world_name()));
+            if component.environment.client == Requirement::Unsupported {
+                continue;
+            }
+            if !optional.is_enabled(&component) {
+                if path.exists() {
+                    plan.push(Action::RemoveFile { path });
+                }
+                continue;
+            }
+            plan.push(Action::Download {
+                path,
+                sha512: component.hashes.hex(Algorithm::Sha512),
+                url: component.download_url,
+            });
+        }
+        for (side, relative, contents) in self.override_files()? {
+            if !side.on_client() {
+                continue;
+            }
+            let path = target.join(&relative);
+            if DEFAULT_OPTION_FILES.contains(&relative.to_string_lossy().as_ref()) && path.exists()
+            {
+                continue;
+            }
+            plan.push(Action::write_file(path, contents));
+        }
+        plan.push(Action::write_file(
+            target.join(OptionalComponents::FILE_NAME),
+            optional.to_string(),
+        ));
+        Ok(plan)
+    }
 
+    /// Compute the [`Plan`] for installing this [`Pack`] into a server
+    /// directory at `target`.
+    ///
+    /// All server-side components are downloaded to their [runtime
+    /// paths](Component::runtime_path), and the override files needed on the
+    /// server are written over the directory.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if loading the components or reading
+    /// the overrides fails.
+    pub fn plan_install_server(&self, target: &Path) -> local_storage::Result<Plan> {
+        let mut plan = Plan::new();
+        plan.push(Action::CreateDir {
+            path: target.to_path_buf(),
+        });
+        for component in self.components()? {
+            if !OverrideSide::Server.wants(&component.environment) {
+                continue;
+            }
+            plan.push(Action::Download {
+                path: target.join(component.runtime_path(self.settings.world_name())),
+                sha512: component.hashes.hex(Algorithm::Sha512),
+                url: component.download_url,
+            });
+        }
+        for (side, relative, contents) in self.override_files()? {
+            if OverrideSide::Server.covers(side) {
+                plan.push(Action::write_file(target.join(relative), contents));
+            }
+        }
+        Ok(plan)
+    }
+
+    /// [Fetch](Include::fetch) all of this [`Pack`]'s [`Include`]s.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if fetching any of the includes
+    /// fails.
+    pub fn fetch_includes(&self) -> Result<(), FetchError> {
+        for include in &self.includes {
+            include.fetch()?;
+        }
         Ok(())
     }
+
+    /// The path to the most recent [export](Self::export) of this [`Pack`] in
+    /// `format`.
+    #[must_use]
+    pub fn export_path(&self, format: ExportFormat) -> PathBuf {
+        Artifact::latest_link(&self.artifact_name(OverrideSide::Both), format)
+    }
+
+    /// The path to the most recent `.mrpack` [export](Self::plan_export) of
+    /// this [`Pack`] for `side`.
+    #[must_use]
+    pub fn side_export_path(&self, side: OverrideSide) -> PathBuf {
+        Artifact::latest_link(&self.artifact_name(side), ExportFormat::Mrpack)
+    }
 }
@@ -0,0 +1,140 @@
+use crate::component::{modrinth, Attribution, Component};
+use crate::progress::Task;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A `NOTICES.txt` file aggregating the [`Attribution`]s of a pack's
+/// [`Component`]s, for packs that have to credit their authors.
+///
+/// Exports ship it at the root of the archive when
+/// [`Settings::notices`](super::Settings::notices) is set.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Notices {
+    pub attributions: Vec<Attribution>,
+    /// Full texts of the declared licenses, by their ID.
+    pub license_texts: BTreeMap<String, String>,
+    /// Slugs of the components whose attribution couldn't be fetched.
+    pub unknown: Vec<String>,
+}
+
+impl Notices {
+    /// Name of the notices file in exports.
+    pub const FILE_NAME: &'static str = "NOTICES.txt";
+
+    /// [Fetch the attributions](Component::fetch_attribution) of all
+    /// `components`, along with the text of every license they declare.
+    ///
+    /// Components whose attribution can't be fetched are listed as
+    /// [`unknown`](Self::unknown) instead of failing the whole export, with a
+    /// warning logged for each.
+    #[must_use]
+    pub fn fetch(components: &[Component]) -> Self {
+        let task = Task::start("Fetching attributions", components.len());
+        let mut notices = Self::default();
+        for component in components {
+            task.advance(Some(&component.slug));
+            match component.fetch_attribution() {
+                Ok(attribution) => notices.attributions.push(attribution),
+                Err(error) => {
+                    tracing::warn!(slug = ?component.slug, %error, "Failed to fetch the attribution");
+                    notices.unknown.push(component.slug.clone());
+                }
+            }
+        }
+
+        for attribution in &notices.attributions {
+            let Some(license) = &attribution.license else {
+                continue;
+            };
+            if notices.license_texts.contains_key(&license.id) {
+                continue;
+            }
+            match modrinth::fetch_license_text(&license.id) {
+                Ok(Some(text)) => {
+                    notices.license_texts.insert(license.id.clone(), text);
+                }
+                Ok(None) => {}
+                Err(error) => {
+                    tracing::warn!(license = ?license.id, %error, "Failed to fetch the license text");
+                }
+            }
+        }
+        notices
+    }
+}
+
+impl fmt::Display for Notices {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "THIRD-PARTY NOTICES\n")?;
+        writeln!(
+            f,
+            "This pack refers to the following third-party components, which remain\nthe property of their authors and are provided under their own licenses.\n"
+        )?;
+        for attribution in &self.attributions {
+            writeln!(f, "{} ({})", attribution.name, attribution.slug)?;
+            if !attribution.authors.is_empty() {
+                writeln!(f, "  Authors: {}", attribution.authors.join(", "))?;
+            }
+            match &attribution.license {
+                Some(license) => {
+                    writeln!(f, "  License: {} ({})", license.name, license.id)?;
+                    if let Some(url) = &license.url {
+                        writeln!(f, "  License text: {url}")?;
+                    }
+                }
+                None => writeln!(f, "  License: see the project page")?,
+            }
+            if let Some(url) = &attribution.url {
+                writeln!(f, "  Project page: {url}")?;
+            }
+            writeln!(f)?;
+        }
+        if !self.unknown.is_empty() {
+            writeln!(
+                f,
+                "The attribution of these components couldn't be fetched: {}\n",
+                self.unknown.join(", ")
+            )?;
+        }
+        for (id, text) in &self.license_texts {
+            let rule = "=".repeat(id.len());
+            writeln!(f, "{rule}\n{id}\n{rule}\n\n{}\n", text.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Notices;
+    use crate::component::{Attribution, License};
+
+    #[test]
+    fn license_texts_are_listed_once() {
+        let mit = License {
+            id: "MIT".to_string(),
+            name: "MIT License".to_string(),
+            url: None,
+        };
+        let attribution = |slug: &str| Attribution {
+            slug: slug.to_string(),
+            name: slug.to_uppercase(),
+            authors: vec!["someone".to_string()],
+            url: None,
+            license: Some(mit.clone()),
+        };
+        let notices = Notices {
+            attributions: vec![attribution("a"), attribution("b")],
+            license_texts: [(
+                "MIT".to_string(),
+                "Permission is hereby granted".to_string(),
+            )]
+            .into(),
+            unknown: vec!["c".to_string()],
+        };
+        let text = notices.to_string();
+        assert!(text.contains("A (a)\n  Authors: someone\n  License: MIT License (MIT)\n"));
+        assert!(text.contains("couldn't be fetched: c"));
+        assert_eq!(text.matches("Permission is hereby granted").count(), 1);
+    }
+}
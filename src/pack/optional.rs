@@ -0,0 +1,86 @@
+use crate::component::Component;
+use crate::index::file::Requirement;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// An `options.txt`-style manifest of optional [`Component`]s.
+///
+/// Each line is a `slug:true` or `slug:false` pair, saying whether the player
+/// opted into that component. Exports ship it with every optional component
+/// enabled, and `invar pack install` keeps the player's choices in the
+/// installed instance.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OptionalComponents {
+    pub entries: BTreeMap<String, bool>,
+}
+
+impl OptionalComponents {
+    /// Name of the manifest file, both in exports and in installed instances.
+    pub const FILE_NAME: &'static str = "optional.txt";
+
+    /// Build a manifest with all the optional [`Component`]s enabled.
+    #[must_use]
+    pub fn from_components(components: &[Component]) -> Self {
+        let entries = components
+            .iter()
+            .filter(|component| component.is_optional())
+            .map(|component| (component.slug.clone(), true))
+            .collect();
+        Self { entries }
+    }
+
+    /// Parse a manifest, ignoring empty lines, `#` comments and malformed
+    /// lines.
+    #[must_use]
+    pub fn parse(text: &str) -> Self {
+        let entries = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let (slug, enabled) = line.split_once(':')?;
+                Some((slug.trim().to_string(), enabled.trim().parse().ok()?))
+            })
+            .collect();
+        Self { entries }
+    }
+
+    /// Whether a [`Component`] should be installed according to this
+    /// manifest. Components that aren't optional or aren't listed are.
+    #[must_use]
+    pub fn is_enabled(&self, component: &Component) -> bool {
+        self.entries.get(&component.slug).copied().unwrap_or(true)
+    }
+}
+
+impl Component {
+    /// Whether this [`Component`] is optional on either side.
+    #[must_use]
+    pub fn is_optional(&self) -> bool {
+        self.environment.client == Requirement::Optional
+            || self.environment.server == Requirement::Optional
+    }
+}
+
+impl fmt::Display for OptionalComponents {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# Optional components. Set to `false` to opt out.")?;
+        for (slug, enabled) in &self.entries {
+            writeln!(f, "{slug}:{enabled}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OptionalComponents;
+
+    #[test]
+    fn text_roundtrip() {
+        let manifest = OptionalComponents::parse("# comment\niris:false\n\nsodium: true\nbroken\n");
+        assert_eq!(manifest.entries.len(), 2);
+        assert_eq!(manifest.entries.get("iris"), Some(&false));
+        assert_eq!(OptionalComponents::parse(&manifest.to_string()), manifest);
+    }
+}
@@ -0,0 +1,142 @@
+use crate::index::{Mrpack, OverrideSide};
+use crate::local_storage;
+use crate::plan::{Action, Plan};
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Override files kept in the pack's own `overrides/`, `client-overrides/` and
+/// `server-overrides/` directories, laid out like the folders of an `.mrpack`.
+#[derive(Debug, Clone, Default)]
+pub struct Overrides {
+    /// The side each file is needed on, and its path relative to the instance
+    /// directory.
+    pub files: Vec<(OverrideSide, PathBuf, Vec<u8>)>,
+}
+
+impl Overrides {
+    /// Read the override directories under `root`. Missing ones are taken as
+    /// empty.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if walking a directory or reading
+    /// one of its files fails.
+    pub fn scan(root: &Path) -> local_storage::Result<Self> {
+        let mut files = vec![];
+        for side in OverrideSide::ALL {
+            let dir = root.join(side.folder());
+            if dir.is_dir() {
+                files.extend(
+                    read_tree(&dir)?
+                        .into_iter()
+                        .map(|(relative, contents)| (side, relative, contents)),
+                );
+            }
+        }
+        Ok(Self { files })
+    }
+
+    /// Take the override files of an `.mrpack`, on all sides.
+    #[must_use]
+    pub fn from_mrpack(mrpack: &Mrpack) -> Self {
+        let sides = [
+            (OverrideSide::Both, &mrpack.overrides),
+            (OverrideSide::Client, &mrpack.client_overrides),
+            (OverrideSide::Server, &mrpack.server_overrides),
+        ];
+        let files = sides
+            .into_iter()
+            .flat_map(|(side, files)| {
+                files
+                    .iter()
+                    .map(move |(relative, contents)| (side, relative.clone(), contents.clone()))
+            })
+            .collect();
+        Self { files }
+    }
+
+    /// Compute the [`Plan`] for writing these files into the override
+    /// directories of the pack in the current directory.
+    #[must_use]
+    pub fn plan_write(&self) -> Plan {
+        let mut plan = Plan::new();
+        for (side, relative, contents) in &self.files {
+            plan.push(Action::write_file(
+                Path::new(side.folder()).join(relative),
+                contents.clone(),
+            ));
+        }
+        plan
+    }
+
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+/// Read every file under `root`, with paths relative to it. Git metadata and
+/// `.gitkeep` files are left out.
+pub(super) fn read_tree(root: &Path) -> local_storage::Result<Vec<(PathBuf, Vec<u8>)>> {
+    let entries = WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|entry| entry.file_name() != ".git" && entry.file_name() != ".gitkeep")
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut files = vec![];
+    for entry in entries.iter().filter(|entry| entry.file_type().is_file()) {
+        let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+        let contents = fs::read(entry.path()).map_err(|source| local_storage::Error::Io {
+            source,
+            faulty_path: Some(entry.path().to_path_buf()),
+        })?;
+        files.push((relative.to_path_buf(), contents));
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Overrides;
+    use crate::index::OverrideSide;
+    use std::fs;
+    use std::path::Path;
+
+    #[test]
+    fn scans_all_sides() {
+        let root = std::env::temp_dir().join(format!("invar-overrides-{}", std::process::id()));
+        for (folder, file) in [
+            ("overrides", "config/a.toml"),
+            ("client-overrides", "options.txt"),
+            ("server-overrides", "server.properties"),
+        ] {
+            let path = root.join(folder).join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, file).unwrap();
+        }
+        fs::write(root.join("overrides/.gitkeep"), "").unwrap();
+
+        let mut overrides = Overrides::scan(&root).unwrap();
+        overrides.files.sort_by(|a, b| a.1.cmp(&b.1));
+        let found = overrides
+            .files
+            .iter()
+            .map(|(side, path, _)| (*side, path.as_path()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [
+                (OverrideSide::Both, Path::new("config/a.toml")),
+                (OverrideSide::Client, Path::new("options.txt")),
+                (OverrideSide::Server, Path::new("server.properties")),
+            ]
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
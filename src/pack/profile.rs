@@ -0,0 +1,166 @@
+use super::{ExportPreset, Pack, Settings};
+use crate::component::{Component, Filter};
+use crate::local_storage;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A named set of deltas to the pack's settings and components, like a `dev`
+/// server with less memory and a profiler, applied with `--profile`.
+///
+/// Settings a profile leaves unset keep the pack's own values.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct Profile {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory_limit_gb: Option<u8>,
+    /// The port players connect to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub motd: Option<String>,
+    /// Components left out of this profile, like `slug=spark` or `tag=debug`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub disabled: Vec<Filter>,
+    /// Groups of components turned on or off, over the pack's own
+    /// [`features`](Settings::features).
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub features: BTreeMap<String, bool>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProfileError {
+    #[error("The pack has no profile named {0:?}")]
+    Unknown(String),
+    #[error("The pack has no export preset named {0:?}")]
+    UnknownPreset(String),
+}
+
+impl Settings {
+    /// The [`Profile`] these settings were [resolved](Pack::with_profile)
+    /// with, if any.
+    #[must_use]
+    pub fn active_profile(&self) -> Option<&Profile> {
+        self.profiles.get(self.profile.as_deref()?)
+    }
+
+    /// Apply the deltas of the [`Profile`] called `name` to these settings.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there's no such profile.
+    pub fn apply_profile(&mut self, name: &str) -> Result<(), ProfileError> {
+        let profile = self
+            .profiles
+            .get(name)
+            .ok_or_else(|| ProfileError::Unknown(name.to_string()))?;
+        let server = &mut self.server;
+        server.memory_limit_gb = profile.memory_limit_gb.unwrap_or(server.memory_limit_gb);
+        server.port = profile.port.or(server.port);
+        server.motd = profile.motd.clone().or_else(|| server.motd.clone());
+        self.features.extend(profile.features.clone());
+        self.profile = Some(name.to_string());
+        Ok(())
+    }
+}
+
+impl Pack {
+    /// [Apply](Settings::apply_profile) the [`Profile`] called `name` to this
+    /// pack's settings, and have [`components`](Self::components) leave out the
+    /// ones it disables. Does nothing if `name` is [`None`].
+    ///
+    /// A resolved pack is only meant to be read: writing it would save the
+    /// deltas into the pack file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pack has no such profile.
+    pub fn with_profile(mut self, name: Option<&str>) -> Result<Self, ProfileError> {
+        if let Some(name) = name {
+            self.settings.apply_profile(name)?;
+        }
+        Ok(self)
+    }
+
+    /// Resolve this pack for the [`ExportPreset`] called `name`: apply its
+    /// [`Profile`] if it has one, and have [`components`](Self::components)
+    /// leave out the ones it excludes.
+    ///
+    /// Returns the resolved pack along with the preset.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pack has no such preset, or no such profile.
+    pub fn with_preset(self, name: &str) -> Result<(Self, ExportPreset), ProfileError> {
+        let preset = self
+            .settings
+            .export_presets
+            .get(name)
+            .cloned()
+            .ok_or_else(|| ProfileError::UnknownPreset(name.to_string()))?;
+        let mut pack = self.with_profile(preset.profile.as_deref())?;
+        pack.settings
+            .excluded
+            .extend(preset.exclude.iter().cloned());
+        if preset.compression_level.is_some() {
+            pack.settings.export_compression_level = preset.compression_level;
+        }
+        Ok((pack, preset))
+    }
+
+    /// Load all the [`Component`]s of this pack, except for those in groups
+    /// turned off by its [`features`](Settings::features), those its
+    /// [active profile](Settings::active_profile) disables and those
+    /// [excluded](Settings::excluded) by an export preset.
+    ///
+    /// # Errors
+    ///
+    /// This function will propagate errors from [`Component::load_all`].
+    pub fn components(&self) -> local_storage::Result<Vec<Component>> {
+        let mut components = Component::load_all()?;
+        components.retain(|component| {
+            self.settings.is_enabled(component)
+                && !self
+                    .settings
+                    .excluded
+                    .iter()
+                    .any(|filter| filter.matches(component))
+        });
+        if let Some(profile) = self.settings.active_profile() {
+            components.retain(|component| {
+                !profile
+                    .disabled
+                    .iter()
+                    .any(|filter| filter.matches(component))
+            });
+        }
+        Ok(components)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profiles_override_what_they_set() {
+        let yaml = indoc::indoc! {"
+            memory_limit_gb: 4
+            disabled: [tag=visual, slug=spark]
+            features: { shaders: false }
+        "};
+        let profile = serde_yml::from_str::<Profile>(yaml).unwrap();
+        assert_eq!(profile.disabled[1], Filter::Slug("spark".into()));
+        assert_eq!(profile.disabled[0].to_string(), "tag=visual");
+
+        let mut settings = Settings::default();
+        settings.server.motd = Some("Ground Zero".into());
+        settings.features.insert("shaders".into(), true);
+        settings.profiles.insert("dev".into(), profile);
+        assert!(settings.apply_profile("prod").is_err());
+        settings.apply_profile("dev").unwrap();
+        assert_eq!(settings.server.memory_limit_gb, 4);
+        assert_eq!(settings.server.motd.as_deref(), Some("Ground Zero"));
+        assert!(!settings.features["shaders"]);
+        assert!(settings.active_profile().is_some());
+    }
+}
@@ -0,0 +1,88 @@
+use super::{Pack, EXPORT_DIR};
+use crate::local_storage::{self, PersistedEntity};
+use crate::plan::{Action, Plan};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, thiserror::Error)]
+pub enum RenameError {
+    #[error("The pack is already called {0:?}")]
+    SameName(String),
+    #[error("{0:?} can't be used as a pack name, as it's part of file names")]
+    InvalidName(String),
+    #[error(transparent)]
+    LocalStorage(#[from] local_storage::Error),
+}
+
+impl Pack {
+    /// Compute the [`Plan`] for renaming this [`Pack`] to `new_name`: write
+    /// the pack file with the new name, and move the exported artifacts filed
+    /// under the old one over to the new one, `-latest` symlinks included.
+    ///
+    /// Returns the renamed [`Pack`] along with the [`Plan`]. Server backups
+    /// are left alone, as they're looked up by their sequence numbers.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `new_name` isn't usable in file
+    /// names, the pack can't be serialized or [`EXPORT_DIR`] can't be read.
+    pub fn plan_rename(&self, new_name: &str) -> Result<(Self, Plan), RenameError> {
+        let new_name = new_name.trim();
+        if new_name == self.name {
+            return Err(RenameError::SameName(self.name.clone()));
+        }
+        if new_name.is_empty() || new_name.contains(['/', '\\']) || new_name.starts_with('.') {
+            return Err(RenameError::InvalidName(new_name.to_string()));
+        }
+        let renamed = Self {
+            name: new_name.to_string(),
+            ..self.clone()
+        };
+        let mut plan = Plan::new();
+        plan.push(Action::write_entity(
+            <Self as PersistedEntity>::FILE_PATH,
+            serde_yml::to_string(&renamed).map_err(local_storage::Error::from)?,
+        ));
+
+        let export_dir = Path::new(EXPORT_DIR);
+        if !export_dir.is_dir() {
+            return Ok((renamed, plan));
+        }
+        let io_error = |source| local_storage::Error::Io {
+            source,
+            faulty_path: Some(export_dir.to_path_buf()),
+        };
+        let mut entries = fs::read_dir(export_dir)
+            .map_err(io_error)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(io_error)?;
+        entries.sort_by_key(fs::DirEntry::file_name);
+        let (mut moves, mut links) = (vec![], vec![]);
+        for entry in entries {
+            let path = entry.path();
+            let Some(to) = self.renamed_artifact(&path, new_name) else {
+                continue;
+            };
+            if entry.file_type().map_err(io_error)?.is_symlink() {
+                let target = fs::read_link(&path).map_err(io_error)?;
+                let target = self.renamed_artifact(&target, new_name).unwrap_or(target);
+                links.push(Action::RemoveFile { path });
+                links.push(Action::Symlink { target, link: to });
+            } else {
+                moves.push(Action::MoveFile { from: path, to });
+            }
+        }
+        plan.actions.extend(moves);
+        plan.actions.extend(links);
+        Ok((renamed, plan))
+    }
+
+    /// The path an artifact at `path` is moved to when this pack is renamed
+    /// to `new_name`, or [`None`] if it's not filed under this pack's name.
+    fn renamed_artifact(&self, path: &Path, new_name: &str) -> Option<PathBuf> {
+        let file_name = path.file_name()?.to_str()?;
+        let rest = file_name.strip_prefix(&self.name)?;
+        rest.starts_with('-')
+            .then(|| path.with_file_name(format!("{new_name}{rest}")))
+    }
+}
@@ -0,0 +1,139 @@
+use super::Pack;
+use crate::component::{AddError, Component, DependencyGraph};
+use crate::instance::Instance;
+use crate::local_storage::{self, PersistedEntity};
+use crate::plan::{Action, Plan};
+use crate::progress::Task;
+use crate::{budget, network};
+
+#[derive(Debug, thiserror::Error)]
+pub enum RetargetError {
+    #[error("Components can't be checked against another instance in offline mode")]
+    Offline,
+    #[error("The time budget ran out before every component was checked")]
+    BudgetExhausted,
+    #[error("Failed to check {slug}")]
+    Fetch {
+        slug: String,
+        #[source]
+        source: AddError,
+    },
+    #[error(transparent)]
+    LocalStorage(#[from] local_storage::Error),
+}
+
+/// The result of checking every [`Component`] of a [`Pack`] against another
+/// [`Instance`], usually one with a newer Minecraft version.
+#[derive(Debug)]
+pub struct Retarget {
+    /// The instance the pack is being moved to.
+    pub instance: Instance,
+    pub components: Vec<(Component, Availability)>,
+}
+
+/// Whether a [`Component`] is available for the [`Retarget`]'s instance.
+#[derive(Debug)]
+pub enum Availability {
+    /// The current version is compatible as it is.
+    Unchanged,
+    /// A newer version is compatible.
+    Updated(Box<Component>),
+    /// No compatible version could be found, always with
+    /// [`AddError::Incompatible`].
+    Unavailable(AddError),
+}
+
+impl Pack {
+    /// Check the availability of every [`Component`] of this [`Pack`] for
+    /// another [`Instance`].
+    ///
+    /// Dependency conflicts are not taken into account here, since pins on the
+    /// current versions don't mean anything for the new instance.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if loading the components fails,
+    /// or in offline mode. Components without a compatible version are
+    /// recorded as [`Availability::Unavailable`], but any other failure to
+    /// fetch one, like a network error or the [time budget](budget) running
+    /// out, aborts the whole check, so that the component isn't removed
+    /// for no good reason.
+    pub fn check_retarget(&self, instance: Instance) -> Result<Retarget, RetargetError> {
+        if network::is_offline() {
+            return Err(RetargetError::Offline);
+        }
+        let graph = DependencyGraph::new(&[]);
+        let components = Component::load_all()?;
+        let count = components.len();
+        let task = Task::start("Checking components", count);
+        let components = components
+            .into_iter()
+            .map(|component| {
+                task.advance(Some(&component.slug));
+                if budget::is_exhausted() {
+                    return Err(RetargetError::BudgetExhausted);
+                }
+                let availability = match component.fetch_retarget(&instance, &graph) {
+                    Ok(Some(update)) => Availability::Updated(Box::new(update)),
+                    Ok(None) => Availability::Unchanged,
+                    Err(error @ AddError::Incompatible) => Availability::Unavailable(error),
+                    Err(source) => {
+                        return Err(RetargetError::Fetch {
+                            slug: component.slug,
+                            source,
+                        })
+                    }
+                };
+                Ok((component, availability))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Retarget {
+            instance,
+            components,
+        })
+    }
+}
+
+impl Retarget {
+    /// The [`Component`]s that have no version for the new instance.
+    pub fn unavailable(&self) -> impl Iterator<Item = (&Component, &AddError)> {
+        self.components
+            .iter()
+            .filter_map(|(component, availability)| match availability {
+                Availability::Unavailable(error) => Some((component, error)),
+                _ => None,
+            })
+    }
+
+    /// Compute the [`Plan`] for moving `pack` to the new instance: updating
+    /// `pack.yml` and all the available components, and removing the
+    /// unavailable ones.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if serializing the pack or the
+    /// components fails, or if a component's metadata file can't be found.
+    pub fn plan(&self, pack: &Pack) -> local_storage::Result<Plan> {
+        let pack = Pack {
+            instance: self.instance.clone(),
+            ..pack.clone()
+        };
+        let mut plan = Plan::new();
+        plan.push(Action::write_entity(
+            Pack::FILE_PATH,
+            serde_yml::to_string(&pack)?,
+        ));
+        for (component, availability) in &self.components {
+            match availability {
+                Availability::Unchanged => {}
+                Availability::Updated(update) => {
+                    plan.extend(update.plan_save()?);
+                }
+                Availability::Unavailable(_) => {
+                    plan.extend(Component::plan_remove(&component.slug)?);
+                }
+            }
+        }
+        Ok(plan)
+    }
+}
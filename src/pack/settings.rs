@@ -1,15 +1,434 @@
+use super::{Destination, ExportFormat, Profile};
+use crate::component::{Category, Component, Filter, TagInformation};
+use crate::index::file::Env;
+use crate::index::OverrideSide;
+use crate::network::NetworkSettings;
+use crate::plan::GitSignature;
+use crate::server::{Difficulty, Gamemode, DEFAULT_MINECRAFT_PORT};
+use crate::terminal;
+use clap::ValueEnum;
+use inquire::InquireError;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use url::Url;
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
 pub struct Settings {
     pub vcs_mode: VcsMode,
     pub backup_mode: BackupMode,
+
+    /// How new backups of the server are stored.
+    #[serde(default, skip_serializing_if = "BackupFormat::is_plain")]
+    pub backup_format: BackupFormat,
+
+    /// How `invar server` runs the server.
+    #[serde(default)]
+    pub server_backend: ServerBackend,
+
+    /// How `invar server setup` configures the server.
+    #[serde(default, skip_serializing_if = "ServerSettings::is_default")]
+    pub server: ServerSettings,
+
+    /// Who's an operator, whitelisted or banned on the hosted server.
+    #[serde(default, skip_serializing_if = "PlayerSettings::is_empty")]
+    pub players: PlayerSettings,
+
+    /// A self-hosted mirror of all the pack's remote files.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mirror: Option<Mirror>,
+
+    /// How many exported artifacts to keep around. All of them are kept if
+    /// this is not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub export_retention: Option<ExportRetention>,
+
+    /// The Deflate level (0 to 9) of exported archives, 6 if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub export_compression_level: Option<i64>,
+
+    /// Named sets of `invar pack export` options, run with `--preset`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub export_presets: BTreeMap<String, ExportPreset>,
+
+    /// The identity to create commits with when Git has none configured.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_signature: Option<GitSignature>,
+
+    /// Don't back up the server before operations that can damage its world,
+    /// like retargeting the pack to another Minecraft version.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub skip_safety_backups: bool,
+
+    /// Name of the world directory that datapacks are placed into at runtime,
+    /// [`DEFAULT_WORLD_NAME`] if not set. Servers call it `level-name`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub world: Option<String>,
+
+    /// Ship a [`Notices`](super::Notices) file crediting the components'
+    /// authors with exports. Its contents are fetched on every export.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub notices: bool,
+
+    /// Metadata given to components of a category when they're added
+    /// non-interactively, so that they're tagged the same way by everyone.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub category_defaults: BTreeMap<Category, CategoryDefaults>,
+
+    /// Groups of components turned on or off on export, by their
+    /// [`group`](Component::group). Groups not listed here are on.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub features: BTreeMap<String, bool>,
+
+    /// Components known not to work together, though their sources don't
+    /// declare it, reported by `invar pack doctor`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conflicts: Vec<KnownConflict>,
+
+    /// Named deltas to these settings and the pack's components, like for
+    /// `dev` and `prod` servers.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Profile>,
+
+    /// How requests to Modrinth and CurseForge are retried and timed out.
+    #[serde(default, skip_serializing_if = "NetworkSettings::is_default")]
+    pub network: NetworkSettings,
+
+    /// The name of the [`Profile`] these settings were resolved with.
+    #[serde(skip)]
+    pub profile: Option<String>,
+
+    /// Components left out of exports by the [`ExportPreset`] these settings
+    /// were resolved with.
+    #[serde(skip)]
+    pub excluded: Vec<Filter>,
+}
+
+/// Metadata given to every new [`Component`] of a [`Category`], like the
+/// `visual` tag and a client-only environment for shaders. See
+/// [`Settings::category_defaults`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct CategoryDefaults {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<TagInformation>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<Env>,
+}
+
+impl CategoryDefaults {
+    /// Override the `component`'s metadata with the defaults that are set.
+    pub fn apply(&self, component: &mut Component) {
+        if let Some(tags) = &self.tags {
+            component.tags = tags.clone();
+        }
+        if let Some(environment) = &self.environment {
+            component.environment = environment.clone();
+        }
+    }
+}
+
+/// The players the hosted server treats specially, applied on its first start
+/// and by `invar server sync-players`.
+///
+/// Players are only ever added to these lists on the server: removing one here
+/// has to be mirrored with `invar server exec` by hand.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct PlayerSettings {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ops: Vec<String>,
+    /// Turns the whitelist on if not empty. Operators are whitelisted too.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub whitelist: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bans: Vec<String>,
+}
+
+impl PlayerSettings {
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.ops.is_empty() && self.whitelist.is_empty() && self.bans.is_empty()
+    }
+}
+
+/// Components that break when they're all in the pack, see
+/// [`Settings::conflicts`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct KnownConflict {
+    pub slugs: Vec<String>,
+    /// What goes wrong, shown along with the conflict.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// The world directory a server creates unless told otherwise.
+pub const DEFAULT_WORLD_NAME: &str = "world";
+
+impl Settings {
+    /// Name of the world directory that datapacks are placed into.
+    #[must_use]
+    pub fn world_name(&self) -> &str {
+        self.world.as_deref().unwrap_or(DEFAULT_WORLD_NAME)
+    }
+
+    /// Whether `component` is exported, which it is unless its
+    /// [`group`](Component::group) is turned off by the
+    /// [`features`](Self::features).
+    #[must_use]
+    pub fn is_enabled(&self, component: &Component) -> bool {
+        component
+            .group
+            .as_ref()
+            .and_then(|group| self.features.get(group))
+            .copied()
+            .unwrap_or(true)
+    }
+
+    /// Point the [`conflicts`](Self::conflicts) and the `slug=` filters of
+    /// profiles and export presets at a component renamed from `old` to
+    /// `new`. Returns whether anything changed.
+    pub fn rename_component(&mut self, old: &str, new: &str) -> bool {
+        let mut changed = false;
+        let slugs = self
+            .conflicts
+            .iter_mut()
+            .flat_map(|conflict| conflict.slugs.iter_mut());
+        for slug in slugs.filter(|slug| *slug == old) {
+            *slug = new.to_string();
+            changed = true;
+        }
+        let filters = self
+            .profiles
+            .values_mut()
+            .flat_map(|profile| profile.disabled.iter_mut())
+            .chain(
+                self.export_presets
+                    .values_mut()
+                    .flat_map(|preset| preset.exclude.iter_mut()),
+            );
+        for filter in filters {
+            if matches!(filter, Filter::Slug(slug) if slug == old) {
+                *filter = Filter::Slug(new.to_string());
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// The console commands that apply the [`players`](Self::players) lists,
+    /// with the server's [operator](ServerSettings::operator_username) among
+    /// the `ops`.
+    #[must_use]
+    pub fn player_commands(&self) -> Vec<String> {
+        let mut ops = Vec::<&String>::new();
+        for op in self
+            .server
+            .operator_username
+            .iter()
+            .chain(&self.players.ops)
+        {
+            if !ops.contains(&op) {
+                ops.push(op);
+            }
+        }
+        let mut whitelist = self.players.whitelist.iter().collect::<Vec<_>>();
+        whitelist.extend(ops.iter().filter(|op| !self.players.whitelist.contains(op)));
+        let mut commands = vec![];
+        if !whitelist.is_empty() {
+            commands.push("whitelist on".to_string());
+        }
+        commands.extend(
+            whitelist
+                .iter()
+                .map(|player| format!("whitelist add {player}")),
+        );
+        commands.extend(ops.iter().map(|player| format!("op {player}")));
+        commands.extend(
+            self.players
+                .bans
+                .iter()
+                .map(|player| format!("ban {player}")),
+        );
+        commands
+    }
+}
+
+/// How the hosted server is configured by `invar server setup`.
+///
+/// Values missing from the pack file take their defaults, except for the
+/// [operator](Self::operator_username) and the [MOTD](Self::motd), which are
+/// asked for during setup.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct ServerSettings {
+    /// The player made an operator and whitelisted on the first start.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operator_username: Option<String>,
+    /// The message shown in the players' server lists.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub motd: Option<String>,
+    /// The server's icon, the Ground Zero one if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<Url>,
+    /// The port players connect to, [`DEFAULT_MINECRAFT_PORT`] if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    pub memory_limit_gb: u8,
+    pub max_players: u16,
+    pub gamemode: Gamemode,
+    pub difficulty: Difficulty,
+    pub view_distance: u8,
+    pub online_mode: bool,
+    pub allow_flight: bool,
+    /// Pause the server while nobody is playing on it.
+    pub autopause: bool,
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        Self {
+            operator_username: None,
+            motd: None,
+            icon: None,
+            port: None,
+            memory_limit_gb: 12,
+            max_players: 4,
+            gamemode: Gamemode::Survival,
+            difficulty: Difficulty::Hard,
+            view_distance: 12,
+            online_mode: false,
+            allow_flight: true,
+            autopause: true,
+        }
+    }
+}
+
+impl ServerSettings {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The port players connect to.
+    #[must_use]
+    pub fn port(&self) -> u16 {
+        self.port.unwrap_or(DEFAULT_MINECRAFT_PORT)
+    }
+
+    /// Ask for the values that have no sensible defaults, if they're missing.
+    /// Returns whether anything was filled in. When running
+    /// [non-interactively](terminal::set_interactive), nothing is asked.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if prompting fails.
+    pub fn complete(&mut self, pack_name: &str) -> Result<bool, InquireError> {
+        if !terminal::is_interactive() {
+            return Ok(false);
+        }
+        let mut changed = false;
+        if self.operator_username.is_none() {
+            let message = "Who should be the server's operator?";
+            self.operator_username = terminal::suspend(|| {
+                inquire::Text::new(message)
+                    .with_help_message("Skip with [Escape] to have no operator")
+                    .prompt_skippable()
+            })?
+            .map(|username| username.trim().to_string())
+            .filter(|username| !username.is_empty());
+            changed |= self.operator_username.is_some();
+        }
+        if self.motd.is_none() {
+            let message = "What should the server's MOTD be?";
+            self.motd = Some(terminal::suspend(|| {
+                inquire::Text::new(message).with_default(pack_name).prompt()
+            })?);
+            changed = true;
+        }
+        Ok(changed)
+    }
+}
+
+/// Rules for pruning old artifacts from [`EXPORT_DIR`](super::EXPORT_DIR)
+/// after an export.
+///
+/// An artifact is kept if any of the set rules says so.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExportRetention {
+    /// Keep this many most recent artifacts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep_last: Option<usize>,
+
+    /// Keep this many most recent artifacts of each pack version.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep_per_version: Option<usize>,
+}
+
+/// The options of an `invar pack export` run, saved under a name in
+/// [`Settings::export_presets`], like a `release-server` preset uploading a
+/// compressed server pack.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct ExportPreset {
+    pub format: ExportFormat,
+    pub side: OverrideSide,
+    /// The [`Profile`] to export the pack with.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// Components left out of the export, like `tag=debug`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<Filter>,
+    /// Overrides [`Settings::export_compression_level`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compression_level: Option<i64>,
+    /// Where to upload the exported artifact.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upload: Option<Destination>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_url: Option<Url>,
+}
+
+/// A self-hosted mirror of all the pack's remote files, for players who can't
+/// reliably reach the Modrinth CDN.
+///
+/// When set, exported indices list the mirror's URL for each file before the
+/// original one, and `invar mirror push` populates the mirror.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Mirror {
+    /// The public base URL of the mirror.
+    pub url: Url,
+
+    /// A local directory served at [`Mirror::url`] that `invar mirror push`
+    /// places files into.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local_path: Option<PathBuf>,
+}
+
+impl Mirror {
+    /// The URL a [`Component`]'s file is served at by this mirror.
+    ///
+    /// Files are laid out by their [runtime path](Component::runtime_path) in
+    /// the `world`.
+    #[must_use]
+    pub fn url_for(&self, component: &Component, world: &str) -> Option<Url> {
+        let mut base = self.url.clone();
+        if !base.path().ends_with('/') {
+            base.set_path(&format!("{}/", base.path()));
+        }
+        let path = component.runtime_path(world);
+        match base.join(&path.to_string_lossy()) {
+            Ok(url) => Some(url),
+            Err(error) => {
+                tracing::warn!(%error, ?path, "Failed to construct a mirror URL");
+                None
+            }
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum VcsMode {
-    /// Auto-commit each added or removed component.
+    /// Auto-commit every change to the components and the pack, like adding,
+    /// removing or renaming them. `--no-commit` skips it once.
     #[default]
     TrackComponents,
 
@@ -17,7 +436,21 @@ pub enum VcsMode {
     Manual,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+/// The ways `invar server` can run the server.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerBackend {
+    /// Run the server in a container, see
+    /// [`DockerCompose`](crate::server::docker_compose::DockerCompose).
+    #[default]
+    DockerCompose,
+
+    /// Run the server as a bare Java process, for hosts without Docker. See
+    /// [`BareServer`](crate::server::bare::BareServer).
+    Bare,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum BackupMode {
     /// Create a backup before starting the server and after stopping it.
@@ -27,10 +460,88 @@ pub enum BackupMode {
     /// are deleted until there are exactly `min_depth` backups remaining.
     StartStop { min_depth: usize },
 
+    /// Create a backup on a `schedule`, like `0 0 */6 * * *` for every 6
+    /// hours, while `invar server backup daemon` is running.
+    ///
+    /// Older backups are deleted unless the `retention` policy keeps them.
+    Scheduled {
+        #[serde(with = "schedule")]
+        schedule: Box<cron::Schedule>,
+        #[serde(default)]
+        retention: BackupRetention,
+    },
+
     /// Do not create or delete backups automatically.
     Manual,
 }
 
+/// How a backup of the server's data is stored.
+#[derive(
+    Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq, ValueEnum, strum::Display,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum BackupFormat {
+    /// A plain copy of the data's directory.
+    #[default]
+    Plain,
+
+    /// A zstd-compressed tar archive, smaller but slower to create and restore.
+    Archive,
+
+    /// A copy of the data's directory that hard-links the files unchanged
+    /// since the previous backup into it, instead of copying them again.
+    Incremental,
+}
+
+impl BackupFormat {
+    #[must_use]
+    pub const fn is_plain(&self) -> bool {
+        matches!(self, Self::Plain)
+    }
+}
+
+/// How many backups [`BackupMode::Scheduled`] keeps around.
+///
+/// The newest backup of each of the last `daily` days and of each of the last
+/// `weekly` weeks is kept, the others are deleted.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackupRetention {
+    pub daily: usize,
+    pub weekly: usize,
+}
+
+impl Default for BackupRetention {
+    fn default() -> Self {
+        Self {
+            daily: 7,
+            weekly: 4,
+        }
+    }
+}
+
+/// (De)serialize a [`cron::Schedule`] as its expression.
+mod schedule {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+    use std::str::FromStr;
+
+    pub fn serialize<S: Serializer>(
+        schedule: &cron::Schedule,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(schedule)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Box<cron::Schedule>, D::Error> {
+        let expression = String::deserialize(deserializer)?;
+        cron::Schedule::from_str(&expression)
+            .map(Box::new)
+            .map_err(de::Error::custom)
+    }
+}
+
 impl Default for BackupMode {
     fn default() -> Self {
         Self::StartStop {
@@ -39,3 +550,26 @@ impl Default for BackupMode {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn operators_are_whitelisted() {
+        let mut settings = Settings::default();
+        settings.server.operator_username = Some("Alex".into());
+        settings.players.whitelist = vec!["Steve".into()];
+        settings.players.bans = vec!["Herobrine".into()];
+        assert_eq!(
+            settings.player_commands(),
+            [
+                "whitelist on",
+                "whitelist add Steve",
+                "whitelist add Alex",
+                "op Alex",
+                "ban Herobrine",
+            ]
+        );
+    }
+}
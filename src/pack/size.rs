@@ -0,0 +1,131 @@
+use super::Pack;
+use crate::index::OverrideSide;
+use crate::local_storage;
+use color_eyre::owo_colors::OwoColorize;
+use serde::Serialize;
+use std::fmt;
+use std::io::Cursor;
+use zip::ZipArchive;
+
+/// How big a [`Pack`] is, as reported by `pack size`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct PackSize {
+    /// How many remote components the pack has.
+    pub remote_count: usize,
+    /// The sizes of the remote components' files, summed.
+    pub remote_bytes: usize,
+    /// How many [`LocalComponent`](super::LocalComponent)s the pack has.
+    pub local_count: usize,
+    /// The sizes of all files placed into the overrides, local components
+    /// included, as they are on disk.
+    pub override_bytes: usize,
+    /// The size of the exported `.mrpack` itself, which has the overrides
+    /// compressed.
+    pub archive_bytes: usize,
+}
+
+impl PackSize {
+    /// Measure `pack` as it would be exported for `side`.
+    ///
+    /// # Errors
+    ///
+    /// This function may return a [`local_storage::Error`]. Look there for
+    /// possible causes.
+    pub fn measure(pack: &Pack, side: OverrideSide) -> local_storage::Result<Self> {
+        let mut components = pack.components()?;
+        components.retain(|component| side.wants(&component.environment));
+        let archive = pack.mrpack_archive(side)?;
+        // NOTE: The archive already has the overrides the side needs, and
+        // records how big each of them is uncompressed.
+        let mut zip = ZipArchive::new(Cursor::new(archive.as_slice()))?;
+        let mut override_bytes = 0;
+        for index in 0..zip.len() {
+            let file = zip.by_index(index)?;
+            let is_override = OverrideSide::ALL
+                .iter()
+                .any(|folder| file.name().starts_with(&format!("{}/", folder.folder())));
+            if is_override {
+                override_bytes += usize::try_from(file.size()).unwrap_or(usize::MAX);
+            }
+        }
+        Ok(Self {
+            remote_count: components.len(),
+            remote_bytes: components.iter().map(|component| component.file_size).sum(),
+            local_count: pack.local_components.len(),
+            override_bytes,
+            archive_bytes: archive.len(),
+        })
+    }
+
+    /// What a launcher downloads to install the pack: the `.mrpack` and then
+    /// the remote components' files.
+    #[must_use]
+    pub const fn download_bytes(&self) -> usize {
+        self.archive_bytes + self.remote_bytes
+    }
+
+    /// Roughly what the pack takes up once installed, with the overrides
+    /// extracted. Doesn't count Minecraft and the loader themselves.
+    #[must_use]
+    pub const fn installed_bytes(&self) -> usize {
+        self.remote_bytes + self.override_bytes
+    }
+}
+
+/// Format `bytes` for humans, like `12.5 MiB`.
+#[must_use]
+pub fn format_size(bytes: usize) -> String {
+    let mut unit = "B";
+    let mut tenths = bytes * 10;
+    for next in ["KiB", "MiB", "GiB"] {
+        if tenths < 1024 * 10 {
+            break;
+        }
+        tenths /= 1024;
+        unit = next;
+    }
+    match unit {
+        "B" => format!("{bytes} B"),
+        unit => format!("{}.{} {unit}", tenths / 10, tenths % 10),
+    }
+}
+
+impl fmt::Display for PackSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Remote components: {} ({})",
+            self.remote_count,
+            format_size(self.remote_bytes)
+        )?;
+        writeln!(
+            f,
+            "Overrides: {} local components, {} in total",
+            self.local_count,
+            format_size(self.override_bytes)
+        )?;
+        writeln!(f, "Pack archive: {}", format_size(self.archive_bytes))?;
+        writeln!(
+            f,
+            "Download size: {}",
+            format_size(self.download_bytes()).yellow().bold()
+        )?;
+        writeln!(
+            f,
+            "Installed size: ~{}",
+            format_size(self.installed_bytes()).yellow().bold()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes_are_formatted() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(3 * 1024 * 1024), "3.0 MiB");
+    }
+}
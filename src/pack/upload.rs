@@ -0,0 +1,235 @@
+use serde_with::{DeserializeFromStr, SerializeDisplay};
+use std::fmt;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use url::Url;
+
+/// A remote location exported artifacts can be uploaded to, given as an
+/// `s3://<bucket>/<prefix>` or `sftp://[<user>@]<host>[:<port>]/<dir>` URL.
+///
+/// Uploads are done by external tools, which pick up credentials the way they
+/// usually do: the AWS CLI for S3, and OpenSSH's `sftp` (with keys, as it runs
+/// in batch mode) for SFTP.
+#[derive(Debug, Clone, PartialEq, Eq, SerializeDisplay, DeserializeFromStr)]
+pub enum Destination {
+    S3 {
+        bucket: String,
+        prefix: String,
+    },
+    Sftp {
+        host: String,
+        user: Option<String>,
+        port: Option<u16>,
+        dir: String,
+    },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum UploadError {
+    #[error("Invalid destination URL")]
+    Url(#[from] url::ParseError),
+    #[error("Artifacts can only be uploaded to `s3://` or `sftp://` URLs, not `{0}://`")]
+    UnsupportedScheme(String),
+    #[error("The destination URL has no {0}")]
+    Incomplete(&'static str),
+    #[error("The artifact at {0:?} has no file name")]
+    NoFileName(std::path::PathBuf),
+    #[error("Failed to run `{tool}`")]
+    Spawn {
+        tool: &'static str,
+        source: io::Error,
+    },
+    #[error("`{tool}` failed to upload the artifact: {stderr}")]
+    Failed { tool: &'static str, stderr: String },
+}
+
+impl FromStr for Destination {
+    type Err = UploadError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let url = Url::parse(s)?;
+        let host = url
+            .host_str()
+            .filter(|host| !host.is_empty())
+            .ok_or(UploadError::Incomplete("host"))?
+            .to_string();
+        match url.scheme() {
+            "s3" => Ok(Self::S3 {
+                bucket: host,
+                prefix: url.path().trim_matches('/').to_string(),
+            }),
+            "sftp" => Ok(Self::Sftp {
+                host,
+                user: Some(url.username())
+                    .filter(|user| !user.is_empty())
+                    .map(String::from),
+                port: url.port(),
+                dir: url.path().trim_end_matches('/').to_string(),
+            }),
+            scheme => Err(UploadError::UnsupportedScheme(scheme.to_string())),
+        }
+    }
+}
+
+impl Destination {
+    /// Upload the file at `path` into this destination, under its own name.
+    ///
+    /// Returns the URL the file can be downloaded from: `public_base` joined
+    /// with the file name if set, the bucket's public URL for S3, or the
+    /// remote file's URL otherwise.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the upload tool can't be run or
+    /// fails.
+    pub fn upload(&self, path: &Path, public_base: Option<&Url>) -> Result<Url, UploadError> {
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .ok_or_else(|| UploadError::NoFileName(path.to_path_buf()))?;
+        match self {
+            Self::S3 { bucket, .. } => {
+                let target = format!("s3://{bucket}/{}", self.remote_path(&file_name));
+                let mut command = Command::new("aws");
+                command.args(["s3", "cp", "--only-show-errors"]);
+                command.arg(path).arg(target);
+                run("aws", &mut command, None)?;
+            }
+            Self::Sftp {
+                host, user, port, ..
+            } => {
+                let mut command = Command::new("sftp");
+                command.args(["-b", "-"]);
+                if let Some(port) = port {
+                    command.args(["-P", &port.to_string()]);
+                }
+                match user {
+                    Some(user) => command.arg(format!("{user}@{host}")),
+                    None => command.arg(host),
+                };
+                // NOTE: The leading `-` makes `sftp` carry on if the directory
+                // already exists.
+                let batch = format!(
+                    "-mkdir \"{dir}\"\nput \"{local}\" \"{remote}\"\n",
+                    dir = self.remote_path(""),
+                    local = path.display(),
+                    remote = self.remote_path(&file_name),
+                );
+                run("sftp", &mut command, Some(&batch))?;
+            }
+        }
+        self.public_url(&file_name, public_base)
+    }
+
+    /// Where a file with this name is downloaded from once uploaded, see
+    /// [`upload`](Self::upload).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the URL can't be built.
+    pub fn public_url(&self, file_name: &str, base: Option<&Url>) -> Result<Url, UploadError> {
+        let remote_path = self.remote_path(file_name);
+        let url = match (base, self) {
+            (Some(base), _) => {
+                let mut base = base.clone();
+                if !base.path().ends_with('/') {
+                    base.set_path(&format!("{}/", base.path()));
+                }
+                base.join(file_name)?
+            }
+            (None, Self::S3 { bucket, .. }) => {
+                Url::parse(&format!("https://{bucket}.s3.amazonaws.com/{remote_path}"))?
+            }
+            (None, Self::Sftp { .. }) => Url::parse(&format!("{self}/{file_name}"))?,
+        };
+        Ok(url)
+    }
+
+    /// The path of a file with this name on the remote side: the key in the
+    /// bucket for S3, an absolute path for SFTP unless the URL has no path.
+    fn remote_path(&self, file_name: &str) -> String {
+        let dir = match self {
+            Self::S3 { prefix, .. } => prefix,
+            Self::Sftp { dir, .. } => dir,
+        };
+        match (dir.is_empty(), file_name.is_empty()) {
+            (true, _) => file_name.to_string(),
+            (false, true) => dir.clone(),
+            (false, false) => format!("{dir}/{file_name}"),
+        }
+    }
+}
+
+/// Run an upload `command`, feeding it `stdin` if set.
+fn run(tool: &'static str, command: &mut Command, stdin: Option<&str>) -> Result<(), UploadError> {
+    let spawn_error = |source| UploadError::Spawn { tool, source };
+    let mut child = command
+        .stdin(match stdin {
+            Some(_) => Stdio::piped(),
+            None => Stdio::null(),
+        })
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(spawn_error)?;
+    if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        pipe.write_all(input.as_bytes()).map_err(spawn_error)?;
+    }
+    let output = child.wait_with_output().map_err(spawn_error)?;
+    if !output.status.success() {
+        return Err(UploadError::Failed {
+            tool,
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+    Ok(())
+}
+
+impl fmt::Display for Destination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::S3 { bucket, .. } => write!(f, "s3://{bucket}/{}", self.remote_path("")),
+            Self::Sftp {
+                host, user, port, ..
+            } => {
+                write!(f, "sftp://")?;
+                if let Some(user) = user {
+                    write!(f, "{user}@")?;
+                }
+                write!(f, "{host}")?;
+                if let Some(port) = port {
+                    write!(f, ":{port}")?;
+                }
+                write!(f, "{}", self.remote_path(""))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn destinations_are_parsed() {
+        let s3 = "s3://packs/releases/".parse::<Destination>().unwrap();
+        assert_eq!(
+            s3.public_url("pack.mrpack", None).unwrap().as_str(),
+            "https://packs.s3.amazonaws.com/releases/pack.mrpack"
+        );
+        let sftp = "sftp://deploy@example.org:2222/srv/packs"
+            .parse::<Destination>()
+            .unwrap();
+        assert_eq!(sftp.to_string(), "sftp://deploy@example.org:2222/srv/packs");
+        let base = Url::parse("https://example.org/packs").unwrap();
+        assert_eq!(
+            sftp.public_url("pack.mrpack", Some(&base))
+                .unwrap()
+                .as_str(),
+            "https://example.org/packs/pack.mrpack"
+        );
+        assert!("https://example.org".parse::<Destination>().is_err());
+    }
+}
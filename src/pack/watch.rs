@@ -0,0 +1,76 @@
+use super::Pack;
+use crate::index::OverrideSide;
+use crate::local_storage::{self, PersistedEntity};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use walkdir::WalkDir;
+
+/// What the files a [`Pack`] is exported from looked like at some point, to
+/// tell when one of them changes, like for `pack export --watch`.
+///
+/// Files are compared by modification time and size, which is plenty for
+/// files being edited by hand and spares reading them all every time.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WatchedFiles {
+    stamps: BTreeMap<PathBuf, (Option<SystemTime>, u64)>,
+}
+
+impl WatchedFiles {
+    /// How long to wait between [scans](Self::scan) when watching.
+    pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+    /// Look at the files of `pack` in the current directory: its pack file,
+    /// the metadata files of its components, its local components, and the
+    /// override and [defaults](Pack::DEFAULTS_DIR) directories.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the pack can't be scanned.
+    pub fn scan(pack: &Pack) -> local_storage::Result<Self> {
+        let mut roots = vec![PathBuf::from(<Pack as PersistedEntity>::FILE_PATH)];
+        roots.extend(local_storage::metadata_files(".")?.map(|entry| entry.path().to_path_buf()));
+        roots.extend(
+            pack.local_components
+                .iter()
+                .map(super::LocalComponent::base),
+        );
+        roots.extend(OverrideSide::ALL.map(|side| PathBuf::from(side.folder())));
+        roots.push(PathBuf::from(Pack::DEFAULTS_DIR));
+
+        let mut stamps = BTreeMap::new();
+        for root in roots.iter().filter(|root| root.exists()) {
+            for entry in WalkDir::new(root)
+                .into_iter()
+                .filter_entry(|entry| entry.file_name() != ".git")
+                .filter_map(Result::ok)
+                .filter(|entry| entry.file_type().is_file())
+            {
+                let Ok(metadata) = entry.metadata() else {
+                    continue;
+                };
+                stamps.insert(
+                    entry.path().to_path_buf(),
+                    (metadata.modified().ok(), metadata.len()),
+                );
+            }
+        }
+        Ok(Self { stamps })
+    }
+
+    /// The files that were added, removed or changed in `newer`.
+    #[must_use]
+    pub fn changes<'a>(&'a self, newer: &'a Self) -> Vec<&'a Path> {
+        let changed = newer
+            .stamps
+            .iter()
+            .filter(|(path, stamp)| self.stamps.get(*path) != Some(stamp))
+            .map(|(path, _)| path.as_path());
+        let removed = self
+            .stamps
+            .keys()
+            .filter(|path| !newer.stamps.contains_key(*path))
+            .map(PathBuf::as_path);
+        changed.chain(removed).collect()
+    }
+}
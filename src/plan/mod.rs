@@ -0,0 +1,655 @@
+use crate::cache::{self, DEFAULT_CONCURRENCY};
+use crate::{local_storage, network, progress};
+use color_eyre::owo_colors::OwoColorize;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::ffi::{OsStr, OsString};
+use std::path::{Component, Path, PathBuf};
+use std::process::ExitStatus;
+use std::{fmt, fs};
+use url::Url;
+
+mod rollback;
+use rollback::Journal;
+
+/// A typed description of everything a mutating operation is going to do.
+///
+/// Operations in this crate are split into two phases: first a [`Plan`] is
+/// computed without touching anything, then it is [applied](Plan::apply). This
+/// lets the CLI implement `--dry-run` and lets other frontends show the plan
+/// to the user for confirmation before anything is written.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Plan {
+    pub actions: Vec<Action>,
+}
+
+/// A single step of a [`Plan`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Action {
+    /// Create a directory (and all of its parents).
+    CreateDir { path: PathBuf },
+
+    /// Write `contents` to a file [atomically](local_storage::write_atomic),
+    /// replacing it if it exists. With `backup`, the replaced version is kept
+    /// as a [backup](local_storage::bak_path).
+    WriteFile {
+        path: PathBuf,
+        #[serde(skip)]
+        contents: Vec<u8>,
+        #[serde(skip_serializing_if = "std::ops::Not::not")]
+        backup: bool,
+    },
+
+    /// Copy a file, replacing the destination if it exists.
+    CopyFile { from: PathBuf, to: PathBuf },
+
+    /// Move (rename) a file, replacing the destination if it exists.
+    MoveFile { from: PathBuf, to: PathBuf },
+
+    /// Remove a file.
+    RemoveFile { path: PathBuf },
+
+    /// Point a symbolic link at `target`, replacing the link if it exists.
+    ///
+    /// On platforms without symlinks, `target` is copied instead.
+    Symlink { target: PathBuf, link: PathBuf },
+
+    /// Download a file from `url` into `path`.
+    ///
+    /// If the file's SHA512 hash is known, it goes through the [download
+    /// cache](crate::cache) and is verified.
+    Download {
+        url: Url,
+        path: PathBuf,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sha512: Option<String>,
+    },
+
+    /// Initialize a Git repository in the current directory.
+    GitInit,
+
+    /// Create a new Git branch and switch to it.
+    GitBranch { name: String },
+
+    /// Stage `paths` (or all changes, if empty) and create a Git commit.
+    ///
+    /// With `paths`, only they are committed, so other changes that happen to
+    /// be staged stay out of it (and staged). Paths that neither exist nor are
+    /// tracked, and ignored ones, are skipped. Having nothing to commit isn't
+    /// an error, no commit is made then.
+    ///
+    /// The commit is made as `author` if it's set, and with Git's configured
+    /// identity otherwise.
+    GitCommit {
+        message: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        paths: Vec<PathBuf>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        author: Option<GitSignature>,
+    },
+
+    /// Create an annotated Git tag on the current commit, tagged by `author`
+    /// like [`GitCommit`](Self::GitCommit).
+    GitTag {
+        name: String,
+        message: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        author: Option<GitSignature>,
+    },
+}
+
+/// A name and an email to create Git commits with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GitSignature {
+    pub name: String,
+    pub email: String,
+}
+
+impl GitSignature {
+    /// The identity Git is configured with (`user.name` and `user.email`), if
+    /// it has a complete one.
+    #[must_use]
+    pub fn from_git_config() -> Option<Self> {
+        let get = |key: &str| {
+            let output = std::process::Command::new("git")
+                .args(["config", "--get", key])
+                .output()
+                .ok()?;
+            let value = String::from_utf8(output.stdout).ok()?.trim().to_string();
+            (output.status.success() && !value.is_empty()).then_some(value)
+        };
+        Some(Self {
+            name: get("user.name")?,
+            email: get("user.email")?,
+        })
+    }
+}
+
+impl Plan {
+    /// Create an empty [`Plan`].
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            actions: Vec::new(),
+        }
+    }
+
+    /// Append an [`Action`] to this [`Plan`].
+    pub fn push(&mut self, action: Action) -> &mut Self {
+        self.actions.push(action);
+        self
+    }
+
+    /// Append all [`Action`]s of another [`Plan`] to this one.
+    pub fn extend(&mut self, other: Self) -> &mut Self {
+        self.actions.extend(other.actions);
+        self
+    }
+
+    /// The paths this [`Plan`] creates, changes or removes, each once, like
+    /// for committing them.
+    #[must_use]
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.actions
+            .iter()
+            .flat_map(Action::touched_paths)
+            .unique()
+            .map(Path::to_path_buf)
+            .collect()
+    }
+
+    /// Whether this [`Plan`] does nothing at all.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    /// Execute all the [`Action`]s of this [`Plan`], in order.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error as soon as any of the actions fails,
+    /// or right away in [read-only mode](local_storage::set_read_only).
+    ///
+    /// The plan is applied as a whole or not at all: if an action fails, the
+    /// files changed by the ones before it are restored. Git actions can't be
+    /// undone, so only the changes made since the last of them are.
+    pub fn apply(&self) -> Result<(), Error> {
+        local_storage::ensure_writable()?;
+        // Cached downloads are fetched in parallel up front, so applying them
+        // below is just a copy. Failures will resurface when it's their turn.
+        let requests = self
+            .actions
+            .iter()
+            .filter_map(|action| match action {
+                Action::Download {
+                    url,
+                    sha512: Some(sha512),
+                    ..
+                } => Some(cache::Request {
+                    url: url.clone(),
+                    sha512: sha512.clone(),
+                }),
+                _ => None,
+            })
+            .unique()
+            .collect::<Vec<_>>();
+        if !requests.is_empty() {
+            let _ = cache::fetch_all(&requests, DEFAULT_CONCURRENCY);
+        }
+
+        let mut journal = Journal::default();
+        for action in &self.actions {
+            tracing::debug!(%action, "Applying");
+            if let Err(error) = journal.record(action).and_then(|()| action.apply()) {
+                tracing::warn!(%action, "Failed, rolling back the changes made so far");
+                journal.roll_back();
+                return Err(error);
+            }
+            if action.is_git() {
+                journal.settle();
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Action {
+    /// Shorthand for an [`Action::WriteFile`].
+    pub fn write_file<P, C>(path: P, contents: C) -> Self
+    where
+        P: Into<PathBuf>,
+        C: Into<Vec<u8>>,
+    {
+        Self::WriteFile {
+            path: path.into(),
+            contents: contents.into(),
+            backup: false,
+        }
+    }
+
+    /// Shorthand for an [`Action::WriteFile`] of a
+    /// [`PersistedEntity`](local_storage::PersistedEntity)'s file, keeping the
+    /// previous version as a backup like
+    /// [`PersistedEntity::write`](local_storage::PersistedEntity::write) does.
+    pub fn write_entity<P, C>(path: P, contents: C) -> Self
+    where
+        P: Into<PathBuf>,
+        C: Into<Vec<u8>>,
+    {
+        Self::WriteFile {
+            path: path.into(),
+            contents: contents.into(),
+            backup: true,
+        }
+    }
+
+    /// The paths this action creates, changes or removes.
+    fn touched_paths(&self) -> Vec<&Path> {
+        match self {
+            Self::CreateDir { path }
+            | Self::WriteFile { path, .. }
+            | Self::RemoveFile { path }
+            | Self::Download { path, .. } => vec![path],
+            Self::CopyFile { to, .. } => vec![to],
+            Self::MoveFile { from, to } => vec![from, to],
+            Self::Symlink { link, .. } => vec![link],
+            Self::GitInit
+            | Self::GitBranch { .. }
+            | Self::GitCommit { .. }
+            | Self::GitTag { .. } => vec![],
+        }
+    }
+
+    /// Whether this action writes over the file at its path in place, rather
+    /// than replacing it with a new one.
+    const fn rewrites_in_place(&self) -> bool {
+        matches!(self, Self::CopyFile { .. } | Self::Download { .. })
+    }
+
+    const fn is_git(&self) -> bool {
+        matches!(
+            self,
+            Self::GitInit | Self::GitBranch { .. } | Self::GitCommit { .. } | Self::GitTag { .. }
+        )
+    }
+
+    fn apply(&self) -> Result<(), Error> {
+        match self {
+            Self::CreateDir { path } => fs::create_dir_all(path).map_err(|source| io(source, path)),
+            Self::WriteFile {
+                path,
+                contents,
+                backup,
+            } => {
+                create_parent(path)?;
+                local_storage::write_atomic(path, contents, *backup)
+                    .map_err(|source| io(source, path))?;
+                progress::emit(&progress::Event::FileSaved { path });
+                Ok(())
+            }
+            Self::CopyFile { from, to } => {
+                create_parent(to)?;
+                fs::copy(from, to)
+                    .map(|_| ())
+                    .map_err(|source| io(source, from))
+            }
+            Self::MoveFile { from, to } => {
+                create_parent(to)?;
+                fs::rename(from, to).map_err(|source| io(source, from))
+            }
+            Self::RemoveFile { path } => fs::remove_file(path).map_err(|source| io(source, path)),
+            Self::Symlink { target, link } => {
+                if link.symlink_metadata().is_ok() {
+                    fs::remove_file(link).map_err(|source| io(source, link))?;
+                }
+                create_parent(link)?;
+                symlink(target, link).map_err(|source| io(source, link))
+            }
+            Self::Download {
+                url,
+                path,
+                sha512: Some(sha512),
+            } => {
+                let request = cache::Request {
+                    url: url.clone(),
+                    sha512: sha512.clone(),
+                };
+                let cached = cache::fetch(&reqwest::blocking::Client::new(), &request)?;
+                create_parent(path)?;
+                fs::copy(&cached, path)
+                    .map(|_| ())
+                    .map_err(|source| io(source, &cached))
+            }
+            Self::Download {
+                url,
+                path,
+                sha512: None,
+            } => {
+                let download = |url: &Url| -> Result<_, network::Error> {
+                    let request = reqwest::blocking::Client::new().get(url.clone());
+                    Ok(network::send(request)?.error_for_status()?.bytes()?)
+                };
+                let bytes = download(url).map_err(|source| Error::Download {
+                    url: url.clone(),
+                    source,
+                })?;
+                create_parent(path)?;
+                fs::write(path, bytes).map_err(|source| io(source, path))
+            }
+            Self::GitInit => git(&["init", "--quiet"]),
+            Self::GitBranch { name } => git(&["switch", "--create", name]),
+            Self::GitCommit {
+                message,
+                paths,
+                author,
+            } => git_commit(message, paths, author.as_ref()),
+            Self::GitTag {
+                name,
+                message,
+                author,
+            } => {
+                let mut tag = identity_args(author.as_ref());
+                tag.extend(["tag", "--annotate", name, "--message", message].map(String::from));
+                git(&tag)
+            }
+        }
+    }
+}
+
+/// Errors that may arise while [applying](Plan::apply) a [`Plan`].
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    LocalStorage(#[from] local_storage::Error),
+
+    #[error("Failed to download {url}")]
+    Download { url: Url, source: network::Error },
+
+    #[error(transparent)]
+    Cache(#[from] cache::Error),
+
+    #[error("`git` exited unsuccessfully ({status})")]
+    Git { status: ExitStatus },
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CreateDir { path } => {
+                write!(f, "{} {}", "mkdir".green().bold(), path.display())
+            }
+            Self::WriteFile { path, contents, .. } => write!(
+                f,
+                "{} {} ({} bytes)",
+                "write".yellow().bold(),
+                path.display(),
+                contents.len()
+            ),
+            Self::CopyFile { from, to } => write!(
+                f,
+                "{} {} -> {}",
+                "copy".blue().bold(),
+                from.display(),
+                to.display()
+            ),
+            Self::MoveFile { from, to } => write!(
+                f,
+                "{} {} -> {}",
+                "move".blue().bold(),
+                from.display(),
+                to.display()
+            ),
+            Self::RemoveFile { path } => {
+                write!(f, "{} {}", "remove".red().bold(), path.display())
+            }
+            Self::Symlink { target, link } => write!(
+                f,
+                "{} {} -> {}",
+                "link".blue().bold(),
+                link.display(),
+                target.display()
+            ),
+            Self::Download { url, path, .. } => {
+                write!(
+                    f,
+                    "{} {url} -> {}",
+                    "download".cyan().bold(),
+                    path.display()
+                )
+            }
+            Self::GitInit => write!(f, "{}", "init".magenta().bold()),
+            Self::GitBranch { name } => write!(f, "{} {name}", "branch".magenta().bold()),
+            Self::GitCommit { message, .. } => {
+                write!(f, "{} {message:?}", "commit".magenta().bold())
+            }
+            Self::GitTag { name, .. } => write!(f, "{} {name}", "tag".magenta().bold()),
+        }
+    }
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for action in &self.actions {
+            writeln!(f, "{action}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Arguments making `git` act as `author`, if one is set.
+fn identity_args(author: Option<&GitSignature>) -> Vec<String> {
+    match author {
+        Some(GitSignature { name, email }) => vec![
+            "-c".to_string(),
+            format!("user.name={name}"),
+            "-c".to_string(),
+            format!("user.email={email}"),
+        ],
+        None => vec![],
+    }
+}
+
+fn git<S: AsRef<OsStr>>(args: &[S]) -> Result<(), Error> {
+    let status = std::process::Command::new("git")
+        .args(args)
+        .status()
+        .map_err(|source| local_storage::Error::Io {
+            source,
+            faulty_path: None,
+        })?;
+    match status.success() {
+        true => Ok(()),
+        false => Err(Error::Git { status }),
+    }
+}
+
+/// Stage `paths` (or all changes) and commit them, unless there's nothing to
+/// commit, see [`Action::GitCommit`].
+fn git_commit(
+    message: &str,
+    paths: &[PathBuf],
+    author: Option<&GitSignature>,
+) -> Result<(), Error> {
+    let requested = paths;
+    let paths = committable(Path::new("."), paths)?;
+    if paths.is_empty() && !requested.is_empty() {
+        tracing::debug!(message, "Nothing to commit");
+        return Ok(());
+    }
+    let mut add: Vec<&OsStr> = vec!["add".as_ref(), "--all".as_ref(), "--".as_ref()];
+    add.extend(paths.iter().map(|path| path.as_os_str()));
+    git(&add)?;
+    let mut diff: Vec<&OsStr> = vec![
+        "diff".as_ref(),
+        "--cached".as_ref(),
+        "--quiet".as_ref(),
+        "--".as_ref(),
+    ];
+    diff.extend(paths.iter().map(|path| path.as_os_str()));
+    if !has_changes(&diff)? {
+        tracing::debug!(message, "Nothing to commit");
+        return Ok(());
+    }
+    let mut commit: Vec<OsString> = identity_args(author)
+        .into_iter()
+        .map(OsString::from)
+        .collect();
+    commit.extend(["commit", "--message", message].map(OsString::from));
+    if !paths.is_empty() {
+        commit.extend(["--only", "--"].map(OsString::from));
+        commit.extend(paths.iter().map(|path| path.as_os_str().to_owned()));
+    }
+    git(&commit)
+}
+
+/// The ones of `paths` (relative to the repository's `root`) Git can commit:
+/// those that exist or are tracked, and aren't ignored or inside an ignored
+/// directory.
+fn committable(root: &Path, paths: &[PathBuf]) -> Result<Vec<PathBuf>, Error> {
+    if paths.is_empty() {
+        return Ok(vec![]);
+    }
+    let paths = paths
+        .iter()
+        .map(|path| {
+            path.components()
+                .filter(|component| *component != Component::CurDir)
+                .collect::<PathBuf>()
+        })
+        .collect_vec();
+    let tracked = git_paths(root, &["ls-files", "-z", "--"], &paths)?;
+    // NOTE: With `--directory`, ignored directories are listed instead of
+    // the files in them, so paths are matched by their ancestors too.
+    let ignored = git_paths(
+        root,
+        &[
+            "ls-files",
+            "-z",
+            "--others",
+            "--ignored",
+            "--exclude-standard",
+            "--directory",
+            "--",
+        ],
+        &paths,
+    )?;
+    Ok(paths
+        .into_iter()
+        .filter(|path| root.join(path).symlink_metadata().is_ok() || tracked.contains(path))
+        .filter(|path| !ignored.iter().any(|ignored| path.starts_with(ignored)))
+        .collect())
+}
+
+/// The paths a `git ls-files -z` command run in `root` lists for `paths`.
+fn git_paths(root: &Path, args: &[&str], paths: &[PathBuf]) -> Result<HashSet<PathBuf>, Error> {
+    let output = std::process::Command::new("git")
+        .current_dir(root)
+        .args(args)
+        .args(paths)
+        .stderr(std::process::Stdio::inherit())
+        .output()
+        .map_err(|source| local_storage::Error::Io {
+            source,
+            faulty_path: None,
+        })?;
+    match output.status.success() {
+        true => Ok(output
+            .stdout
+            .split(|byte| *byte == 0)
+            .filter(|path| !path.is_empty())
+            .map(|path| PathBuf::from(String::from_utf8_lossy(path).into_owned()))
+            .collect()),
+        false => Err(Error::Git {
+            status: output.status,
+        }),
+    }
+}
+
+/// Run a `git` command that exits with `1` when there are changes, like
+/// `git diff --quiet`.
+fn has_changes<S: AsRef<OsStr>>(args: &[S]) -> Result<bool, Error> {
+    let status = std::process::Command::new("git")
+        .args(args)
+        .status()
+        .map_err(|source| local_storage::Error::Io {
+            source,
+            faulty_path: None,
+        })?;
+    match status.code() {
+        Some(0) => Ok(false),
+        Some(1) => Ok(true),
+        _ => Err(Error::Git { status }),
+    }
+}
+
+// NOTE: `target` is relative to the link's parent directory, like `ln -s`.
+#[cfg(unix)]
+fn symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(not(unix))]
+fn symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    let target = link.parent().unwrap_or(Path::new("")).join(target);
+    fs::copy(target, link).map(|_| ())
+}
+
+fn create_parent(path: &Path) -> Result<(), Error> {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => {
+            fs::create_dir_all(parent).map_err(|source| io(source, parent))
+        }
+        _ => Ok(()),
+    }
+}
+
+// NOTE: A shorthand for wrapping an I/O error with the path that caused it.
+fn io(source: std::io::Error, path: &Path) -> Error {
+    local_storage::Error::Io {
+        source,
+        faulty_path: Some(path.to_path_buf()),
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{committable, Action, Plan};
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    #[test]
+    fn contents_are_not_serialized() {
+        let mut plan = Plan::new();
+        plan.push(Action::write_file("pack.yml", "name: test\n"));
+        let yaml = serde_yml::to_string(&plan).unwrap();
+        assert!(yaml.contains("write_file"));
+        assert!(!yaml.contains("name: test"));
+    }
+
+    #[test]
+    fn files_in_ignored_directories_are_not_committable() {
+        let root = std::env::temp_dir().join(format!("invar-committable-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("ignored")).unwrap();
+        fs::write(root.join(".gitignore"), "ignored/\n").unwrap();
+        fs::write(root.join("ignored/a.txt"), "a").unwrap();
+        fs::write(root.join("b.txt"), "b").unwrap();
+        let init = Command::new("git")
+            .arg("init")
+            .arg("-q")
+            .arg(&root)
+            .status();
+        assert!(init.unwrap().success());
+
+        let paths = ["ignored/a.txt", "./b.txt", "missing.txt"].map(PathBuf::from);
+        let committable = committable(&root, &paths).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(committable, [PathBuf::from("b.txt")]);
+    }
+}
@@ -0,0 +1,178 @@
+use super::{io, symlink, Action, Error};
+use crate::local_storage;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Directory (relative to the pack root) large files are stashed in while a
+/// [`Plan`](super::Plan) that replaces them is applied.
+pub const JOURNAL_DIR: &str = ".invar/journal";
+
+/// Files up to this size (in bytes) are snapshotted in memory, larger ones are
+/// stashed in the [`JOURNAL_DIR`].
+const IN_MEMORY_LIMIT: u64 = 1024 * 1024;
+
+static JOURNALS: AtomicUsize = AtomicUsize::new(0);
+
+/// What a path looked like before a [`Plan`](super::Plan) touched it.
+#[derive(Debug)]
+enum Snapshot {
+    Missing(PathBuf),
+    File(PathBuf, Vec<u8>),
+    /// A large file, stashed at the second path.
+    Stashed(PathBuf, PathBuf),
+    Link(PathBuf, PathBuf),
+}
+
+/// The paths changed by a [`Plan`](super::Plan) being applied, as they were
+/// before, to put them back if one of its actions fails.
+#[derive(Debug, Default)]
+pub struct Journal {
+    snapshots: Vec<Snapshot>,
+    recorded: HashSet<PathBuf>,
+    /// Where this journal stashes large files, once it had to.
+    stash_dir: Option<PathBuf>,
+}
+
+impl Journal {
+    /// Take a snapshot of the paths `action` is about to change, unless they
+    /// already have one. Directories that exist are left alone.
+    pub fn record(&mut self, action: &Action) -> Result<(), Error> {
+        for path in action.touched_paths() {
+            if !self.recorded.insert(path.to_path_buf()) {
+                continue;
+            }
+            let snapshot = match path.symlink_metadata() {
+                Err(_) => Snapshot::Missing(path.to_path_buf()),
+                Ok(metadata) if metadata.is_symlink() => Snapshot::Link(
+                    path.to_path_buf(),
+                    fs::read_link(path).map_err(|source| io(source, path))?,
+                ),
+                Ok(metadata) if metadata.len() > IN_MEMORY_LIMIT && metadata.is_file() => {
+                    let stashed = self
+                        .stash(path, action.rewrites_in_place())
+                        .map_err(|source| io(source, path))?;
+                    Snapshot::Stashed(path.to_path_buf(), stashed)
+                }
+                Ok(metadata) if metadata.is_file() => Snapshot::File(
+                    path.to_path_buf(),
+                    fs::read(path).map_err(|source| io(source, path))?,
+                ),
+                Ok(_) => continue,
+            };
+            self.snapshots.push(snapshot);
+        }
+        Ok(())
+    }
+
+    /// Keep the file at `path` in this journal's stash directory, by hard
+    /// linking it there, or by moving it if the action is going to `take` it
+    /// over and rewrite it in place. It's copied if neither works.
+    fn stash(&mut self, path: &Path, take: bool) -> std::io::Result<PathBuf> {
+        let dir = match &self.stash_dir {
+            Some(dir) => dir.clone(),
+            None => {
+                let id = JOURNALS.fetch_add(1, Ordering::Relaxed);
+                let dir = Path::new(JOURNAL_DIR).join(format!("{}-{id}", std::process::id()));
+                fs::create_dir_all(&dir)?;
+                let gitignore = Path::new(JOURNAL_DIR).join(".gitignore");
+                if !gitignore.exists() {
+                    fs::write(gitignore, "*\n")?;
+                }
+                self.stash_dir.insert(dir).clone()
+            }
+        };
+        let stashed = dir.join(self.snapshots.len().to_string());
+        let moved = match take {
+            true => fs::rename(path, &stashed),
+            false => fs::hard_link(path, &stashed),
+        };
+        if moved.is_err() {
+            fs::copy(path, &stashed)?;
+        }
+        Ok(stashed)
+    }
+
+    /// Forget the snapshots taken so far, making the changes permanent, like
+    /// once they're committed to Git.
+    pub fn settle(&mut self) {
+        self.snapshots.clear();
+        self.recorded.clear();
+        self.clean_up();
+    }
+
+    fn clean_up(&mut self) {
+        if let Some(dir) = self.stash_dir.take() {
+            if let Err(error) = fs::remove_dir_all(&dir) {
+                tracing::warn!(path = %dir.display(), %error, "Failed to remove stashed files");
+            }
+        }
+    }
+
+    /// Put every recorded path back the way it was, newest change first.
+    /// Paths that can't be restored are only logged, so that the rest still
+    /// are.
+    pub fn roll_back(mut self) {
+        for snapshot in std::mem::take(&mut self.snapshots).into_iter().rev() {
+            let (path, result) = match &snapshot {
+                Snapshot::Missing(path) => (path, remove(path)),
+                Snapshot::File(path, contents) => {
+                    (path, local_storage::write_atomic(path, contents, false))
+                }
+                Snapshot::Stashed(path, stashed) => (
+                    path,
+                    fs::rename(stashed, path).or_else(|_| fs::copy(stashed, path).map(|_| ())),
+                ),
+                Snapshot::Link(path, target) => {
+                    (path, remove(path).and_then(|()| symlink(target, path)))
+                }
+            };
+            if let Err(error) = result {
+                tracing::warn!(path = %path.display(), %error, "Failed to roll back");
+            }
+        }
+    }
+}
+
+impl Drop for Journal {
+    fn drop(&mut self) {
+        self.clean_up();
+    }
+}
+
+fn remove(path: &Path) -> std::io::Result<()> {
+    let result = match path.symlink_metadata() {
+        Ok(metadata) if metadata.is_dir() => fs::remove_dir(path),
+        _ => fs::remove_file(path),
+    };
+    match result {
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Plan;
+    use super::*;
+
+    #[test]
+    fn failed_plans_are_rolled_back() {
+        let root = std::env::temp_dir().join(format!("invar-rollback-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let (kept, added) = (root.join("pack.yml"), root.join("sodium.invar.yaml"));
+        fs::write(&kept, "old").unwrap();
+
+        let mut plan = Plan::new();
+        plan.push(Action::write_file(&kept, "new"))
+            .push(Action::write_file(&added, "slug: sodium"))
+            .push(Action::RemoveFile {
+                path: root.join("missing"),
+            });
+        assert!(plan.apply().is_err());
+        assert_eq!(fs::read_to_string(&kept).unwrap(), "old");
+        assert!(!added.exists());
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,151 @@
+use crate::component::{Component, DependencyKind};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{PoisonError, RwLock};
+use url::Url;
+
+/// Something that happened during an operation, for rendering progress.
+#[derive(Debug, Clone, Copy)]
+pub enum Event<'a> {
+    /// A [`Task`] over `total` items started, like checking all components.
+    TaskStarted { task: &'a str, total: usize },
+    /// The `current`th item of a [`Task`] is being (or was just) handled.
+    TaskAdvanced {
+        task: &'a str,
+        current: usize,
+        total: usize,
+        item: Option<&'a str>,
+    },
+    /// A [`Task`] is done, successfully or not.
+    TaskFinished { task: &'a str },
+    /// A file started downloading.
+    FetchStarted { url: &'a Url },
+    /// A version of the component was picked, like when adding or updating it.
+    VersionResolved { component: &'a Component },
+    /// A resolved component declares a dependency.
+    DependencyFound {
+        slug: &'a str,
+        project_id: &'a str,
+        kind: DependencyKind,
+    },
+    /// A file was written.
+    FileSaved { path: &'a Path },
+}
+
+type Handler = Box<dyn Fn(&Event<'_>) + Send + Sync>;
+
+static HANDLER: RwLock<Option<Handler>> = RwLock::new(None);
+
+/// Have `handler` called with every [`Event`] for the rest of the process,
+/// replacing the previous one. Nothing is reported until a handler is set.
+///
+/// The handler may be called from several threads at once.
+pub fn set_handler(handler: impl Fn(&Event<'_>) + Send + Sync + 'static) {
+    *HANDLER.write().unwrap_or_else(PoisonError::into_inner) = Some(Box::new(handler));
+}
+
+/// Report `event` to the handler, if one is [set](set_handler).
+pub fn emit(event: &Event<'_>) {
+    if let Some(handler) = HANDLER
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .as_ref()
+    {
+        handler(event);
+    }
+}
+
+/// Report a [resolved](Event::VersionResolved) `component` along with its
+/// dependencies.
+pub(crate) fn resolved(component: &Component) {
+    emit(&Event::VersionResolved { component });
+    for dependency in &component.dependencies {
+        emit(&Event::DependencyFound {
+            slug: &component.slug,
+            project_id: &dependency.project_id,
+            kind: dependency.kind,
+        });
+    }
+}
+
+/// A named operation over a known number of items, reported as it goes.
+///
+/// [`Event::TaskFinished`] is emitted when the task is dropped.
+#[derive(Debug)]
+pub struct Task {
+    name: String,
+    total: usize,
+    current: AtomicUsize,
+}
+
+impl Task {
+    #[must_use]
+    pub fn start(name: impl Into<String>, total: usize) -> Self {
+        let task = Self {
+            name: name.into(),
+            total,
+            current: AtomicUsize::new(0),
+        };
+        emit(&Event::TaskStarted {
+            task: &task.name,
+            total,
+        });
+        task
+    }
+
+    /// Move on to the next item, named `item` if it has a name.
+    pub fn advance(&self, item: Option<&str>) {
+        let current = self.current.fetch_add(1, Ordering::Relaxed) + 1;
+        emit(&Event::TaskAdvanced {
+            task: &self.name,
+            current,
+            total: self.total,
+            item,
+        });
+    }
+}
+
+impl Drop for Task {
+    fn drop(&mut self) {
+        emit(&Event::TaskFinished { task: &self.name });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn tasks_report_their_progress() {
+        let seen = Arc::new(Mutex::new(vec![]));
+        let sink = Arc::clone(&seen);
+        set_handler(move |event| match *event {
+            Event::TaskStarted {
+                task: "Testing",
+                total,
+            } => {
+                sink.lock().unwrap().push(format!("started {total}"));
+            }
+            Event::TaskAdvanced {
+                task: "Testing",
+                current,
+                item,
+                ..
+            } => sink
+                .lock()
+                .unwrap()
+                .push(format!("{current} {}", item.unwrap_or("-"))),
+            Event::TaskFinished { task: "Testing" } => sink.lock().unwrap().push("done".into()),
+            _ => {}
+        });
+        let task = Task::start("Testing", 2);
+        task.advance(Some("sodium"));
+        task.advance(None);
+        drop(task);
+        assert_eq!(
+            *seen.lock().unwrap(),
+            ["started 2", "1 sodium", "2 -", "done"]
+        );
+    }
+}
@@ -1,26 +1,142 @@
 use crate::local_storage::PersistedEntity;
 use crate::server::docker_compose;
-use crate::{local_storage, BackupMode, Pack};
-use chrono::{DateTime, Local};
+use crate::{local_storage, BackupFormat, BackupMode, BackupRetention, Pack};
+use chrono::{DateTime, Datelike, Local};
 use color_eyre::owo_colors::OwoColorize;
-use itertools::Itertools;
+use itertools::{Either, Itertools};
+use semver::Version;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
-use std::{fmt, fs};
+use std::{fmt, fs, io};
+use walkdir::WalkDir;
 
 pub const BACKUP_FOLDER: &str = ".backups";
 pub const BACKUP_FOLDER_SEP: char = '_';
 pub const GC_DELAY: Duration = Duration::from_secs(3);
+/// Appended to the names of [archived](BackupFormat::Archive) backups.
+pub const ARCHIVE_EXTENSION: &str = ".tar.zst";
+/// The zstd compression level of [archived](BackupFormat::Archive) backups.
+pub const ARCHIVE_COMPRESSION_LEVEL: i32 = 3;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Backup {
-    /// Path to the directory where the backup lives.
+    /// Path to the directory (or archive) where the backup lives.
     pub path: PathBuf,
+    /// How the backup is stored. Backups from before formats were recorded
+    /// are plain copies.
+    #[serde(default, skip_serializing_if = "BackupFormat::is_plain")]
+    pub format: BackupFormat,
     /// The sequential number of the backup.
     pub seq_number: usize,
     /// When this backup was created.
     pub created_at: DateTime<Local>,
+    /// What triggered this backup (`pre-start`, `ondemand`, etc.).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// Version of the [`Pack`] the backed up server was running.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pack_version: Option<Version>,
+    /// SHA256 checksum of the backup's contents, see [`checksum_dir`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+    /// Total size of the backup's files, in bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+}
+
+/// An index of all the backups in [`BACKUP_FOLDER`], written on every backup
+/// creation and garbage collection.
+///
+/// Backups that are missing from the journal (for example, ones created by
+/// older versions of Invar) are still picked up by [`get_all_backups`], which
+/// then falls back to parsing their directory names.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Journal {
+    pub entries: Vec<JournalEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// Name of the backup's directory (or archive) inside [`BACKUP_FOLDER`].
+    pub dir_name: String,
+    #[serde(default, skip_serializing_if = "BackupFormat::is_plain")]
+    pub format: BackupFormat,
+    pub seq_number: usize,
+    pub created_at: DateTime<Local>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    pub pack_version: Version,
+    pub checksum: String,
+    pub size: u64,
+}
+
+impl PersistedEntity for Journal {
+    const FILE_PATH: &'static str = ".backups/index.yml";
+}
+
+impl Journal {
+    /// Read the journal, or start a new one if there is none yet.
+    ///
+    /// # Errors
+    ///
+    /// See [`local_storage::Error`] for possible error causes.
+    pub fn read_or_default() -> local_storage::Result<Self> {
+        match Path::new(Self::FILE_PATH).exists() {
+            true => Self::read(),
+            false => Ok(Self::default()),
+        }
+    }
+
+    fn find(&self, dir_name: &str) -> Option<&JournalEntry> {
+        self.entries.iter().find(|entry| entry.dir_name == dir_name)
+    }
+}
+
+impl Backup {
+    fn from_journal(entry: &JournalEntry, path: PathBuf) -> Self {
+        Self {
+            path,
+            format: entry.format,
+            seq_number: entry.seq_number,
+            created_at: entry.created_at,
+            tag: entry.tag.clone(),
+            pack_version: Some(entry.pack_version.clone()),
+            checksum: Some(entry.checksum.clone()),
+            size: Some(entry.size),
+        }
+    }
+
+    // NOTE: Only used for backups that aren't in the [`Journal`]. The pack name
+    // may contain the separator as well, so this is a best-effort guess.
+    fn from_dir_name(dir_name: &str, path: PathBuf) -> Self {
+        let (dir_name, format) = match dir_name.strip_suffix(ARCHIVE_EXTENSION) {
+            Some(stem) => (stem, BackupFormat::Archive),
+            None => (dir_name, BackupFormat::Plain),
+        };
+        let seq_number = dir_name
+            .split(BACKUP_FOLDER_SEP)
+            .next()
+            .and_then(|marker| marker.parse::<usize>().ok())
+            .unwrap_or(usize::MAX);
+        let created_at = dir_name
+            .rsplit(BACKUP_FOLDER_SEP)
+            .next()
+            .and_then(|marker| marker.parse::<DateTime<Local>>().ok())
+            .unwrap_or(DateTime::UNIX_EPOCH.into());
+        Self {
+            path,
+            format,
+            seq_number,
+            created_at,
+            tag: None,
+            pack_version: None,
+            checksum: None,
+            size: None,
+        }
+    }
 }
 
 /// Load all backups found in `local_storage`.
@@ -29,6 +145,7 @@ pub struct Backup {
 ///
 /// See [`local_storage::Error`] for possible error causes.
 pub fn get_all_backups() -> local_storage::Result<Vec<Backup>> {
+    let journal = Journal::read_or_default()?;
     let backups = fs::read_dir(BACKUP_FOLDER)
         .map_err(|source| local_storage::Error::Io {
             source,
@@ -40,46 +157,29 @@ pub fn get_all_backups() -> local_storage::Result<Vec<Backup>> {
             faulty_path: Some(PathBuf::from(BACKUP_FOLDER)),
         })?
         .into_iter()
+        // NOTE: Hidden directories are used while restoring, see [`Restore`].
+        .filter(|folder| !folder.file_name().to_string_lossy().starts_with('.'))
         .filter(|folder| {
+            let is_archive = folder
+                .file_name()
+                .to_string_lossy()
+                .ends_with(ARCHIVE_EXTENSION);
             folder
                 .metadata()
-                .ok()
-                .map(|md| md.is_dir())
-                .is_some_and(|id_dir| id_dir)
+                .is_ok_and(|md| md.is_dir() || (is_archive && md.is_file()))
         })
         .map(|folder| -> Result<_, local_storage::Error> {
-            let seq_number = folder
-                .path()
-                .file_name()
-                .and_then(|folder_name| {
-                    folder_name
-                        .to_string_lossy()
-                        .split(BACKUP_FOLDER_SEP)
-                        .next()
-                        .and_then(|marker| marker.parse::<usize>().ok())
-                })
-                .unwrap_or(usize::MAX);
-            let created_at = folder
+            let dir_name = folder.file_name().to_string_lossy().into_owned();
+            let path = folder
                 .path()
-                .file_name()
-                .and_then(|folder_name| {
-                    folder_name
-                        .to_string_lossy()
-                        .split(BACKUP_FOLDER_SEP)
-                        .last()
-                        .and_then(|marker| marker.parse::<DateTime<Local>>().ok())
-                })
-                .unwrap_or(DateTime::UNIX_EPOCH.into());
-            Ok(Backup {
-                seq_number,
-                created_at,
-                path: folder
-                    .path()
-                    .canonicalize()
-                    .map_err(|source| local_storage::Error::Io {
-                        source,
-                        faulty_path: Some(folder.path()),
-                    })?,
+                .canonicalize()
+                .map_err(|source| local_storage::Error::Io {
+                    source,
+                    faulty_path: Some(folder.path()),
+                })?;
+            Ok(match journal.find(&dir_name) {
+                Some(entry) => Backup::from_journal(entry, path),
+                None => Backup::from_dir_name(&dir_name, path),
             })
         })
         .collect::<Result<Vec<_>, _>>()?
@@ -90,15 +190,29 @@ pub fn get_all_backups() -> local_storage::Result<Vec<Backup>> {
     Ok(backups)
 }
 
-/// Create a new [`Backup`].
+/// Create a new [`Backup`] in the format set in the pack's
+/// [`Settings::backup_format`](crate::Settings::backup_format).
 ///
 /// # Errors
 ///
 /// See [`local_storage::Error`] for possible error causes.
 pub fn create_new(tag: Option<&str>) -> Result<Backup, self::Error> {
-    let pack_name = Pack::read()?.name;
-    let seq_number = get_all_backups()?
-        .into_iter()
+    let format = Pack::read()?.settings.backup_format;
+    create_new_as(tag, format)
+}
+
+/// Create a new [`Backup`] in this `format`.
+///
+/// # Errors
+///
+/// See [`local_storage::Error`] for possible error causes.
+pub fn create_new_as(tag: Option<&str>, format: BackupFormat) -> Result<Backup, self::Error> {
+    local_storage::ensure_writable()?;
+    let pack = Pack::read()?;
+    let pack_name = &pack.name;
+    let all_backups = get_all_backups()?;
+    let seq_number = all_backups
+        .iter()
         .map(|backup| backup.seq_number)
         .sorted_unstable()
         .last()
@@ -106,28 +220,263 @@ pub fn create_new(tag: Option<&str>) -> Result<Backup, self::Error> {
         + 1;
     let created_at = Local::now();
     let target_dir = format!(
-        "{BACKUP_FOLDER}/{seq_number}_{pack_name}{tag}_{created_at}",
+        "{BACKUP_FOLDER}/{seq_number}_{pack_name}{tag}_{created_at}{extension}",
         tag = tag.map(|tag| format!("({tag})")).unwrap_or_default(),
+        extension = match format {
+            BackupFormat::Archive => ARCHIVE_EXTENSION,
+            BackupFormat::Plain | BackupFormat::Incremental => "",
+        },
     );
-    match copy_dir::copy_dir(docker_compose::DATA_VOLUME_PATH, &target_dir) {
-        Err(source) => {
-            return Err(local_storage::Error::Io {
-                source,
-                faulty_path: Some(target_dir.into()),
-            }
-            .into())
+    let data = Path::new(docker_compose::DATA_VOLUME_PATH);
+    let target = Path::new(&target_dir);
+    match format {
+        BackupFormat::Plain => copy_plain(data, target)?,
+        BackupFormat::Archive => archive(data, target)?,
+        BackupFormat::Incremental => {
+            // NOTE: Archives can't be linked into, so look for the newest
+            // backup that's a directory.
+            let previous = all_backups
+                .iter()
+                .find(|backup| backup.format != BackupFormat::Archive && backup.path.is_dir());
+            copy_incremental(data, target, previous)?;
         }
-        Ok(error_list) if !error_list.is_empty() => return Err(Error::CopyDir { error_list }),
-        Ok(_) => {}
-    };
+    }
 
-    Ok(Backup {
-        path: target_dir.into(),
+    let (checksum, size) = checksum_dir(target)?;
+    let entry = JournalEntry {
+        dir_name: target_dir
+            .trim_start_matches(&format!("{BACKUP_FOLDER}/"))
+            .to_string(),
+        format,
         seq_number,
         created_at,
+        tag: tag.map(String::from),
+        pack_version: pack.version,
+        checksum,
+        size,
+    };
+    let mut journal = Journal::read_or_default()?;
+    journal.entries.push(entry.clone());
+    journal.write()?;
+
+    Ok(Backup::from_journal(&entry, target_dir.into()))
+}
+
+/// Back up the server's data before an `operation` that can damage it,
+/// tagging the backup `pre-<operation>`.
+///
+/// Nothing is backed up if there is no server data yet, or the pack's
+/// [`Settings::skip_safety_backups`](crate::Settings::skip_safety_backups)
+/// is set.
+///
+/// # Errors
+///
+/// See [`local_storage::Error`] for possible error causes.
+pub fn create_safety(operation: &str) -> Result<Option<Backup>, self::Error> {
+    let data = Path::new(docker_compose::DATA_VOLUME_PATH);
+    if !data.is_dir() || Pack::read()?.settings.skip_safety_backups {
+        return Ok(None);
+    }
+    create_new(Some(&format!("pre-{operation}"))).map(Some)
+}
+
+fn copy_plain(from: &Path, to: &Path) -> Result<(), self::Error> {
+    match copy_dir::copy_dir(from, to) {
+        Err(source) => Err(local_storage::Error::Io {
+            source,
+            faulty_path: Some(to.to_path_buf()),
+        }
+        .into()),
+        Ok(error_list) if !error_list.is_empty() => Err(Error::CopyDir { error_list }),
+        Ok(_) => Ok(()),
+    }
+}
+
+fn archive(from: &Path, to: &Path) -> Result<(), self::Error> {
+    let io_error = |source| local_storage::Error::Io {
+        source,
+        faulty_path: Some(to.to_path_buf()),
+    };
+    let file = fs::File::create(to).map_err(io_error)?;
+    let encoder = zstd::Encoder::new(file, ARCHIVE_COMPRESSION_LEVEL).map_err(io_error)?;
+    let mut builder = tar::Builder::new(encoder);
+    builder.append_dir_all(".", from).map_err(io_error)?;
+    builder
+        .into_inner()
+        .and_then(zstd::Encoder::finish)
+        .map_err(io_error)?;
+    Ok(())
+}
+
+/// Copy `from` into `to` like [`copy_plain`], but hard-link the files that
+/// haven't changed since the `previous` backup was created from it.
+///
+/// A file counts as unchanged if it wasn't modified after the previous backup
+/// was created and has the same size as its copy in there.
+fn copy_incremental(from: &Path, to: &Path, previous: Option<&Backup>) -> Result<(), self::Error> {
+    for entry in WalkDir::new(from) {
+        let entry = entry.map_err(local_storage::Error::from)?;
+        let relative_path = entry.path().strip_prefix(from).unwrap_or(entry.path());
+        let target = to.join(relative_path);
+        let io_error = |source| local_storage::Error::Io {
+            source,
+            faulty_path: Some(target.clone()),
+        };
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target).map_err(io_error)?;
+            continue;
+        }
+        let metadata = entry.metadata().map_err(local_storage::Error::from)?;
+        let unchanged = previous.and_then(|previous| {
+            let copy = previous.path.join(relative_path);
+            let modified = DateTime::<Local>::from(metadata.modified().ok()?);
+            let same_size = fs::metadata(&copy).ok()?.len() == metadata.len();
+            (modified < previous.created_at && same_size).then_some(copy)
+        });
+        match unchanged {
+            Some(copy) => fs::hard_link(copy, &target).map_err(io_error)?,
+            None => fs::copy(entry.path(), &target)
+                .map(drop)
+                .map_err(io_error)?,
+        }
+    }
+    Ok(())
+}
+
+/// Put the contents of the `backup` into the `target` directory.
+fn unpack(backup: &Backup, target: &Path) -> Result<(), self::Error> {
+    if backup.format != BackupFormat::Archive {
+        return copy_plain(&backup.path, target);
+    }
+    let io_error = |source| local_storage::Error::Io {
+        source,
+        faulty_path: Some(backup.path.clone()),
+    };
+    let file = fs::File::open(&backup.path).map_err(io_error)?;
+    let decoder = zstd::Decoder::new(file).map_err(io_error)?;
+    tar::Archive::new(decoder)
+        .unpack(target)
+        .map_err(io_error)?;
+    Ok(())
+}
+
+/// Remove the `backup` from disk, whatever its format.
+fn remove(backup: &Backup) -> local_storage::Result<()> {
+    match backup.format {
+        BackupFormat::Archive => fs::remove_file(&backup.path),
+        BackupFormat::Plain | BackupFormat::Incremental => fs::remove_dir_all(&backup.path),
+    }
+    .map_err(|source| local_storage::Error::Io {
+        source,
+        faulty_path: Some(backup.path.clone()),
     })
 }
 
+/// A [`Backup`] copied next to the server's data, ready to take its place.
+///
+/// The copy is made up front, so that backups created and garbage-collected
+/// while stopping the server can't take the restored one away.
+#[derive(Debug)]
+pub struct Restore {
+    pub backup: Backup,
+    staging_dir: PathBuf,
+}
+
+impl Restore {
+    /// Where the backup is copied to until it's [applied](Self::apply).
+    pub const STAGING_DIR: &'static str = ".backups/.restoring";
+
+    /// Copy the backup with this `seq_number` aside, checking it against its
+    /// recorded checksum first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there's no such backup, it's corrupted, or copying
+    /// it fails.
+    pub fn prepare(seq_number: usize) -> Result<Self, self::Error> {
+        let backup = get_all_backups()?
+            .into_iter()
+            .find(|backup| backup.seq_number == seq_number)
+            .ok_or(Error::NotFound(seq_number))?;
+        if let Some(expected) = &backup.checksum {
+            let (actual, _) = checksum_dir(&backup.path)?;
+            if actual != *expected {
+                return Err(Error::Corrupted(seq_number));
+            }
+        }
+
+        let staging_dir = PathBuf::from(Self::STAGING_DIR);
+        let io_error = |source| local_storage::Error::Io {
+            source,
+            faulty_path: Some(staging_dir.clone()),
+        };
+        if staging_dir.exists() {
+            fs::remove_dir_all(&staging_dir).map_err(io_error)?;
+        }
+        unpack(&backup, &staging_dir)?;
+        Ok(Self {
+            backup,
+            staging_dir,
+        })
+    }
+
+    /// Replace the server's data with the backup. The server must be stopped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if moving the directories around fails.
+    pub fn apply(self) -> Result<Backup, self::Error> {
+        let data = Path::new(docker_compose::DATA_VOLUME_PATH);
+        let replaced = PathBuf::from(format!("{BACKUP_FOLDER}/.replaced"));
+        let io_error = |path: &Path| {
+            let path = path.to_path_buf();
+            move |source| local_storage::Error::Io {
+                source,
+                faulty_path: Some(path),
+            }
+        };
+        if replaced.exists() {
+            fs::remove_dir_all(&replaced).map_err(io_error(&replaced))?;
+        }
+        if data.exists() {
+            fs::rename(data, &replaced).map_err(io_error(data))?;
+        }
+        fs::rename(&self.staging_dir, data).map_err(io_error(&self.staging_dir))?;
+        if replaced.exists() {
+            fs::remove_dir_all(&replaced).map_err(io_error(&replaced))?;
+        }
+        Ok(self.backup)
+    }
+}
+
+/// Compute the SHA256 checksum and the total size of a directory's files.
+///
+/// Files are hashed in a stable order, together with their paths relative to
+/// `dir`, so renaming or moving a file changes the checksum too.
+///
+/// # Errors
+///
+/// See [`local_storage::Error`] for possible error causes.
+pub fn checksum_dir(dir: &Path) -> local_storage::Result<(String, u64)> {
+    let mut hasher = Sha256::new();
+    let mut size = 0;
+    for entry in WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let io_error = |source| local_storage::Error::Io {
+            source,
+            faulty_path: Some(entry.path().to_path_buf()),
+        };
+        let relative_path = entry.path().strip_prefix(dir).unwrap_or(entry.path());
+        hasher.update(relative_path.to_string_lossy().as_bytes());
+        let mut file = fs::File::open(entry.path()).map_err(io_error)?;
+        size += io::copy(&mut file, &mut hasher).map_err(io_error)?;
+    }
+    Ok((format!("{:x}", hasher.finalize()), size))
+}
+
 /// Remove backups that are old enough to be removed.
 ///
 /// # Errors
@@ -143,30 +492,58 @@ pub fn gc() -> Result<GcResult, self::Error> {
         }
     }
 
-    let mut all_backups = get_all_backups()?;
-    match Pack::read()?.settings.backup_mode {
-        BackupMode::StartStop { min_depth } => {
-            let remaining = all_backups.drain(..min_depth).collect_vec();
-            let removed = all_backups;
-            for old_backup in removed.iter().rev() {
-                fs::remove_dir_all(&old_backup.path).map_err(|source| {
-                    local_storage::Error::Io {
-                        source,
-                        faulty_path: Some(old_backup.path.clone()),
-                    }
-                })?;
-            }
-            return Ok(GcResult { removed, remaining });
-        }
+    let all_backups = get_all_backups()?;
+    let kept = match Pack::read()?.settings.backup_mode {
+        BackupMode::StartStop { min_depth } => (0..all_backups.len())
+            .map(|index| index < min_depth)
+            .collect_vec(),
+        BackupMode::Scheduled { retention, .. } => retained(&all_backups, retention),
         BackupMode::Manual => {
             tracing::warn!("The pack's setting dictate manual backups. Doing nothing");
+            return Ok(GcResult {
+                removed: vec![],
+                remaining: all_backups,
+            });
         }
+    };
+    let (remaining, removed): (Vec<_>, Vec<_>) =
+        all_backups
+            .into_iter()
+            .zip(kept)
+            .partition_map(|(backup, kept)| match kept {
+                true => Either::Left(backup),
+                false => Either::Right(backup),
+            });
+    for old_backup in removed.iter().rev() {
+        remove(old_backup)?;
     }
 
-    Ok(GcResult {
-        removed: vec![],
-        remaining: all_backups,
-    })
+    let mut journal = Journal::read_or_default()?;
+    journal.entries.retain(|entry| {
+        !removed
+            .iter()
+            .any(|backup| backup.path.ends_with(&entry.dir_name))
+    });
+    journal.write()?;
+
+    Ok(GcResult { removed, remaining })
+}
+
+/// Which of the `backups`, newest first, the `retention` policy keeps: the
+/// newest one of each of the last days and weeks that have backups.
+fn retained(backups: &[Backup], retention: BackupRetention) -> Vec<bool> {
+    let mut days = HashSet::new();
+    let mut weeks = HashSet::new();
+    backups
+        .iter()
+        .map(|backup| {
+            let day = backup.created_at.date_naive();
+            let week = day.iso_week();
+            let new_day = days.len() < retention.daily && days.insert(day);
+            let new_week = weeks.len() < retention.weekly && weeks.insert(week);
+            new_day || new_week
+        })
+        .collect()
 }
 
 #[derive(Serialize, Clone, Debug)]
@@ -181,14 +558,23 @@ pub enum Error {
     LocalStorage(#[from] local_storage::Error),
     #[error("Errors occured while creating backup")]
     CopyDir { error_list: Vec<std::io::Error> },
+    #[error("There is no backup #{0}")]
+    NotFound(usize),
+    #[error("Backup #{0} doesn't match its checksum")]
+    Corrupted(usize),
 }
 
 impl fmt::Display for Backup {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "Backup {seq_number}, created at {created_at}, path: {path:?}",
+            "Backup {seq_number}{tag}, created at {created_at}, path: {path:?}",
             seq_number = format!("#{}", self.seq_number).bold().yellow(),
+            tag = self
+                .tag
+                .as_ref()
+                .map(|tag| format!(" ({tag})"))
+                .unwrap_or_default(),
             created_at = self
                 .created_at
                 .format("%d/%m/%Y %H:%M:%S")
@@ -198,3 +584,46 @@ impl fmt::Display for Backup {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn retention_keeps_the_newest_of_each_day_and_week() {
+        // NOTE: Newest first, two backups a day from Monday, Jan 1st, 2024.
+        let backups = (0..28)
+            .rev()
+            .map(|half_days| Backup {
+                path: PathBuf::new(),
+                format: BackupFormat::Plain,
+                seq_number: half_days,
+                created_at: Local
+                    .with_ymd_and_hms(2024, 1, 1, 0, 0, 0)
+                    .unwrap()
+                    .checked_add_signed(chrono::Duration::hours(
+                        12 * i64::try_from(half_days).unwrap(),
+                    ))
+                    .unwrap(),
+                tag: None,
+                pack_version: None,
+                checksum: None,
+                size: None,
+            })
+            .collect_vec();
+        let retention = BackupRetention {
+            daily: 3,
+            weekly: 2,
+        };
+        let kept = retained(&backups, retention)
+            .into_iter()
+            .zip(&backups)
+            .filter(|(kept, _)| *kept)
+            .map(|(_, backup)| backup.seq_number)
+            .collect_vec();
+        // Sunday, Saturday and Friday of the second week, then the first
+        // week's Sunday.
+        assert_eq!(kept, [27, 25, 23, 13]);
+    }
+}
@@ -0,0 +1,436 @@
+use super::docker_compose::{DATA_VOLUME_PATH, DEFAULT_ICON_URL};
+use super::rcon::{self, DEFAULT_RCON_PORT};
+use super::status::{self, ServerStatus};
+use super::{backup, read_properties, Server, DEFAULT_MINECRAFT_PORT};
+use crate::instance::{minecraft_id, minecraft_server_url, Instance, Loader, FABRIC_META_URL};
+use crate::local_storage::{self, PersistedEntity};
+use crate::pack::ProfileError;
+use crate::plan::{self, Action, Plan};
+use crate::{network, Pack};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use url::Url;
+
+/// File (relative to [`DATA_VOLUME_PATH`]) holding the ID of the running
+/// server process, followed by its start time where the system tells it.
+pub const PID_FILE: &str = "server.pid";
+
+/// File (relative to [`DATA_VOLUME_PATH`]) the server's output goes to.
+pub const CONSOLE_LOG: &str = "logs/console.log";
+
+/// Environment variable with the Java executable to run the server with,
+/// `java` from the `PATH` if it's not set.
+pub const JAVA_VAR: &str = "INVAR_JAVA";
+
+/// How long to wait for the server to save the world and exit.
+pub const STOP_TIMEOUT: Duration = Duration::from_secs(90);
+
+pub const QUILT_INSTALLER_URL: &str =
+    "https://quiltmc.org/api/v1/download-latest-installer/java-universal";
+pub const FORGE_MAVEN_URL: &str = "https://maven.minecraftforge.net/net/minecraftforge/forge";
+pub const NEOFORGE_MAVEN_URL: &str = "https://maven.neoforged.net/releases/net/neoforged/neoforge";
+
+const INSTALLER_JAR: &str = "installer.jar";
+
+/// A server run as a bare Java process in [`DATA_VOLUME_PATH`], for hosts
+/// without Docker.
+///
+/// Setting up installs the pack and the loader's server, and keeps the command
+/// that launches it. While the server runs, its process ID is kept in
+/// [`PID_FILE`] and its output goes to [`CONSOLE_LOG`]. Like with Docker, the
+/// pack is installed again on every start, so updates reach the server.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BareServer {
+    /// The command that launches the server, run in [`DATA_VOLUME_PATH`].
+    pub command: Vec<String>,
+}
+
+impl PersistedEntity for BareServer {
+    const FILE_PATH: &'static str = ".invar/server.yml";
+}
+
+#[derive(Deserialize)]
+struct FabricInstaller {
+    version: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SetupError {
+    #[error("A bare server is already set up for this pack")]
+    AlreadySetUp,
+    #[error("The {0} loader has no server to install")]
+    UnsupportedLoader(Loader),
+    #[error("Failed to look up the server to install")]
+    Lookup(#[from] network::Error),
+    #[error("Failed to run {program}")]
+    Run {
+        program: String,
+        #[source]
+        source: io::Error,
+    },
+    #[error("Failed to generate a password for RCON")]
+    RconPassword(#[source] io::Error),
+    #[error(transparent)]
+    Profile(#[from] ProfileError),
+    #[error("Failed to ask for the server's settings")]
+    Prompt(#[from] inquire::InquireError),
+    #[error("Invalid download URL")]
+    InvalidUrl(#[from] url::ParseError),
+    #[error("The server installer failed ({0})")]
+    Installer(ExitStatus),
+    #[error(transparent)]
+    Other(#[from] local_storage::Error),
+    #[error(transparent)]
+    Plan(#[from] plan::Error),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum StartStopError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("The server's launch command is empty")]
+    NoCommand,
+    #[error("The server didn't stop within {0:?}")]
+    StopTimeout(Duration),
+    #[error("Failed to backup server")]
+    BackupError(#[from] backup::Error),
+    #[error(transparent)]
+    Other(#[from] local_storage::Error),
+    #[error(transparent)]
+    Plan(#[from] plan::Error),
+}
+
+impl Server for BareServer {
+    type SetupError = self::SetupError;
+    type StartStopError = self::StartStopError;
+    type StatusError = status::Error;
+
+    fn setup(profile: Option<&str>) -> Result<Self, Self::SetupError> {
+        if Path::new(Self::FILE_PATH).exists() {
+            tracing::warn!(
+                "A bare server is already set up. Delete {:?} before re-setup",
+                Self::FILE_PATH
+            );
+            return Err(SetupError::AlreadySetUp);
+        }
+        let mut pack = Pack::read()?;
+        if pack.settings.server.complete(&pack.name)? {
+            pack.write()?;
+        }
+        let pack = pack.with_profile(profile)?;
+        let settings = &pack.settings.server;
+        let data = Path::new(DATA_VOLUME_PATH);
+        pack.plan_install_server(data)?.apply()?;
+        let command = install_loader(&pack.instance, settings.memory_limit_gb, data)?;
+
+        let server = Self { command };
+        let mut plan = Plan::new();
+        plan.push(Action::write_file(data.join("eula.txt"), "eula=true\n"));
+        // NOTE: The server fills in the rest of the properties on first start.
+        let properties = data.join("server.properties");
+        if !properties.exists() {
+            let password = rcon::generate_password().map_err(SetupError::RconPassword)?;
+            let motd = settings.motd.as_deref().unwrap_or(&pack.name);
+            plan.push(Action::write_file(
+                properties,
+                [
+                    format!("motd={motd}"),
+                    format!("server-port={}", settings.port()),
+                    format!("max-players={}", settings.max_players),
+                    format!("gamemode={}", settings.gamemode),
+                    format!("difficulty={}", settings.difficulty),
+                    format!("view-distance={}", settings.view_distance),
+                    format!("online-mode={}", settings.online_mode),
+                    format!("allow-flight={}", settings.allow_flight),
+                    "enable-rcon=true".to_string(),
+                    format!("rcon.port={DEFAULT_RCON_PORT}"),
+                    format!("rcon.password={password}"),
+                ]
+                .join("\n")
+                    + "\n",
+            ));
+        }
+        plan.push(Action::Download {
+            url: match &settings.icon {
+                Some(icon) => icon.clone(),
+                None => DEFAULT_ICON_URL.parse()?,
+            },
+            path: data.join("server-icon.png"),
+            sha512: None,
+        });
+        if !pack.settings.player_commands().is_empty() {
+            tracing::info!("Once the server runs, apply the pack's player lists with `invar server sync-players`");
+        }
+        plan.push(Action::write_entity(
+            Self::FILE_PATH,
+            serde_yml::to_string(&server).map_err(local_storage::Error::from)?,
+        ));
+        plan.apply()?;
+        Ok(server)
+    }
+
+    fn start(&self) -> Result<(), Self::StartStopError> {
+        if let Some(pid) = running_pid()? {
+            tracing::info!(pid, "The server is already running");
+            return Ok(());
+        }
+        let _new_backup = backup::create_new(Some("pre-start"))?;
+        let _gc_result = backup::gc()?;
+        let data = Path::new(DATA_VOLUME_PATH);
+        Pack::read()?.plan_install_server(data)?.apply()?;
+
+        let (program, args) = self
+            .command
+            .split_first()
+            .ok_or(StartStopError::NoCommand)?;
+        let log_path = data.join(CONSOLE_LOG);
+        if let Some(parent) = log_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&log_path)?;
+        let mut command = Command::new(program);
+        command
+            .args(args)
+            .current_dir(data)
+            .stdin(Stdio::null())
+            .stdout(log.try_clone()?)
+            .stderr(log);
+        // NOTE: A process group of its own keeps the server out of the way of
+        // signals sent to the terminal, like Ctrl-C.
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+        let child = command.spawn()?;
+        let pid_file = match start_time(child.id()) {
+            Some(started) => format!("{} {started}", child.id()),
+            None => child.id().to_string(),
+        };
+        fs::write(data.join(PID_FILE), pid_file)?;
+        tracing::info!(pid = child.id(), log = ?log_path, "Started the server");
+        Ok(())
+    }
+
+    fn stop(&self) -> Result<(), Self::StartStopError> {
+        let Some(pid) = running_pid()? else {
+            tracing::info!("The server isn't running");
+            return Ok(());
+        };
+        // NOTE: The server saves the world before exiting on SIGTERM.
+        kill(pid, "-TERM")?;
+        let started = Instant::now();
+        while is_alive(pid)? {
+            if started.elapsed() > STOP_TIMEOUT {
+                return Err(StartStopError::StopTimeout(STOP_TIMEOUT));
+            }
+            thread::sleep(Duration::from_millis(500));
+        }
+        fs::remove_file(Path::new(DATA_VOLUME_PATH).join(PID_FILE))?;
+        let _new_backup = backup::create_new(Some("post-stop"))?;
+        let _gc_result = backup::gc()?;
+        Ok(())
+    }
+
+    fn logs(&self, follow: bool) -> Result<(), Self::StartStopError> {
+        let mut log = File::open(Path::new(DATA_VOLUME_PATH).join(CONSOLE_LOG))?;
+        let mut stdout = io::stdout();
+        loop {
+            io::copy(&mut log, &mut stdout)?;
+            if !follow {
+                return Ok(());
+            }
+            stdout.flush()?;
+            thread::sleep(Duration::from_millis(500));
+        }
+    }
+
+    fn status(&self) -> Result<ServerStatus, Self::StatusError> {
+        let pid = running_pid()?;
+        Ok(ServerStatus {
+            pid,
+            ..ServerStatus::probe(pid.is_some(), server_port()?)
+        })
+    }
+}
+
+/// Download the server of the instance's loader into `data`, running its
+/// installer if it has one, and return the command that launches it.
+///
+/// Forge and NeoForge servers are launched with the argument files their
+/// installers write, which Forge only does since Minecraft 1.17.
+fn install_loader(
+    instance: &Instance,
+    memory_limit_gb: u8,
+    data: &Path,
+) -> Result<Vec<String>, SetupError> {
+    let java = std::env::var(JAVA_VAR).unwrap_or_else(|_| "java".to_string());
+    let memory = format!("-Xmx{memory_limit_gb}G");
+    let minecraft = minecraft_id(&instance.minecraft_version);
+    let loader = &instance.loader_version;
+    let launch = |args: &[&str]| {
+        [java.clone(), memory.clone()]
+            .into_iter()
+            .chain(args.iter().map(ToString::to_string))
+            .collect()
+    };
+    let command = match instance.loader {
+        Loader::Minecraft => {
+            let url = minecraft_server_url(&instance.minecraft_version)?
+                .ok_or(SetupError::UnsupportedLoader(instance.loader))?;
+            download(url, &data.join("server.jar"))?;
+            launch(&["-jar", "server.jar", "nogui"])
+        }
+        Loader::Fabric => {
+            let installers: Vec<FabricInstaller> =
+                network::get_json(format!("{FABRIC_META_URL}/versions/installer"))?;
+            let installer = installers
+                .first()
+                .ok_or(SetupError::UnsupportedLoader(instance.loader))?;
+            let url = format!(
+                "{FABRIC_META_URL}/versions/loader/{minecraft}/{loader}/{}/server/jar",
+                installer.version
+            );
+            download(url.parse()?, &data.join("fabric-server-launch.jar"))?;
+            launch(&["-jar", "fabric-server-launch.jar", "nogui"])
+        }
+        Loader::Quilt => {
+            download(QUILT_INSTALLER_URL.parse()?, &data.join(INSTALLER_JAR))?;
+            let loader_arg = loader.to_string();
+            run_installer(
+                &java,
+                data,
+                &[
+                    "install",
+                    "server",
+                    &minecraft,
+                    &loader_arg,
+                    "--download-server",
+                    "--install-dir=.",
+                ],
+            )?;
+            launch(&["-jar", "quilt-server-launch.jar", "nogui"])
+        }
+        Loader::Forge => {
+            let version = format!("{minecraft}-{loader}");
+            let url = format!("{FORGE_MAVEN_URL}/{version}/forge-{version}-installer.jar");
+            download(url.parse()?, &data.join(INSTALLER_JAR))?;
+            run_installer(&java, data, &["--installServer"])?;
+            let args = format!("@libraries/net/minecraftforge/forge/{version}/unix_args.txt");
+            launch(&[&args, "nogui"])
+        }
+        Loader::Neoforge => {
+            let url = format!("{NEOFORGE_MAVEN_URL}/{loader}/neoforge-{loader}-installer.jar");
+            download(url.parse()?, &data.join(INSTALLER_JAR))?;
+            run_installer(&java, data, &["--installServer"])?;
+            let args = format!("@libraries/net/neoforged/neoforge/{loader}/unix_args.txt");
+            launch(&[&args, "nogui"])
+        }
+        Loader::Other => return Err(SetupError::UnsupportedLoader(instance.loader)),
+    };
+    Ok(command)
+}
+
+fn download(url: Url, path: &Path) -> Result<(), SetupError> {
+    let mut plan = Plan::new();
+    plan.push(Action::Download {
+        url,
+        path: path.to_path_buf(),
+        sha512: None,
+    });
+    Ok(plan.apply()?)
+}
+
+/// Run the [`INSTALLER_JAR`] in `data` with `args`, removing it afterwards.
+fn run_installer(java: &str, data: &Path, args: &[&str]) -> Result<(), SetupError> {
+    let status = Command::new(java)
+        .args(["-jar", INSTALLER_JAR])
+        .args(args)
+        .current_dir(data)
+        .status()
+        .map_err(|source| SetupError::Run {
+            program: java.to_string(),
+            source,
+        })?;
+    if !status.success() {
+        return Err(SetupError::Installer(status));
+    }
+    let installer = data.join(INSTALLER_JAR);
+    if let Err(error) = fs::remove_file(&installer) {
+        tracing::warn!(%error, path = ?installer, "Failed to remove the installer");
+    }
+    Ok(())
+}
+
+/// The ID of the running server process, if there is one. A [`PID_FILE`]
+/// left behind by a server that is gone is removed, even if another process
+/// got its ID since, which is told apart by its start time.
+fn running_pid() -> io::Result<Option<u32>> {
+    let path = PathBuf::from(DATA_VOLUME_PATH).join(PID_FILE);
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(error),
+    };
+    let mut fields = contents.split_whitespace();
+    let pid = fields.next().and_then(|pid| pid.parse::<u32>().ok());
+    let started = fields
+        .next()
+        .and_then(|started| started.parse::<u64>().ok());
+    match pid {
+        Some(pid) if started.is_some() && start_time(pid) != started => {
+            tracing::debug!(
+                pid,
+                "The server's process ID belongs to another process now"
+            );
+            fs::remove_file(&path)?;
+            Ok(None)
+        }
+        Some(pid) if is_alive(pid)? => Ok(Some(pid)),
+        _ => {
+            fs::remove_file(&path)?;
+            Ok(None)
+        }
+    }
+}
+
+/// The port the server listens on, as configured in its `server.properties`.
+fn server_port() -> io::Result<u16> {
+    Ok(read_properties(Path::new(DATA_VOLUME_PATH))?
+        .get("server-port")
+        .and_then(|port| port.parse().ok())
+        .unwrap_or(DEFAULT_MINECRAFT_PORT))
+}
+
+/// When the process with this ID started, in clock ticks since boot, if the
+/// system tells (only Linux does, through `/proc`).
+fn start_time(pid: u32) -> Option<u64> {
+    let stat = fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    // NOTE: The process name is in parentheses and may contain anything, so
+    // the fields are counted from after it, starting at the 3rd.
+    let (_, fields) = stat.rsplit_once(')')?;
+    fields.split_whitespace().nth(22 - 3)?.parse().ok()
+}
+
+fn is_alive(pid: u32) -> io::Result<bool> {
+    Ok(Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(Stdio::null())
+        .status()?
+        .success())
+}
+
+fn kill(pid: u32, signal: &str) -> io::Result<()> {
+    let status = Command::new("kill")
+        .args([signal, &pid.to_string()])
+        .status()?;
+    match status.success() {
+        true => Ok(()),
+        false => Err(io::Error::other(format!("`kill {signal} {pid}` failed"))),
+    }
+}
@@ -0,0 +1,182 @@
+use super::docker_compose::DockerCompose;
+use crate::index::OverrideSide;
+use crate::local_storage::{self, PersistedEntity};
+use crate::pack::{Pack, EXPORT_DIR};
+use crate::plan::{Action, Plan};
+use clap::ValueEnum;
+use std::fs;
+use std::io::{self, Cursor, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+/// The file the server's secrets are kept in, as `KEY=value` lines next to
+/// the compose file. It should never be committed as it is.
+pub const SECRETS_FILE: &str = ".env";
+
+/// Tools the secrets of a server bundle can be encrypted with. Both take
+/// [age](https://age-encryption.org) public keys as recipients.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, strum::Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum SecretsEncryption {
+    /// Encrypt the whole file with `age`, into an armored `.env.age`.
+    Age,
+    /// Encrypt the values with `sops`, into a `.env.sops` whose keys stay
+    /// readable.
+    Sops,
+}
+
+impl SecretsEncryption {
+    /// The name of the encrypted secrets file in the bundle.
+    #[must_use]
+    pub const fn file_name(self) -> &'static str {
+        match self {
+            Self::Age => ".env.age",
+            Self::Sops => ".env.sops",
+        }
+    }
+
+    /// Encrypt the secrets file at `path` for `recipients` by running the
+    /// tool.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there are no recipients, or the
+    /// tool can't be run or fails.
+    pub fn encrypt(self, path: &Path, recipients: &[String]) -> Result<Vec<u8>, BundleError> {
+        if recipients.is_empty() {
+            return Err(BundleError::NoRecipients);
+        }
+        let mut command = Command::new(self.to_string());
+        match self {
+            Self::Age => {
+                command.args(["--encrypt", "--armor"]);
+                for recipient in recipients {
+                    command.args(["--recipient", recipient]);
+                }
+            }
+            Self::Sops => {
+                command.args(["--encrypt", "--age", &recipients.join(",")]);
+                command.args(["--input-type", "dotenv", "--output-type", "dotenv"]);
+            }
+        }
+        let output = command
+            .arg(path)
+            .output()
+            .map_err(|source| BundleError::Spawn { tool: self, source })?;
+        if !output.status.success() {
+            return Err(BundleError::Encryption {
+                tool: self,
+                stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            });
+        }
+        Ok(output.stdout)
+    }
+}
+
+/// Compute the [`Plan`] for writing a deployment bundle of the pack's server
+/// into [`EXPORT_DIR`].
+///
+/// The bundle is a `.zip` of the compose file, the server-side `.mrpack` and
+/// the [`SECRETS_FILE`]. The `.mrpack` is at [`Pack::side_export_path`], where
+/// the compose file mounts it from, but as a regular file rather than the
+/// `-latest` symlink it is in the pack.
+///
+/// The secrets are only bundled encrypted with `encryption`, so the bundle can
+/// be checked into an infrastructure repository. Without it, they are left
+/// out and a warning is logged.
+///
+/// Returns the path of the bundle along with the [`Plan`].
+///
+/// # Errors
+///
+/// This function will return an error if the server isn't set up, the pack
+/// can't be exported, or the secrets can't be encrypted.
+pub fn plan_bundle(
+    pack: &Pack,
+    encryption: Option<SecretsEncryption>,
+    recipients: &[String],
+) -> Result<(PathBuf, Plan), BundleError> {
+    let compose_path = <DockerCompose as PersistedEntity>::FILE_PATH;
+    let compose = match fs::read(compose_path) {
+        Ok(compose) => compose,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Err(BundleError::NotSetUp),
+        Err(source) => {
+            return Err(local_storage::Error::Io {
+                source,
+                faulty_path: Some(PathBuf::from(compose_path)),
+            }
+            .into())
+        }
+    };
+    let mut files = vec![
+        (PathBuf::from(compose_path), compose),
+        // NOTE: The archive is built afresh rather than read through the
+        // symlink, which a `.zip` couldn't hold anyway.
+        (
+            pack.side_export_path(OverrideSide::Server),
+            pack.mrpack_archive(OverrideSide::Server)?,
+        ),
+    ];
+    let secrets = Path::new(SECRETS_FILE);
+    match (secrets.is_file(), encryption) {
+        (false, _) => {}
+        (true, Some(encryption)) => files.push((
+            PathBuf::from(encryption.file_name()),
+            encryption.encrypt(secrets, recipients)?,
+        )),
+        (true, None) => tracing::warn!(
+            "Leaving {SECRETS_FILE} out of the bundle, pass `--encrypt` to include it encrypted"
+        ),
+    }
+
+    let path = Path::new(EXPORT_DIR).join(format!(
+        "{name}-{version}-server-bundle.zip",
+        name = pack.name,
+        version = pack.version
+    ));
+    let mut archive = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    for (relative, contents) in files {
+        archive
+            .start_file_from_path(&relative, options)
+            .map_err(local_storage::Error::from)?;
+        archive
+            .write_all(&contents)
+            .map_err(|source| local_storage::Error::Io {
+                source,
+                faulty_path: Some(relative),
+            })?;
+    }
+    let archive = archive
+        .finish()
+        .map_err(local_storage::Error::from)?
+        .into_inner();
+
+    let mut plan = Plan::new();
+    plan.push(Action::write_file(&path, archive));
+    Ok((path, plan))
+}
+
+/// Errors that may arise when [bundling](plan_bundle) a server.
+#[derive(Debug, thiserror::Error)]
+pub enum BundleError {
+    #[error("No server is set up for this pack")]
+    NotSetUp,
+    #[error("Encrypting the secrets takes at least one recipient")]
+    NoRecipients,
+    #[error("Failed to run `{tool}`")]
+    Spawn {
+        tool: SecretsEncryption,
+        #[source]
+        source: io::Error,
+    },
+    #[error("`{tool}` failed to encrypt the secrets: {stderr}")]
+    Encryption {
+        tool: SecretsEncryption,
+        stderr: String,
+    },
+    #[error(transparent)]
+    LocalStorage(#[from] local_storage::Error),
+}
@@ -1,15 +1,19 @@
-use super::{Difficulty, Gamemode, Server, DEFAULT_MINECRAFT_PORT};
-use crate::instance::Instance;
-use crate::local_storage;
+use super::rcon::{self, DEFAULT_RCON_PORT};
+use super::status::{self, Container, ServerStatus};
+use super::{Server, DEFAULT_MINECRAFT_PORT};
+use crate::index::OverrideSide;
+use crate::instance::{Instance, Loader};
 use crate::local_storage::PersistedEntity;
-use crate::pack::Pack;
+use crate::pack::{Pack, ProfileError, ServerSettings, EXPORT_DIR};
 use crate::server::backup;
+use crate::{local_storage, plan};
 use bon::bon;
 use docker_compose_types::{AdvancedVolumes, Compose, Environment, Service, SingleValue, Volumes};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::{fs, io};
+use url::Url;
 
 pub const DATA_VOLUME_PATH: &str = "server";
 pub const DEFAULT_ICON_URL: &str =
@@ -22,64 +26,128 @@ impl PersistedEntity for DockerCompose {
     const FILE_PATH: &'static str = "docker-compose.yml";
 }
 
+impl DockerCompose {
+    /// Rename the containers named after the pack `old_name` and point the
+    /// volumes at the exports of `new_name`, following a
+    /// [rename](Pack::plan_rename) of the pack.
+    #[must_use]
+    pub fn renamed(mut self, old_name: &str, new_name: &str) -> Self {
+        let old_hostname = format!("{old_name}_server");
+        let old_export = format!("./{EXPORT_DIR}/{old_name}-");
+        for service in self.0.services.0.values_mut().flatten() {
+            for name in [&mut service.hostname, &mut service.container_name]
+                .into_iter()
+                .flatten()
+            {
+                if *name == old_hostname {
+                    *name = format!("{new_name}_server");
+                }
+            }
+            for volume in &mut service.volumes {
+                let Volumes::Advanced(AdvancedVolumes {
+                    source: Some(source),
+                    ..
+                }) = volume
+                else {
+                    continue;
+                };
+                if let Some(rest) = source.strip_prefix(&old_export) {
+                    *source = format!("./{EXPORT_DIR}/{new_name}-{rest}");
+                }
+            }
+        }
+        self
+    }
+}
+
 #[allow(clippy::empty_enum, reason = "Rises from within bon")]
 #[bon]
 impl DockerCompose {
     pub const MODPACK_PATH: &'static str = "/data/modpack.mrpack";
 
+    /// The variable the server image takes the version of `loader` from, if
+    /// it takes one. Fabric and Quilt name it after the loader itself, not
+    /// the whole toolchain.
+    #[must_use]
+    pub const fn loader_version_key(loader: Loader) -> Option<&'static str> {
+        match loader {
+            Loader::Fabric => Some("FABRIC_LOADER_VERSION"),
+            Loader::Quilt => Some("QUILT_LOADER_VERSION"),
+            Loader::Forge => Some("FORGE_VERSION"),
+            Loader::Neoforge => Some("NEOFORGE_VERSION"),
+            Loader::Minecraft | Loader::Other => None,
+        }
+    }
+
     #[builder]
     #[must_use]
     pub fn environment(
         instance: &Instance,
-        operator_username: &str,
-        memlimit_gb: u8,
-        max_players: u16,
-        online_mode: bool,
-        allow_flight: bool,
-        gamemode: &Gamemode,
-        difficulty: &Difficulty,
+        settings: &ServerSettings,
+        /// Console commands to run once the first player connects.
+        player_commands: &[String],
+        motd: &str,
+        rcon_password: &str,
     ) -> Environment {
-        let kv_pairs = [
+        let icon = settings.icon.as_ref().map_or(DEFAULT_ICON_URL, Url::as_str);
+        let mut kv_pairs = vec![
             ("EULA", SingleValue::String("TRUE".into())),
             (
                 "VERSION",
                 SingleValue::String(instance.minecraft_version.to_string()),
             ),
             ("TYPE", SingleValue::String("MODRINTH".into())),
-            (
-                format!("{}_VERSION", instance.loader.to_string().to_uppercase()).as_str(),
-                SingleValue::String(instance.loader_version.to_string()),
-            ),
             (
                 "MODRINTH_MODPACK",
                 SingleValue::String(Self::MODPACK_PATH.into()),
             ),
-            ("MEMORY", SingleValue::String(format!("{memlimit_gb}G"))),
+            (
+                "MEMORY",
+                SingleValue::String(format!("{}G", settings.memory_limit_gb)),
+            ),
             ("USE_AIKAR_FLAGS", SingleValue::Bool(true)),
-            ("ENABLE_AUTOPAUSE", SingleValue::Bool(true)),
-            ("VIEW_DISTANCE", SingleValue::Unsigned(12)),
-            ("MODE", SingleValue::String(gamemode.to_string())),
-            ("DIFFICULTY", SingleValue::String(difficulty.to_string())),
-            ("MAX_PLAYERS", SingleValue::Unsigned(max_players.into())),
-            ("MOTD", SingleValue::String("TODO".into())),
-            ("ICON", SingleValue::String(DEFAULT_ICON_URL.into())),
-            ("ALLOW_FLIGHT", SingleValue::Bool(allow_flight)),
-            ("ONLINE_MODE", SingleValue::Bool(online_mode)),
-            {
-                let rcon_first_connect = indoc::indoc! {"
-                        /whitelist on
-                        /whitelist add username
-                        /op username
-                    "}
-                .replace("username", operator_username);
-                (
-                    "RCON_CMDS_FIRST_CONNECT",
-                    SingleValue::String(rcon_first_connect),
-                )
-            },
-        ]
-        .map(|(key, value)| (key.to_string(), Some(value)));
-        let kv_hashmap = HashMap::from_iter(kv_pairs);
+            ("ENABLE_AUTOPAUSE", SingleValue::Bool(settings.autopause)),
+            (
+                "VIEW_DISTANCE",
+                SingleValue::Unsigned(settings.view_distance.into()),
+            ),
+            ("MODE", SingleValue::String(settings.gamemode.to_string())),
+            (
+                "DIFFICULTY",
+                SingleValue::String(settings.difficulty.to_string()),
+            ),
+            (
+                "MAX_PLAYERS",
+                SingleValue::Unsigned(settings.max_players.into()),
+            ),
+            ("MOTD", SingleValue::String(motd.into())),
+            ("ICON", SingleValue::String(icon.into())),
+            ("ALLOW_FLIGHT", SingleValue::Bool(settings.allow_flight)),
+            ("ONLINE_MODE", SingleValue::Bool(settings.online_mode)),
+            ("RCON_PASSWORD", SingleValue::String(rcon_password.into())),
+        ];
+        if let Some(key) = Self::loader_version_key(instance.loader) {
+            kv_pairs.push((
+                key,
+                SingleValue::String(instance.loader_version.to_string()),
+            ));
+        }
+        if !player_commands.is_empty() {
+            let rcon_first_connect = player_commands
+                .iter()
+                .map(|command| format!("/{command}"))
+                .collect::<Vec<_>>()
+                .join("\n")
+                + "\n";
+            kv_pairs.push((
+                "RCON_CMDS_FIRST_CONNECT",
+                SingleValue::String(rcon_first_connect),
+            ));
+        }
+        let kv_hashmap = kv_pairs
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), Some(value)))
+            .collect();
 
         Environment::KvPair(kv_hashmap)
     }
@@ -89,8 +157,16 @@ impl DockerCompose {
 pub enum SetupError {
     #[error("A local server is already configured for this pack")]
     AlreadySetUp,
+    #[error("Failed to generate a password for RCON")]
+    RconPassword(#[source] io::Error),
+    #[error(transparent)]
+    Profile(#[from] ProfileError),
+    #[error("Failed to ask for the server's settings")]
+    Prompt(#[from] inquire::InquireError),
     #[error(transparent)]
     Other(#[from] local_storage::Error),
+    #[error(transparent)]
+    Plan(#[from] plan::Error),
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -106,9 +182,14 @@ pub enum StartStopError {
 impl Server for DockerCompose {
     type SetupError = self::SetupError;
     type StartStopError = self::StartStopError;
+    type StatusError = status::Error;
 
-    fn setup() -> Result<Self, Self::SetupError> {
-        let pack = Pack::read()?;
+    fn setup(profile: Option<&str>) -> Result<Self, Self::SetupError> {
+        let mut pack = Pack::read()?;
+        if pack.settings.server.complete(&pack.name)? {
+            pack.write()?;
+        }
+        let pack = pack.with_profile(profile)?;
 
         if let Err(error) = fs::create_dir_all(DATA_VOLUME_PATH) {
             match error.kind() {
@@ -134,11 +215,15 @@ impl Server for DockerCompose {
                 volume: None,
                 tmpfs: None,
             }),
-            // A "symlink" to our exported modpack.
+            // A "symlink" to our exported modpack, with only the server-side
+            // components and overrides.
             Volumes::Advanced(AdvancedVolumes {
                 source: Some({
-                    pack.export()?;
-                    format!("./{}.mrpack", pack.name)
+                    pack.plan_export(OverrideSide::Server)?.apply()?;
+                    format!(
+                        "./{}",
+                        pack.side_export_path(OverrideSide::Server).display()
+                    )
                 }),
                 target: Self::MODPACK_PATH.into(),
                 _type: "bind".into(),
@@ -149,21 +234,20 @@ impl Server for DockerCompose {
             }),
         ];
 
-        let ports = docker_compose_types::Ports::Short(vec![format!(
-            "{DEFAULT_MINECRAFT_PORT}:{DEFAULT_MINECRAFT_PORT}"
-        )]);
+        // NOTE: RCON is only reachable from this host, for `invar server exec`.
+        let ports = docker_compose_types::Ports::Short(vec![
+            format!("{}:{DEFAULT_MINECRAFT_PORT}", pack.settings.server.port()),
+            format!("127.0.0.1:{DEFAULT_RCON_PORT}:{DEFAULT_RCON_PORT}"),
+        ]);
 
         let hostname = format!("{}_server", pack.name);
         let image = "itzg/minecraft-server:java17-alpine".to_string();
         let environment = Self::environment()
             .instance(&pack.instance)
-            .operator_username("mxxntype")
-            .memlimit_gb(12)
-            .max_players(4)
-            .online_mode(false)
-            .allow_flight(true)
-            .gamemode(&Gamemode::Survival)
-            .difficulty(&Difficulty::Hard)
+            .settings(&pack.settings.server)
+            .player_commands(&pack.settings.player_commands())
+            .motd(pack.settings.server.motd.as_deref().unwrap_or(&pack.name))
+            .rcon_password(&rcon::generate_password().map_err(SetupError::RconPassword)?)
             .call();
 
         let services = HashMap::from([(
@@ -257,4 +341,53 @@ impl Server for DockerCompose {
             Err(StartStopError::Terminated)
         }
     }
+
+    fn logs(&self, follow: bool) -> Result<(), Self::StartStopError> {
+        let mut command = std::process::Command::new("docker");
+        command.args([
+            "compose",
+            "--file",
+            <Self as PersistedEntity>::FILE_PATH,
+            "logs",
+        ]);
+        if follow {
+            command.arg("--follow");
+        }
+        let status = command.status()?;
+        if let Some(status_code) = status.code() {
+            match status_code {
+                0 => Ok(()),
+                error => Err(io::Error::from_raw_os_error(error).into()),
+            }
+        } else {
+            Err(StartStopError::Terminated)
+        }
+    }
+
+    fn status(&self) -> Result<ServerStatus, Self::StatusError> {
+        let output = std::process::Command::new("docker")
+            .args([
+                "compose",
+                "--file",
+                <Self as PersistedEntity>::FILE_PATH,
+                "ps",
+                "--all",
+                "--format",
+                "json",
+            ])
+            .output()?;
+        if !output.status.success() {
+            return Err(status::Error::Compose(output.status));
+        }
+        let containers = Container::parse_all(&String::from_utf8_lossy(&output.stdout))?;
+        let running = containers.iter().any(Container::is_running);
+        let port = containers
+            .iter()
+            .find_map(|container| container.published_port(DEFAULT_MINECRAFT_PORT))
+            .unwrap_or(DEFAULT_MINECRAFT_PORT);
+        Ok(ServerStatus {
+            containers,
+            ..ServerStatus::probe(running, port)
+        })
+    }
 }
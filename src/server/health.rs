@@ -0,0 +1,117 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+/// How long a probe may take to send its request before it's dropped.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A check a supervisor (like systemd or a container runtime) runs against a
+/// long-running mode, like the backup daemon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Probe {
+    /// `/healthz`: whether the process works at all, like whether the pack
+    /// can still be read.
+    Health,
+    /// `/readyz`: whether the process is doing its job, like whether it has
+    /// a backup scheduled and the last one succeeded.
+    Readiness,
+}
+
+impl Probe {
+    fn from_path(path: &str) -> Option<Self> {
+        match path {
+            "/healthz" => Some(Self::Health),
+            "/readyz" => Some(Self::Readiness),
+            _ => None,
+        }
+    }
+}
+
+/// Answer [`Probe`]s over HTTP on `address`, in the background, for the rest
+/// of the process.
+///
+/// Each probe is answered by `check`: with `200 OK` and the details it gives
+/// if it passes, and with `503 Service Unavailable` and the reason it gives
+/// otherwise. Other paths are `404 Not Found`.
+///
+/// Returns the address actually listened on, which tells the port if `0` was
+/// asked for.
+///
+/// # Errors
+///
+/// This function will return an error if `address` can't be listened on.
+pub fn serve<F>(address: SocketAddr, check: F) -> io::Result<SocketAddr>
+where
+    F: Fn(Probe) -> Result<String, String> + Send + 'static,
+{
+    let listener = TcpListener::bind(address)?;
+    let address = listener.local_addr()?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            if let Err(error) = respond(stream, &check) {
+                tracing::debug!(%error, "Failed to answer a probe");
+            }
+        }
+    });
+    Ok(address)
+}
+
+fn respond<F>(mut stream: TcpStream, check: &F) -> io::Result<()>
+where
+    F: Fn(Probe) -> Result<String, String>,
+{
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut request_line = String::new();
+    let mut reader = BufReader::new(&stream);
+    reader.read_line(&mut request_line)?;
+    // NOTE: Drain the headers, probes never have a body.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let target = request_line.split_whitespace().nth(1).unwrap_or_default();
+    let path = target.split_once('?').map_or(target, |(path, _)| path);
+    let (status, body) = match Probe::from_path(path).map(check) {
+        Some(Ok(details)) => ("200 OK", details),
+        Some(Err(reason)) => ("503 Service Unavailable", reason),
+        None => ("404 Not Found", "not found".to_string()),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}\n",
+        body.len() + 1
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn get(address: SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn probes_report_their_checks() {
+        let address = serve("127.0.0.1:0".parse().unwrap(), |probe| match probe {
+            Probe::Health => Ok("ok".to_string()),
+            Probe::Readiness => Err("No backup is scheduled".to_string()),
+        })
+        .unwrap();
+
+        let health = get(address, "/healthz");
+        assert!(health.starts_with("HTTP/1.1 200 OK"));
+        assert!(health.ends_with("\nok\n"));
+        let readiness = get(address, "/readyz");
+        assert!(readiness.starts_with("HTTP/1.1 503"));
+        assert!(readiness.ends_with("No backup is scheduled\n"));
+        assert!(get(address, "/metrics").starts_with("HTTP/1.1 404"));
+    }
+}
@@ -1,21 +1,30 @@
 use serde::{Deserialize, Serialize};
-use std::fmt;
+use std::collections::HashMap;
+use std::path::Path;
+use std::{fmt, fs, io};
 
 pub mod backup;
+pub mod bare;
+pub mod bundle;
 pub mod docker_compose;
+pub mod health;
+pub mod rcon;
+pub mod status;
 
 pub const DEFAULT_MINECRAFT_PORT: u16 = 25565;
 
 pub trait Server: fmt::Debug + Serialize + for<'de> Deserialize<'de> {
     type SetupError;
     type StartStopError;
+    type StatusError;
 
-    /// Prepare everything for the first start of the server.
+    /// Prepare everything for the first start of the server, with the pack's
+    /// [`Profile`](crate::pack::Profile) called `profile` applied if set.
     ///
     /// # Errors
     ///
     /// ...
-    fn setup() -> Result<Self, Self::SetupError>;
+    fn setup(profile: Option<&str>) -> Result<Self, Self::SetupError>;
 
     /// Start the hosted server, do nothing if it is already running.
     ///
@@ -31,20 +40,48 @@ pub trait Server: fmt::Debug + Serialize + for<'de> Deserialize<'de> {
     /// ...
     fn stop(&self) -> Result<(), Self::StartStopError>;
 
-    /// Report the status of the server.
+    /// Print the server's console output, waiting for more if `follow` is
+    /// set.
     ///
     /// # Errors
     ///
     /// ...
-    fn status(&self) -> Result<(), !> {
-        todo!("Querying the server's status isn't yet implemented")
-    }
+    fn logs(&self, follow: bool) -> Result<(), Self::StartStopError>;
+
+    /// Report the status of the server, pinging it if it's running.
+    ///
+    /// # Errors
+    ///
+    /// ...
+    fn status(&self) -> Result<status::ServerStatus, Self::StatusError>;
+}
+
+/// Read the `server.properties` of the server in `data`, which are empty if
+/// the server hasn't written them yet.
+///
+/// # Errors
+///
+/// Returns an error if the file exists but can't be read.
+pub fn read_properties(data: &Path) -> io::Result<HashMap<String, String>> {
+    let properties = match fs::read_to_string(data.join("server.properties")) {
+        Ok(properties) => properties,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(error) => return Err(error),
+    };
+    Ok(properties
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect())
 }
 
 /// The server's default `gamemode` for new players.
 ///
 /// Variants are self-explanatory, I think...
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, strum::Display)]
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, strum::Display,
+)]
 #[serde(rename_all = "lowercase")]
 #[strum(serialize_all = "lowercase")]
 pub enum Gamemode {
@@ -57,7 +94,9 @@ pub enum Gamemode {
 /// The server's difficulty level.
 ///
 /// Variants are self-explanatory, I think...
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, strum::Display)]
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, strum::Display,
+)]
 #[serde(rename_all = "lowercase")]
 #[strum(serialize_all = "lowercase")]
 pub enum Difficulty {
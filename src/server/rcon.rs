@@ -0,0 +1,211 @@
+use super::read_properties;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::path::Path;
+use std::time::Duration;
+
+/// The port servers listen for RCON connections on unless told otherwise.
+pub const DEFAULT_RCON_PORT: u16 = 25575;
+
+/// How long to wait for the server to answer.
+pub const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The longest command the server accepts in a single packet.
+pub const MAX_COMMAND_LENGTH: usize = 1446;
+
+const TYPE_RESPONSE: i32 = 0;
+const TYPE_COMMAND: i32 = 2;
+const TYPE_AUTH_RESPONSE: i32 = 2;
+const TYPE_AUTH: i32 = 3;
+
+/// Where and how to reach a server's RCON, as configured in its
+/// `server.properties`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RconSettings {
+    pub port: u16,
+    pub password: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("RCON isn't enabled in the server's properties")]
+    Disabled,
+    #[error("The server rejected the RCON password")]
+    AuthFailed,
+    #[error("The command is {0} bytes long, RCON only takes {MAX_COMMAND_LENGTH}")]
+    TooLong(usize),
+    #[error("The server sent a malformed RCON packet")]
+    Malformed,
+}
+
+impl RconSettings {
+    /// Read the RCON settings of the server in `data`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `server.properties` can't be read or don't
+    /// enable RCON with a password.
+    pub fn read(data: &Path) -> Result<Self, Error> {
+        let properties = read_properties(data)?;
+        let enabled = properties.get("enable-rcon").is_some_and(|on| on == "true");
+        let password = properties
+            .get("rcon.password")
+            .filter(|password| !password.is_empty());
+        match (enabled, password) {
+            (true, Some(password)) => Ok(Self {
+                port: properties
+                    .get("rcon.port")
+                    .and_then(|port| port.parse().ok())
+                    .unwrap_or(DEFAULT_RCON_PORT),
+                password: password.clone(),
+            }),
+            _ => Err(Error::Disabled),
+        }
+    }
+}
+
+/// Generate a random password for the server's RCON.
+///
+/// # Errors
+///
+/// Returns an error if the system's randomness can't be read.
+pub fn generate_password() -> io::Result<String> {
+    let mut bytes = [0; 16];
+    File::open("/dev/urandom")?.read_exact(&mut bytes)?;
+    Ok(bytes.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02x}");
+        hex
+    }))
+}
+
+/// A client of the [RCON protocol](https://wiki.vg/RCON), for running console
+/// commands on a running server.
+#[derive(Debug)]
+pub struct Rcon {
+    stream: TcpStream,
+    last_id: i32,
+}
+
+impl Rcon {
+    /// Connect to the server's RCON on this host and log in.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the server can't be reached or rejects the password.
+    pub fn connect(settings: &RconSettings) -> Result<Self, Error> {
+        let stream = TcpStream::connect(("127.0.0.1", settings.port))?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+        let mut rcon = Self { stream, last_id: 0 };
+
+        let id = rcon.send(TYPE_AUTH, &settings.password)?;
+        loop {
+            let packet = read_packet(&mut rcon.stream)?;
+            match packet {
+                Packet { id: -1, .. } => return Err(Error::AuthFailed),
+                Packet {
+                    id: reply, kind, ..
+                } if reply == id && kind == TYPE_AUTH_RESPONSE => return Ok(rcon),
+                // NOTE: Some servers send an empty response before the actual
+                // auth response.
+                _ => {}
+            }
+        }
+    }
+
+    /// Run `command` on the server and return its output.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the command is too long or the connection fails.
+    pub fn command(&mut self, command: &str) -> Result<String, Error> {
+        if command.len() > MAX_COMMAND_LENGTH {
+            return Err(Error::TooLong(command.len()));
+        }
+        let id = self.send(TYPE_COMMAND, command)?;
+        // NOTE: Long outputs are split over several packets with no end
+        // marker, so send a bogus request: its answer comes after the last one.
+        let marker = self.send(TYPE_RESPONSE, "")?;
+        let mut output = String::new();
+        loop {
+            let packet = read_packet(&mut self.stream)?;
+            if packet.id == marker {
+                return Ok(output);
+            }
+            if packet.id == id {
+                output.push_str(&packet.body);
+            }
+        }
+    }
+
+    fn send(&mut self, kind: i32, body: &str) -> Result<i32, Error> {
+        self.last_id += 1;
+        let packet = Packet {
+            id: self.last_id,
+            kind,
+            body: body.to_string(),
+        };
+        self.stream.write_all(&packet.encode())?;
+        Ok(self.last_id)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Packet {
+    id: i32,
+    kind: i32,
+    body: String,
+}
+
+impl Packet {
+    fn encode(&self) -> Vec<u8> {
+        let length = i32::try_from(self.body.len() + 10).unwrap_or(i32::MAX);
+        let mut bytes = Vec::with_capacity(self.body.len() + 14);
+        bytes.extend(length.to_le_bytes());
+        bytes.extend(self.id.to_le_bytes());
+        bytes.extend(self.kind.to_le_bytes());
+        bytes.extend(self.body.as_bytes());
+        bytes.extend([0, 0]);
+        bytes
+    }
+}
+
+fn read_packet(stream: &mut impl Read) -> Result<Packet, Error> {
+    let mut int = [0; 4];
+    stream.read_exact(&mut int)?;
+    let length = usize::try_from(i32::from_le_bytes(int)).map_err(|_| Error::Malformed)?;
+    if length < 10 {
+        return Err(Error::Malformed);
+    }
+    let mut rest = vec![0; length];
+    stream.read_exact(&mut rest)?;
+    let int_at =
+        |at: usize| i32::from_le_bytes([rest[at], rest[at + 1], rest[at + 2], rest[at + 3]]);
+    let body = rest[8..length - 2].to_vec();
+    Ok(Packet {
+        id: int_at(0),
+        kind: int_at(4),
+        body: String::from_utf8_lossy(&body).into_owned(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packets_round_trip() {
+        let packet = Packet {
+            id: 7,
+            kind: TYPE_COMMAND,
+            body: "list".to_string(),
+        };
+        let bytes = packet.encode();
+        assert_eq!(bytes[..4], 14_i32.to_le_bytes());
+        assert_eq!(read_packet(&mut bytes.as_slice()).unwrap(), packet);
+    }
+}
@@ -0,0 +1,380 @@
+use color_eyre::owo_colors::OwoColorize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::process::ExitStatus;
+use std::time::{Duration, Instant};
+
+/// How long to wait for the server to answer a [`ping`].
+pub const PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The state of a hosted server, as reported by its backend and by the server
+/// itself.
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerStatus {
+    /// Whether the server's process (or container) is up.
+    pub running: bool,
+    /// The ID of the server's process, for backends that run it directly.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+    /// The server's containers, for backends that run it in one.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub containers: Vec<Container>,
+    /// Where the server was [pinged](ping).
+    pub address: String,
+    /// What the server said when pinged, if it's running and answered.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ping: Option<Ping>,
+    /// Why the server couldn't be pinged.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ping_error: Option<String>,
+}
+
+/// A container of the server, as listed by `docker compose ps`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "PascalCase"))]
+pub struct Container {
+    pub name: String,
+    pub service: String,
+    /// Like `running` or `exited`.
+    pub state: String,
+    /// Like `healthy`, empty if the container has no health check.
+    #[serde(default)]
+    pub health: String,
+    /// A human-readable summary, like `Up 5 minutes`.
+    pub status: String,
+    #[serde(default, skip_serializing)]
+    pub publishers: Option<Vec<Publisher>>,
+}
+
+/// A port of a [`Container`] published on the host.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Publisher {
+    pub target_port: u16,
+    pub published_port: u16,
+}
+
+/// The answer of a server to a [`ping`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Ping {
+    /// The server's version, like `1.20.1`.
+    pub version: String,
+    pub protocol: i32,
+    /// The message of the day, without formatting codes.
+    pub motd: String,
+    pub players_online: u32,
+    pub players_max: u32,
+    pub latency_ms: u64,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("`docker compose ps` failed ({0})")]
+    Compose(ExitStatus),
+    #[error("Failed to parse the containers listed by `docker compose ps`")]
+    Json(#[from] serde_json::Error),
+}
+
+impl ServerStatus {
+    /// The status of a server listening on `port` of this host, [pinging](ping)
+    /// it if it's `running`.
+    #[must_use]
+    pub fn probe(running: bool, port: u16) -> Self {
+        let host = "127.0.0.1";
+        let (ping, ping_error) = match running.then(|| ping(host, port)) {
+            Some(Ok(ping)) => (Some(ping), None),
+            Some(Err(error)) => (None, Some(error.to_string())),
+            None => (None, None),
+        };
+        Self {
+            running,
+            pid: None,
+            containers: vec![],
+            address: format!("{host}:{port}"),
+            ping,
+            ping_error,
+        }
+    }
+}
+
+impl Container {
+    /// Parse the output of `docker compose ps --format json`, which is either
+    /// a JSON array or one JSON object per line, depending on the version of
+    /// Docker Compose.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the output is neither.
+    pub fn parse_all(output: &str) -> Result<Vec<Self>, serde_json::Error> {
+        let output = output.trim();
+        if output.starts_with('[') {
+            return serde_json::from_str(output);
+        }
+        output
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect()
+    }
+
+    /// Whether this container is up.
+    #[must_use]
+    pub fn is_running(&self) -> bool {
+        self.state == "running"
+    }
+
+    /// The host port that `target_port` of this container is published on.
+    #[must_use]
+    pub fn published_port(&self, target_port: u16) -> Option<u16> {
+        self.publishers
+            .iter()
+            .flatten()
+            .find(|publisher| publisher.target_port == target_port && publisher.published_port != 0)
+            .map(|publisher| publisher.published_port)
+    }
+}
+
+/// Ask the Minecraft server at `host:port` for its status, using the
+/// [Server List Ping](https://wiki.vg/Server_List_Ping) protocol that game
+/// clients use for their server list.
+///
+/// # Errors
+///
+/// Returns an error if the server can't be reached within [`PING_TIMEOUT`] or
+/// doesn't answer like a Minecraft server.
+pub fn ping(host: &str, port: u16) -> io::Result<Ping> {
+    let address = (host, port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No address to ping"))?;
+    let mut stream = TcpStream::connect_timeout(&address, PING_TIMEOUT)?;
+    stream.set_read_timeout(Some(PING_TIMEOUT))?;
+    stream.set_write_timeout(Some(PING_TIMEOUT))?;
+
+    let mut handshake = vec![];
+    write_varint(&mut handshake, 0x00);
+    // NOTE: `-1` means that the client doesn't care about the version.
+    write_varint(&mut handshake, -1);
+    write_varint(
+        &mut handshake,
+        i32::try_from(host.len()).unwrap_or(i32::MAX),
+    );
+    handshake.extend(host.as_bytes());
+    handshake.extend(port.to_be_bytes());
+    write_varint(&mut handshake, 1);
+    write_packet(&mut stream, &handshake)?;
+
+    let started = Instant::now();
+    write_packet(&mut stream, &[0x00])?;
+    let _length = read_varint(&mut stream)?;
+    let packet_id = read_varint(&mut stream)?;
+    if packet_id != 0x00 {
+        return Err(invalid_data(format!("Unexpected packet {packet_id:#x}")));
+    }
+    let length = usize::try_from(read_varint(&mut stream)?).map_err(invalid_data)?;
+    let mut response = vec![0; length];
+    stream.read_exact(&mut response)?;
+    let latency = started.elapsed();
+
+    parse_response(&response, latency)
+}
+
+#[derive(Deserialize)]
+struct Response {
+    version: ResponseVersion,
+    players: ResponsePlayers,
+    #[serde(default)]
+    description: Value,
+}
+
+#[derive(Deserialize)]
+struct ResponseVersion {
+    name: String,
+    protocol: i32,
+}
+
+#[derive(Deserialize)]
+struct ResponsePlayers {
+    max: u32,
+    online: u32,
+}
+
+fn parse_response(response: &[u8], latency: Duration) -> io::Result<Ping> {
+    let response: Response = serde_json::from_slice(response).map_err(invalid_data)?;
+    Ok(Ping {
+        version: response.version.name,
+        protocol: response.version.protocol,
+        motd: strip_formatting(&text_of(&response.description)),
+        players_online: response.players.online,
+        players_max: response.players.max,
+        latency_ms: u64::try_from(latency.as_millis()).unwrap_or(u64::MAX),
+    })
+}
+
+/// The plain text of a chat component, which is either a string, an object
+/// with `text` and `extra` components, or an array of components.
+fn text_of(component: &Value) -> String {
+    match component {
+        Value::String(text) => text.clone(),
+        Value::Array(components) => components.iter().map(text_of).collect(),
+        Value::Object(object) => {
+            let text = object.get("text").map(text_of).unwrap_or_default();
+            let extra = object.get("extra").map(text_of).unwrap_or_default();
+            text + &extra
+        }
+        _ => String::new(),
+    }
+}
+
+/// Remove the legacy `§`-prefixed formatting codes from `text`.
+fn strip_formatting(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(char) = chars.next() {
+        match char {
+            '§' => {
+                chars.next();
+            }
+            _ => stripped.push(char),
+        }
+    }
+    stripped.trim().to_string()
+}
+
+fn write_packet(stream: &mut impl Write, payload: &[u8]) -> io::Result<()> {
+    let mut packet = vec![];
+    write_varint(
+        &mut packet,
+        i32::try_from(payload.len()).map_err(invalid_data)?,
+    );
+    packet.extend(payload);
+    stream.write_all(&packet)
+}
+
+#[expect(clippy::cast_sign_loss, reason = "VarInts are two's complement")]
+fn write_varint(buffer: &mut Vec<u8>, value: i32) {
+    let mut value = value as u32;
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buffer.push(byte);
+            return;
+        }
+        buffer.push(byte | 0x80);
+    }
+}
+
+#[expect(clippy::cast_possible_wrap, reason = "VarInts are two's complement")]
+fn read_varint(stream: &mut impl Read) -> io::Result<i32> {
+    let mut value = 0_u32;
+    for position in 0..5 {
+        let mut byte = [0];
+        stream.read_exact(&mut byte)?;
+        value |= u32::from(byte[0] & 0x7f) << (7 * position);
+        if byte[0] & 0x80 == 0 {
+            return Ok(value as i32);
+        }
+    }
+    Err(invalid_data("VarInt is too long"))
+}
+
+fn invalid_data(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+impl fmt::Display for ServerStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = match self.running {
+            true => "running".green().bold().to_string(),
+            false => "stopped".red().bold().to_string(),
+        };
+        match self.pid {
+            Some(pid) => writeln!(f, "The server is {state} (PID {pid})")?,
+            None => writeln!(f, "The server is {state}")?,
+        }
+        for container in &self.containers {
+            let health = match container.health.as_str() {
+                "" => String::new(),
+                health => format!(", {health}"),
+            };
+            writeln!(
+                f,
+                "- {} ({}{health}): {}",
+                container.name.bold(),
+                container.state,
+                container.status
+            )?;
+        }
+        match (&self.ping, &self.ping_error) {
+            (Some(ping), _) => {
+                writeln!(
+                    f,
+                    "{} answers as Minecraft {}, {}/{} players online, {}ms",
+                    self.address.bold(),
+                    ping.version.yellow().bold(),
+                    ping.players_online.bold(),
+                    ping.players_max,
+                    ping.latency_ms
+                )?;
+                if !ping.motd.is_empty() {
+                    writeln!(f, "MOTD: {}", ping.motd)?;
+                }
+            }
+            (None, Some(error)) => writeln!(
+                f,
+                "{} doesn't answer pings ({})",
+                self.address.bold(),
+                error.red()
+            )?,
+            (None, None) => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn varints_round_trip() {
+        for (value, bytes) in [
+            (0, vec![0x00]),
+            (300, vec![0xac, 0x02]),
+            (-1, vec![0xff, 0xff, 0xff, 0xff, 0x0f]),
+        ] {
+            let mut buffer = vec![];
+            write_varint(&mut buffer, value);
+            assert_eq!(buffer, bytes);
+            assert_eq!(read_varint(&mut bytes.as_slice()).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn responses_are_flattened() {
+        let response = json!({
+            "version": { "name": "1.20.1", "protocol": 763 },
+            "players": { "max": 4, "online": 1 },
+            "description": { "text": "§aGround", "extra": [{ "text": " Zero" }] },
+        });
+        let ping = parse_response(response.to_string().as_bytes(), Duration::ZERO).unwrap();
+        assert_eq!(ping.motd, "Ground Zero");
+        assert_eq!((ping.players_online, ping.players_max), (1, 4));
+    }
+
+    #[test]
+    fn both_compose_formats_are_parsed() {
+        let line = r#"{"Name":"pack_server","Service":"server","State":"running","Health":"","Status":"Up 5 minutes","Publishers":[{"URL":"0.0.0.0","TargetPort":25565,"PublishedPort":25566,"Protocol":"tcp"}]}"#;
+        let containers = Container::parse_all(line).unwrap();
+        assert!(containers[0].is_running());
+        assert_eq!(containers[0].published_port(25565), Some(25566));
+        assert_eq!(Container::parse_all(&format!("[{line}]")).unwrap().len(), 1);
+    }
+}
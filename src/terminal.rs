@@ -0,0 +1,197 @@
+use inquire::error::{InquireError, InquireResult};
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use tracing_subscriber::fmt::MakeWriter;
+
+/// The single writer everything printed to `stderr` goes through.
+///
+/// Log events are written whole under a lock, so events from parallel
+/// operations never interleave. While an interactive prompt is running (see
+/// [`suspend`]), events are buffered and only written once the prompt is done,
+/// and the [`Status`] line is redrawn below the latest event.
+static TERMINAL: Mutex<State> = Mutex::new(State {
+    suspended: 0,
+    buffered: Vec::new(),
+    status: None,
+});
+
+struct State {
+    suspended: usize,
+    buffered: Vec<u8>,
+    status: Option<String>,
+}
+
+impl State {
+    fn write(&mut self, bytes: &[u8]) {
+        if self.suspended > 0 {
+            self.buffered.extend_from_slice(bytes);
+            return;
+        }
+        let mut stderr = io::stderr().lock();
+        let _ = clear_status(&mut stderr, self.status.as_ref());
+        let _ = stderr.write_all(bytes);
+        let _ = draw_status(&mut stderr, self.status.as_ref());
+        let _ = stderr.flush();
+    }
+
+    fn suspend(&mut self) {
+        if self.suspended == 0 {
+            let mut stderr = io::stderr().lock();
+            let _ = clear_status(&mut stderr, self.status.as_ref());
+            let _ = stderr.flush();
+        }
+        self.suspended += 1;
+    }
+
+    fn resume(&mut self) {
+        self.suspended -= 1;
+        if self.suspended == 0 {
+            let buffered = std::mem::take(&mut self.buffered);
+            let mut stderr = io::stderr().lock();
+            let _ = stderr.write_all(&buffered);
+            let _ = draw_status(&mut stderr, self.status.as_ref());
+            let _ = stderr.flush();
+        }
+    }
+
+    fn set_status(&mut self, status: Option<String>) {
+        if self.suspended > 0 {
+            self.status = status;
+            return;
+        }
+        let mut stderr = io::stderr().lock();
+        let _ = clear_status(&mut stderr, self.status.as_ref());
+        self.status = status;
+        let _ = draw_status(&mut stderr, self.status.as_ref());
+        let _ = stderr.flush();
+    }
+}
+
+// NOTE: Writing to the terminal is best-effort, there's nowhere to report
+// failures to anyway.
+fn lock() -> MutexGuard<'static, State> {
+    TERMINAL
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+fn clear_status(stderr: &mut impl Write, status: Option<&String>) -> io::Result<()> {
+    match status {
+        Some(_) if io::stderr().is_terminal() => write!(stderr, "\r\x1b[2K"),
+        _ => Ok(()),
+    }
+}
+
+fn draw_status(stderr: &mut impl Write, status: Option<&String>) -> io::Result<()> {
+    match status {
+        Some(status) if io::stderr().is_terminal() => write!(stderr, "{status}"),
+        _ => Ok(()),
+    }
+}
+
+/// Environment variable that turns off all prompts, like `--non-interactive`.
+pub const NONINTERACTIVE_VAR: &str = "INVAR_NONINTERACTIVE";
+
+static INTERACTIVE: AtomicBool = AtomicBool::new(true);
+
+/// Allow or forbid prompts for the rest of the process.
+///
+/// Without prompts, callers fall back to defaults where there is a sensible
+/// one, and [`suspend`] fails with [`InputRequired`] otherwise.
+pub fn set_interactive(interactive: bool) {
+    INTERACTIVE.store(interactive, Ordering::Relaxed);
+}
+
+/// Whether prompts may be shown, see [`set_interactive`].
+#[must_use]
+pub fn is_interactive() -> bool {
+    INTERACTIVE.load(Ordering::Relaxed)
+}
+
+/// A prompt was needed while running [non-interactively](set_interactive).
+#[derive(thiserror::Error, Debug)]
+#[error("Input is required, but prompts are disabled by --non-interactive or {NONINTERACTIVE_VAR}")]
+pub struct InputRequired;
+
+/// Run `prompt` with all other terminal output held back, so it can't mess up
+/// an interactive prompt. Output produced in the meantime is written once the
+/// prompt returns.
+///
+/// # Errors
+///
+/// This function will return the prompt's error, or [`InputRequired`] if
+/// prompts are [disabled](set_interactive).
+pub fn suspend<T>(prompt: impl FnOnce() -> InquireResult<T>) -> InquireResult<T> {
+    if !is_interactive() {
+        return Err(InquireError::Custom(Box::new(InputRequired)));
+    }
+    lock().suspend();
+    let result = prompt();
+    lock().resume();
+    result
+}
+
+/// A transient status line shown below the log output, like `[3/120]
+/// Fetching sodium`. It's cleared when dropped.
+///
+/// Only one status is shown at a time; creating a new one replaces the old.
+/// Nothing is drawn if `stderr` isn't a terminal.
+#[derive(Debug)]
+pub struct Status(());
+
+impl Status {
+    #[must_use]
+    pub fn new(message: impl Into<String>) -> Self {
+        let status = Self(());
+        status.set(message);
+        status
+    }
+
+    /// Replace the status line's text.
+    pub fn set(&self, message: impl Into<String>) {
+        lock().set_status(Some(message.into()));
+    }
+}
+
+impl Drop for Status {
+    fn drop(&mut self) {
+        lock().set_status(None);
+    }
+}
+
+/// A [`MakeWriter`] for [`tracing_subscriber`] that routes events through
+/// the coordinated terminal.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Writer;
+
+/// Collects a single event and writes it as a whole when dropped.
+#[derive(Debug, Default)]
+pub struct EventWriter(Vec<u8>);
+
+impl<'a> MakeWriter<'a> for Writer {
+    type Writer = EventWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        EventWriter::default()
+    }
+}
+
+impl Write for EventWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for EventWriter {
+    fn drop(&mut self) {
+        if !self.0.is_empty() {
+            lock().write(&self.0);
+        }
+    }
+}
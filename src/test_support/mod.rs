@@ -0,0 +1,313 @@
+use crate::component::{
+    modrinth, Category, Channel, Component, Constraint, FileNaming, Source, TagInformation,
+};
+use crate::index::file::{Env, Hashes, Requirement};
+use crate::instance::{Instance, Loader};
+use chrono::{DateTime, Duration, Utc};
+use semver::Version;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+
+/// A Fabric `1.20.1` [`Instance`] that accepts every release channel.
+#[must_use]
+pub fn instance() -> Instance {
+    Instance {
+        minecraft_version: Version::new(1, 20, 1),
+        loader: Loader::Fabric,
+        loader_version: Version::new(0, 16, 9),
+        allowed_foreign_loaders: HashSet::new(),
+        release_channel: None,
+    }
+}
+
+/// A project served by [`MockModrinth`].
+#[derive(Debug, Clone)]
+pub struct MockProject {
+    pub id: String,
+    pub slug: String,
+    pub category: Category,
+    /// Oldest first: each version is published a day after the previous one.
+    pub versions: Vec<MockVersion>,
+}
+
+/// A version of a [`MockProject`], compatible with the served [`Instance`]
+/// unless it's for another `minecraft_version`.
+#[derive(Debug, Clone)]
+pub struct MockVersion {
+    pub id: String,
+    pub version_number: String,
+    pub channel: Channel,
+    pub minecraft_version: Option<Version>,
+}
+
+impl MockProject {
+    #[must_use]
+    pub fn new(id: &str, slug: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            slug: slug.to_string(),
+            category: Category::Mod,
+            versions: vec![],
+        }
+    }
+
+    /// Publish another version from the [release](Channel::Release) channel.
+    #[must_use]
+    pub fn version(self, id: &str, version_number: &str) -> Self {
+        self.version_in(id, version_number, Channel::Release)
+    }
+
+    /// Publish another version from the provided `channel`.
+    #[must_use]
+    pub fn version_in(mut self, id: &str, version_number: &str, channel: Channel) -> Self {
+        self.versions.push(MockVersion {
+            id: id.to_string(),
+            version_number: version_number.to_string(),
+            channel,
+            minecraft_version: None,
+        });
+        self
+    }
+
+    /// Publish another release for another `minecraft_version` than the
+    /// served [`Instance`]'s.
+    #[must_use]
+    pub fn version_for(
+        mut self,
+        id: &str,
+        version_number: &str,
+        minecraft_version: Version,
+    ) -> Self {
+        self = self.version(id, version_number);
+        if let Some(version) = self.versions.last_mut() {
+            version.minecraft_version = Some(minecraft_version);
+        }
+        self
+    }
+}
+
+/// A local stand-in for the **Modrinth API**, serving canned [`MockProject`]s
+/// over plain HTTP so that adding, updating and exporting components can be
+/// tested without the network.
+///
+/// The contents of a version's file are its ID, so their hashes are real.
+#[derive(Debug, Clone)]
+pub struct MockModrinth {
+    address: SocketAddr,
+    state: Arc<State>,
+}
+
+#[derive(Debug)]
+struct State {
+    instance: Instance,
+    projects: Vec<MockProject>,
+}
+
+impl MockModrinth {
+    /// Start serving `projects` on a random local port, in the background.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the listener can't be bound.
+    #[must_use]
+    pub fn start(instance: &Instance, projects: Vec<MockProject>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind the mock server");
+        let address = listener
+            .local_addr()
+            .expect("Failed to get the mock address");
+        let state = Arc::new(State {
+            instance: instance.clone(),
+            projects,
+        });
+        let server_state = Arc::clone(&state);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let _ = server_state.respond(address, stream);
+            }
+        });
+        Self { address, state }
+    }
+
+    /// The base URL to use instead of [`modrinth::API_URL`].
+    #[must_use]
+    pub fn url(&self) -> String {
+        format!("http://{}", self.address)
+    }
+
+    /// Point [`modrinth`] requests of this whole process to this server.
+    pub fn install(&self) {
+        std::env::set_var(modrinth::API_URL_VAR, self.url());
+    }
+
+    /// A [`Component`] at the `version_id` of the project with this `slug`,
+    /// as if it was added while that version was the latest.
+    #[must_use]
+    pub fn component(&self, slug: &str, version_id: &str) -> Option<Component> {
+        let project = self.state.project(slug)?;
+        let version = project.versions.iter().find(|v| v.id == version_id)?;
+        Some(Component {
+            slug: project.slug.clone(),
+            source: Source::Modrinth,
+            project_id: Some(project.id.clone()),
+            category: project.category,
+            tags: TagInformation {
+                main: None,
+                others: vec![],
+            },
+            environment: Env {
+                client: Requirement::Required,
+                server: Requirement::Required,
+            },
+            version_id: version.id.clone(),
+            file_name: file_name(project, version),
+            file_size: version.id.len(),
+            download_url: format!("{}/files/{}", self.url(), version.id)
+                .parse()
+                .ok()?,
+            hashes: Hashes::compute(version.id.as_bytes()),
+            dependencies: vec![],
+            runtime_subdir: None,
+            constraint: Constraint::default(),
+            essential: false,
+            file_naming: FileNaming::default(),
+            group: None,
+            metadata: None,
+        })
+    }
+}
+
+impl State {
+    fn project(&self, slug_or_id: &str) -> Option<&MockProject> {
+        self.projects
+            .iter()
+            .find(|project| project.slug == slug_or_id || project.id == slug_or_id)
+    }
+
+    fn respond(&self, address: SocketAddr, mut stream: TcpStream) -> std::io::Result<()> {
+        let mut request_line = String::new();
+        let mut reader = BufReader::new(&stream);
+        reader.read_line(&mut request_line)?;
+        // NOTE: Drain the headers, the requests never have a body.
+        let mut header = String::new();
+        while reader.read_line(&mut header)? > 2 {
+            header.clear();
+        }
+
+        let target = request_line.split_whitespace().nth(1).unwrap_or_default();
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let base = format!("http://{address}");
+        let (status, body) = match self.route(&base, path, query) {
+            Some(body) => ("200 OK", body),
+            None => ("404 Not Found", b"{}".to_vec()),
+        };
+        write!(
+            stream,
+            "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            body.len()
+        )?;
+        stream.write_all(&body)
+    }
+
+    fn route(&self, base: &str, path: &str, query: &str) -> Option<Vec<u8>> {
+        let segments = path.trim_matches('/').split('/').collect::<Vec<_>>();
+        let body = match segments.as_slice() {
+            ["project", slug] => {
+                let project = self.project(slug)?;
+                json!({
+                    "id": project.id,
+                    "project_type": project.category,
+                    "client_side": Requirement::Required,
+                    "server_side": Requirement::Required,
+                })
+            }
+            ["project", slug, "version"] => {
+                let project = self.project(slug)?;
+                let versions = (0..).zip(&project.versions);
+                versions
+                    .map(|(index, version)| self.version_json(base, project, index, version))
+                    .collect()
+            }
+            ["version", version_id] => {
+                let (project, index, version) = self.projects.iter().find_map(|project| {
+                    let (index, version) = (0..)
+                        .zip(&project.versions)
+                        .find(|(_, version)| version.id == *version_id)?;
+                    Some((project, index, version))
+                })?;
+                self.version_json(base, project, index, version)
+            }
+            ["versions"] => self
+                .projects
+                .iter()
+                .flat_map(|project| {
+                    (0..)
+                        .zip(&project.versions)
+                        .map(move |(index, version)| (project, index, version))
+                })
+                .filter(|(_, _, version)| query.contains(&version.id))
+                .map(|(project, index, version)| self.version_json(base, project, index, version))
+                .collect(),
+            ["projects"] => self
+                .projects
+                .iter()
+                .filter(|project| query.contains(&project.id))
+                .map(|project| {
+                    json!({
+                        "id": project.id,
+                        "slug": project.slug,
+                        "title": project.slug,
+                        "description": null,
+                    })
+                })
+                .collect(),
+            ["files", version_id] => {
+                let exists = self.projects.iter().any(|project| {
+                    project
+                        .versions
+                        .iter()
+                        .any(|version| version.id == *version_id)
+                });
+                return exists.then(|| version_id.as_bytes().to_vec());
+            }
+            _ => return None,
+        };
+        serde_json::to_vec(&body).ok()
+    }
+
+    fn version_json(
+        &self,
+        base: &str,
+        project: &MockProject,
+        index: i64,
+        version: &MockVersion,
+    ) -> Value {
+        let published = DateTime::<Utc>::UNIX_EPOCH + Duration::days(index);
+        let minecraft_version = version
+            .minecraft_version
+            .as_ref()
+            .unwrap_or(&self.instance.minecraft_version);
+        json!({
+            "id": version.id,
+            "name": version.version_number,
+            "version_number": version.version_number,
+            "version_type": version.channel,
+            "game_versions": [minecraft_version.to_string()],
+            "loaders": [self.instance.loader],
+            "date_published": published,
+            "files": [{
+                "hashes": Hashes::compute(version.id.as_bytes()),
+                "url": format!("{base}/files/{}", version.id),
+                "filename": file_name(project, version),
+                "size": version.id.len(),
+            }],
+        })
+    }
+}
+
+fn file_name(project: &MockProject, version: &MockVersion) -> String {
+    format!("{}-{}.jar", project.slug, version.version_number)
+}
@@ -0,0 +1,140 @@
+use invar::component::{
+    modrinth, Channel, Dependency, DependencyGraph, DependencyKind, Outdated, Probe, Source,
+};
+use invar::test_support::{self, MockModrinth, MockProject};
+use invar::{Diagnosis, Instance, Pack, Problem, Settings};
+use semver::Version;
+use std::sync::OnceLock;
+
+// NOTE: The API URL is process-wide, so all tests share one server.
+fn server() -> &'static MockModrinth {
+    static SERVER: OnceLock<MockModrinth> = OnceLock::new();
+    SERVER.get_or_init(|| {
+        let sodium = MockProject::new("AANobbMI", "sodium")
+            .version("v1", "mc1.20.1-0.5.0")
+            .version("v2", "mc1.20.1-0.5.3")
+            .version_in("v3", "mc1.20.1-0.6.0-beta.1", Channel::Beta);
+        let lithium = MockProject::new("gvQqBUqZ", "lithium")
+            .version("l1", "mc1.20.1-0.11.2")
+            .version_for("l2", "mc1.21-0.12.0", Version::new(1, 21, 0));
+        let server = MockModrinth::start(&test_support::instance(), vec![sodium, lithium]);
+        server.install();
+        server
+    })
+}
+
+#[test]
+fn updates_follow_channels_and_constraints() {
+    let server = server();
+    let instance = test_support::instance();
+    let component = server.component("sodium", "v1").unwrap();
+    let components = [component.clone()];
+    let graph = DependencyGraph::new(&components);
+
+    let update = component.fetch_update(&instance, &graph).unwrap().unwrap();
+    assert_eq!(update.version_id, "v3");
+    assert_eq!(update.file_name, "sodium-mc1.20.1-0.6.0-beta.1.jar");
+
+    let mut stable = component;
+    stable.constraint.channel = Some(Channel::Release);
+    let update = stable.fetch_update(&instance, &graph).unwrap().unwrap();
+    assert_eq!(update.version_id, "v2");
+
+    let mut pinned = server.component("sodium", "v3").unwrap();
+    pinned.constraint.pin = Some("v1".to_string());
+    let update = pinned.fetch_update(&instance, &graph).unwrap().unwrap();
+    assert_eq!(update.version_id, "v1");
+
+    let latest = server.component("sodium", "v3").unwrap();
+    assert!(latest.fetch_update(&instance, &graph).unwrap().is_none());
+
+    let outdated = Outdated::check(&components, &instance);
+    assert!(outdated.is_stale());
+    assert_eq!(outdated.updates[0].latest_version_id, "v3");
+    assert!(!Outdated::check(&[latest], &instance).is_stale());
+
+    let slugs = modrinth::fetch_slugs(&["AANobbMI".to_string()]).unwrap();
+    assert_eq!(slugs["AANobbMI"], "sodium");
+}
+
+#[test]
+fn updates_skip_versions_older_than_an_incompatible_one() {
+    let server = server();
+    let instance = test_support::instance();
+    let component = server.component("lithium", "l2").unwrap();
+    let graph = DependencyGraph::new(std::slice::from_ref(&component));
+
+    assert!(component.fetch_update(&instance, &graph).unwrap().is_none());
+    let retarget = component
+        .fetch_retarget(&instance, &graph)
+        .unwrap()
+        .unwrap();
+    assert_eq!(retarget.version_id, "l1");
+}
+
+#[test]
+fn dependency_metadata_is_cached() {
+    let server = server();
+    let mut component = server.component("sodium", "v1").unwrap();
+    component.slug = "indium".to_string();
+    component.dependencies.push(Dependency {
+        project_id: "AANobbMI".to_string(),
+        version_id: None,
+        kind: DependencyKind::Required,
+        metadata: None,
+    });
+    component.fetch_dependency_metadata().unwrap();
+    let dependency = &component.dependencies[0];
+    assert_eq!(dependency.name(), "sodium");
+    assert_eq!(dependency.metadata.as_ref().unwrap().slug, "sodium");
+
+    let update = component
+        .with_version(&modrinth::fetch_version("v2").unwrap())
+        .unwrap();
+    assert!(update.dependencies.is_empty());
+}
+
+#[test]
+fn doctor_finds_incompatible_components() {
+    let server = server();
+    let component = server.component("sodium", "v2").unwrap();
+    let mut duplicate = component.clone();
+    duplicate.slug = "sodium-copy".to_string();
+    let pack = Pack {
+        name: "Example".to_string(),
+        version: Version::new(0, 1, 0),
+        authors: vec![],
+        instance: Instance {
+            minecraft_version: Version::new(1, 21, 0),
+            ..test_support::instance()
+        },
+        settings: Settings::default(),
+        includes: vec![],
+        local_components: vec![],
+    };
+
+    let diagnosis = Diagnosis::check(&pack, std::slice::from_ref(&component), false);
+    assert!(matches!(
+        diagnosis.problems[..],
+        [Problem::WrongMinecraftVersion { .. }]
+    ));
+
+    let diagnosis = Diagnosis::check(&pack, &[component, duplicate], true);
+    assert!(matches!(
+        diagnosis.problems[..],
+        [Problem::DuplicateFile { .. }]
+    ));
+}
+
+#[test]
+fn probe_lists_every_fitting_version() {
+    let _server = server();
+    let mut instance = test_support::instance();
+    instance.release_channel = Some(Channel::Release);
+
+    let probe = Probe::fetch("sodium", Source::Modrinth, &instance, &[]).unwrap();
+    let versions = probe.versions.iter().map(|v| v.version_id.as_str());
+    assert_eq!(versions.collect::<Vec<_>>(), ["v3", "v2", "v1"]);
+    assert!(!probe.versions[0].accepted);
+    assert!(probe.versions[1].accepted);
+}